@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use surrealdb::Connection;
+use tokio::sync::oneshot;
+
+use crate::{FsError, Result, SurrealFs, normalize_path};
+
+#[derive(Debug, Default)]
+struct Pending {
+    /// Text appended to each path since the last flush, in order.
+    appends: BTreeMap<String, String>,
+    writes: usize,
+    since: Option<Instant>,
+}
+
+/// Appends held in memory behind a `SurrealFs` built with `SurrealFsBuilder::write_buffer`,
+/// written out together by `flush`, by a background task every `max_delay`, and when the
+/// buffer is dropped.
+#[derive(Debug)]
+pub(crate) struct WriteBuffer {
+    max_pending: usize,
+    max_delay: Duration,
+    inner: Mutex<Pending>,
+    /// Held for the whole of a flush, so flushes apply in order and the lookups a flush
+    /// makes do not start another one.
+    flushing: tokio::sync::Mutex<()>,
+    /// Set once the background flush task is running; hands it whatever is still queued
+    /// when the buffer goes away.
+    ticker: Mutex<Option<oneshot::Sender<BTreeMap<String, String>>>>,
+    /// Why the last background flush failed, for the next `flush` to return.
+    failed: Mutex<Option<FsError>>,
+}
+
+impl WriteBuffer {
+    pub(crate) fn new(max_pending: usize, max_delay: Duration) -> Self {
+        Self {
+            max_pending: max_pending.max(1),
+            max_delay,
+            inner: Mutex::new(Pending::default()),
+            flushing: tokio::sync::Mutex::new(()),
+            ticker: Mutex::new(None),
+            failed: Mutex::new(None),
+        }
+    }
+
+    /// Queue `content` for `path`; true once the buffer is due to be flushed.
+    fn push(&self, path: String, content: &str) -> bool {
+        let mut pending = self.lock();
+        pending.appends.entry(path).or_default().push_str(content);
+        pending.writes += 1;
+        let since = *pending.since.get_or_insert_with(Instant::now);
+        pending.writes >= self.max_pending || since.elapsed() >= self.max_delay
+    }
+
+    fn take(&self) -> BTreeMap<String, String> {
+        std::mem::take(&mut *self.lock()).appends
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lock().appends.is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn fail(&self, err: FsError) {
+        *self.failed.lock().unwrap_or_else(|e| e.into_inner()) = Some(err);
+    }
+
+    fn take_failure(&self) -> Result<()> {
+        match self.failed.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for WriteBuffer {
+    fn drop(&mut self) {
+        let ticker = self
+            .ticker
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let Some(ticker) = ticker {
+            let _ = ticker.send(self.take());
+        }
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Add `content` to the end of the text file `path`, creating it when missing. A
+    /// trailing symlink is followed. With `SurrealFsBuilder::write_buffer` the text is only
+    /// queued, and written once the buffer is full or due, or by the next `flush`; otherwise
+    /// it is written at once.
+    pub async fn append_file(&self, path: impl AsRef<str>, content: impl AsRef<str>) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::NotAFile(path));
+        }
+        self.ensure_writable(&path)?;
+        let Some(buffer) = &self.buffer else {
+            let (path, current) = self.appended(path, content.as_ref()).await?;
+            self.write_file(&path, current).await?;
+            return Ok(());
+        };
+        let due = buffer.push(path, content.as_ref());
+        self.start_ticker(buffer);
+        if due {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Write out every buffered append in one transaction. Missing parent directories are
+    /// created, as with `write_files`. When the write fails the error is returned and the
+    /// batch is dropped, as a failed `write_file` would be; a failure of the background
+    /// flush is returned by the next call. A no-op without a write buffer.
+    pub async fn flush(&self) -> Result<()> {
+        let Some(buffer) = &self.buffer else {
+            return Ok(());
+        };
+        self.flush_buffer(buffer).await?;
+        buffer.take_failure()
+    }
+
+    async fn flush_buffer(&self, buffer: &WriteBuffer) -> Result<()> {
+        let _flushing = buffer.flushing.lock().await;
+        self.write_appends(buffer.take()).await
+    }
+
+    async fn write_appends(&self, appends: BTreeMap<String, String>) -> Result<()> {
+        if appends.is_empty() {
+            return Ok(());
+        }
+        // Symlinks may send several queued paths to one file; stack their text in turn.
+        let mut files: BTreeMap<String, String> = BTreeMap::new();
+        for (path, content) in appends {
+            let (path, current) = self.appended(path, &content).await?;
+            match files.get_mut(&path) {
+                Some(existing) => existing.push_str(&content),
+                None => {
+                    files.insert(path, current);
+                }
+            }
+        }
+        self.write_files(files).await
+    }
+
+    /// Flush `buffer` every `max_delay` on a background tokio task, started by its first
+    /// append. The task writes through a handle without the buffer and holds the buffer only
+    /// while flushing; once the buffer is dropped it writes out what was still queued and
+    /// stops. Errors there have no caller to go to and are dropped.
+    fn start_ticker(&self, buffer: &Arc<WriteBuffer>) {
+        let mut ticker = buffer.ticker.lock().unwrap_or_else(|e| e.into_inner());
+        if ticker.is_some() {
+            return;
+        }
+        let (sender, mut dropped) = oneshot::channel();
+        *ticker = Some(sender);
+        let fs = SurrealFs {
+            buffer: None,
+            ..self.sibling()
+        };
+        let weak = Arc::downgrade(buffer);
+        let period = buffer.max_delay.max(Duration::from_millis(1));
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + period;
+            let mut interval = tokio::time::interval_at(start, period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        let Some(buffer) = weak.upgrade() else {
+                            continue;
+                        };
+                        if let Err(err) = fs.flush_buffer(&buffer).await {
+                            buffer.fail(err);
+                        }
+                    }
+                    left = &mut dropped => {
+                        if let Ok(appends) = left {
+                            let _ = fs.write_appends(appends).await;
+                        }
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Flush buffered appends before a read, so reads through this handle see them. Lookups
+    /// made while a flush is under way read what is stored.
+    pub(crate) async fn flush_pending(&self) -> Result<()> {
+        let Some(buffer) = &self.buffer else {
+            return Ok(());
+        };
+        if buffer.is_empty() || buffer.flushing.try_lock().is_err() {
+            return Ok(());
+        }
+        Box::pin(self.flush()).await
+    }
+
+    /// The path a write to `path` lands on and its current text followed by `content`.
+    async fn appended(&self, path: String, content: &str) -> Result<(String, String)> {
+        let (path, existing) = self.follow(path).await?;
+        let mut current = match existing {
+            Some(entry) if entry.is_dir => return Err(FsError::NotAFile(path)),
+            Some(entry) if entry.is_binary() => return Err(FsError::Binary(path)),
+            Some(entry) => entry.text()?.unwrap_or_default(),
+            None => String::new(),
+        };
+        current.push_str(content);
+        Ok((path, current))
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use serde::Deserialize;
+use surrealdb::Connection;
+use tokio::task::AbortHandle;
+
+use crate::{Entry, Result, SurrealFs, parent_path};
+
+/// What a cached slot holds: one entry (or its absence) by path, or a directory listing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Entry(String),
+    Listing(String),
+}
+
+#[derive(Debug, Clone)]
+enum Cached {
+    Entry(Box<Option<Entry>>),
+    Listing(Vec<Entry>),
+}
+
+#[derive(Debug)]
+struct Slot {
+    value: Cached,
+    stored: Instant,
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Slots {
+    slots: HashMap<Key, Slot>,
+    /// Keys by last use, oldest first, for eviction.
+    order: BTreeMap<u64, Key>,
+    clock: u64,
+}
+
+/// LRU cache of entries and directory listings kept behind a `SurrealFs` built with
+/// `SurrealFsBuilder::cache`. Writes through the same handle invalidate what they touch;
+/// changes made by other clients show up once the TTL runs out, or as they happen once
+/// `subscribe_cache` is running.
+#[derive(Debug)]
+pub(crate) struct EntryCache {
+    capacity: usize,
+    ttl: Duration,
+    inner: Mutex<Slots>,
+    /// The task applying live query notifications, stopped when the cache goes away.
+    live: Mutex<Option<AbortHandle>>,
+}
+
+/// The part of a changed row a live query notification needs.
+#[derive(Deserialize)]
+struct Changed {
+    path: String,
+}
+
+impl EntryCache {
+    pub(crate) fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttl,
+            inner: Mutex::new(Slots::default()),
+            live: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn is_live(&self) -> bool {
+        self.live
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+
+    /// Make `task` the one feeding notifications to this cache, stopping any before it.
+    fn set_live(&self, task: AbortHandle) {
+        let previous = self
+            .live
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(task);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+
+    /// The cached lookup of `path`: `Some(None)` when it is known not to exist.
+    pub(crate) fn entry(&self, path: &str) -> Option<Option<Entry>> {
+        match self.get(Key::Entry(path.to_string()))? {
+            Cached::Entry(entry) => Some(*entry),
+            Cached::Listing(_) => None,
+        }
+    }
+
+    pub(crate) fn listing(&self, path: &str) -> Option<Vec<Entry>> {
+        match self.get(Key::Listing(path.to_string()))? {
+            Cached::Listing(children) => Some(children),
+            Cached::Entry(_) => None,
+        }
+    }
+
+    /// Remember the lookup of `path`. Hard-linked files are skipped, since a write through
+    /// another of their paths would leave the copy here stale.
+    pub(crate) fn store_entry(&self, path: &str, entry: &Option<Entry>) {
+        if entry.as_ref().is_some_and(|e| e.inode.is_some()) {
+            return;
+        }
+        self.put(
+            Key::Entry(path.to_string()),
+            Cached::Entry(Box::new(entry.clone())),
+        );
+    }
+
+    /// Remember the children of `path`, unless any is hard-linked.
+    pub(crate) fn store_listing(&self, path: &str, children: &[Entry]) {
+        if children.iter().any(|e| e.inode.is_some()) {
+            return;
+        }
+        self.put(
+            Key::Listing(path.to_string()),
+            Cached::Listing(children.to_vec()),
+        );
+    }
+
+    /// Drop everything a change at each of `paths` can make stale: the entries at and below
+    /// it and at its ancestors (their `updated_at` may have moved), and the listings of it,
+    /// its parent, and everything below it.
+    pub(crate) fn forget(&self, paths: &[&str]) {
+        let mut slots = self.lock();
+        let stale: Vec<Key> = slots
+            .slots
+            .keys()
+            .filter(|key| paths.iter().any(|path| is_stale(key, path)))
+            .cloned()
+            .collect();
+        for key in stale {
+            slots.remove(&key);
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.lock() = Slots::default();
+    }
+
+    fn get(&self, key: Key) -> Option<Cached> {
+        let mut slots = self.lock();
+        let expired = slots.slots.get(&key)?.stored.elapsed() > self.ttl;
+        if expired {
+            slots.remove(&key);
+            return None;
+        }
+        slots.clock += 1;
+        let now = slots.clock;
+        let slot = slots.slots.get_mut(&key)?;
+        let previous = std::mem::replace(&mut slot.used, now);
+        let value = slot.value.clone();
+        slots.order.remove(&previous);
+        slots.order.insert(now, key);
+        Some(value)
+    }
+
+    fn put(&self, key: Key, value: Cached) {
+        let mut slots = self.lock();
+        slots.remove(&key);
+        while slots.slots.len() >= self.capacity {
+            let Some((_, oldest)) = slots.order.pop_first() else {
+                break;
+            };
+            slots.slots.remove(&oldest);
+        }
+        slots.clock += 1;
+        let used = slots.clock;
+        slots.order.insert(used, key.clone());
+        slots.slots.insert(
+            key,
+            Slot {
+                value,
+                stored: Instant::now(),
+                used,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Slots> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for EntryCache {
+    fn drop(&mut self) {
+        if let Some(task) = self
+            .live
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+        {
+            task.abort();
+        }
+    }
+}
+
+impl Slots {
+    fn remove(&mut self, key: &Key) {
+        if let Some(slot) = self.slots.remove(key) {
+            self.order.remove(&slot.used);
+        }
+    }
+}
+
+fn is_stale(key: &Key, changed: &str) -> bool {
+    let below = |path: &str| changed == "/" || path.starts_with(&format!("{}/", changed));
+    match key {
+        Key::Entry(path) => {
+            path == changed || below(path) || changed.starts_with(&format!("{}/", path))
+        }
+        Key::Listing(path) => {
+            path == changed
+                || below(path)
+                || parent_path(changed).is_some_and(|parent| parent == *path)
+        }
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Invalidate cached state around `paths` after a change; a no-op without a cache.
+    pub(crate) fn forget(&self, paths: &[&str]) {
+        if let Some(cache) = &self.cache {
+            cache.forget(paths);
+        }
+    }
+
+    /// Invalidate the whole cache, for changes too broad to track path by path.
+    pub(crate) fn forget_all(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Keep the cache coherent with changes made by other processes: subscribe to a `LIVE
+    /// SELECT` on the table and invalidate what each created, updated, or deleted row
+    /// touches as the notification arrives, instead of waiting out the TTL. Runs on a
+    /// background tokio task until the cache is dropped or this is called again, as
+    /// `use_ns_db` does. A no-op without a cache. The TTL still bounds staleness while a
+    /// notification is in flight or if the subscription is lost.
+    pub async fn subscribe_cache(&self) -> Result<()> {
+        let Some(cache) = &self.cache else {
+            return Ok(());
+        };
+        let mut stream = self
+            .db
+            .select::<Vec<Changed>>(self.table.as_str())
+            .live()
+            .await?;
+        let weak = Arc::downgrade(cache);
+        let task = tokio::spawn(async move {
+            while let Some(notification) = stream.next().await {
+                let Some(cache) = weak.upgrade() else {
+                    break;
+                };
+                match notification {
+                    Ok(notification) => cache.forget(&[&notification.data.path]),
+                    // A row that cannot be read back could be anything.
+                    Err(_) => cache.clear(),
+                }
+            }
+            // Whatever changed while nothing was listening is unknown.
+            if let Some(cache) = weak.upgrade() {
+                cache.clear();
+            }
+        });
+        cache.set_live(task.abort_handle());
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use surrealdb::Connection;
+
+use crate::{Entry, FsError, Result, SurrealFs, normalize_path};
+
+/// Outcome of `SurrealFs::verify`: the checksum stored at the last write against a fresh hash
+/// of the stored content.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Verification {
+    pub path: String,
+    /// `None` for files written before checksums were stored.
+    pub expected: Option<String>,
+    pub actual: String,
+}
+
+impl Verification {
+    /// The content no longer hashes to what was recorded when it was written.
+    pub fn is_corrupt(&self) -> bool {
+        self.expected.as_ref().is_some_and(|e| *e != self.actual)
+    }
+}
+
+/// Lowercase hex SHA-256 of `bytes`.
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// The checksum to store for `entry`; directories and symlinks have none.
+pub(crate) fn of_entry(entry: &Entry) -> Option<String> {
+    if entry.is_dir || entry.symlink.is_some() {
+        return None;
+    }
+    Some(sha256_hex(&entry.bytes().unwrap_or_default()))
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Re-hash the content of the file at `path` and compare it with the checksum stored
+    /// when it was last written, following a trailing symlink.
+    pub async fn verify(&self, path: impl AsRef<str>) -> Result<Verification> {
+        let path = normalize_path(path.as_ref())?;
+        let (path, entry) = self.follow(path).await?;
+        let entry = entry.ok_or_else(|| FsError::NotFound(path.clone()))?;
+        if entry.is_dir {
+            return Err(FsError::NotAFile(path));
+        }
+        Ok(Verification {
+            actual: sha256_hex(&entry.bytes().unwrap_or_default()),
+            expected: entry.checksum,
+            path,
+        })
+    }
+}
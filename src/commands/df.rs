@@ -0,0 +1,44 @@
+use surrealdb::Connection;
+
+use surrealfs::FsError;
+
+use super::ReplState;
+use super::util::{help_error, human_size};
+
+pub async fn run<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let human = match args {
+        [] => false,
+        ["-h"] => true,
+        _ => return Err(help_error()),
+    };
+
+    let usage = state.fs.df().await?;
+    let fmt = |bytes: usize| {
+        if human {
+            let (val, unit) = human_size(bytes as f64);
+            format!("{:.1}{}", val, unit)
+        } else {
+            bytes.to_string()
+        }
+    };
+
+    println!("table: {}", usage.table);
+    println!(
+        "entries: {} ({} files, {} directories)",
+        usage.entries, usage.files, usage.directories
+    );
+    println!("used: {}", fmt(usage.bytes));
+    match (usage.quota_bytes, usage.remaining_bytes) {
+        (Some(limit), Some(remaining)) => {
+            println!("quota: {} ({} remaining)", fmt(limit), fmt(remaining))
+        }
+        _ => println!("quota: none"),
+    }
+    for dir in &usage.top_level {
+        println!("{:>10} {:>6} {}", fmt(dir.bytes), dir.entries, dir.path);
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use surrealdb::Connection;
+
+use surrealfs::FsError;
+
+use super::ReplState;
+use super::util::{help_error, human_size, resolve_cli_path};
+
+pub async fn run<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let (human, target) = match args {
+        [] => (false, None),
+        ["-h"] => (true, None),
+        ["-h", path] => (true, Some(*path)),
+        [path] => (false, Some(*path)),
+        _ => return Err(help_error()),
+    };
+    let path = match target {
+        Some(p) => resolve_cli_path(&state.cwd, p),
+        None => state.cwd.clone(),
+    };
+
+    let usage = state.fs.du(&path).await?;
+    let fmt = |bytes: usize| {
+        if human {
+            let (val, unit) = human_size(bytes as f64);
+            format!("{:.1}{}", val, unit)
+        } else {
+            bytes.to_string()
+        }
+    };
+
+    for child in &usage.children {
+        println!("{:>10}  {}", fmt(child.bytes), child.path);
+    }
+    println!("{:>10}  {}", fmt(usage.bytes), usage.path);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use surrealdb::Connection;
+
+use surrealfs::{EntryKind, FindOptions, FsError};
+
+use super::ReplState;
+use super::util::{help_error, resolve_cli_path};
+
+pub async fn run<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let mut opts = FindOptions::default();
+    let mut target = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).copied();
+        match (args[i], value) {
+            ("-name", Some(pattern)) => opts.name = Some(pattern.to_string()),
+            ("-type", Some("f")) => opts.kind = Some(EntryKind::File),
+            ("-type", Some("d")) => opts.kind = Some(EntryKind::Dir),
+            ("-size", Some(size)) => (opts.min_size, opts.max_size) = parse_size(size)?,
+            ("-since", Some(millis)) => {
+                opts.modified_since = Some(millis.parse::<i64>().map_err(|_| help_error())?)
+            }
+            (arg, _) if !arg.starts_with('-') && target.is_none() => {
+                target = Some(arg);
+                i += 1;
+                continue;
+            }
+            _ => return Err(help_error()),
+        }
+        i += 2;
+    }
+
+    let path = match target {
+        Some(p) => resolve_cli_path(&state.cwd, p),
+        None => state.cwd.clone(),
+    };
+    for entry in state.fs.find(&path, &opts).await? {
+        let suffix = if entry.is_dir && entry.path != "/" {
+            "/"
+        } else {
+            ""
+        };
+        println!("{}{}", entry.path, suffix);
+    }
+    Ok(())
+}
+
+/// Size bounds for `-size`: `+N` means larger than N bytes, `-N` smaller than N, plain `N`
+/// exactly N.
+pub(super) fn parse_size(input: &str) -> Result<(Option<usize>, Option<usize>), FsError> {
+    let parse = |s: &str| s.parse::<usize>().map_err(|_| help_error());
+    if let Some(rest) = input.strip_prefix('+') {
+        Ok((Some(parse(rest)? + 1), None))
+    } else if let Some(rest) = input.strip_prefix('-') {
+        Ok((None, Some(parse(rest)?.saturating_sub(1))))
+    } else {
+        let exact = parse(input)?;
+        Ok((Some(exact), Some(exact)))
+    }
+}
@@ -1,13 +1,17 @@
 use std::path::PathBuf;
+use std::pin::pin;
 
-use regex::Regex;
+use futures::TryStreamExt;
 use surrealdb::Connection;
 use tokio::{fs, fs::OpenOptions, io::AsyncWriteExt};
 
-use surrealfs::FsError;
+use surrealfs::{
+    CopyOptions, EntryKind, FsError, GlobOptions, GrepOptions, SortOptions, SplitBy, format_mode,
+};
 
 use super::ReplState;
-use super::util::{help_error, resolve_cli_path};
+use super::find::parse_size;
+use super::util::{format_timestamp, help_error, resolve_cli_path};
 
 pub async fn cat<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
 where
@@ -87,53 +91,602 @@ where
     }
 }
 
+pub async fn wc<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if args.is_empty() {
+        return Err(help_error());
+    }
+    for path in args {
+        let path = resolve_cli_path(&state.cwd, path);
+        let counts = state.fs.wc(&path).await?;
+        println!(
+            "{:>7} {:>7} {:>7} {}",
+            counts.lines, counts.words, counts.bytes, path
+        );
+    }
+    Ok(())
+}
+
+pub async fn sort<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let mut opts = SortOptions::default();
+    let mut paths = Vec::new();
+    for arg in args {
+        match *arg {
+            "-r" => opts.reverse = true,
+            "-n" => opts.numeric = true,
+            "-rn" | "-nr" => {
+                opts.reverse = true;
+                opts.numeric = true;
+            }
+            _ => paths.push(*arg),
+        }
+    }
+
+    match paths.as_slice() {
+        [path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            for line in state.fs.sort(&path, &opts).await? {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn uniq<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let count = args.contains(&"-c");
+    let paths: Vec<&str> = args.iter().copied().filter(|a| *a != "-c").collect();
+
+    match paths.as_slice() {
+        [path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            for run in state.fs.uniq(&path).await? {
+                if count {
+                    println!("{:>7} {}", run.count, run.line);
+                } else {
+                    println!("{}", run.line);
+                }
+            }
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn diff<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        [a, b] => {
+            let a = resolve_cli_path(&state.cwd, a);
+            let b = resolve_cli_path(&state.cwd, b);
+            print!("{}", state.fs.diff(&a, &b).await?);
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
 pub async fn grep<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
 where
     DB: Connection,
 {
-    if args.len() < 2 {
-        Err(help_error())
+    let mut opts = GrepOptions::default();
+    let (mut count, mut files_only) = (false, false);
+    let limit = |n: Option<&&str>| n.and_then(|n| n.parse().ok()).ok_or_else(help_error);
+    let mut operands = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "-r" | "--recursive" => opts.recursive = true,
+            "-i" => opts.ignore_case = true,
+            "-w" => opts.word = true,
+            "-v" => opts.invert = true,
+            "-F" => opts.fixed_strings = true,
+            "-U" | "--multiline" => opts.multiline = true,
+            "-c" => count = true,
+            "-l" => files_only = true,
+            "-m" => opts.max_matches = Some(limit(args.next())?),
+            "--max-files" => opts.max_files = Some(limit(args.next())?),
+            _ => operands.push(*arg),
+        }
+    }
+    let [pattern, path] = operands[..] else {
+        return Err(help_error());
+    };
+    opts.pattern = pattern.to_string();
+    let path = resolve_cli_path(&state.cwd, path);
+    let result = if files_only {
+        state.fs.grep_files(&path, &opts).await.map(|paths| {
+            for p in paths {
+                println!("{}", p);
+            }
+        })
+    } else if count {
+        state.fs.grep_count(&path, &opts).await.map(|counts| {
+            for c in counts {
+                println!("{}:{}", c.path, c.count);
+            }
+        })
     } else {
-        let recursive = args.contains(&"-r") || args.contains(&"--recursive");
-        let pattern = args[0];
-        let path = resolve_cli_path(&state.cwd, args[1]);
-        match Regex::new(pattern) {
-            Ok(re) => state.fs.grep(&re, &path, recursive).await.map(|matches| {
-                for m in matches {
+        // Print matches as they are found rather than after the whole search.
+        let mut matches = pin!(state.fs.grep_stream(&path, &opts));
+        async {
+            while let Some(m) = matches.try_next().await? {
+                if m.binary {
+                    println!("grep: {}: binary file matches", m.path);
+                } else if m.end_line == m.line_number {
                     println!("{}:{}: {}", m.path, m.line_number, m.line);
+                } else {
+                    println!("{}:{}-{}: {}", m.path, m.line_number, m.end_line, m.line);
                 }
-            }),
-            Err(e) => {
-                println!("Invalid regex: {}", e);
-                Ok(())
             }
+            Ok(())
+        }
+        .await
+    };
+    match result {
+        Err(FsError::InvalidPattern(e)) => {
+            println!("Invalid regex: {}", e);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// `replace [-n] [-r] [-i] [-w] [-F] [-U] <pattern> <replacement> <path>`: sed-style
+/// find-and-replace across files, printing each file's diff. `-n` previews without writing.
+pub async fn replace<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let mut opts = GrepOptions::default();
+    let mut dry_run = false;
+    let mut operands = Vec::new();
+    for arg in args {
+        match *arg {
+            "-n" => dry_run = true,
+            "-r" | "--recursive" => opts.recursive = true,
+            "-i" => opts.ignore_case = true,
+            "-w" => opts.word = true,
+            "-F" => opts.fixed_strings = true,
+            "-U" | "--multiline" => opts.multiline = true,
+            _ => operands.push(*arg),
+        }
+    }
+    let [pattern, replacement, path] = operands[..] else {
+        return Err(help_error());
+    };
+    opts.pattern = unquote(pattern);
+    let path = resolve_cli_path(&state.cwd, path);
+    match state
+        .fs
+        .replace_in(&opts, &unquote(replacement), &path, dry_run)
+        .await
+    {
+        Ok(changed) => {
+            for file in &changed {
+                print!("{}", file.diff);
+            }
+            let total: usize = changed.iter().map(|file| file.replacements).sum();
+            println!("{} replacements in {} files", total, changed.len());
+            Ok(())
+        }
+        Err(FsError::InvalidPattern(e)) => {
+            println!("Invalid regex: {}", e);
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// `search <path> <terms...>`: ranked full-text search, one `path: snippet` line per file
+/// with matched terms in brackets.
+pub async fn search<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let [path, terms @ ..] = args else {
+        return Err(help_error());
+    };
+    if terms.is_empty() {
+        return Err(help_error());
+    }
+    let path = resolve_cli_path(&state.cwd, path);
+    for hit in state.fs.search(&unquote(&terms.join(" ")), &path).await? {
+        println!("{}: {}", hit.path, hit.highlighted("[", "]"));
+    }
+    Ok(())
+}
+
+/// `symbols [query]`: definitions whose name contains the query, as `path:line: kind name`.
+pub async fn symbols<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if args.len() > 1 {
+        return Err(help_error());
+    }
+    let query = args.first().copied().unwrap_or_default();
+    for symbol in state.fs.symbols(query).await? {
+        println!(
+            "{}:{}: {} {}",
+            symbol.path,
+            symbol.line,
+            symbol.kind.as_str(),
+            symbol.name
+        );
+    }
+    Ok(())
+}
+
+/// `recent [-n <limit>] [path]`: the most recently modified children, newest first.
+pub async fn recent<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let (limit, path) = match args {
+        ["-n", n, rest @ ..] => (n.parse().map_err(|_| help_error())?, rest),
+        rest => (10, rest),
+    };
+    let path = match path {
+        [] => state.cwd.clone(),
+        [path] => resolve_cli_path(&state.cwd, path),
+        _ => return Err(help_error()),
+    };
+    for node in state.fs.ls_by_mtime(&path, limit).await? {
+        println!(
+            "{}  {}",
+            format_timestamp(node.attrs().updated_at),
+            node.path()
+        );
+    }
+    Ok(())
+}
+
+/// `changed <since-millis> [path]`: everything modified at or after the time, newest first.
+pub async fn changed<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let (since, path) = match args {
+        [since] => (since, state.cwd.clone()),
+        [since, path] => (since, resolve_cli_path(&state.cwd, path)),
+        _ => return Err(help_error()),
+    };
+    let since = since.parse().map_err(|_| help_error())?;
+    for meta in state.fs.modified_since(&path, since).await? {
+        println!("{}  {}", format_timestamp(meta.updated_at), meta.path);
+    }
+    Ok(())
+}
+
+/// `history <path>`: the kept revisions of a file, newest first; `history <path> <version>`
+/// prints one of them.
+pub async fn history<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        [path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            for revision in state.fs.history(&path).await? {
+                println!(
+                    "{:>6}  {:>8}  {}",
+                    revision.version,
+                    revision.size,
+                    format_timestamp(revision.updated_at)
+                );
+            }
+            Ok(())
+        }
+        [path, version] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            let version = version.parse().map_err(|_| help_error())?;
+            print!("{}", state.fs.cat_revision(&path, version).await?);
+            Ok(())
         }
+        _ => Err(help_error()),
     }
 }
 
+/// `locate <fragment>`: paths of entries whose name contains the fragment, ignoring case.
+pub async fn locate<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if args.is_empty() {
+        return Err(help_error());
+    }
+    for path in state.fs.locate(&args.join(" ")).await? {
+        println!("{}", path);
+    }
+    Ok(())
+}
+
+/// `glob [-type f|d] [-size [+|-]N] [-since <millis>] [-l] <pattern>...`: paths matching any
+/// pattern, less those matching a `!pattern`, filtered as `find` filters. `-l` adds each
+/// entry's size and modification time.
 pub async fn glob<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let mut opts = GlobOptions::default();
+    let mut long = false;
+    let mut patterns = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (*arg, args.as_slice().first().copied()) {
+            ("-type", Some("f")) => opts.kind = Some(EntryKind::File),
+            ("-type", Some("d")) => opts.kind = Some(EntryKind::Dir),
+            ("-size", Some(size)) => (opts.min_size, opts.max_size) = parse_size(size)?,
+            ("-since", Some(millis)) => {
+                opts.modified_since = Some(millis.parse::<i64>().map_err(|_| help_error())?)
+            }
+            ("-l", _) => {
+                long = true;
+                continue;
+            }
+            (arg, _) if !arg.starts_with('-') => {
+                patterns.push(match arg.strip_prefix('!') {
+                    Some(negated) => format!("!{}", resolve_cli_path(&state.cwd, negated)),
+                    None => resolve_cli_path(&state.cwd, arg),
+                });
+                continue;
+            }
+            _ => return Err(help_error()),
+        }
+        args.next();
+    }
+    if patterns.is_empty() {
+        return Err(help_error());
+    }
+    if !long {
+        for p in state.fs.glob_with(&patterns, &opts).await? {
+            println!("{}", p);
+        }
+        return Ok(());
+    }
+    for entry in state.fs.glob_entries(&patterns, &opts).await? {
+        println!(
+            "{:>8} {} {}",
+            entry.size(),
+            format_timestamp(entry.updated_at),
+            entry.path
+        );
+    }
+    Ok(())
+}
+
+/// Describe an entry without following a trailing symlink, like `stat` without `-L`.
+pub async fn stat<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let [path] = args else {
+        return Err(help_error());
+    };
+    let path = resolve_cli_path(&state.cwd, path);
+    let meta = state
+        .fs
+        .symlink_metadata(&path)
+        .await?
+        .ok_or(FsError::NotFound(path))?;
+
+    let kind = match &meta.symlink {
+        Some(target) => format!("symbolic link -> {}", target),
+        None if meta.is_dir => "directory".into(),
+        None => "regular file".into(),
+    };
+    println!("    File: {}", meta.path);
+    println!("    Type: {}", kind);
+    println!("    Size: {}", meta.size);
+    if !meta.is_dir && !meta.is_symlink() {
+        println!(" Version: {}", meta.version);
+    }
+    println!(
+        "  Access: ({:04o}/{})",
+        meta.permissions(),
+        format_mode(meta.is_dir, meta.is_symlink(), meta.permissions())
+    );
+    if let Some(content_type) = &meta.content_type {
+        println!(" Content: {}", content_type);
+    }
+    if let Some(encoding) = &meta.encoding {
+        println!("Encoding: {}", encoding);
+    }
+    if let Some(checksum) = &meta.checksum {
+        println!("  SHA256: {}", checksum);
+    }
+    println!(
+        "   Owner: {}:{}",
+        meta.owner.as_deref().unwrap_or("-"),
+        meta.group.as_deref().unwrap_or("-")
+    );
+    if let Some(inode) = &meta.inode {
+        println!("   Inode: {}", inode);
+    }
+    println!(" Created: {}", format_timestamp(meta.created_at));
+    println!("Modified: {}", format_timestamp(meta.updated_at));
+    if meta.accessed_at.is_some() {
+        println!("Accessed: {}", format_timestamp(meta.accessed_at));
+    }
+    if let Some(expires_at) = meta.expires_at {
+        println!(" Expires: {}", format_timestamp(Some(expires_at)));
+    }
+    let flags: Vec<&str> = [(meta.immutable, "immutable"), (meta.hidden, "hidden")]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect();
+    if !flags.is_empty() {
+        println!("   Flags: {}", flags.join(", "));
+    }
+    Ok(())
+}
+
+/// `file <path>...`: print a file(1)-style description of each path.
+pub async fn file<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if args.is_empty() {
+        return Err(help_error());
+    }
+    for path in args {
+        let resolved = resolve_cli_path(&state.cwd, path);
+        let description = state.fs.file_type(&resolved).await?;
+        println!("{}: {}", path, description);
+    }
+    Ok(())
+}
+
+/// Set permission bits from an octal mode such as `644`.
+pub async fn chmod<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let [mode, path] = args else {
+        return Err(help_error());
+    };
+    let mode = u32::from_str_radix(mode, 8).map_err(|_| help_error())?;
+    state
+        .fs
+        .chmod(&resolve_cli_path(&state.cwd, path), mode)
+        .await
+}
+
+/// `chattr +i|-i path` toggles the immutable flag and `chattr +h|-h path` the hidden flag.
+pub async fn chattr<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let [flag, path] = args else {
+        return Err(help_error());
+    };
+    let path = resolve_cli_path(&state.cwd, path);
+    match *flag {
+        "+i" => state.fs.set_immutable(&path, true).await,
+        "-i" => state.fs.set_immutable(&path, false).await,
+        "+h" => state.fs.set_hidden(&path, true).await,
+        "-h" => state.fs.set_hidden(&path, false).await,
+        _ => Err(help_error()),
+    }
+}
+
+/// `chown owner[:group] path`, as in coreutils.
+pub async fn chown<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let [spec, path] = args else {
+        return Err(help_error());
+    };
+    let (owner, group) = match spec.split_once(':') {
+        Some((owner, group)) => (owner, Some(group)),
+        None => (*spec, None),
+    };
+    state
+        .fs
+        .chown(&resolve_cli_path(&state.cwd, path), owner, group)
+        .await
+}
+
+pub async fn chgrp<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let [group, path] = args else {
+        return Err(help_error());
+    };
+    state
+        .fs
+        .chgrp(&resolve_cli_path(&state.cwd, path), *group)
+        .await
+}
+
+/// `xattr <path>` lists attributes, `xattr -w <name> <value> <path>` sets one and
+/// `xattr -d <name> <path>` removes one, as in the macOS tool.
+pub async fn xattr<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
 where
     DB: Connection,
 {
     match args {
-        [pattern] => {
-            let pattern = resolve_cli_path(&state.cwd, pattern);
-            state.fs.glob(&pattern).await.map(|paths| {
-                for p in paths {
-                    println!("{}", p);
-                }
-            })
+        ["-w", name, value, path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            state.fs.setxattr(&path, *name, unquote(value)).await
+        }
+        ["-d", name, path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            state.fs.removexattr(&path, name).await.map(|_| ())
+        }
+        [path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            let meta = state
+                .fs
+                .metadata(&path)
+                .await?
+                .ok_or(FsError::NotFound(path))?;
+            for (name, value) in meta.xattrs {
+                println!("{}: {}", name, value);
+            }
+            Ok(())
         }
         _ => Err(help_error()),
     }
 }
 
+pub async fn verify<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if args.is_empty() {
+        return Err(help_error());
+    }
+    for path in args {
+        let report = state.fs.verify(&resolve_cli_path(&state.cwd, path)).await?;
+        let status = match &report.expected {
+            None => "UNCHECKED",
+            Some(_) if report.is_corrupt() => "CORRUPT",
+            Some(_) => "OK",
+        };
+        println!("{}: {}", report.path, status);
+    }
+    Ok(())
+}
+
+pub async fn expire<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if !args.is_empty() {
+        return Err(help_error());
+    }
+    for path in state.fs.expire().await? {
+        println!("removed {}", path);
+    }
+    Ok(())
+}
+
 pub async fn touch<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
 where
     DB: Connection,
 {
     match args {
-        [path] => state.fs.touch(&resolve_cli_path(&state.cwd, path)).await,
+        [path] => state
+            .fs
+            .touch(&resolve_cli_path(&state.cwd, path))
+            .await
+            .map(|_| ()),
         _ => Err(help_error()),
     }
 }
@@ -142,6 +695,10 @@ pub async fn edit<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), Fs
 where
     DB: Connection,
 {
+    let (preview, args) = match args.split_first() {
+        Some((&"-n", rest)) => (true, rest),
+        _ => (false, args),
+    };
     if args.len() < 3 {
         return Err(help_error());
     }
@@ -166,7 +723,7 @@ where
 
     state
         .fs
-        .edit(&path, old.as_str(), new.as_str(), replace_all)
+        .edit(&path, old.as_str(), new.as_str(), replace_all, preview)
         .await
         .map(|diff| {
             if !diff.is_empty() {
@@ -189,6 +746,203 @@ fn unquote(input: &str) -> String {
     input.to_string()
 }
 
+pub async fn rm<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        ["-r", path] | ["-R", path] => {
+            let path = resolve_cli_path(&state.cwd, path);
+            match state.fs.rm(&path).await {
+                Err(FsError::NotAFile(_)) => state.fs.rmdir(&path, true).await,
+                other => other,
+            }
+        }
+        ["-n", pattern] => {
+            let pattern = resolve_cli_path(&state.cwd, pattern);
+            for path in state.fs.rm_glob(&pattern, true).await? {
+                println!("would remove {}", path);
+            }
+            Ok(())
+        }
+        [pattern] if is_glob(pattern) => {
+            let pattern = resolve_cli_path(&state.cwd, pattern);
+            state.fs.rm_glob(&pattern, false).await.map(|_| ())
+        }
+        [path] => state.fs.rm(&resolve_cli_path(&state.cwd, path)).await,
+        _ => Err(help_error()),
+    }
+}
+
+fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?', '[', '{'])
+}
+
+pub async fn rmdir<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        ["-r", path] | ["-R", path] => {
+            state
+                .fs
+                .rmdir(&resolve_cli_path(&state.cwd, path), true)
+                .await
+        }
+        [path] => {
+            state
+                .fs
+                .rmdir(&resolve_cli_path(&state.cwd, path), false)
+                .await
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn trash<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if !args.is_empty() {
+        return Err(help_error());
+    }
+    for item in state.fs.list_trash().await? {
+        println!(
+            "{:>16} {} -> {}",
+            format_timestamp(Some(item.trashed_at)),
+            item.original,
+            item.path
+        );
+    }
+    Ok(())
+}
+
+pub async fn restore<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        [path] => {
+            let restored = state
+                .fs
+                .restore(&resolve_cli_path(&state.cwd, path))
+                .await?;
+            println!("restored {}", restored);
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn empty_trash<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if !args.is_empty() {
+        return Err(help_error());
+    }
+    let removed = state.fs.empty_trash().await?;
+    println!("removed {} item(s)", removed);
+    Ok(())
+}
+
+pub async fn vacuum<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    if !args.is_empty() {
+        return Err(help_error());
+    }
+    let report = state.fs.vacuum().await?;
+    println!(
+        "removed {} orphaned, {} expired, {} trashed entries; reclaimed {} bytes",
+        report.orphaned_entries, report.expired_files, report.trash_entries, report.reclaimed_bytes
+    );
+    Ok(())
+}
+
+pub async fn fsck<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let repair = match args {
+        [] => false,
+        ["-r"] => true,
+        _ => return Err(help_error()),
+    };
+    println!("{}", state.fs.fsck(repair).await?.render());
+    Ok(())
+}
+
+pub async fn mv<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let overwrite = args.contains(&"-f");
+    let targets: Vec<&str> = args.iter().copied().filter(|a| *a != "-f").collect();
+
+    match targets.as_slice() {
+        [src, dest] => {
+            let src = resolve_cli_path(&state.cwd, src);
+            let dest = resolve_cli_path(&state.cwd, dest);
+            state.fs.mv(&src, &dest, overwrite).await
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn ln<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        ["-s", target, dest] => {
+            let dest = resolve_cli_path(&state.cwd, dest);
+            state.fs.symlink(target, &dest).await
+        }
+        [src, dest] => {
+            let src = resolve_cli_path(&state.cwd, src);
+            let dest = resolve_cli_path(&state.cwd, dest);
+            state.fs.link(&src, &dest).await
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn readlink<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        [path] => {
+            let target = state
+                .fs
+                .readlink(&resolve_cli_path(&state.cwd, path))
+                .await?;
+            println!("{}", target);
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn realpath<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        [path] => {
+            let real = state
+                .fs
+                .realpath(&resolve_cli_path(&state.cwd, path))
+                .await?;
+            println!("{}", real);
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
 pub async fn mkdir<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
 where
     DB: Connection,
@@ -204,12 +958,11 @@ where
     }
 
     match targets.as_slice() {
-        [path] => {
-            state
-                .fs
-                .mkdir(&resolve_cli_path(&state.cwd, path), parents)
-                .await
-        }
+        [path] => state
+            .fs
+            .mkdir(&resolve_cli_path(&state.cwd, path), parents)
+            .await
+            .map(|_| ()),
         _ => Err(help_error()),
     }
 }
@@ -223,15 +976,64 @@ where
     } else {
         let path = resolve_cli_path(&state.cwd, args[0]);
         let content = args[1..].join(" ");
-        state.fs.write_file(&path, content).await
+        state.fs.write_file(&path, content).await.map(|_| ())
     }
 }
 
-pub async fn cp<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+pub async fn split<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let (by, path) = match args {
+        [flag, n, path] => {
+            let n = n.parse::<usize>().map_err(|_| help_error())?;
+            match *flag {
+                "-l" => (SplitBy::Lines(n), path),
+                "-b" => (SplitBy::Bytes(n), path),
+                _ => return Err(help_error()),
+            }
+        }
+        _ => return Err(help_error()),
+    };
+    for part in state
+        .fs
+        .split(&resolve_cli_path(&state.cwd, path), by)
+        .await?
+    {
+        println!("{}", part);
+    }
+    Ok(())
+}
+
+pub async fn join<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
 where
     DB: Connection,
 {
     match args {
+        [path] => state
+            .fs
+            .join(&resolve_cli_path(&state.cwd, path))
+            .await
+            .map(|_| ()),
+        _ => Err(help_error()),
+    }
+}
+
+pub async fn cp<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let opts = CopyOptions {
+        no_clobber: args.contains(&"-n"),
+        preserve: args.contains(&"-p"),
+    };
+    let args: Vec<&str> = args
+        .iter()
+        .copied()
+        .filter(|a| !matches!(*a, "-n" | "-p"))
+        .collect();
+
+    match args.as_slice() {
         [src, dest] => {
             let src_is_host = src.starts_with("host:");
             let dest_is_host = dest.starts_with("host:");
@@ -246,19 +1048,19 @@ where
                     .await
                     .map_err(|e| FsError::Http(format!("read host {}: {}", host_path, e)))?;
                 let dest = resolve_cli_path(&state.cwd, dest);
-                state.fs.write_bytes(&dest, data).await
+                state.fs.write_bytes(&dest, data).await.map(|_| ())
             } else if dest_is_host {
                 let src = resolve_cli_path(&state.cwd, src);
-                let bytes = state.fs.cat_bytes(&src).await?;
+                let bytes = state.fs.export_bytes(&src).await?;
                 let host_path = &dest[5..];
                 let host_pathbuf = PathBuf::from(host_path);
 
-                if let Some(parent) = host_pathbuf.parent() {
-                    if !parent.as_os_str().is_empty() {
-                        fs::create_dir_all(parent).await.map_err(|e| {
-                            FsError::Http(format!("create host dir {}: {}", parent.display(), e))
-                        })?;
-                    }
+                if let Some(parent) = host_pathbuf.parent()
+                    && !parent.as_os_str().is_empty()
+                {
+                    fs::create_dir_all(parent).await.map_err(|e| {
+                        FsError::Http(format!("create host dir {}: {}", parent.display(), e))
+                    })?;
                 }
 
                 if fs::metadata(&host_pathbuf).await.is_ok() {
@@ -280,8 +1082,19 @@ where
             } else {
                 let src = resolve_cli_path(&state.cwd, src);
                 let dest = resolve_cli_path(&state.cwd, dest);
-                state.fs.cp(&src, &dest).await
+                state.fs.cp(&src, &dest, &opts).await.map(|_| ())
+            }
+        }
+        [srcs @ .., dest_dir] if srcs.len() > 1 => {
+            if args.iter().any(|a| a.starts_with("host:")) {
+                return Err(FsError::InvalidPath);
             }
+            let srcs: Vec<String> = srcs
+                .iter()
+                .map(|src| resolve_cli_path(&state.cwd, src))
+                .collect();
+            let dest_dir = resolve_cli_path(&state.cwd, dest_dir);
+            state.fs.cp_into(&srcs, &dest_dir, &opts).await
         }
         _ => Err(help_error()),
     }
@@ -1,9 +1,12 @@
+use std::pin::pin;
+
+use futures::TryStreamExt;
 use surrealdb::Connection;
 
-use surrealfs::{Entry, FsError, SurrealFs};
+use surrealfs::{FsError, Node, SurrealFs};
 
 use super::ReplState;
-use super::util::resolve_cli_path;
+use super::util::{format_timestamp, human_size, resolve_cli_path};
 
 #[derive(Debug, Clone, Copy)]
 struct LsOptions {
@@ -12,6 +15,8 @@ struct LsOptions {
     recursive: bool,
     dir_only: bool,
     human: bool,
+    /// Show creation time instead of modification time in long listings, as BSD `ls -U`.
+    created: bool,
 }
 
 pub async fn run<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
@@ -34,6 +39,7 @@ fn parse_ls_args<'a>(args: &'a [&str]) -> (LsOptions, Option<&'a str>) {
         recursive: false,
         dir_only: false,
         human: false,
+        created: false,
     };
 
     let mut path: Option<&str> = None;
@@ -47,6 +53,7 @@ fn parse_ls_args<'a>(args: &'a [&str]) -> (LsOptions, Option<&'a str>) {
                     'R' => opts.recursive = true,
                     'd' => opts.dir_only = true,
                     'h' => opts.human = true,
+                    'U' => opts.created = true,
                     _ => {}
                 }
             }
@@ -67,7 +74,7 @@ where
         match fs.ls(path).await {
             Ok(entries) => {
                 for e in entries {
-                    if e.path == path {
+                    if e.path() == path {
                         print_entry(&e, opts);
                     }
                 }
@@ -76,29 +83,21 @@ where
             Err(e) => Err(e),
         }
     } else if opts.recursive {
-        let mut stack = vec![path.to_string()];
-        while let Some(p) = stack.pop() {
-            match fs.ls(&p).await {
-                Ok(entries) => {
-                    for e in entries.iter() {
-                        if !opts.all && e.name.starts_with('.') {
-                            continue;
-                        }
-                        print_entry(e, opts);
-                        if e.is_dir {
-                            stack.push(e.path.clone());
-                        }
-                    }
-                }
-                Err(e) => return Err(e),
+        let mut walk = pin!(fs.walk(path));
+        let mut hidden = HiddenDirs::default();
+        while let Some(e) = walk.try_next().await? {
+            let node = Node::from(e);
+            if !opts.all && hidden.skip(&node) {
+                continue;
             }
+            print_entry(&node, opts);
         }
         Ok(())
     } else {
         match fs.ls(path).await {
             Ok(entries) => {
                 for e in entries {
-                    if !opts.all && e.name.starts_with('.') {
+                    if !opts.all && e.is_hidden() {
                         continue;
                     }
                     print_entry(&e, opts);
@@ -110,32 +109,46 @@ where
     }
 }
 
-fn print_entry(entry: &Entry, opts: LsOptions) {
+/// Tracks hidden directories during a depth-first walk so everything inside them is skipped
+/// along with the directory itself.
+#[derive(Default)]
+struct HiddenDirs {
+    prefixes: Vec<String>,
+}
+
+impl HiddenDirs {
+    fn skip(&mut self, node: &Node) -> bool {
+        if self.prefixes.iter().any(|p| node.path().starts_with(p)) {
+            return true;
+        }
+        if !node.is_hidden() {
+            return false;
+        }
+        if node.is_dir() {
+            self.prefixes.push(format!("{}/", node.path()));
+        }
+        true
+    }
+}
+
+fn print_entry(node: &Node, opts: LsOptions) {
     if opts.long {
-        let kind = if entry.is_dir { 'd' } else { '-' };
-        let size = entry.size();
+        let kind = node.mode_string();
+        let size = node.size();
+        let attrs = node.attrs();
+        let time = format_timestamp(if opts.created {
+            attrs.created_at
+        } else {
+            attrs.updated_at
+        });
         if opts.human {
             let (val, unit) = human_size(size as f64);
-            println!("{} {:>6.1}{} {}", kind, val, unit, entry.path);
+            println!("{} {:>6.1}{} {:>16} {}", kind, val, unit, time, node.path());
         } else {
-            println!("{} {:>8} {}", kind, size, entry.path);
+            println!("{} {:>8} {:>16} {}", kind, size, time, node.path());
         }
     } else {
-        let suffix = if entry.is_dir { "/" } else { "" };
-        println!("{}{}", entry.path, suffix);
-    }
-}
-
-fn human_size(bytes: f64) -> (f64, &'static str) {
-    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
-    if bytes < 1.0 {
-        return (bytes, "B");
-    }
-    let mut value = bytes;
-    let mut idx = 0;
-    while value >= 1024.0 && idx < UNITS.len() - 1 {
-        value /= 1024.0;
-        idx += 1;
+        let suffix = if node.is_dir() { "/" } else { "" };
+        println!("{}{}", node.path(), suffix);
     }
-    (value, UNITS[idx])
 }
@@ -2,9 +2,14 @@ use surrealdb::Connection;
 use surrealfs::SurrealFs;
 
 pub mod curl;
+mod df;
+mod du;
+mod find;
 mod fs_ops;
 mod ls;
 mod shell;
+mod stats;
+mod tree;
 pub mod util;
 
 pub struct ReplState<DB: Connection> {
@@ -37,28 +42,127 @@ where
             .await
             .map(|_| ReplControl::Continue),
         "nl" => fs_ops::nl(args, state).await.map(|_| ReplControl::Continue),
+        "wc" => fs_ops::wc(args, state).await.map(|_| ReplControl::Continue),
+        "sort" => fs_ops::sort(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "uniq" => fs_ops::uniq(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "diff" => fs_ops::diff(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
         "grep" => fs_ops::grep(args, state)
             .await
             .map(|_| ReplControl::Continue),
+        "search" => fs_ops::search(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "stat" => fs_ops::stat(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "file" => fs_ops::file(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "chattr" => fs_ops::chattr(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "chmod" => fs_ops::chmod(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "chown" => fs_ops::chown(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "chgrp" => fs_ops::chgrp(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "xattr" => fs_ops::xattr(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "verify" => fs_ops::verify(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "expire" => fs_ops::expire(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
         "touch" => fs_ops::touch(args, state)
             .await
             .map(|_| ReplControl::Continue),
         "edit" => fs_ops::edit(args, state)
             .await
             .map(|_| ReplControl::Continue),
+        "replace" => fs_ops::replace(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
         "mkdir" => fs_ops::mkdir(args, state)
             .await
             .map(|_| ReplControl::Continue),
         "write_file" => fs_ops::write_file(args, state)
             .await
             .map(|_| ReplControl::Continue),
+        "rm" => fs_ops::rm(args, state).await.map(|_| ReplControl::Continue),
+        "trash" => fs_ops::trash(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "restore" => fs_ops::restore(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "empty_trash" => fs_ops::empty_trash(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "vacuum" => fs_ops::vacuum(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "fsck" => fs_ops::fsck(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "rmdir" => fs_ops::rmdir(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "mv" => fs_ops::mv(args, state).await.map(|_| ReplControl::Continue),
         "cp" => fs_ops::cp(args, state).await.map(|_| ReplControl::Continue),
+        "split" => fs_ops::split(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "join" => fs_ops::join(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "ln" => fs_ops::ln(args, state).await.map(|_| ReplControl::Continue),
+        "readlink" => fs_ops::readlink(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "realpath" => fs_ops::realpath(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "symbols" => fs_ops::symbols(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "recent" => fs_ops::recent(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "changed" => fs_ops::changed(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "history" => fs_ops::history(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
+        "locate" => fs_ops::locate(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
         "glob" => fs_ops::glob(args, state)
             .await
             .map(|_| ReplControl::Continue),
         "curl" => curl::run(args, state).await.map(|_| ReplControl::Continue),
+        "find" => find::run(args, state).await.map(|_| ReplControl::Continue),
+        "tree" => tree::run(args, state).await.map(|_| ReplControl::Continue),
+        "du" => du::run(args, state).await.map(|_| ReplControl::Continue),
+        "df" => df::run(args, state).await.map(|_| ReplControl::Continue),
+        "stats" => stats::run(args, state).await.map(|_| ReplControl::Continue),
         "pwd" => shell::pwd(state).map(|_| ReplControl::Continue),
         "cd" => shell::cd(args, state).await.map(|_| ReplControl::Continue),
+        "use" => shell::use_ns_db(args, state)
+            .await
+            .map(|_| ReplControl::Continue),
         "help" => {
             shell::print_help();
             Ok(ReplControl::Continue)
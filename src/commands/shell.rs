@@ -32,27 +32,95 @@ where
     }
 }
 
+pub async fn use_ns_db<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    match args {
+        [] => {
+            let status = state.fs.status().await?;
+            println!(
+                "namespace: {}, database: {}",
+                status.namespace.unwrap_or_default(),
+                status.database.unwrap_or_default()
+            );
+            Ok(())
+        }
+        [ns, db] => {
+            state.fs.use_ns_db(ns, db).await?;
+            state.cwd = String::from("/");
+            Ok(())
+        }
+        _ => Err(help_error()),
+    }
+}
+
 pub fn print_help() {
     println!("Commands:");
     println!("  ls [options] [path]");
-    println!("     options: -l (long), -a (all), -R (recursive), -d (dir only), -h (human sizes)");
+    println!("     options: -l (long), -a (all), -R (recursive), -d (dir only), -h (human sizes),");
+    println!("              -U (creation time in long listings)");
     println!("  cat <path>");
+    println!("     /proc/stats, /proc/connections, /proc/quota, /proc/changes/tail are virtual");
     println!("  tail [n] <path>");
     println!("  read <path> <offset> <limit>");
     println!("  nl <path> [start]");
-    println!("  grep [-r|--recursive] <pattern> <path>");
-    println!("  glob <pattern>");
+    println!("  wc <path>...");
+    println!("  sort [-r] [-n] <path>");
+    println!("  uniq [-c] <path>");
+    println!("  diff <a> <b>");
+    println!(
+        "  grep [-r|--recursive] [-i] [-w] [-v] [-F] [-U] [-c|-l] [-m <n>] [--max-files <n>] <pattern> <path>"
+    );
+    println!("  search <path> <terms...>");
+    println!("  symbols [query]");
+    println!("  recent [-n <limit>] [path]");
+    println!("  changed <since-millis> [path]");
+    println!("  history <path> [version]");
+    println!("  locate <fragment>");
+    println!("  glob [-type f|d] [-size [+|-]N] [-since <millis>] [-l] <pattern> [!<exclude>]...");
+    println!("  find [path] [-name <glob>] [-type f|d] [-size [+|-]N] [-since <millis>]");
+    println!("  stat <path>");
+    println!("  file <path>...");
+    println!("  chmod <octal-mode> <path>");
+    println!("  chattr +i|-i|+h|-h <path>");
+    println!("  chown <owner>[:group] <path>");
+    println!("  chgrp <group> <path>");
+    println!("  xattr [-w <name> <value> | -d <name>] <path>");
+    println!("  verify <path>...");
+    println!("  expire");
     println!("  touch <path>");
-    println!("  edit <path> <old> <new> [replace_all]");
+    println!("  edit [-n] <path> <old> <new> [replace_all]");
+    println!("  replace [-n] [-r] [-i] [-w] [-F] [-U] <pattern> <replacement> <path>");
     println!("  mkdir [-p] <path>");
     println!("  write_file <path> <content>");
-    println!("  cp <src> <dest>");
+    println!("  rm [-r] <path>");
+    println!("  rm [-n] <glob>");
+    println!("  rmdir [-r] <path>");
+    println!("  trash");
+    println!("  restore <path>");
+    println!("  empty_trash");
+    println!("  vacuum");
+    println!("  fsck [-r]");
+    println!("  mv [-f] <src> <dest>");
+    println!("  ln [-s] <src> <dest>");
+    println!("  readlink <path>");
+    println!("  realpath <path>");
+    println!("  cp [-n] [-p] <src> <dest>");
+    println!("  split -l <lines>|-b <bytes> <path>");
+    println!("  join <path>");
+    println!("  cp [-n] <src>... <dir>");
     println!("     use host:<path> to copy to/from host (no host overwrite)");
     println!("  curl [options] <url>");
     println!("     options: -o <file>, -O, -L, -H <h:v>, -d <data>, -X <method>, > <file>");
     println!("     pipeline: curl <url> | write_file <path>");
+    println!("  tree [-L depth] [path]");
+    println!("  du [-h] [path]");
+    println!("  df [-h]");
+    println!("  stats [-h] [path]");
     println!("  pwd");
     println!("  cd <path>");
+    println!("  use [<namespace> <database>]");
     println!("  help");
     println!("  exit | quit");
 }
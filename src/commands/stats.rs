@@ -0,0 +1,51 @@
+use surrealdb::Connection;
+
+use surrealfs::FsError;
+
+use super::ReplState;
+use super::util::{help_error, human_size, resolve_cli_path};
+
+/// `stats [-h] [path]`: files and bytes per extension, with the largest file of each.
+pub async fn run<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let (human, target) = match args {
+        [] => (false, None),
+        ["-h"] => (true, None),
+        ["-h", path] => (true, Some(*path)),
+        [path] => (false, Some(*path)),
+        _ => return Err(help_error()),
+    };
+    let path = match target {
+        Some(p) => resolve_cli_path(&state.cwd, p),
+        None => state.cwd.clone(),
+    };
+
+    let stats = state.fs.stats(&path).await?;
+    let fmt = |bytes: usize| {
+        if human {
+            let (val, unit) = human_size(bytes as f64);
+            format!("{:.1}{}", val, unit)
+        } else {
+            bytes.to_string()
+        }
+    };
+
+    for ty in &stats.types {
+        let extension = ty
+            .extension
+            .as_deref()
+            .map_or("(none)".to_string(), |ext| format!(".{}", ext));
+        let largest = ty.largest.first().map_or("", |f| f.path.as_str());
+        println!(
+            "{:>10}  {:>6}  {:<10}  {}",
+            fmt(ty.bytes),
+            ty.files,
+            extension,
+            largest
+        );
+    }
+    println!("{:>10}  {:>6}  total", fmt(stats.bytes), stats.files);
+    Ok(())
+}
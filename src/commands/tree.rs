@@ -0,0 +1,31 @@
+use surrealdb::Connection;
+
+use surrealfs::FsError;
+
+use super::ReplState;
+use super::util::{help_error, resolve_cli_path};
+
+pub async fn run<DB>(args: &[&str], state: &mut ReplState<DB>) -> Result<(), FsError>
+where
+    DB: Connection,
+{
+    let (max_depth, target) = match args {
+        [] => (None, None),
+        [path] => (None, Some(*path)),
+        ["-L", depth] => (Some(parse_depth(depth)?), None),
+        ["-L", depth, path] => (Some(parse_depth(depth)?), Some(*path)),
+        _ => return Err(help_error()),
+    };
+    let path = match target {
+        Some(p) => resolve_cli_path(&state.cwd, p),
+        None => state.cwd.clone(),
+    };
+
+    let tree = state.fs.tree(&path, max_depth).await?;
+    print!("{}", tree.render());
+    Ok(())
+}
+
+fn parse_depth(input: &str) -> Result<usize, FsError> {
+    input.parse::<usize>().map_err(|_| help_error())
+}
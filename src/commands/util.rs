@@ -16,3 +16,62 @@ pub fn resolve_cli_path(cwd: &str, input: &str) -> String {
 pub fn help_error() -> FsError {
     FsError::InvalidPath
 }
+
+pub fn human_size(bytes: f64) -> (f64, &'static str) {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
+    if bytes < 1.0 {
+        return (bytes, "B");
+    }
+    let mut value = bytes;
+    let mut idx = 0;
+    while value >= 1024.0 && idx < UNITS.len() - 1 {
+        value /= 1024.0;
+        idx += 1;
+    }
+    (value, UNITS[idx])
+}
+
+/// Render epoch millis as a UTC `YYYY-MM-DD HH:MM` stamp, or `-` when unknown.
+pub fn format_timestamp(millis: Option<i64>) -> String {
+    let Some(millis) = millis else {
+        return "-".into();
+    };
+    let secs = millis.div_euclid(1000);
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's civil_from_days).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_timestamp(Some(0)), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(Some(951_782_400_000)), "2000-02-29 00:00");
+        assert_eq!(
+            format_timestamp(Some(1_700_000_000_000)),
+            "2023-11-14 22:13"
+        );
+        assert_eq!(format_timestamp(None), "-");
+    }
+}
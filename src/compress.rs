@@ -0,0 +1,117 @@
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+use crate::{Entry, FsError, Result};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_LEVEL: u8 = 6;
+
+/// Codec file content is compressed with before it is stored; see
+/// `SurrealFsBuilder::compression` and `SurrealFs::write_file_compressed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Store content as written.
+    #[default]
+    None,
+    /// RFC 1952 gzip, so stored bodies can be inspected with standard tools.
+    Gzip,
+}
+
+/// How a row's content is compressed at rest: the codec, and whether it was written as text
+/// or bytes, so loading restores it to the same field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Compressed {
+    pub codec: Compression,
+    pub text: bool,
+}
+
+/// The `content`, `content_bytes`, and `compression` values to store for a file. Content
+/// that does not shrink is stored as is.
+pub(crate) fn pack(
+    codec: Compression,
+    content: Option<String>,
+    content_bytes: Option<ByteBuf>,
+) -> (Option<String>, Option<ByteBuf>, Option<Compressed>) {
+    if codec == Compression::None {
+        return (content, content_bytes, None);
+    }
+    let (raw, text) = match (&content, &content_bytes) {
+        (Some(text), _) => (text.as_bytes(), true),
+        (None, Some(bytes)) => (bytes.as_slice(), false),
+        (None, None) => return (content, content_bytes, None),
+    };
+    let packed = gzip(raw);
+    if packed.len() >= raw.len() {
+        return (content, content_bytes, None);
+    }
+    (
+        None,
+        Some(ByteBuf::from(packed)),
+        Some(Compressed { codec, text }),
+    )
+}
+
+/// Decompress an entry loaded from the database in place, so callers only ever see content
+/// as it was written.
+pub(crate) fn unpack(entry: &mut Entry) -> Result<()> {
+    let Some(compressed) = entry.compression.take() else {
+        return Ok(());
+    };
+    let stored = entry.content_bytes.take().unwrap_or_default();
+    let raw = match compressed.codec {
+        Compression::None => stored.into_vec(),
+        Compression::Gzip => {
+            gunzip(&stored).ok_or_else(|| FsError::Corrupted(entry.path.clone()))?
+        }
+    };
+    if compressed.text {
+        let text = String::from_utf8(raw).map_err(|_| FsError::InvalidUtf8(entry.path.clone()))?;
+        entry.content = Some(text);
+    } else {
+        entry.content_bytes = Some(ByteBuf::from(raw));
+    }
+    Ok(())
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    // Magic, deflate, no flags, no mtime, no extra flags, unknown OS.
+    let mut out = vec![GZIP_MAGIC[0], GZIP_MAGIC[1], 8, 0, 0, 0, 0, 0, 0, 0xff];
+    out.extend(compress_to_vec(data, GZIP_LEVEL));
+    out.extend(crc32fast::hash(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+fn gunzip(data: &[u8]) -> Option<Vec<u8>> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    if data.len() < 18 || data[..2] != GZIP_MAGIC || data[2] != 8 {
+        return None;
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    if flags & FEXTRA != 0 {
+        let len = u16::from_le_bytes([*data.get(pos)?, *data.get(pos + 1)?]) as usize;
+        pos += 2 + len;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            pos += data.get(pos..)?.iter().position(|b| *b == 0)? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+
+    let trailer = data.len() - 8;
+    let raw = decompress_to_vec(data.get(pos..trailer)?).ok()?;
+    let crc = u32::from_le_bytes(data[trailer..trailer + 4].try_into().ok()?);
+    let len = u32::from_le_bytes(data[trailer + 4..].try_into().ok()?);
+    (crc32fast::hash(&raw) == crc && len == raw.len() as u32).then_some(raw)
+}
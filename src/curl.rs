@@ -1,7 +1,7 @@
 use reqwest::{Client, Method, StatusCode, Url};
 use surrealdb::Connection;
 
-use crate::{FsError, SurrealFs};
+use crate::{FsError, SurrealFs, encoding};
 
 #[derive(Debug, Clone)]
 pub struct CurlRequest {
@@ -67,7 +67,9 @@ where
         .await
         .map_err(|e| FsError::Http(e.to_string()))?;
 
-    let body = String::from_utf8_lossy(&bytes).to_string();
+    let body = encoding::detect(&bytes)
+        .and_then(|enc| encoding::decode(&bytes, enc))
+        .unwrap_or_else(|| String::from_utf8_lossy(&bytes).to_string());
 
     let saved_to = if let Some(output) = request.output {
         let target = match output {
@@ -92,13 +94,12 @@ where
 }
 
 fn derive_out_name(url: &str) -> String {
-    if let Ok(parsed) = Url::parse(url) {
-        if let Some(seg) = parsed
+    if let Ok(parsed) = Url::parse(url)
+        && let Some(seg) = parsed
             .path_segments()
-            .and_then(|s| s.filter(|v| !v.is_empty()).last())
-        {
-            return seg.to_string();
-        }
+            .and_then(|mut s| s.rfind(|v| !v.is_empty()))
+    {
+        return seg.to_string();
     }
     "index.html".to_string()
 }
@@ -0,0 +1,44 @@
+/// The umask that yields `DEFAULT_FILE_MODE` and `DEFAULT_DIR_MODE`.
+pub const DEFAULT_UMASK: u32 = 0o022;
+
+const WRITE_BITS: u32 = 0o222;
+
+/// Attributes given to every entry the filesystem creates, configured on the builder.
+#[derive(Debug, Clone)]
+pub(crate) struct EntryDefaults {
+    pub(crate) umask: u32,
+    pub(crate) owner: Option<String>,
+    pub(crate) group: Option<String>,
+    pub(crate) hidden: bool,
+    pub(crate) readonly: bool,
+}
+
+impl Default for EntryDefaults {
+    fn default() -> Self {
+        Self {
+            umask: DEFAULT_UMASK,
+            owner: None,
+            group: None,
+            hidden: false,
+            readonly: false,
+        }
+    }
+}
+
+impl EntryDefaults {
+    /// Permission bits for a new file: `rw-rw-rw-` less the umask, and less every write bit
+    /// when new files are read-only.
+    pub(crate) fn file_mode(&self) -> u32 {
+        let mode = 0o666 & !self.umask;
+        if self.readonly {
+            mode & !WRITE_BITS
+        } else {
+            mode
+        }
+    }
+
+    /// Permission bits for a new directory: `rwxrwxrwx` less the umask.
+    pub(crate) fn dir_mode(&self) -> u32 {
+        0o777 & !self.umask
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{Entry, FsError, Result, SurrealFs, join_path, normalize_path, parent_path};
+
+/// Value of the `format` field on the first line of every dump.
+pub const DUMP_FORMAT: &str = "surrealfs-dump";
+/// Dump format version written by `export`; `import` reads this version and older.
+pub const DUMP_VERSION: u32 = 1;
+
+/// First line of a dump.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpHeader {
+    format: String,
+    version: u32,
+    /// The path that was exported, where `import` restores to by default.
+    root: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DumpKind {
+    Dir,
+    File,
+    Symlink,
+}
+
+/// One entry of a dump, on a line of its own.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpRecord {
+    /// Relative to the exported root; empty for the root itself.
+    path: String,
+    kind: DumpKind,
+    /// Content of a text file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    /// Content of a file stored as bytes, base64 encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base64: Option<String>,
+    /// Target of a symlink, as stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// Shared by the records of files hard linked together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    xattrs: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    immutable: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    hidden: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+/// Attributes applied once every entry of an import is in place.
+#[derive(Clone, Serialize)]
+struct RestoredAttrs {
+    path: String,
+    mode: Option<u32>,
+    owner: Option<String>,
+    group: Option<String>,
+    xattrs: BTreeMap<String, String>,
+    immutable: bool,
+    hidden: bool,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    expires_at: Option<i64>,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Serialize the file or directory tree at `path` as a portable dump, so it can be
+    /// restored into another database with `import` or kept as a backup. The dump is JSON
+    /// Lines: a header `{"format":"surrealfs-dump","version":1,"root":"/src"}`, then one
+    /// record per entry, parents before their contents, with `path` relative to the root
+    /// (empty for the root itself), `kind` (`dir`, `file`, or `symlink`), the content as
+    /// `text` or `base64`, a symlink's `target`, and the entry's mode, owner, group, xattrs,
+    /// flags, and timestamps. Hard linked files share an `inode`. A trailing symlink at
+    /// `path` is followed.
+    pub async fn export(&self, path: impl AsRef<str>) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        let (root, entry) = if path == "/" {
+            (path, None)
+        } else {
+            let (path, entry) = self.follow(path).await?;
+            let entry = entry.ok_or_else(|| FsError::NotFound(path.clone()))?;
+            (path, Some(entry))
+        };
+
+        let header = DumpHeader {
+            format: DUMP_FORMAT.to_string(),
+            version: DUMP_VERSION,
+            root: root.clone(),
+        };
+        let mut lines = vec![to_line(&header)?];
+        match entry {
+            Some(entry) if !entry.is_dir => lines.push(to_line(&dump_record(&root, entry))?),
+            entry => {
+                let root_record = match entry {
+                    Some(entry) => dump_record(&root, entry),
+                    None => DumpRecord::root_dir(),
+                };
+                lines.push(to_line(&root_record)?);
+                for entry in self.subtree(&root, false, Route::Replica).await? {
+                    lines.push(to_line(&dump_record(&root, entry))?);
+                }
+            }
+        }
+        lines.push(String::new());
+        Ok(lines.join("\n"))
+    }
+
+    /// Restore a dump made by `export` under `dest`, or where it was exported from when
+    /// `dest` is `None`, and return how many entries it held. Directories are merged into,
+    /// entries of another kind already at a dumped path are replaced, and attributes and
+    /// timestamps are restored once all content is written. Content is written as on any
+    /// other write, so checksums and versions are fresh and the newline policy applies.
+    pub async fn import(&self, dump: &str, dest: Option<&str>) -> Result<usize> {
+        let mut lines = dump.lines().filter(|line| !line.trim().is_empty());
+        let header: DumpHeader = from_line(lines.next().unwrap_or_default())?;
+        if header.format != DUMP_FORMAT || header.version > DUMP_VERSION {
+            return Err(FsError::InvalidDump(format!(
+                "unsupported format {} version {}",
+                header.format, header.version
+            )));
+        }
+        let dest = normalize_path(dest.unwrap_or(&header.root))?;
+        self.ensure_writable(&dest)?;
+        let records = lines
+            .map(from_line::<DumpRecord>)
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(parent) = parent_path(&dest) {
+            self.mkdir(parent, true).await?;
+        }
+        let mut linked: HashMap<String, String> = HashMap::new();
+        let mut restored = Vec::with_capacity(records.len());
+        for record in records {
+            let path = restore_path(&dest, &record.path)?;
+            self.clear_for(&path, record.kind).await?;
+            match record.kind {
+                DumpKind::Dir => {
+                    self.mkdir(&path, true).await?;
+                }
+                DumpKind::Symlink => {
+                    let target = record.target.as_deref().ok_or_else(|| {
+                        FsError::InvalidDump(format!("symlink without target: {}", record.path))
+                    })?;
+                    self.symlink(target, &path).await?;
+                }
+                DumpKind::File => match record.inode.as_ref().and_then(|i| linked.get(i)) {
+                    Some(first) => {
+                        if self.symlink_metadata(&path).await?.is_some() {
+                            self.rm(&path).await?;
+                        }
+                        self.link(first, &path).await?
+                    }
+                    None => {
+                        match (&record.text, &record.base64) {
+                            (_, Some(encoded)) => {
+                                let data = base64_decode(encoded).ok_or_else(|| {
+                                    FsError::InvalidDump(format!("bad base64: {}", record.path))
+                                })?;
+                                self.write_bytes(&path, data).await?
+                            }
+                            (text, None) => {
+                                self.write_file(&path, text.clone().unwrap_or_default())
+                                    .await?
+                            }
+                        };
+                        if let Some(inode) = &record.inode {
+                            linked.insert(inode.clone(), path.clone());
+                        }
+                    }
+                },
+            }
+            restored.push(RestoredAttrs {
+                path,
+                mode: record.mode,
+                owner: record.owner,
+                group: record.group,
+                xattrs: record.xattrs,
+                immutable: record.immutable,
+                hidden: record.hidden,
+                created_at: record.created_at,
+                updated_at: record.updated_at,
+                expires_at: record.expires_at,
+            });
+        }
+
+        let count = restored.len();
+        // The root of a whole-filesystem dump has no row to restore attributes onto.
+        restored.retain(|attrs| attrs.path != "/");
+        let _permit = self.permit().await;
+        self.forget_all();
+        self.run(|| {
+            self.db
+                .query(
+                    "FOR $e IN $entries { UPDATE type::thing($table, $e.path) SET mode = $e.mode, owner = $e.owner, group = $e.group, xattrs = $e.xattrs, immutable = $e.immutable, hidden = $e.hidden, created_at = $e.created_at, updated_at = $e.updated_at, expires_at = $e.expires_at; }",
+                )
+                .bind(("table", self.table.clone()))
+                .bind(("entries", restored.clone()))
+        })
+        .await?;
+        Ok(count)
+    }
+
+    /// Remove whatever is at `path` unless it is a directory and a directory is coming.
+    async fn clear_for(&self, path: &str, kind: DumpKind) -> Result<()> {
+        match self.symlink_metadata(path).await? {
+            Some(meta) if meta.is_dir && meta.symlink.is_none() => {
+                if kind != DumpKind::Dir {
+                    self.rmdir(path, true).await?;
+                }
+            }
+            Some(meta) if meta.symlink.is_some() || kind != DumpKind::File => self.rm(path).await?,
+            Some(_) | None => {}
+        }
+        Ok(())
+    }
+}
+
+impl DumpRecord {
+    /// The record for `/`, which has no row of its own.
+    fn root_dir() -> Self {
+        DumpRecord {
+            path: String::new(),
+            kind: DumpKind::Dir,
+            text: None,
+            base64: None,
+            target: None,
+            inode: None,
+            mode: None,
+            owner: None,
+            group: None,
+            xattrs: BTreeMap::new(),
+            immutable: false,
+            hidden: false,
+            created_at: None,
+            updated_at: None,
+            expires_at: None,
+        }
+    }
+}
+
+fn dump_record(root: &str, entry: Entry) -> DumpRecord {
+    let path = if entry.path == root {
+        String::new()
+    } else if root == "/" {
+        entry.path[1..].to_string()
+    } else {
+        entry.path[root.len() + 1..].to_string()
+    };
+    let kind = if entry.symlink.is_some() {
+        DumpKind::Symlink
+    } else if entry.is_dir {
+        DumpKind::Dir
+    } else {
+        DumpKind::File
+    };
+    let (text, base64) = match (kind, entry.content_bytes) {
+        (DumpKind::File, Some(bytes)) => (None, Some(base64_encode(&bytes))),
+        (DumpKind::File, None) => (entry.content.or(Some(String::new())), None),
+        _ => (None, None),
+    };
+    DumpRecord {
+        path,
+        kind,
+        text,
+        base64,
+        target: entry.symlink,
+        inode: entry.inode,
+        mode: entry.mode,
+        owner: entry.owner,
+        group: entry.group,
+        xattrs: entry.xattrs,
+        immutable: entry.immutable,
+        hidden: entry.hidden,
+        created_at: entry.created_at,
+        updated_at: entry.updated_at,
+        expires_at: entry.expires_at,
+    }
+}
+
+/// Where the record at `relative` lands under `dest`; records may not climb out of it.
+fn restore_path(dest: &str, relative: &str) -> Result<String> {
+    if relative.is_empty() {
+        return Ok(dest.to_string());
+    }
+    let path = normalize_path(&join_path(dest, relative))?;
+    let inside = dest == "/" || path.starts_with(&format!("{}/", dest));
+    if !inside {
+        return Err(FsError::InvalidDump(format!(
+            "path outside dump: {}",
+            relative
+        )));
+    }
+    Ok(path)
+}
+
+fn to_line<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| FsError::InvalidDump(e.to_string()))
+}
+
+fn from_line<T: for<'de> Deserialize<'de>>(line: &str) -> Result<T> {
+    serde_json::from_str(line).map_err(|e| FsError::InvalidDump(e.to_string()))
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+        bits = bits << 6 | value;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,92 @@
+use crate::Entry;
+
+const UTF16LE: &str = "utf-16le";
+const UTF16BE: &str = "utf-16be";
+const LATIN1: &str = "iso-8859-1";
+
+/// The legacy text encoding of `bytes`, or `None` for UTF-8 and for binary data. UTF-16 is
+/// recognised by its byte order mark or by the zero bytes ASCII text leaves in every other
+/// position; anything else that is not UTF-8 but free of control bytes is taken as Latin-1.
+pub(crate) fn detect(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some(UTF16LE).filter(|enc| decode(bytes, enc).is_some());
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some(UTF16BE).filter(|enc| decode(bytes, enc).is_some());
+    }
+    // Checked before UTF-8, since ASCII text in UTF-16 is also valid UTF-8 full of NULs.
+    if let Some(enc) = utf16_without_bom(bytes) {
+        return Some(enc);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return None;
+    }
+    let control = |b: &u8| *b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B);
+    if bytes.iter().any(control) {
+        return None;
+    }
+    Some(LATIN1)
+}
+
+/// Decode `bytes` stored in `encoding`, as recorded by `detect`. `None` when the label is
+/// unknown or the bytes are not valid in it.
+pub(crate) fn decode(bytes: &[u8], encoding: &str) -> Option<String> {
+    match encoding {
+        UTF16LE => decode_utf16(
+            bytes.strip_prefix(&[0xFF, 0xFE]).unwrap_or(bytes),
+            u16::from_le_bytes,
+        ),
+        UTF16BE => decode_utf16(
+            bytes.strip_prefix(&[0xFE, 0xFF]).unwrap_or(bytes),
+            u16::from_be_bytes,
+        ),
+        LATIN1 => Some(bytes.iter().map(|&b| char::from(b)).collect()),
+        _ => None,
+    }
+}
+
+/// The encoding to store for `entry`; `None` for text written as UTF-8, directories, and
+/// symlinks.
+pub(crate) fn of_entry(entry: &Entry) -> Option<String> {
+    if entry.is_dir || entry.symlink.is_some() || entry.content.is_some() {
+        return None;
+    }
+    entry
+        .content_bytes
+        .as_ref()
+        .and_then(|bytes| detect(bytes))
+        .map(str::to_string)
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|c| unit([c[0], c[1]])).collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Mostly-ASCII UTF-16 has a zero in nearly every high byte and almost no zero low bytes.
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let zeros_at = |offset: usize| {
+        bytes
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    let enc = if odd * 10 >= pairs * 9 && even * 10 <= pairs {
+        UTF16LE
+    } else if even * 10 >= pairs * 9 && odd * 10 <= pairs {
+        UTF16BE
+    } else {
+        return None;
+    };
+    decode(bytes, enc).map(|_| enc)
+}
@@ -0,0 +1,60 @@
+use surrealdb::Connection;
+use surrealdb::engine::any::{self, Any};
+
+use crate::{Result, SurrealFs, SurrealFsBuilder};
+
+/// Namespace selected by `SurrealFs::open`.
+pub const DEFAULT_NAMESPACE: &str = "surrealfs";
+/// Database selected by `SurrealFs::open`.
+pub const DEFAULT_DATABASE: &str = "surrealfs";
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Point the connection, and the read replica if any, at another namespace and database,
+    /// so one connection can serve several filesystems in turn. Buffered appends are flushed
+    /// and the cache emptied first. The session belongs to the connection, so every handle
+    /// sharing it switches too. A `subscribe_cache` subscription follows to the new database.
+    pub async fn use_ns_db(&self, namespace: &str, database: &str) -> Result<()> {
+        self.flush().await?;
+        self.db.use_ns(namespace).use_db(database).await?;
+        if let Some(replica) = &self.replica {
+            replica.use_ns(namespace).use_db(database).await?;
+        }
+        self.forget_all();
+        if self.cache.as_ref().is_some_and(|cache| cache.is_live()) {
+            self.subscribe_cache().await?;
+        }
+        Ok(())
+    }
+}
+
+impl SurrealFs<Any> {
+    /// Open a filesystem on whatever engine `endpoint` names, picked at runtime: `"memory"`,
+    /// `"rocksdb:./data"`, `"ws://host:8000"`, and so on. Local engines need the matching
+    /// crate feature (`kv-mem`, `kv-rocksdb`). Selects `DEFAULT_NAMESPACE` and
+    /// `DEFAULT_DATABASE`; servers that require signing in are better connected by hand and
+    /// passed to `SurrealFs::new`.
+    pub async fn open(endpoint: &str) -> Result<Self> {
+        Ok(
+            Self::open_builder(endpoint, DEFAULT_NAMESPACE, DEFAULT_DATABASE)
+                .await?
+                .build(),
+        )
+    }
+
+    /// `open` with the namespace and database given, returning the builder so the
+    /// filesystem can be configured before use.
+    pub async fn open_builder(
+        endpoint: &str,
+        namespace: &str,
+        database: &str,
+    ) -> Result<SurrealFsBuilder<Any>> {
+        let db = any::connect(endpoint).await?;
+        db.use_ns(namespace).use_db(database).await?;
+        let mut builder = Self::builder(db);
+        builder.endpoint = Some(endpoint.to_string());
+        Ok(builder)
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use surrealdb::Connection;
+
+use crate::{Metadata, Result, SurrealFs, now_millis};
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Like `write_file`, but the file becomes eligible for removal by `expire` once `ttl`
+    /// has passed. Rewriting the file later keeps the deadline; call this again to extend it.
+    pub async fn write_file_with_ttl(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+        ttl: Duration,
+    ) -> Result<Metadata> {
+        let mut meta = self.write_file(path, content).await?;
+        let expires_at = now_millis().saturating_add(ttl.as_millis() as i64);
+
+        let _permit = self.permit().await;
+        self.forget(&[&meta.path]);
+        self.run(|| {
+            self.db
+                .query("UPDATE type::thing($table, $path) SET expires_at = $expires_at")
+                .bind(("table", self.table.clone()))
+                .bind(("path", meta.path.clone()))
+                .bind(("expires_at", expires_at))
+        })
+        .await?;
+        meta.expires_at = Some(expires_at);
+        Ok(meta)
+    }
+
+    /// Remove every file whose TTL has run out and return their paths, sorted; immutable files
+    /// are kept until the flag is cleared. Nothing runs
+    /// in the background; call this periodically, e.g. from a `tokio::time::interval` loop.
+    pub async fn expire(&self) -> Result<Vec<String>> {
+        self.ensure_writable("/")?;
+        let mut res = {
+            let _permit = self.permit().await;
+            self.run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT VALUE path FROM {} WHERE expires_at != NONE AND expires_at <= $now AND immutable != true",
+                        self.table
+                    ))
+                    .bind(("now", now_millis()))
+            })
+            .await?
+        };
+        let mut expired: Vec<String> = res.take(0)?;
+        expired.sort();
+        if !expired.is_empty() {
+            self.delete_paths(expired.clone()).await?;
+            let paths: Vec<&str> = expired.iter().map(String::as_str).collect();
+            self.touch_ancestors(&paths, now_millis()).await?;
+        }
+        Ok(expired)
+    }
+}
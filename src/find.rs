@@ -0,0 +1,95 @@
+use globset::{Glob, GlobMatcher};
+use surrealdb::Connection;
+
+use crate::inode::UPDATED_AT_EXPR;
+use crate::replica::Route;
+use crate::usage::SIZE_EXPR;
+use crate::{Entry, FsError, Result, SurrealFs, lazy, normalize_path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+}
+
+/// Predicates for `SurrealFs::find`. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct FindOptions {
+    /// Glob matched against the entry name (not the full path), like `find -name`.
+    pub name: Option<String>,
+    pub kind: Option<EntryKind>,
+    /// Inclusive size bounds in bytes; directories have size 0.
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    /// Only entries whose `updated_at` (epoch millis) is at or after this value.
+    pub modified_since: Option<i64>,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Search `path` and everything beneath it for entries matching `opts`, sorted by path.
+    /// Type, size, and time predicates are evaluated server-side, and file content is left
+    /// in the database until `Entry::load_content`.
+    pub async fn find(&self, path: impl AsRef<str>, opts: &FindOptions) -> Result<Vec<Entry>> {
+        let path = normalize_path(path.as_ref())?;
+        if path != "/" && self.symlink_metadata(&path).await?.is_none() {
+            return Err(FsError::NotFound(path));
+        }
+        let name_matcher = opts.name.as_deref().map(compile_name_glob).transpose()?;
+
+        let prefix = if path == "/" {
+            path.clone()
+        } else {
+            format!("{}/", path)
+        };
+        let mut conditions =
+            vec!["(path = $path OR string::starts_with(path, $prefix))".to_string()];
+        match opts.kind {
+            Some(EntryKind::File) => conditions.push("is_dir = false".into()),
+            Some(EntryKind::Dir) => conditions.push("is_dir = true".into()),
+            None => {}
+        }
+        if opts.min_size.is_some() {
+            conditions.push(format!("{} >= $min_size", SIZE_EXPR));
+        }
+        if opts.max_size.is_some() {
+            conditions.push(format!("{} <= $max_size", SIZE_EXPR));
+        }
+        if opts.modified_since.is_some() {
+            conditions.push(format!("{} >= $since", UPDATED_AT_EXPR));
+        }
+
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(Route::Replica)
+                    .query(format!(
+                        "SELECT {} FROM {} WHERE {} ORDER BY path",
+                        lazy::lazy_entry_fields(),
+                        self.table,
+                        conditions.join(" AND ")
+                    ))
+                    .bind(("path", path.clone()))
+                    .bind(("prefix", prefix.clone()))
+                    .bind(("min_size", opts.min_size))
+                    .bind(("max_size", opts.max_size))
+                    .bind(("since", opts.modified_since))
+            })
+            .await?;
+        let mut entries = self.lazy_entries(res.take(0)?);
+
+        if let Some(matcher) = name_matcher {
+            entries.retain(|e| matcher.is_match(&e.name));
+        }
+        Ok(entries)
+    }
+}
+
+fn compile_name_glob(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|g| g.compile_matcher())
+        .map_err(|_| FsError::InvalidPattern(pattern.to_string()))
+}
@@ -0,0 +1,211 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{FsError, Result, SurrealFs, inode, parent_path};
+
+/// What `fsck` found, each list sorted by path. A repair run reports what it found before
+/// repairing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FsckReport {
+    /// Paths stored on more than one row.
+    pub duplicate_paths: Vec<String>,
+    /// Directories that entries name as their parent but that have no entry of their own.
+    pub missing_parents: Vec<String>,
+    /// Entries stored as files or symlinks that other entries name as their parent.
+    pub files_with_children: Vec<String>,
+    /// Symlinks whose target does not resolve.
+    pub dangling_symlinks: Vec<String>,
+    pub repaired: bool,
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.duplicate_paths.is_empty()
+            && self.missing_parents.is_empty()
+            && self.files_with_children.is_empty()
+            && self.dangling_symlinks.is_empty()
+    }
+
+    /// One line per problem, or `clean`.
+    pub fn render(&self) -> String {
+        if self.is_clean() {
+            return "clean".to_string();
+        }
+        let sections = [
+            ("duplicate path", &self.duplicate_paths),
+            ("missing parent", &self.missing_parents),
+            ("file with children", &self.files_with_children),
+            ("dangling symlink", &self.dangling_symlinks),
+        ];
+        let verb = if self.repaired { "repaired " } else { "" };
+        sections
+            .iter()
+            .flat_map(|(label, paths)| {
+                paths
+                    .iter()
+                    .map(move |path| format!("{verb}{label}: {path}"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Check the table for inconsistencies the API itself never produces but crashes,
+    /// concurrent writers without the schema's unique index, or hand edits can leave behind.
+    /// With `repair`, duplicate rows are dropped (keeping the row stored under the path's own
+    /// record id, else the most recently updated), files with children become directories
+    /// (any content is kept in a `<path>.fsck` sibling), missing parents are recreated as
+    /// empty directories, and dangling symlinks are removed.
+    pub async fn fsck(&self, repair: bool) -> Result<FsckReport> {
+        if repair {
+            self.ensure_writable("/")?;
+        }
+        let entries = self.all_entries().await?;
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for entry in &entries {
+            *counts.entry(entry.path.as_str()).or_default() += 1;
+        }
+        let mut by_path = HashMap::new();
+        for entry in &entries {
+            by_path.entry(entry.path.as_str()).or_insert(entry);
+        }
+
+        let mut duplicates: Vec<String> = counts
+            .iter()
+            .filter(|(_, n)| **n > 1)
+            .map(|(path, _)| path.to_string())
+            .collect();
+        duplicates.sort();
+
+        let parents: BTreeSet<&str> = entries
+            .iter()
+            .filter_map(|e| e.parent.as_deref())
+            .filter(|p| *p != "/")
+            .collect();
+        let mut missing = BTreeSet::new();
+        for parent in &parents {
+            let mut dir = Some(parent.to_string());
+            while let Some(path) = dir.filter(|p| p != "/" && !by_path.contains_key(p.as_str())) {
+                dir = parent_path(&path);
+                missing.insert(path);
+            }
+        }
+        let files_with_children: Vec<String> = parents
+            .iter()
+            .filter(|p| {
+                by_path
+                    .get(*p)
+                    .is_some_and(|e| !e.is_dir || e.symlink.is_some())
+            })
+            .map(|p| p.to_string())
+            .collect();
+
+        let mut dangling = Vec::new();
+        let mut symlinks: Vec<&str> = by_path
+            .values()
+            .filter(|e| e.symlink.is_some())
+            .map(|e| e.path.as_str())
+            .collect();
+        symlinks.sort();
+        for path in symlinks {
+            match self.realpath(path).await {
+                Ok(_) => {}
+                Err(FsError::NotFound(_) | FsError::SymlinkLoop(_) | FsError::NotADirectory(_)) => {
+                    dangling.push(path.to_string())
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut report = FsckReport {
+            duplicate_paths: duplicates,
+            missing_parents: missing.into_iter().collect(),
+            files_with_children,
+            dangling_symlinks: dangling,
+            repaired: false,
+        };
+        if repair && !report.is_clean() {
+            self.repair(&report).await?;
+            report.repaired = true;
+        }
+        Ok(report)
+    }
+
+    async fn repair(&self, report: &FsckReport) -> Result<()> {
+        self.forget_all();
+        if !report.duplicate_paths.is_empty() {
+            let _permit = self.permit().await;
+            self.run_once(|| {
+                self.db
+                    .query(format!(
+                        "BEGIN TRANSACTION;\
+                         FOR $path IN $paths {{\
+                             LET $canonical = type::thing($table, $path);\
+                             LET $rows = (SELECT id, updated_at FROM type::table($table) WHERE path = $path ORDER BY updated_at DESC);\
+                             LET $keep = IF record::exists($canonical) THEN $canonical ELSE $rows[0].id END;\
+                             LET $removed = (DELETE (SELECT VALUE id FROM type::table($table) WHERE path = $path AND id != $keep) RETURN VALUE $before.inode);\
+                             {}\
+                         }};\
+                         COMMIT TRANSACTION;",
+                        inode::release("$removed")
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("paths", report.duplicate_paths.clone()))
+            })
+            .await?;
+        }
+
+        for path in &report.files_with_children {
+            let content = match self.get_entry(path).await? {
+                Some(entry) if entry.size() > 0 && entry.symlink.is_none() => Some(entry),
+                _ => None,
+            };
+            {
+                let _permit = self.permit().await;
+                self.run_once(|| {
+                    self.db
+                        .query(format!(
+                            "BEGIN TRANSACTION;\
+                             LET $cleared = (UPDATE {} SET is_dir = true, content = NONE, content_bytes = NONE, compression = NONE, content_type = NONE, encoding = NONE, checksum = NONE, size = 0, symlink = NONE, inode = NONE, mode = $mode WHERE path = $path RETURN VALUE $before.inode);\
+                             {}\
+                             COMMIT TRANSACTION;",
+                            self.table,
+                            inode::release("$cleared")
+                        ))
+                        .bind(("path", path.clone()))
+                        .bind(("mode", self.defaults.dir_mode()))
+                })
+                .await?;
+            }
+            self.forget_all();
+            if let Some(entry) = content {
+                let mut kept = format!("{}.fsck", path);
+                let mut n = 1;
+                while self.symlink_metadata(&kept).await?.is_some() {
+                    kept = format!("{}.fsck.{}", path, n);
+                    n += 1;
+                }
+                let parent = parent_path(&kept).ok_or(FsError::InvalidPath)?;
+                self.create_file(&kept, &parent, entry.content, entry.content_bytes)
+                    .await?;
+            }
+        }
+
+        // Parents sort before their children, so each directory's own parent exists by then.
+        for path in &report.missing_parents {
+            let parent = parent_path(path).ok_or(FsError::InvalidPath)?;
+            self.create_dir(path, &parent).await?;
+        }
+
+        if !report.dangling_symlinks.is_empty() {
+            self.delete_paths(report.dangling_symlinks.clone()).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,456 @@
+use serde::Deserialize;
+use surrealdb::Connection;
+
+use crate::inode::{self, UPDATED_AT_EXPR};
+use crate::replica::Route;
+use crate::usage::SIZE_EXPR;
+use crate::{Entry, EntryKind, FsError, Page, Result, SurrealFs, lazy, normalize_path, now_millis};
+
+/// Filters for `SurrealFs::glob_with` and `SurrealFs::glob_entries`, evaluated server-side
+/// along with the patterns. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct GlobOptions {
+    pub kind: Option<EntryKind>,
+    /// Inclusive size bounds in bytes; directories have size 0.
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    /// Only entries whose `updated_at` (epoch millis) is at or after this value.
+    pub modified_since: Option<i64>,
+}
+
+impl GlobOptions {
+    /// The filters as SurrealQL conditions over `$min_size`, `$max_size`, and `$since`.
+    fn conditions(&self) -> String {
+        let mut conditions = String::new();
+        match self.kind {
+            Some(EntryKind::File) => conditions.push_str(" AND is_dir = false"),
+            Some(EntryKind::Dir) => conditions.push_str(" AND is_dir = true"),
+            None => {}
+        }
+        if self.min_size.is_some() {
+            conditions.push_str(&format!(" AND {} >= $min_size", SIZE_EXPR));
+        }
+        if self.max_size.is_some() {
+            conditions.push_str(&format!(" AND {} <= $max_size", SIZE_EXPR));
+        }
+        if self.modified_since.is_some() {
+            conditions.push_str(&format!(" AND {} >= $since", UPDATED_AT_EXPR));
+        }
+        conditions
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    pub async fn glob(&self, pattern: impl AsRef<str>) -> Result<Vec<String>> {
+        self.glob_many(&[pattern]).await
+    }
+
+    /// `glob` over several patterns at once, as one server-side predicate: paths matching
+    /// any plain pattern, less those matching any pattern written with a leading `!`, such
+    /// as `["/src/**/*.rs", "!**/target/**"]`. At least one plain pattern is required.
+    pub async fn glob_many(&self, patterns: &[impl AsRef<str>]) -> Result<Vec<String>> {
+        self.glob_with(patterns, &GlobOptions::default()).await
+    }
+
+    /// `glob_many` keeping only the matches `opts` lets through.
+    pub async fn glob_with(
+        &self,
+        patterns: &[impl AsRef<str>],
+        opts: &GlobOptions,
+    ) -> Result<Vec<String>> {
+        let predicate = GlobPredicate::new(patterns)?;
+        let entries = self
+            .glob_rows(&predicate, opts, false, Route::Replica)
+            .await?;
+        Ok(entries.into_iter().map(|e| e.path).collect())
+    }
+
+    /// `glob_with` returning the matching entries with their metadata, newest first. File
+    /// content is left in the database until `Entry::load_content`.
+    pub async fn glob_entries(
+        &self,
+        patterns: &[impl AsRef<str>],
+        opts: &GlobOptions,
+    ) -> Result<Vec<Entry>> {
+        let predicate = GlobPredicate::new(patterns)?;
+        self.glob_rows(&predicate, opts, true, Route::Replica).await
+    }
+
+    /// `glob` one page at a time: up to `limit` (at least one) matching paths in the same
+    /// newest-first order, starting after `cursor`.
+    pub async fn glob_page(
+        &self,
+        pattern: impl AsRef<str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<Page<String>> {
+        let predicate = GlobPredicate::new(&[pattern])?;
+        let limit = limit.max(1);
+        // The cursor is the last item's `updated_at` and path; the next page starts at the
+        // first match ordered after it.
+        let after = cursor
+            .map(|cursor| {
+                let (at, path) = cursor.split_once(':').ok_or(FsError::InvalidPath)?;
+                let at: i64 = at.parse().map_err(|_| FsError::InvalidPath)?;
+                Ok::<_, FsError>((at, path.to_string()))
+            })
+            .transpose()?;
+
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(Route::Replica)
+                    .query(format!(
+                        "SELECT path, {mtime} ?? 0 AS updated_at FROM {table} \
+                         WHERE {matches} \
+                         AND ($after = NONE OR ({mtime} ?? 0) < $after[0] OR (({mtime} ?? 0) = $after[0] AND path > $after[1])) \
+                         ORDER BY updated_at DESC, path LIMIT $limit",
+                        mtime = UPDATED_AT_EXPR,
+                        table = self.table,
+                        matches = GlobPredicate::CONDITION
+                    ))
+                    .bind(("prefix", predicate.prefix.clone()))
+                    .bind(("regex", predicate.regex.clone()))
+                    .bind(("exclude", predicate.exclude.clone()))
+                    .bind(("after", after.clone()))
+                    .bind(("limit", limit + 1))
+            })
+            .await?;
+        let mut matches: Vec<PageKey> = res.take(0)?;
+
+        let next = if matches.len() > limit {
+            matches.truncate(limit);
+            matches
+                .last()
+                .map(|last| format!("{}:{}", last.updated_at, last.path))
+        } else {
+            None
+        };
+        Ok(Page {
+            items: matches.into_iter().map(|m| m.path).collect(),
+            next,
+        })
+    }
+
+    /// Delete every file matching `pattern` with one server-side query and return the matched
+    /// paths, sorted. Directories are never removed. With `dry_run` nothing is deleted, so
+    /// callers can preview the list first. In trash mode each file is moved to the trash one
+    /// by one. Nothing is removed when any match is immutable. A malformed pattern fails with
+    /// `FsError::InvalidPattern`.
+    pub async fn rm_glob(&self, pattern: impl AsRef<str>, dry_run: bool) -> Result<Vec<String>> {
+        let pattern = pattern.as_ref();
+        if !dry_run {
+            self.ensure_writable(pattern)?;
+        }
+        let predicate = GlobPredicate::new(&[pattern])
+            .map_err(|_| FsError::InvalidPattern(pattern.to_string()))?;
+        if dry_run || self.trash {
+            return self.rm_glob_one_by_one(&predicate, dry_run).await;
+        }
+
+        self.flush_pending().await?;
+        let mut res = {
+            let _permit = self.permit().await;
+            self.run_once(|| {
+                self.db
+                    .query(format!(
+                        "BEGIN TRANSACTION;\
+                         LET $locked = (SELECT VALUE path FROM {table} WHERE {matches} AND is_dir = false AND immutable = true ORDER BY path LIMIT 1);\
+                         LET $removed = IF $locked = [] THEN (DELETE {table} WHERE {matches} AND is_dir = false RETURN VALUE {{ path: $before.path, inode: $before.inode }}) ELSE [] END;\
+                         {release}\
+                         RETURN {{ locked: $locked[0], removed: $removed.path }};\
+                         COMMIT TRANSACTION;",
+                        table = self.table,
+                        matches = GlobPredicate::CONDITION,
+                        release = inode::release("$removed.inode"),
+                    ))
+                    .bind(("prefix", predicate.prefix.clone()))
+                    .bind(("regex", predicate.regex.clone()))
+                    .bind(("exclude", predicate.exclude.clone()))
+            })
+            .await?
+        };
+        let outcome: Option<Removal> = res.take(res.num_statements() - 1)?;
+        let Removal {
+            locked,
+            mut removed,
+        } = outcome.unwrap_or_default();
+        if let Some(locked) = locked {
+            return Err(FsError::Immutable(locked));
+        }
+        removed.sort();
+        let paths: Vec<&str> = removed.iter().map(String::as_str).collect();
+        self.forget(&paths);
+        self.touch_ancestors(&paths, now_millis()).await?;
+        Ok(removed)
+    }
+
+    /// `rm_glob` for a preview or under trash mode, where each match is listed first: moved
+    /// to the trash one at a time, or deleted by its id when already in the trash.
+    async fn rm_glob_one_by_one(
+        &self,
+        predicate: &GlobPredicate,
+        dry_run: bool,
+    ) -> Result<Vec<String>> {
+        let files: Vec<Entry> = self
+            .glob_rows(predicate, &GlobOptions::default(), false, Route::Primary)
+            .await?
+            .into_iter()
+            .filter(|e| !e.is_dir)
+            .collect();
+        if !dry_run && let Some(locked) = files.iter().find(|e| e.immutable) {
+            return Err(FsError::Immutable(locked.path.clone()));
+        }
+        let mut paths: Vec<String> = files.into_iter().map(|e| e.path).collect();
+        paths.sort();
+        if dry_run {
+            return Ok(paths);
+        }
+
+        for path in paths.iter().filter(|p| self.trashes(p)) {
+            self.move_to_trash(path).await?;
+        }
+        let doomed: Vec<String> = paths.iter().filter(|p| !self.trashes(p)).cloned().collect();
+        if !doomed.is_empty() {
+            self.delete_paths(doomed.clone()).await?;
+            let removed: Vec<&str> = doomed.iter().map(String::as_str).collect();
+            self.touch_ancestors(&removed, now_millis()).await?;
+        }
+        Ok(paths)
+    }
+
+    /// Entries matching `predicate` and `opts`, newest first. Matching runs server-side as a
+    /// path prefix and regex predicate. Unless `full`, only the columns needed to list and
+    /// filter are returned; otherwise everything but the content is.
+    async fn glob_rows(
+        &self,
+        predicate: &GlobPredicate,
+        opts: &GlobOptions,
+        full: bool,
+        route: Route,
+    ) -> Result<Vec<Entry>> {
+        let fields = if full {
+            lazy::lazy_entry_fields()
+        } else {
+            "path, name, parent, is_dir, immutable ?? false AS immutable, updated_at".to_string()
+        };
+
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(route)
+                    .query(format!(
+                        "SELECT {} FROM {} WHERE {}{} ORDER BY updated_at DESC, path",
+                        fields,
+                        self.table,
+                        GlobPredicate::CONDITION,
+                        opts.conditions()
+                    ))
+                    .bind(("prefix", predicate.prefix.clone()))
+                    .bind(("regex", predicate.regex.clone()))
+                    .bind(("exclude", predicate.exclude.clone()))
+                    .bind(("min_size", opts.min_size))
+                    .bind(("max_size", opts.max_size))
+                    .bind(("since", opts.modified_since))
+            })
+            .await?;
+        if full {
+            return Ok(self.lazy_entries(res.take(0)?));
+        }
+        let entries: Vec<Entry> = res.take(0)?;
+        Ok(entries)
+    }
+}
+
+/// What `rm_glob`'s delete reports: the first immutable match that stopped it, or the paths
+/// it removed.
+#[derive(Debug, Default, Deserialize)]
+struct Removal {
+    locked: Option<String>,
+    removed: Vec<String>,
+}
+
+/// What a page of `glob_page` is ordered and resumed by.
+#[derive(Debug, Deserialize)]
+struct PageKey {
+    path: String,
+    updated_at: i64,
+}
+
+/// What a path must satisfy to match a set of glob patterns: start with `prefix`, match
+/// `regex`, and not match `exclude`.
+struct GlobPredicate {
+    prefix: String,
+    regex: String,
+    exclude: Option<String>,
+}
+
+impl GlobPredicate {
+    /// The predicate as a SurrealQL condition over `$prefix`, `$regex`, and `$exclude`.
+    const CONDITION: &str = "string::starts_with(path, $prefix) AND string::matches(path, $regex) \
+         AND ($exclude = NONE OR !string::matches(path, $exclude))";
+
+    /// Combine plain patterns into one alternation and `!`-negated ones into another.
+    fn new(patterns: &[impl AsRef<str>]) -> Result<Self> {
+        let mut prefix: Option<String> = None;
+        let (mut include, mut exclude) = (Vec::new(), Vec::new());
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            let (negated, pattern) = match pattern.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, pattern),
+            };
+            if pattern.is_empty() {
+                return Err(FsError::InvalidPath);
+            }
+            let normalized = normalize_path(pattern)?;
+            if normalized == "/" {
+                return Err(FsError::InvalidPath);
+            }
+            let regex = glob_to_regex(&normalized)?;
+            if negated {
+                exclude.push(regex);
+                continue;
+            }
+            let literal = literal_prefix(&normalized);
+            prefix = Some(match prefix {
+                None => literal,
+                Some(shared) => common_dir_prefix(&shared, &literal),
+            });
+            include.push(regex);
+        }
+        let prefix = prefix.ok_or(FsError::InvalidPath)?;
+        let alternation = |regexes: Vec<String>| match regexes.as_slice() {
+            [single] => single.clone(),
+            _ => format!("(?:{})", regexes.join("|")),
+        };
+        Ok(Self {
+            prefix,
+            regex: alternation(include),
+            exclude: (!exclude.is_empty()).then(|| alternation(exclude)),
+        })
+    }
+}
+
+/// The longest directory prefix, ending in `/`, shared by two prefixes that both do.
+fn common_dir_prefix(a: &str, b: &str) -> String {
+    let shared = a
+        .char_indices()
+        .zip(b.chars())
+        .take_while(|((_, x), y)| x == y)
+        .last()
+        .map_or(0, |((idx, c), _)| idx + c.len_utf8());
+    match a[..shared].rfind('/') {
+        Some(idx) => a[..=idx].to_string(),
+        None => "/".to_string(),
+    }
+}
+
+/// Translate an absolute glob into an anchored regex over full paths. `*` and `?` stay within
+/// one path segment, `**` as a whole segment spans any number of them, `[...]` is a character
+/// class (`!` or `^` negates), and `{a,b}` is an alternation, which may nest and whose
+/// alternatives may hold `/` and `**`.
+pub(crate) fn glob_to_regex(pattern: &str) -> Result<String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut regex = String::from("^");
+    let mut braces = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                // Inside braces, each alternative starts and ends a segment of its own.
+                let segment_start = i == 0
+                    || chars[i - 1] == '/'
+                    || (braces > 0 && matches!(chars[i - 1], '{' | ','));
+                let segment_end = match chars.get(i + 2) {
+                    None | Some('/') => true,
+                    Some(',' | '}') => braces > 0,
+                    _ => false,
+                };
+                i += 2;
+                if !(segment_start && segment_end) {
+                    regex.push_str("[^/]*");
+                    continue;
+                }
+                if chars.get(i) == Some(&'/') {
+                    regex.push_str("(?:.*/)?");
+                    i += 1;
+                } else {
+                    regex.push_str(".*");
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let close = class_end(&chars, i).ok_or(FsError::InvalidPath)?;
+                regex.push('[');
+                let mut j = i + 1;
+                if matches!(chars[j], '!' | '^') {
+                    regex.push('^');
+                    j += 1;
+                }
+                for &c in &chars[j..close] {
+                    if c == '-' || c.is_alphanumeric() {
+                        regex.push(c);
+                    } else {
+                        regex.push('\\');
+                        regex.push(c);
+                    }
+                }
+                regex.push(']');
+                i = close;
+            }
+            '{' => {
+                braces += 1;
+                regex.push_str("(?:");
+            }
+            '}' if braces > 0 => {
+                braces -= 1;
+                regex.push(')');
+            }
+            ',' if braces > 0 => regex.push('|'),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                regex.push_str(&regex::escape(&chars[i].to_string()));
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    if braces > 0 {
+        return Err(FsError::InvalidPath);
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+/// Index of the `]` closing the class opened at `open`; a `]` right after the opening
+/// bracket (or its negation) is taken literally.
+fn class_end(chars: &[char], open: usize) -> Option<usize> {
+    let mut j = open + 1;
+    if matches!(chars.get(j), Some('!' | '^')) {
+        j += 1;
+    }
+    if chars.get(j) == Some(&']') {
+        j += 1;
+    }
+    (j..chars.len()).find(|&k| chars[k] == ']')
+}
+
+/// The directory part of `pattern` before its first wildcard, ending in `/`, which every
+/// match must start with.
+pub(crate) fn literal_prefix(pattern: &str) -> String {
+    let literal = pattern
+        .find(['*', '?', '[', '{', '\\'])
+        .map_or(pattern, |idx| &pattern[..idx]);
+    match literal.rfind('/') {
+        Some(idx) => literal[..=idx].to_string(),
+        None => "/".to_string(),
+    }
+}
@@ -0,0 +1,77 @@
+use surrealdb::Connection;
+
+use crate::{Result, SurrealFs};
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Define `{table}_child` graph edges from each directory to its entries, kept in step
+    /// with the `parent` field by a table event, and relate every entry already stored.
+    /// Run it before building a handle with `SurrealFsBuilder::graph_edges`; safe to run
+    /// again, since the edges are rebuilt from scratch. Returns how many were created.
+    pub async fn define_graph_edges(&self) -> Result<usize> {
+        self.ensure_writable("/")?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "BEGIN TRANSACTION;\
+                         DEFINE TABLE IF NOT EXISTS {edge} TYPE RELATION IN {table} OUT {table};\
+                         DEFINE INDEX IF NOT EXISTS {edge}_out ON {edge} FIELDS out;\
+                         DEFINE EVENT OVERWRITE {edge} ON TABLE {table} WHEN $event != 'DELETE' AND ($event = 'CREATE' OR $before.parent != $after.parent) THEN {{\
+                             DELETE {edge} WHERE out = $after.id;\
+                             IF $after.parent != NONE {{ LET $parent = type::thing('{table}', $after.parent); RELATE $parent->{edge}->($after.id); }};\
+                         }};\
+                         DELETE {edge};\
+                         FOR $entry IN (SELECT id, parent FROM {table} WHERE parent != NONE) {{\
+                             LET $parent = type::thing($table, $entry.parent);\
+                             RELATE $parent->{edge}->($entry.id);\
+                         }};\
+                         RETURN count(SELECT id FROM {edge});\
+                         COMMIT TRANSACTION;",
+                        table = self.table,
+                        edge = self.edge_table(),
+                    ))
+                    .bind(("table", self.table.clone()))
+            })
+            .await?;
+        let related: Option<usize> = res.take(res.num_statements() - 1)?;
+        Ok(related.unwrap_or(0))
+    }
+
+    /// The relation table holding the entries' parent→child edges.
+    pub(crate) fn edge_table(&self) -> String {
+        format!("{}_child", self.table)
+    }
+
+    /// What to select the children of the directory `$parent` from: its outgoing edges
+    /// with `graph_edges`, the `parent` index otherwise. Bind `$table` and `$parent`.
+    pub(crate) fn children_source(&self) -> String {
+        if self.graph_edges {
+            format!(
+                "type::thing($table, $parent)->{}->{}",
+                self.edge_table(),
+                self.table
+            )
+        } else {
+            format!("{} WHERE parent = $parent", self.table)
+        }
+    }
+
+    /// What to select every entry beneath `$path` from: a recursive walk of the edges with
+    /// `graph_edges`, a scan for the `$prefix` otherwise. Bind `$table`, `$path`, and
+    /// `$prefix`.
+    pub(crate) fn descendants_source(&self) -> String {
+        if self.graph_edges {
+            format!(
+                "type::thing($table, $path).{{..+collect}}(->{}->{})",
+                self.edge_table(),
+                self.table
+            )
+        } else {
+            format!("{} WHERE string::starts_with(path, $prefix)", self.table)
+        }
+    }
+}
@@ -0,0 +1,746 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use futures::stream::{self, Stream};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{
+    ENTRY_FIELDS, Entry, FsError, Result, SurrealFs, compress, mime, normalize_path, procfs,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line_number: usize,
+    /// Last line of the match: `line_number` itself unless a `multiline` pattern spanned
+    /// several lines, which `line` then holds joined with `\n`.
+    pub end_line: usize,
+    pub line: String,
+    /// Set on the one match reported for a binary file the pattern matches, like grep's
+    /// "binary file matches" notice; its `line` is empty and its line numbers are 0.
+    #[serde(default)]
+    pub binary: bool,
+    /// Each match of the pattern within `line`, in order. Empty for inverted and binary
+    /// matches.
+    #[serde(default)]
+    pub submatches: Vec<Submatch>,
+}
+
+/// Where one match of the pattern falls within a `GrepMatch`'s line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Submatch {
+    /// Byte offset of the match within `line`.
+    pub start: usize,
+    /// Byte offset just past the match.
+    pub end: usize,
+    /// `start` counted in characters, for editors that place columns that way.
+    pub char_start: usize,
+    /// `end` counted in characters.
+    pub char_end: usize,
+    /// The matched text.
+    pub text: String,
+    /// What each named capture group that took part in the match captured.
+    pub captures: BTreeMap<String, String>,
+}
+
+/// How many lines of one file matched, as `grep_count` reports it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GrepCount {
+    pub path: String,
+    pub count: usize,
+}
+
+/// How `SurrealFs::grep_with` matches lines. The default is a case-sensitive regular
+/// expression searched for in one file, like plain `grep`.
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// Regular expression, or literal text with `fixed_strings`.
+    pub pattern: String,
+    /// Search directories and everything beneath them, like `grep -r`.
+    pub recursive: bool,
+    /// Match letters regardless of case, like `grep -i`.
+    pub ignore_case: bool,
+    /// Only match the pattern as a whole word, like `grep -w`.
+    pub word: bool,
+    /// Return the lines that do not match instead, like `grep -v`.
+    pub invert: bool,
+    /// Take `pattern` as literal text rather than a regular expression, like `grep -F`.
+    pub fixed_strings: bool,
+    /// Stop once this many lines have matched across all files, like `grep -m` over the
+    /// whole search rather than per file.
+    pub max_matches: Option<usize>,
+    /// Stop once this many files have matched.
+    pub max_files: Option<usize>,
+    /// Match the pattern against whole files so it can span lines, as `rg --multiline`
+    /// does: `^` and `$` still anchor at line boundaries, `\n` matches a line break, and
+    /// `(?s)` lets `.` match one too. Lines touched by overlapping matches are reported
+    /// once; with `invert`, the lines outside every match are.
+    pub multiline: bool,
+}
+
+impl GrepOptions {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            ..Self::default()
+        }
+    }
+
+    /// The regular expression the options describe, in the syntax both the `regex` crate
+    /// and SurrealDB's `string::matches` accept.
+    pub(crate) fn regex(&self) -> Result<Regex> {
+        let mut pattern = if self.fixed_strings {
+            regex::escape(&self.pattern)
+        } else {
+            self.pattern.clone()
+        };
+        if self.word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+        if self.ignore_case {
+            pattern = format!("(?i){}", pattern);
+        }
+        if self.multiline {
+            pattern = format!("(?m){}", pattern);
+        }
+        Regex::new(&pattern).map_err(|e| FsError::InvalidPattern(e.to_string()))
+    }
+
+    fn is_limited(&self) -> bool {
+        self.max_matches.is_some() || self.max_files.is_some()
+    }
+}
+
+/// Files examined per query by a limited or streaming `server_side_grep`, so the search can
+/// stop early.
+const GREP_BATCH: usize = 256;
+
+/// What a grep pass brings back from each file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Collect {
+    Lines,
+    Count,
+}
+
+/// One file's matches: `count` always, and the lines themselves when collecting lines.
+#[derive(Debug, Deserialize)]
+struct FileHits {
+    path: String,
+    #[serde(default)]
+    count: usize,
+    #[serde(default)]
+    hits: Vec<LineHit>,
+    #[serde(default)]
+    binary: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct LineHit {
+    line_number: usize,
+    end_line: usize,
+    line: String,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Lines matching `pattern` in the file at `path`, or in every file beneath it when
+    /// `recursive`. The short form of `grep_with` for a regular expression compiled by the
+    /// caller. `pattern` is used as given, `RegexBuilder` flags included, so lines are
+    /// matched here even under `server_side_grep`.
+    pub async fn grep(
+        &self,
+        pattern: &Regex,
+        path: impl AsRef<str>,
+        recursive: bool,
+    ) -> Result<Vec<GrepMatch>> {
+        let opts = GrepOptions {
+            pattern: pattern.as_str().to_string(),
+            recursive,
+            ..GrepOptions::default()
+        };
+        self.grep_lines(path.as_ref(), pattern, &opts, false).await
+    }
+
+    /// Lines of the file at `path`, or of every file beneath it with `opts.recursive`, that
+    /// match as `opts` describes, in walk order. A binary file is matched against its bytes
+    /// but its lines are not returned: it gets one match with `binary` set instead.
+    pub async fn grep_with(
+        &self,
+        path: impl AsRef<str>,
+        opts: &GrepOptions,
+    ) -> Result<Vec<GrepMatch>> {
+        self.grep_lines(path.as_ref(), &opts.regex()?, opts, self.server_side_grep)
+            .await
+    }
+
+    /// The matches of `pattern` under `path`, with the rest of `opts` applied; `pattern`
+    /// is only handed to SurrealDB when `in_database`.
+    async fn grep_lines(
+        &self,
+        path: &str,
+        pattern: &Regex,
+        opts: &GrepOptions,
+        in_database: bool,
+    ) -> Result<Vec<GrepMatch>> {
+        let files = self
+            .grep_scan(path, pattern, opts, Collect::Lines, in_database)
+            .await?;
+        Ok(files
+            .into_iter()
+            .flat_map(|file| grep_matches(pattern, opts, file))
+            .collect())
+    }
+
+    /// `grep_with` as a stream: matches are yielded as files are scanned, so the first ones
+    /// can be shown before a large recursive search is done. Content is fetched a file at a
+    /// time, or under `server_side_grep` a batch of `GREP_BATCH` files at a time, only as
+    /// the stream is polled. Stops at the limits in `opts`, or whenever the stream is
+    /// dropped.
+    pub fn grep_stream(
+        &self,
+        path: impl AsRef<str>,
+        opts: &GrepOptions,
+    ) -> impl Stream<Item = Result<GrepMatch>> + '_ {
+        let scan = GrepStream {
+            path: Some(path.as_ref().to_string()),
+            pattern: None,
+            source: GrepSource::Entries(Vec::new()),
+            limits: Limits::new(opts),
+            matches: VecDeque::new(),
+            opts: opts.clone(),
+        };
+
+        stream::try_unfold(scan, move |mut scan| async move {
+            loop {
+                if let Some(m) = scan.matches.pop_front() {
+                    return Ok(Some((m, scan)));
+                }
+                if let Some(path) = scan.path.take() {
+                    scan.pattern = Some(scan.opts.regex()?);
+                    scan.source = self
+                        .grep_source(&path, &scan.opts, true, self.server_side_grep)
+                        .await?;
+                }
+                let Some(pattern) = &scan.pattern else {
+                    return Ok(None);
+                };
+                if scan.limits.is_reached() {
+                    return Ok(None);
+                }
+                let Some(files) = self
+                    .grep_next(pattern, &scan.opts, &mut scan.source, Collect::Lines, true)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                for file in files {
+                    if scan.limits.is_reached() {
+                        break;
+                    }
+                    let file = scan.limits.admit(file);
+                    scan.matches.extend(grep_matches(pattern, &scan.opts, file));
+                }
+            }
+        })
+    }
+
+    /// How many lines match in each file that has any, like `grep -c` without the zeros.
+    /// A binary file that matches counts once. Only the counts leave the database under
+    /// `server_side_grep`.
+    pub async fn grep_count(
+        &self,
+        path: impl AsRef<str>,
+        opts: &GrepOptions,
+    ) -> Result<Vec<GrepCount>> {
+        let pattern = &opts.regex()?;
+        let files = self
+            .grep_scan(
+                path.as_ref(),
+                pattern,
+                opts,
+                Collect::Count,
+                self.server_side_grep,
+            )
+            .await?;
+        Ok(files
+            .into_iter()
+            .map(|file| GrepCount {
+                path: file.path,
+                count: file.count,
+            })
+            .collect())
+    }
+
+    /// Paths of the files with at least one matching line, like `grep -l`. Binary files
+    /// are included.
+    pub async fn grep_files(
+        &self,
+        path: impl AsRef<str>,
+        opts: &GrepOptions,
+    ) -> Result<Vec<String>> {
+        let pattern = &opts.regex()?;
+        let files = self
+            .grep_scan(
+                path.as_ref(),
+                pattern,
+                opts,
+                Collect::Count,
+                self.server_side_grep,
+            )
+            .await?;
+        Ok(files.into_iter().map(|file| file.path).collect())
+    }
+
+    /// The files under `path` with matches, in walk order, up to the limits in `opts`. With
+    /// a limit, file content is only fetched until the limit is reached.
+    async fn grep_scan(
+        &self,
+        path: &str,
+        pattern: &Regex,
+        opts: &GrepOptions,
+        collect: Collect,
+        in_database: bool,
+    ) -> Result<Vec<FileHits>> {
+        let mut source = self
+            .grep_source(path, opts, opts.is_limited(), in_database)
+            .await?;
+        let mut limits = Limits::new(opts);
+        let mut found = Vec::new();
+        while !limits.is_reached() {
+            let Some(files) = self
+                .grep_next(pattern, opts, &mut source, collect, opts.is_limited())
+                .await?
+            else {
+                break;
+            };
+            for file in files {
+                if limits.is_reached() {
+                    break;
+                }
+                found.push(limits.admit(file));
+            }
+        }
+        Ok(found)
+    }
+
+    /// Where a grep over `path` finds its files: SurrealDB when `in_database`, unless
+    /// under `/proc`. With `lazy`, entries matched here are listed without their content,
+    /// to be loaded one at a time.
+    async fn grep_source(
+        &self,
+        path: &str,
+        opts: &GrepOptions,
+        lazy: bool,
+        in_database: bool,
+    ) -> Result<GrepSource> {
+        let path = normalize_path(path)?;
+        let meta = self
+            .symlink_metadata(&path)
+            .await?
+            .ok_or_else(|| FsError::NotFound(path.clone()))?;
+        if meta.is_dir && !opts.recursive {
+            return Ok(GrepSource::Entries(Vec::new()));
+        }
+        if in_database && !procfs::is_proc_path(&path) {
+            return Ok(GrepSource::Database {
+                path,
+                recursive: meta.is_dir,
+                after: Some(String::new()),
+            });
+        }
+        let mut entries: Vec<Entry> = if !meta.is_dir {
+            self.get_entry(&path).await?.into_iter().collect()
+        } else {
+            self.subtree(&path, lazy, Route::Replica).await?
+        };
+        entries.reverse();
+        Ok(GrepSource::Entries(entries))
+    }
+
+    /// The next files with matches from `source`: one entry's worth when matching here,
+    /// a batch of the database's otherwise. `None` once the source is exhausted.
+    async fn grep_next(
+        &self,
+        pattern: &Regex,
+        opts: &GrepOptions,
+        source: &mut GrepSource,
+        collect: Collect,
+        batched: bool,
+    ) -> Result<Option<Vec<FileHits>>> {
+        match source {
+            GrepSource::Entries(entries) => {
+                let Some(mut entry) = entries.pop() else {
+                    return Ok(None);
+                };
+                entry.load_content().await?;
+                Ok(Some(
+                    grep_entry(pattern, opts, &entry, collect)?
+                        .into_iter()
+                        .collect(),
+                ))
+            }
+            GrepSource::Database {
+                path,
+                recursive,
+                after,
+            } => {
+                let Some(cursor) = after.take() else {
+                    return Ok(None);
+                };
+                let (files, next) = self
+                    .grep_in_database(pattern, opts, path, *recursive, collect, cursor, batched)
+                    .await?;
+                *after = next;
+                Ok(Some(files))
+            }
+        }
+    }
+
+    /// One pass of `grep` with the line splitting and matching done by SurrealDB, so text
+    /// files that do not match never leave the database, and only counts do when counting.
+    /// Byte-stored files, files without a text content type, and with `multiline` the files
+    /// the pattern matches somewhere in, are fetched and matched here. Files are taken in
+    /// walk order after the walk key `after`; when `batched`, only `GREP_BATCH` of them,
+    /// returning where the next batch starts unless this one was the last.
+    #[allow(clippy::too_many_arguments)]
+    async fn grep_in_database(
+        &self,
+        pattern: &Regex,
+        opts: &GrepOptions,
+        path: &str,
+        recursive: bool,
+        collect: Collect,
+        after: String,
+        batched: bool,
+    ) -> Result<(Vec<FileHits>, Option<String>)> {
+        let scope = if !recursive {
+            "path = $path"
+        } else if path == "/" {
+            "true"
+        } else {
+            "string::starts_with(path, $prefix)"
+        };
+        let lines = "IF content = NONE OR content = '' OR !text OR $multiline THEN [] ELSE (SELECT * FROM array::map(\
+                         string::split(IF string::ends_with($parent.content, \"\\n\") THEN string::slice($parent.content, 0, string::len($parent.content) - 1) ELSE $parent.content END, \"\\n\"),\
+                         |$line, $idx| { line_number: $idx + 1, end_line: $idx + 1, line: IF string::ends_with($line, \"\\r\") THEN string::slice($line, 0, string::len($line) - 1) ELSE $line END }\
+                     ) WHERE string::matches(line, $pattern) != $invert) END";
+        let (per_file, found) = match collect {
+            Collect::Lines => (format!("{lines} AS hits"), "array::len(hits) > 0"),
+            Collect::Count => (format!("array::len({lines}) AS count"), "count > 0"),
+        };
+        let limit = if batched {
+            format!("LIMIT {}", GREP_BATCH)
+        } else {
+            String::new()
+        };
+        // Replacing each `/` with a character below any other orders paths by component,
+        // as `subtree` walks them.
+        let sql = format!(
+            "LET $batch = (\
+                 SELECT path, inode.content ?? content AS content, inode.content_bytes ?? content_bytes AS content_bytes, {text} AS text, string::replace(path, '/', $sep) AS walk_key FROM {table} \
+                 WHERE is_dir = false AND (((inode.content ?? content) != NONE AND (inode.content ?? content) != '') OR (inode.content_bytes ?? content_bytes) != NONE) AND {scope} AND string::replace(path, '/', $sep) > $after \
+                 ORDER BY walk_key {limit}\
+             );\
+             SELECT * FROM (\
+                 SELECT path, content = NONE OR !text OR ($multiline AND ($invert OR string::matches(content, $pattern))) AS match_here, {per_file} FROM $batch\
+             ) WHERE match_here OR {found};\
+             RETURN {{ rows: array::len($batch), last: array::last($batch).walk_key }};",
+            table = self.table,
+            text = "(inode.content_type ?? content_type) != NONE AND (string::starts_with(inode.content_type ?? content_type, 'text/') OR (inode.content_type ?? content_type) IN $text_types)",
+        );
+
+        let (files, batch) = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run(|| {
+                    self.conn(Route::Replica)
+                        .query(&sql)
+                        .bind(("path", path.to_string()))
+                        .bind(("prefix", format!("{}/", path)))
+                        .bind(("pattern", pattern.as_str().to_string()))
+                        .bind(("invert", opts.invert))
+                        .bind(("multiline", opts.multiline))
+                        .bind(("text_types", mime::TEXT_APPLICATION_TYPES))
+                        .bind(("sep", "\u{1}"))
+                        .bind(("after", after.clone()))
+                })
+                .await?;
+            let files: Vec<BatchHits> = res.take(1)?;
+            let batch: Option<Batch> = res.take(2)?;
+            (files, batch.unwrap_or_default())
+        };
+
+        let match_here: Vec<String> = files
+            .iter()
+            .filter(|file| file.match_here)
+            .map(|file| file.hits.path.clone())
+            .collect();
+        let mut entries = self.entries_at(&match_here).await?.into_iter();
+        let mut hits = Vec::new();
+        for file in files {
+            if !file.match_here {
+                let mut file = file.hits;
+                if collect == Collect::Lines {
+                    file.count = file.hits.len();
+                }
+                hits.push(file);
+            } else if let Some(entry) = entries.next() {
+                hits.extend(grep_entry(pattern, opts, &entry, collect)?);
+            }
+        }
+        let next = batch.last.filter(|_| batched && batch.rows == GREP_BATCH);
+        Ok((hits, next))
+    }
+
+    /// The stored entries at `paths`, with their content, in the order given.
+    async fn entries_at(&self, paths: &[String]) -> Result<Vec<Entry>> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(Route::Replica)
+                    .query(format!(
+                        "SELECT {} FROM {} WHERE path IN $paths",
+                        ENTRY_FIELDS, self.table
+                    ))
+                    .bind(("paths", paths.to_vec()))
+            })
+            .await?;
+        let mut entries: Vec<Entry> = res.take(0)?;
+        entries.iter_mut().try_for_each(compress::unpack)?;
+        entries.sort_by_key(|entry| paths.iter().position(|p| *p == entry.path));
+        Ok(entries)
+    }
+}
+
+/// A row of `grep_in_database`'s batch: a text file's matches, or a file to fetch and
+/// match here.
+#[derive(Debug, Deserialize)]
+struct BatchHits {
+    #[serde(flatten)]
+    hits: FileHits,
+    match_here: bool,
+}
+
+/// How many files `grep_in_database` examined in one batch, and where the next one starts.
+#[derive(Debug, Default, Deserialize)]
+struct Batch {
+    rows: usize,
+    last: Option<String>,
+}
+
+/// The state of a `grep_stream`: the path and pattern still to be resolved on first poll,
+/// then the files left to search and the matches found but not yet yielded.
+struct GrepStream {
+    path: Option<String>,
+    pattern: Option<Regex>,
+    source: GrepSource,
+    limits: Limits,
+    matches: VecDeque<GrepMatch>,
+    opts: GrepOptions,
+}
+
+/// Where a grep finds the files to match.
+enum GrepSource {
+    /// Entries matched here, in reverse walk order so the next one is popped off the end.
+    Entries(Vec<Entry>),
+    /// Files matched by SurrealDB, resuming after the walk key `after` until it is `None`.
+    Database {
+        path: String,
+        recursive: bool,
+        after: Option<String>,
+    },
+}
+
+/// The `max_files` and `max_matches` still allowed as files with matches are taken in
+/// walk order.
+struct Limits {
+    files_left: Option<usize>,
+    matches_left: Option<usize>,
+}
+
+impl Limits {
+    fn new(opts: &GrepOptions) -> Self {
+        Self {
+            files_left: opts.max_files,
+            matches_left: opts.max_matches,
+        }
+    }
+
+    fn is_reached(&self) -> bool {
+        self.files_left == Some(0) || self.matches_left == Some(0)
+    }
+
+    /// Take a file's matches, cut down to the lines still allowed.
+    fn admit(&mut self, mut file: FileHits) -> FileHits {
+        if let Some(left) = &mut self.files_left {
+            *left -= 1;
+        }
+        if let Some(left) = &mut self.matches_left {
+            file.count = file.count.min(*left);
+            file.hits.truncate(*left);
+            *left -= file.count;
+        }
+        file
+    }
+}
+
+/// A file's matches as `grep_with` returns them.
+fn grep_matches(
+    pattern: &Regex,
+    opts: &GrepOptions,
+    file: FileHits,
+) -> impl Iterator<Item = GrepMatch> {
+    let (path, binary) = (file.path, file.binary);
+    file.hits.into_iter().map(move |hit| GrepMatch {
+        path: path.clone(),
+        line_number: hit.line_number,
+        end_line: hit.end_line,
+        submatches: if binary || opts.invert {
+            Vec::new()
+        } else {
+            submatches(pattern, &hit.line)
+        },
+        line: hit.line,
+        binary,
+    })
+}
+
+/// The matches in one entry, or `None` when it has none or is not a file. A binary file is
+/// matched against its bytes, read lossily as UTF-8, and reported as a single notice.
+fn grep_entry(
+    pattern: &Regex,
+    opts: &GrepOptions,
+    entry: &Entry,
+    collect: Collect,
+) -> Result<Option<FileHits>> {
+    if entry.is_dir || entry.symlink.is_some() {
+        return Ok(None);
+    }
+    let binary = entry.is_binary();
+    let content = if binary {
+        entry
+            .bytes()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    } else {
+        entry.text()?
+    };
+    let Some(content) = content else {
+        return Ok(None);
+    };
+    let hits = if opts.multiline {
+        multiline_hits(pattern, opts.invert, &content)
+    } else {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| pattern.is_match(line) != opts.invert)
+            .map(|(idx, line)| LineHit {
+                line_number: idx + 1,
+                end_line: idx + 1,
+                line: line.to_string(),
+            })
+            .collect()
+    };
+    if hits.is_empty() {
+        return Ok(None);
+    }
+    let hits = if binary {
+        vec![LineHit {
+            line_number: 0,
+            end_line: 0,
+            line: String::new(),
+        }]
+    } else {
+        hits
+    };
+    Ok(Some(FileHits {
+        path: entry.path.clone(),
+        count: hits.len(),
+        hits: match collect {
+            Collect::Lines => hits,
+            Collect::Count => Vec::new(),
+        },
+        binary,
+    }))
+}
+
+/// Every match of `pattern` in a matched `line`, with its named captures.
+fn submatches(pattern: &Regex, line: &str) -> Vec<Submatch> {
+    let column = |at: usize| line[..at].chars().count();
+    pattern
+        .captures_iter(line)
+        .map(|caps| {
+            let whole = caps.get(0).expect("group 0 always matches");
+            Submatch {
+                start: whole.start(),
+                end: whole.end(),
+                char_start: column(whole.start()),
+                char_end: column(whole.end()),
+                text: whole.as_str().to_string(),
+                captures: pattern
+                    .capture_names()
+                    .flatten()
+                    .filter_map(|name| {
+                        Some((name.to_string(), caps.name(name)?.as_str().to_string()))
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Matches of `pattern` across the whole of `content`, each widened to the lines it touches
+/// and merged with any match sharing a line. With `invert`, the lines no match touches.
+fn multiline_hits(pattern: &Regex, invert: bool, content: &str) -> Vec<LineHit> {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.is_empty() {
+        return Vec::new();
+    }
+    let starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(at, _)| at + 1))
+        .collect();
+    let line_of =
+        |offset: usize| (starts.partition_point(|&s| s <= offset) - 1).min(lines.len() - 1);
+
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for m in pattern.find_iter(content) {
+        let first = line_of(m.start());
+        let last = if m.is_empty() {
+            first
+        } else {
+            line_of(m.end() - 1)
+        };
+        match spans.last_mut() {
+            Some(span) if first <= span.1 => span.1 = span.1.max(last),
+            _ => spans.push((first, last)),
+        }
+    }
+
+    let hit = |first: usize, last: usize| LineHit {
+        line_number: first + 1,
+        end_line: last + 1,
+        line: lines[first..=last].join("\n"),
+    };
+    if !invert {
+        return spans
+            .into_iter()
+            .map(|(first, last)| hit(first, last))
+            .collect();
+    }
+    let mut touched = vec![false; lines.len()];
+    for (first, last) in spans {
+        touched[first..=last].fill(true);
+    }
+    (0..lines.len())
+        .filter(|&idx| !touched[idx])
+        .map(|idx| hit(idx, idx))
+        .collect()
+}
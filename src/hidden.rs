@@ -0,0 +1,33 @@
+use surrealdb::Connection;
+
+use crate::{FsError, Result, SurrealFs, normalize_path};
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Set or clear the hidden flag on `path` itself, without following symlinks. Listings
+    /// treat flagged entries like dotfiles; see `Node::is_hidden`.
+    pub async fn set_hidden(&self, path: impl AsRef<str>, hidden: bool) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        self.ensure_writable(&path)?;
+        if self.symlink_metadata(&path).await?.is_none() {
+            return Err(FsError::NotFound(path));
+        }
+
+        let _permit = self.permit().await;
+        self.forget(&[&path]);
+        self.run(|| {
+            self.db
+                .query("UPDATE type::thing($table, $path) SET hidden = $hidden")
+                .bind(("table", self.table.clone()))
+                .bind(("path", path.clone()))
+                .bind(("hidden", hidden))
+        })
+        .await?;
+        Ok(())
+    }
+}
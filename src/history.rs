@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::usage::SIZE_EXPR;
+use crate::{Entry, FsError, Result, SurrealFs, compress, normalize_path};
+
+/// A previous revision of a file, kept once `enable_history` is on.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Revision {
+    pub path: String,
+    /// The file's `version` while this content was current; pass it to `cat_revision`.
+    pub version: u64,
+    pub size: usize,
+    #[serde(default)]
+    pub checksum: Option<String>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// When this content was written.
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+    /// When it was overwritten.
+    #[serde(default)]
+    pub replaced_at: Option<i64>,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Keep the previous content of a file whenever a write changes it, in the
+    /// `{table}_versions` table, up to the `keep` most recent revisions per path (at least
+    /// one), or all of them with `None`. SurrealDB events on the entry and inode tables
+    /// record them, so every handle and every kind of write is covered: overwrites, edits,
+    /// appends, transactions, writes through hard links (kept under each of their paths),
+    /// and replacing a file with `write_file_atomic`, `mv` or `exchange`. Revisions stay
+    /// under the path they were written at, after the file is moved or removed too. Run
+    /// again to change `keep`; older revisions beyond it go at the file's next write.
+    pub async fn enable_history(&self, keep: Option<usize>) -> Result<()> {
+        self.ensure_writable("/")?;
+        let table = &self.table;
+        // The content `$old` had, kept under `$link`'s path.
+        let mut revision = format!(
+            "CREATE {table}_versions SET path = $link.path, name = $link.name, is_dir = false, version = $old.version ?? 1, content = $old.content, content_bytes = $old.content_bytes, compression = $old.compression, content_type = $old.content_type, encoding = $old.encoding, checksum = $old.checksum, size = $old.size, updated_at = $old.updated_at, replaced_at = $replaced_at;"
+        );
+        if let Some(keep) = keep {
+            revision.push_str(&format!(
+                "DELETE (SELECT id, version FROM {table}_versions WHERE path = $link.path ORDER BY version DESC START {keep});",
+                keep = keep.max(1),
+            ));
+        }
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db.query(format!(
+                "DEFINE INDEX IF NOT EXISTS {table}_versions_path ON {table}_versions FIELDS path;\
+                 DEFINE EVENT OVERWRITE {table}_history ON {table} \
+                 WHEN $event = 'UPDATE' AND $before.is_dir = false AND ($before.inode.checksum ?? $before.checksum) != ($after.inode.checksum ?? $after.checksum) THEN {{\
+                     LET $link = $before;\
+                     LET $old = IF type::is::record($before.inode) THEN $before.inode.* ELSE $before END;\
+                     LET $replaced_at = $after.inode.updated_at ?? $after.updated_at;\
+                     {revision}\
+                 }};\
+                 DEFINE EVENT OVERWRITE {table}_inode_history ON {table}_inode \
+                 WHEN $event = 'UPDATE' AND $before.checksum != $after.checksum THEN {{\
+                     LET $old = $before;\
+                     LET $replaced_at = $after.updated_at;\
+                     FOR $link IN (SELECT path, name FROM {table} WHERE inode = $after.id) {{\
+                         {revision}\
+                     }};\
+                 }};",
+            ))
+        })
+        .await?
+        .check()?;
+        Ok(())
+    }
+
+    /// Stop keeping revisions. Those already kept stay readable.
+    pub async fn disable_history(&self) -> Result<()> {
+        self.ensure_writable("/")?;
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db.query(format!(
+                "REMOVE EVENT IF EXISTS {table}_history ON {table};\
+                 REMOVE EVENT IF EXISTS {table}_inode_history ON {table}_inode;",
+                table = self.table,
+            ))
+        })
+        .await?
+        .check()?;
+        Ok(())
+    }
+
+    /// The kept revisions of the file at `path`, newest first. The current content is not
+    /// among them; see `metadata` for it.
+    pub async fn history(&self, path: impl AsRef<str>) -> Result<Vec<Revision>> {
+        let path = normalize_path(path.as_ref())?;
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT path, version, {size} AS size, checksum, content_type, updated_at, replaced_at \
+                         FROM {table}_versions WHERE path = $path ORDER BY version DESC",
+                        size = SIZE_EXPR,
+                        table = self.table,
+                    ))
+                    .bind(("path", path.clone()))
+            })
+            .await?;
+        let revisions: Vec<Revision> = res.take(0)?;
+        Ok(revisions)
+    }
+
+    /// The text of the revision of `path` that was current at `version`. Binary revisions
+    /// are refused with `FsError::Binary`.
+    pub async fn cat_revision(&self, path: impl AsRef<str>, version: u64) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        let mut revision: Entry = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run(|| {
+                    self.db
+                        .query(format!(
+                            "SELECT path, name, is_dir, content, content_bytes, compression, content_type, encoding, checksum, version \
+                             FROM {}_versions WHERE path = $path AND version = $version LIMIT 1",
+                            self.table,
+                        ))
+                        .bind(("path", path.clone()))
+                        .bind(("version", version))
+                })
+                .await?;
+            let revision: Option<Entry> = res.take(0)?;
+            revision.ok_or_else(|| FsError::NotFound(format!("{}@{}", path, version)))?
+        };
+        compress::unpack(&mut revision)?;
+        if revision.is_binary() {
+            return Err(FsError::Binary(path));
+        }
+        Ok(revision.text()?.unwrap_or_default())
+    }
+}
@@ -0,0 +1,65 @@
+use surrealdb::Connection;
+
+use crate::{FsError, Result, SurrealFs, normalize_path};
+
+/// Refuse to modify or remove an entry flagged immutable.
+pub(crate) fn ensure_mutable(path: &str, immutable: bool) -> Result<()> {
+    if immutable {
+        return Err(FsError::Immutable(path.to_string()));
+    }
+    Ok(())
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Set or clear the immutable flag on `path`, like `chattr +i`. While set, writes, edits,
+    /// renames and removal fail with `Immutable`. A trailing symlink is followed and hard
+    /// links share the flag.
+    pub async fn set_immutable(&self, path: impl AsRef<str>, immutable: bool) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        self.ensure_writable(&path)?;
+        let (path, entry) = self.follow(path).await?;
+        let entry = entry.ok_or_else(|| FsError::NotFound(path.clone()))?;
+
+        let _permit = self.permit().await;
+        self.forget(&[&path]);
+        self.run(|| {
+            self.db
+                .query(format!(
+                    "UPDATE {} SET immutable = $immutable WHERE path = $path OR (inode != NONE AND inode = $inode)",
+                    self.table
+                ))
+                .bind(("path", path.clone()))
+                .bind(("inode", self.inode_record(&entry.inode)))
+                .bind(("immutable", immutable))
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Fail with `Immutable` when anything strictly below directory `path` is immutable, so a
+    /// recursive removal or rename cannot take a protected file with it.
+    pub(crate) async fn ensure_subtree_mutable(&self, path: &str) -> Result<()> {
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT VALUE path FROM {} WHERE string::starts_with(path, $prefix) AND immutable = true LIMIT 1",
+                        self.table
+                    ))
+                    .bind(("prefix", format!("{}/", path)))
+            })
+            .await?;
+        let locked: Option<String> = res.take(0)?;
+        match locked {
+            Some(locked) => Err(FsError::Immutable(locked)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use surrealdb::{Connection, RecordId};
+
+use crate::SurrealFs;
+
+/// An entry's `updated_at`, which a hard-linked file keeps on its inode.
+pub(crate) const UPDATED_AT_EXPR: &str = "(inode.updated_at ?? updated_at)";
+
+/// An entry's `version`, likewise kept on the inode of a hard-linked file.
+pub(crate) const VERSION_EXPR: &str = "(inode.version ?? version ?? 1)";
+
+/// SurrealQL for the record holding the content of the file at `path`, itself a SurrealQL
+/// expression: the inode it shares with its hard links, or its own record when it has none.
+/// Bind `$table`.
+pub(crate) fn content_record(path: &str) -> String {
+    format!(
+        "((SELECT VALUE inode FROM type::thing($table, {path}) WHERE type::is::record(inode))[0] ?? type::thing($table, {path}))"
+    )
+}
+
+/// SurrealQL dropping one link from each inode in the array `inodes` and removing the inodes
+/// no path holds any more. Other values are skipped, so the `inode` fields of removed rows
+/// can be passed as they are.
+pub(crate) fn release(inodes: &str) -> String {
+    format!(
+        "FOR $inode IN {inodes} {{ IF type::is::record($inode) {{ UPDATE $inode SET links -= 1; IF $inode.links <= 0 {{ DELETE $inode; }}; }}; }};"
+    )
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// The table holding the content of hard-linked files, one record per file.
+    pub(crate) fn inode_table(&self) -> String {
+        format!("{}_inode", self.table)
+    }
+
+    /// The record behind an entry's `inode`, to bind where rows are matched against it.
+    pub(crate) fn inode_record(&self, inode: &Option<String>) -> Option<RecordId> {
+        inode
+            .as_deref()
+            .map(|key| RecordId::from_table_key(self.inode_table(), key))
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use surrealdb::Connection;
+
+use crate::usage::SIZE_EXPR;
+use crate::{ENTRY_FIELDS, Entry, FsError, Result, SurrealFs, compress, retry};
+
+type Loader = Arc<dyn Fn() -> BoxFuture<'static, Result<Entry>> + Send + Sync>;
+
+/// Stands in for the content of an entry returned without it, as `walk`, `find`, and `tree`
+/// return entries; `Entry::load_content` fetches it. Entries loaded with their content carry
+/// an empty handle.
+#[derive(Clone, Default)]
+pub struct ContentHandle {
+    loader: Option<Loader>,
+    size: usize,
+}
+
+impl ContentHandle {
+    /// Whether the content still has to be fetched.
+    pub fn is_pending(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Size of the content not yet fetched, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl fmt::Debug for ContentHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentHandle")
+            .field("pending", &self.is_pending())
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl PartialEq for ContentHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_pending() == other.is_pending() && self.size == other.size
+    }
+}
+
+impl Eq for ContentHandle {}
+
+impl Entry {
+    /// Fetch the content of an entry returned without it, so `text`, `bytes`, and `size`
+    /// describe the file as stored now. A no-op for entries that already have it.
+    pub async fn load_content(&mut self) -> Result<()> {
+        let Some(loader) = self.content_handle.loader.clone() else {
+            return Ok(());
+        };
+        let loaded = loader().await?;
+        self.content = loaded.content;
+        self.content_bytes = loaded.content_bytes;
+        self.content_handle = ContentHandle::default();
+        Ok(())
+    }
+}
+
+/// An entry row selected without content, with its size computed server-side.
+#[derive(Deserialize)]
+pub(crate) struct LazyRow {
+    #[serde(flatten)]
+    entry: Entry,
+    size: usize,
+}
+
+/// Columns selected for entries whose content is fetched on demand.
+pub(crate) fn lazy_entry_fields() -> String {
+    format!(
+        "path, name, parent, is_dir, inode.content_type ?? content_type AS content_type, inode.encoding ?? encoding AS encoding, inode.checksum ?? checksum AS checksum, mode, owner, group, xattrs ?? {{}} AS xattrs, immutable ?? false AS immutable, hidden ?? false AS hidden, created_at, inode.updated_at ?? updated_at AS updated_at, accessed_at, expires_at, inode.version ?? version ?? 1 AS version, IF type::is::record(inode) THEN record::id(inode) END AS inode, symlink, {} AS size",
+        SIZE_EXPR
+    )
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Turn rows selected with `lazy_entry_fields` into entries that fetch their content
+    /// through this filesystem's connection when asked to.
+    pub(crate) fn lazy_entries(&self, rows: Vec<LazyRow>) -> Vec<Entry> {
+        rows.into_iter()
+            .map(|LazyRow { mut entry, size }| {
+                if !entry.is_dir && entry.symlink.is_none() {
+                    entry.content_handle = self.content_handle(&entry.path, size);
+                }
+                entry
+            })
+            .collect()
+    }
+
+    fn content_handle(&self, path: &str, size: usize) -> ContentHandle {
+        let db = self.db.clone();
+        let table = self.table.clone();
+        let limiter = self.limiter.clone();
+        let policy = self.retry;
+        let path = path.to_string();
+        let loader: Loader = Arc::new(move || {
+            let (db, table, limiter, path) =
+                (db.clone(), table.clone(), limiter.clone(), path.clone());
+            Box::pin(async move {
+                let _permit = match &limiter {
+                    Some(limiter) => limiter.acquire().await.ok(),
+                    None => None,
+                };
+                let mut res = retry::retrying(policy, || {
+                    db.query(format!(
+                        "SELECT {} FROM type::thing($table, $path)",
+                        ENTRY_FIELDS
+                    ))
+                    .bind(("table", table.clone()))
+                    .bind(("path", path.clone()))
+                })
+                .await?;
+                let entry: Option<Entry> = res.take(0)?;
+                let mut entry = entry.ok_or_else(|| FsError::NotFound(path.clone()))?;
+                compress::unpack(&mut entry)?;
+                Ok(entry)
+            })
+        });
+        ContentHandle {
+            loader: Some(loader),
+            size,
+        }
+    }
+}
@@ -1,21 +1,110 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use globset::{GlobBuilder, GlobSetBuilder};
-use regex::Regex;
+use futures::{StreamExt, TryStreamExt};
 use rimage::codecs::{
     avif::AvifEncoder, mozjpeg::MozJpegEncoder, oxipng::OxiPngEncoder, webp::WebPEncoder,
 };
 use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use similar::{ChangeTag, TextDiff};
+use surrealdb::error::{Api, Db};
 use surrealdb::{Surreal, engine::remote::ws::Client};
 use thiserror::Error;
+use tokio::sync::{Semaphore, SemaphorePermit};
 use zune_core::{bytestream::ZCursor, options::DecoderOptions};
 use zune_image::{image::Image, traits::EncoderTrait};
 
 pub type Result<T> = std::result::Result<T, FsError>;
 
+/// Columns selected whenever whole entries are loaded. Content and what describes it are
+/// read from the file's inode when it has hard links.
+pub(crate) const ENTRY_FIELDS: &str = "path, name, parent, is_dir, inode.content ?? content AS content, inode.content_bytes ?? content_bytes AS content_bytes, inode.content_type ?? content_type AS content_type, inode.encoding ?? encoding AS encoding, inode.compression ?? compression AS compression, inode.checksum ?? checksum AS checksum, mode, owner, group, xattrs ?? {} AS xattrs, immutable ?? false AS immutable, hidden ?? false AS hidden, created_at, inode.updated_at ?? updated_at AS updated_at, accessed_at, expires_at, inode.version ?? version ?? 1 AS version, IF type::is::record(inode) THEN record::id(inode) END AS inode, symlink";
+
+mod buffer;
+mod cache;
+mod checksum;
+mod compress;
 pub mod curl;
+mod defaults;
+mod dump;
+mod encoding;
+mod engine;
+mod expire;
+mod find;
+mod fsck;
+mod glob;
+mod graph;
+mod grep;
+mod hidden;
+mod history;
+mod immutable;
+mod inode;
+mod lazy;
+mod locate;
+mod maintenance;
+mod metadata;
+mod mime;
+mod mode;
+mod mount;
+mod node;
+mod overlay;
+mod owner;
+mod procfs;
+mod recent;
+mod replace;
+mod replica;
+mod retry;
+mod schema;
+mod search;
+mod split;
+mod status;
+mod stream;
+mod symbols;
+mod symlink;
+mod text;
+mod transaction;
+mod trash;
+mod tree;
+mod usage;
+mod xattr;
+
+pub use checksum::Verification;
+pub use compress::{Compressed, Compression};
+pub use defaults::DEFAULT_UMASK;
+pub use dump::{DUMP_FORMAT, DUMP_VERSION};
+pub use engine::{DEFAULT_DATABASE, DEFAULT_NAMESPACE};
+pub use find::{EntryKind, FindOptions};
+pub use fsck::FsckReport;
+pub use glob::GlobOptions;
+pub use grep::{GrepCount, GrepMatch, GrepOptions, Submatch};
+pub use history::Revision;
+pub use lazy::ContentHandle;
+pub use maintenance::VacuumReport;
+pub use metadata::Metadata;
+pub use mime::is_text_type;
+pub use mode::{DEFAULT_DIR_MODE, DEFAULT_FILE_MODE, format_mode};
+pub use mount::Mounts;
+pub use node::{Attributes, DirEntry, FileContent, FileEntry, Node, SymlinkEntry};
+pub use overlay::{OPAQUE_XATTR, Overlay, WHITEOUT_XATTR};
+pub use replace::FileReplacement;
+pub use retry::RetryPolicy;
+pub use schema::{IndexCheck, IndexReport, IndexStatus, SCHEMA_VERSION};
+pub use search::SearchHit;
+pub use split::SplitBy;
+pub use status::Status;
+pub use symbols::{Symbol, SymbolKind};
+pub use text::{NewlinePolicy, SortOptions, UniqLine};
+pub use transaction::Transaction;
+pub use trash::{TRASH_DIR, TrashItem};
+pub use tree::TreeNode;
+pub use usage::{DirUsage, DiskUsage, FileSize, TreeStats, TypeStats, Usage};
+
+use buffer::WriteBuffer;
+use cache::EntryCache;
+use defaults::EntryDefaults;
+use replica::Route;
 
 #[cfg(feature = "python")]
 pub mod python;
@@ -32,12 +121,45 @@ pub enum FsError {
     NotADirectory(String),
     #[error("invalid path")]
     InvalidPath,
+    #[error("directory not empty: {0}")]
+    DirectoryNotEmpty(String),
+    #[error("not a symlink: {0}")]
+    NotASymlink(String),
+    #[error("too many levels of symbolic links: {0}")]
+    SymlinkLoop(String),
+    #[error("no match for edit: {0}")]
+    NoMatch(String),
+    #[error("line out of range: {0}")]
+    LineOutOfRange(String),
+    #[error("immutable: {0}")]
+    Immutable(String),
+    #[error("version conflict: {0}")]
+    Conflict(String),
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("read-only: {0}")]
+    ReadOnly(String),
     #[error("invalid utf-8 for: {0}")]
     InvalidUtf8(String),
+    #[error("binary file: {0}")]
+    Binary(String),
+    #[error("corrupted content: {0}")]
+    Corrupted(String),
+    #[error("invalid dump: {0}")]
+    InvalidDump(String),
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(String),
     #[error("http error: {0}")]
     Http(String),
+    /// Boxed, as the database error is several times the size of every other variant.
     #[error("database error: {0}")]
-    Surreal(#[from] surrealdb::Error),
+    Surreal(Box<surrealdb::Error>),
+}
+
+impl From<surrealdb::Error> for FsError {
+    fn from(err: surrealdb::Error) -> Self {
+        FsError::Surreal(Box::new(err))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -49,8 +171,63 @@ pub struct Entry {
     pub content: Option<String>,
     #[serde(default)]
     pub content_bytes: Option<ByteBuf>,
+    /// MIME type detected from the content and extension on every write; `None` for
+    /// directories, symlinks, and entries written before it was tracked.
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Legacy text encoding detected in `content_bytes`, such as `utf-16le` or `iso-8859-1`;
+    /// `text` transcodes from it. `None` for UTF-8 and binary content.
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// Set on rows stored compressed. Entries are decompressed as they are loaded, so this
+    /// is `None` on any entry handed out by the filesystem.
+    #[serde(default)]
+    pub compression: Option<Compressed>,
+    /// Lowercase hex SHA-256 of the content, recomputed on every write; see `verify`.
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Permission bits such as `0o644`; see `permissions` for the value that applies.
+    #[serde(default)]
+    pub mode: Option<u32>,
+    /// Principal the entry belongs to, set with `chown`; `None` when unattributed.
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Application-defined attributes managed with `setxattr` and friends.
+    #[serde(default)]
+    pub xattrs: BTreeMap<String, String>,
+    /// Blocks writes, renames, and removal until cleared with `set_immutable`.
+    #[serde(default)]
+    pub immutable: bool,
+    /// Left out of listings unless asked for, like a dotfile; set with `set_hidden`.
+    #[serde(default)]
+    pub hidden: bool,
+    /// Set once when the entry is created; `None` for entries written before it was tracked.
+    #[serde(default)]
+    pub created_at: Option<i64>,
     #[serde(default)]
     pub updated_at: Option<i64>,
+    /// Last read, recorded only when the filesystem was built with `track_access`.
+    #[serde(default)]
+    pub accessed_at: Option<i64>,
+    /// When `expire` may remove the file, for files written with `write_file_with_ttl`.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// Bumped on every content write, starting at 1; pass it to `write_file_if` to detect
+    /// changes made since it was read.
+    #[serde(default)]
+    pub version: u64,
+    /// Key of the `{table}_inode` record holding the content of a hard-linked file, shared
+    /// by all of its paths; `None` for a file that was never linked.
+    #[serde(default)]
+    pub inode: Option<String>,
+    /// Target of a symbolic link, stored as given; `None` for regular files and directories.
+    #[serde(default)]
+    pub symlink: Option<String>,
+    /// Pending content of an entry returned without it; see `load_content`.
+    #[serde(skip)]
+    pub content_handle: ContentHandle,
 }
 
 impl Entry {
@@ -58,21 +235,47 @@ impl Entry {
         if self.is_dir {
             return 0;
         }
+        if self.content_handle.is_pending() {
+            return self.content_handle.size();
+        }
         if let Some(bytes) = &self.content_bytes {
             return bytes.len();
         }
         self.content.as_ref().map(|c| c.len()).unwrap_or(0)
     }
 
+    /// Whether this is a file holding binary data rather than text; see `is_text`.
     pub fn is_binary(&self) -> bool {
-        self.content_bytes.is_some() && self.content.is_none()
+        !self.is_dir && self.symlink.is_none() && !self.is_text()
+    }
+
+    /// The permission bits that apply, with defaults for entries stored without a mode.
+    pub fn permissions(&self) -> u32 {
+        mode::effective(self.is_dir, self.symlink.is_some(), self.mode)
+    }
+
+    /// Whether the content should be treated as text, going by the stored content type and
+    /// detecting it from the content for entries stored without one.
+    pub fn is_text(&self) -> bool {
+        match &self.content_type {
+            Some(content_type) => is_text_type(content_type),
+            None => mime::of_entry(self).is_some_and(|ty| is_text_type(&ty)),
+        }
     }
 
+    /// The content as text, transcoded to UTF-8 when a legacy `encoding` was detected.
     pub fn text(&self) -> Result<Option<String>> {
         if let Some(content) = &self.content {
             return Ok(Some(content.clone()));
         }
         if let Some(bytes) = &self.content_bytes {
+            if let Some(text) = self
+                .encoding
+                .as_deref()
+                .and_then(|enc| encoding::decode(bytes, enc))
+            {
+                return Ok(Some(text));
+            }
             let text = String::from_utf8(bytes.clone().into_vec())
                 .map_err(|_| FsError::InvalidUtf8(self.path.clone()))?;
             return Ok(Some(text));
@@ -94,11 +297,40 @@ pub struct NumberedLine {
     pub line: String,
 }
 
+/// Newline, word, and byte counts for a file, matching `wc` semantics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WordCount {
+    pub lines: usize,
+    pub words: usize,
+    pub bytes: usize,
+}
+
+/// One replacement applied by `SurrealFs::edit_many`, with the same semantics as `edit`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EditOp {
+    pub old: String,
+    pub new: String,
+    pub replace_all: bool,
+}
+
+/// Destination policy for `SurrealFs::cp` and `SurrealFs::cp_into`. The default overwrites
+/// existing files and gives copies fresh attributes, like plain `cp`.
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// Fail with `AlreadyExists` instead of replacing an existing destination, like `cp -n`.
+    pub no_clobber: bool,
+    /// Carry over the source's mode, owner, group, extended attributes, and `updated_at`,
+    /// like `cp -p`.
+    pub preserve: bool,
+}
+
+/// One page of `SurrealFs::ls_page` or `SurrealFs::glob_page`. Pass `next` back as the
+/// cursor to fetch the following page; it is `None` on the last one. Cursors are opaque and
+/// stay valid while entries are added or removed around them.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub struct GrepMatch {
-    pub path: String,
-    pub line_number: usize,
-    pub line: String,
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<String>,
 }
 
 /// SurrealDB-backed filesystem facade. The client connection is provided by the caller.
@@ -107,7 +339,231 @@ where
     DB: surrealdb::Connection,
 {
     db: Surreal<DB>,
+    replica: Option<Surreal<DB>>,
+    endpoint: Option<String>,
+    table: String,
+    limiter: Option<Arc<Semaphore>>,
+    cache: Option<Arc<EntryCache>>,
+    buffer: Option<Arc<WriteBuffer>>,
+    retry: RetryPolicy,
+    quota_bytes: Option<usize>,
+    track_access: bool,
+    trash: bool,
+    trash_retention: Option<Duration>,
+    propagate_updated_at: bool,
+    parallelism: usize,
+    graph_edges: bool,
+    server_side_grep: bool,
+    compression: Compression,
+    newline: NewlinePolicy,
+    defaults: EntryDefaults,
+    read_only: bool,
+}
+
+/// Configures a `SurrealFs` before use. Obtain one with `SurrealFs::builder`.
+pub struct SurrealFsBuilder<DB = Client>
+where
+    DB: surrealdb::Connection,
+{
+    db: Surreal<DB>,
+    replica: Option<Surreal<DB>>,
+    endpoint: Option<String>,
     table: String,
+    max_concurrency: Option<usize>,
+    cache: Option<(usize, Duration)>,
+    write_buffer: Option<(usize, Duration)>,
+    retry: RetryPolicy,
+    quota_bytes: Option<usize>,
+    track_access: bool,
+    trash: bool,
+    trash_retention: Option<Duration>,
+    propagate_updated_at: bool,
+    parallelism: usize,
+    graph_edges: bool,
+    server_side_grep: bool,
+    compression: Compression,
+    newline: NewlinePolicy,
+    defaults: EntryDefaults,
+}
+
+impl<DB> SurrealFsBuilder<DB>
+where
+    DB: surrealdb::Connection,
+{
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Cap the number of SurrealDB queries in flight at once; extra callers wait their turn.
+    /// Zero is treated as one.
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        self.max_concurrency = Some(limit.max(1));
+        self
+    }
+
+    /// Send the heavy reads behind `glob`, `find`, `walk`, `tree`, and recursive `grep` to
+    /// `replica`, a read-only connection to a replica of the same data, while writes and the
+    /// lookups they depend on stay on the primary. Results may trail recent writes by the
+    /// replica's lag.
+    pub fn read_replica(mut self, replica: Surreal<DB>) -> Self {
+        self.replica = Some(replica);
+        self
+    }
+
+    /// Keep up to `capacity` entries and directory listings in memory for `ttl`, so repeated
+    /// lookups of the same paths skip the database. Writes made through this handle
+    /// invalidate what they touch; changes by other clients are seen once `ttl` passes, or as
+    /// they happen after `SurrealFs::subscribe_cache`.
+    pub fn cache(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.cache = Some((capacity, ttl));
+        self
+    }
+
+    /// Queue `append_file` calls in memory and write them out together, once `max_pending`
+    /// appends are waiting, every `max_delay` on a background tokio task, or on `flush`.
+    /// Reads through this handle flush first, so they see queued text; appends still queued
+    /// when the handle is dropped are written out in the background.
+    pub fn write_buffer(mut self, max_pending: usize, max_delay: Duration) -> Self {
+        self.write_buffer = Some((max_pending, max_delay));
+        self
+    }
+
+    /// Retry queries that fail because the connection dropped or the database timed out,
+    /// instead of returning `FsError::Surreal` on the first hiccup.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Byte budget reported by `usage()` and `/proc/quota`.
+    pub fn quota_bytes(mut self, limit: usize) -> Self {
+        self.quota_bytes = Some(limit);
+        self
+    }
+
+    /// Record `accessed_at` whenever a file is read through `cat`, `cat_bytes`, or anything
+    /// built on them. Off by default since every read then costs an extra write.
+    pub fn track_access(mut self, enabled: bool) -> Self {
+        self.track_access = enabled;
+        self
+    }
+
+    /// Make `rm` and `rmdir` move entries under `TRASH_DIR` instead of deleting them, so they
+    /// can be brought back with `restore` until `empty_trash` runs.
+    pub fn trash(mut self, enabled: bool) -> Self {
+        self.trash = enabled;
+        self
+    }
+
+    /// Keep trashed items through `vacuum` until they have been in the trash for `retention`.
+    /// Without it `vacuum` purges the whole trash.
+    pub fn trash_retention(mut self, retention: Duration) -> Self {
+        self.trash_retention = Some(retention);
+        self
+    }
+
+    /// Bump `updated_at` on every ancestor directory whenever an entry below it is written,
+    /// created, renamed, or removed, so recently changed subtrees can be found without
+    /// scanning files. Off by default since each change then costs an extra update.
+    pub fn propagate_updated_at(mut self, enabled: bool) -> Self {
+        self.propagate_updated_at = enabled;
+        self
+    }
+
+    /// How many files recursive operations such as `cp_to` and `cp_into` copy at once. The
+    /// default of 1 copies one after another; any `max_concurrency` limit still applies.
+    pub fn parallelism(mut self, n: usize) -> Self {
+        self.parallelism = n.max(1);
+        self
+    }
+
+    /// Look up directory children and subtrees by walking the `{table}_child` graph edges
+    /// set up by `define_graph_edges`, instead of the `parent` index and a path prefix scan.
+    /// Paths stay the record ids, so renaming a directory still rewrites its descendants.
+    pub fn graph_edges(mut self, enabled: bool) -> Self {
+        self.graph_edges = enabled;
+        self
+    }
+
+    /// Run `grep` inside SurrealDB: lines are split and matched with `string::matches`, so
+    /// only matching lines come back instead of every file body under the search path. Files
+    /// stored as bytes are still fetched and matched locally, since they may need transcoding.
+    pub fn server_side_grep(mut self, enabled: bool) -> Self {
+        self.server_side_grep = enabled;
+        self
+    }
+
+    /// Compress file content with `codec` before storing it; reads decompress transparently.
+    /// Content that would not shrink is stored as is. Off by default, and individual writes
+    /// can pick their own codec with `write_file_compressed`.
+    pub fn compression(mut self, codec: Compression) -> Self {
+        self.compression = codec;
+        self
+    }
+
+    /// Line ending policy applied to text on every write and by `export_bytes`. The default
+    /// preserves content exactly.
+    pub fn newline(mut self, policy: NewlinePolicy) -> Self {
+        self.newline = policy;
+        self
+    }
+
+    /// Permission bits withheld from new entries, as with a process umask. The default,
+    /// `DEFAULT_UMASK`, yields `DEFAULT_FILE_MODE` and `DEFAULT_DIR_MODE`.
+    pub fn umask(mut self, umask: u32) -> Self {
+        self.defaults.umask = umask & 0o777;
+        self
+    }
+
+    /// Owner, and group when given, recorded on every new entry instead of leaving them unset.
+    pub fn default_owner(mut self, owner: impl Into<String>, group: Option<&str>) -> Self {
+        self.defaults.owner = Some(owner.into());
+        self.defaults.group = group.map(str::to_string);
+        self
+    }
+
+    /// Mark every new entry hidden, as `set_hidden` would.
+    pub fn default_hidden(mut self, hidden: bool) -> Self {
+        self.defaults.hidden = hidden;
+        self
+    }
+
+    /// Create files without write bits, so their content is fixed once written until a
+    /// `chmod` allows changes again. Directories are unaffected.
+    pub fn default_readonly(mut self, readonly: bool) -> Self {
+        self.defaults.readonly = readonly;
+        self
+    }
+
+    pub fn build(self) -> SurrealFs<DB> {
+        SurrealFs {
+            db: self.db,
+            replica: self.replica,
+            endpoint: self.endpoint,
+            table: self.table,
+            limiter: self.max_concurrency.map(|n| Arc::new(Semaphore::new(n))),
+            cache: self
+                .cache
+                .map(|(capacity, ttl)| Arc::new(EntryCache::new(capacity, ttl))),
+            buffer: self
+                .write_buffer
+                .map(|(max_pending, max_delay)| Arc::new(WriteBuffer::new(max_pending, max_delay))),
+            retry: self.retry,
+            quota_bytes: self.quota_bytes,
+            track_access: self.track_access,
+            trash: self.trash,
+            trash_retention: self.trash_retention,
+            propagate_updated_at: self.propagate_updated_at,
+            parallelism: self.parallelism,
+            graph_edges: self.graph_edges,
+            server_side_grep: self.server_side_grep,
+            compression: self.compression,
+            newline: self.newline,
+            defaults: self.defaults,
+            read_only: false,
+        }
+    }
 }
 
 impl<DB> SurrealFs<DB>
@@ -115,43 +571,172 @@ where
     DB: surrealdb::Connection,
 {
     pub fn new(db: Surreal<DB>) -> Self {
-        Self {
+        Self::builder(db).build()
+    }
+
+    pub fn with_table(db: Surreal<DB>, table: impl Into<String>) -> Self {
+        Self::builder(db).table(table).build()
+    }
+
+    pub fn builder(db: Surreal<DB>) -> SurrealFsBuilder<DB> {
+        SurrealFsBuilder {
             db,
+            replica: None,
+            endpoint: None,
             table: "fs_entry".into(),
+            max_concurrency: None,
+            cache: None,
+            write_buffer: None,
+            retry: RetryPolicy::default(),
+            quota_bytes: None,
+            track_access: false,
+            trash: false,
+            trash_retention: None,
+            propagate_updated_at: false,
+            parallelism: 1,
+            graph_edges: false,
+            server_side_grep: false,
+            compression: Compression::None,
+            newline: NewlinePolicy::Preserve,
+            defaults: EntryDefaults::default(),
         }
     }
 
-    pub fn with_table(db: Surreal<DB>, table: impl Into<String>) -> Self {
-        Self {
-            db,
-            table: table.into(),
+    /// A handle on the same table and connection on which every mutating method fails with
+    /// `ReadOnly` before touching the database, to hand to viewers and exporters. It shares
+    /// the cache but not the write buffer, so appends still queued on this handle are not
+    /// visible through it until flushed. Reads never record `accessed_at`.
+    pub fn read_only(&self) -> Self {
+        SurrealFs {
+            buffer: None,
+            track_access: false,
+            read_only: true,
+            ..self.sibling()
         }
     }
 
-    pub async fn ls(&self, path: impl AsRef<str>) -> Result<Vec<Entry>> {
+    /// Another handle with every setting of this one, sharing its limiter, cache and buffer.
+    fn sibling(&self) -> Self {
+        SurrealFs {
+            db: self.db.clone(),
+            replica: self.replica.clone(),
+            endpoint: self.endpoint.clone(),
+            table: self.table.clone(),
+            limiter: self.limiter.clone(),
+            cache: self.cache.clone(),
+            buffer: self.buffer.clone(),
+            retry: self.retry,
+            quota_bytes: self.quota_bytes,
+            track_access: self.track_access,
+            trash: self.trash,
+            trash_retention: self.trash_retention,
+            propagate_updated_at: self.propagate_updated_at,
+            parallelism: self.parallelism,
+            graph_edges: self.graph_edges,
+            server_side_grep: self.server_side_grep,
+            compression: self.compression,
+            newline: self.newline,
+            defaults: self.defaults.clone(),
+            read_only: self.read_only,
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The children of a directory in name order, or the file itself when `path` is a file.
+    /// A trailing symlink is followed. File content is not fetched, so listed files carry
+    /// their size but no `content`. Listing `/` includes the synthetic `/proc`.
+    pub async fn ls(&self, path: impl AsRef<str>) -> Result<Vec<Node>> {
         let path = normalize_path(path.as_ref())?;
+        let mut listed = match self.metadata(&path).await? {
+            Some(meta) if meta.is_dir => self.child_metadata(&meta.path).await?,
+            Some(meta) => vec![meta],
+            None => return Err(FsError::NotFound(path)),
+        };
         if path == "/" {
-            return self.children(&path).await;
+            procfs::list_in_root(&mut listed, None);
         }
+        Ok(listed.into_iter().map(Node::from).collect())
+    }
 
-        if let Some(entry) = self.get_entry(&path).await? {
-            if entry.is_dir {
-                self.children(&path).await
-            } else {
-                Ok(vec![entry])
+    /// `ls` one page at a time: up to `limit` (at least one) children of `path` in name
+    /// order, starting after `cursor`. Only the page is fetched, so very large directories
+    /// can be consumed incrementally.
+    pub async fn ls_page(
+        &self,
+        path: impl AsRef<str>,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<Page<Node>> {
+        let path = normalize_path(path.as_ref())?;
+        let limit = limit.max(1);
+        let mut listed = match self.metadata(&path).await? {
+            Some(meta) if !meta.is_dir => {
+                return Ok(Page {
+                    items: vec![Node::from(meta)],
+                    next: None,
+                });
+            }
+            Some(meta) if procfs::is_proc_path(&meta.path) => self
+                .child_metadata(&meta.path)
+                .await?
+                .into_iter()
+                .filter(|child| cursor.is_none_or(|after| child.name.as_str() > after))
+                .take(limit + 1)
+                .collect(),
+            Some(meta) => {
+                let _permit = self.permit().await;
+                let mut res = self
+                    .run(|| {
+                        self.db
+                            .query(format!(
+                                "SELECT {} FROM {} WHERE parent = $parent AND ($cursor = NONE OR name > $cursor) \
+                                 ORDER BY name LIMIT $limit",
+                                metadata::metadata_fields(),
+                                self.table
+                            ))
+                            .bind(("parent", meta.path.clone()))
+                            .bind(("cursor", cursor.map(str::to_string)))
+                            .bind(("limit", limit + 1))
+                    })
+                    .await?;
+                let mut listed: Vec<Metadata> = res.take(0)?;
+                if meta.path == "/" {
+                    procfs::list_in_root(&mut listed, cursor);
+                }
+                listed
             }
+            None => return Err(FsError::NotFound(path)),
+        };
+
+        let next = if listed.len() > limit {
+            listed.truncate(limit);
+            listed.last().map(|meta| meta.name.clone())
         } else {
-            Err(FsError::NotFound(path))
-        }
+            None
+        };
+        Ok(Page {
+            items: listed.into_iter().map(Node::from).collect(),
+            next,
+        })
     }
 
+    /// The content of a text file. Binary files are refused with `FsError::Binary`; read
+    /// them with `cat_bytes`.
     pub async fn cat(&self, path: impl AsRef<str>) -> Result<String> {
         let entry = self.require_file(path.as_ref()).await?;
+        if entry.is_binary() {
+            return Err(FsError::Binary(entry.path));
+        }
+        self.note_access(&entry).await?;
         Ok(entry.text()?.unwrap_or_default())
     }
 
     pub async fn cat_bytes(&self, path: impl AsRef<str>) -> Result<Vec<u8>> {
         let entry = self.require_file(path.as_ref()).await?;
+        self.note_access(&entry).await?;
         Ok(entry.bytes().unwrap_or_default())
     }
 
@@ -191,145 +776,513 @@ where
             .collect())
     }
 
-    pub async fn grep(
-        &self,
-        pattern: &Regex,
-        path: impl AsRef<str>,
-        recursive: bool,
-    ) -> Result<Vec<GrepMatch>> {
+    /// Diff two text files in the same format `edit` reports, labelled with their paths.
+    /// Identical files produce an empty string.
+    pub async fn diff(&self, a: impl AsRef<str>, b: impl AsRef<str>) -> Result<String> {
+        let a = normalize_path(a.as_ref())?;
+        let b = normalize_path(b.as_ref())?;
+        let old = self.cat(&a).await?;
+        let new = self.cat(&b).await?;
+        Ok(render_labeled_diff(&old, &new, &a, &b))
+    }
+
+    /// Count newlines, whitespace-separated words, and bytes. Works on binary files too.
+    pub async fn wc(&self, path: impl AsRef<str>) -> Result<WordCount> {
+        let entry = self.require_file(path.as_ref()).await?;
+        let bytes = entry.bytes().unwrap_or_default();
+        Ok(WordCount {
+            lines: bytes.iter().filter(|b| **b == b'\n').count(),
+            words: bytes
+                .split(|b| b.is_ascii_whitespace())
+                .filter(|w| !w.is_empty())
+                .count(),
+            bytes: bytes.len(),
+        })
+    }
+
+    /// Create an empty file or bump an existing file's `updated_at`, returning its metadata.
+    /// An existing file keeps its content and version, and an immutable one is refused.
+    /// Symlinks are followed.
+    pub async fn touch(&self, path: impl AsRef<str>) -> Result<Metadata> {
         let path = normalize_path(path.as_ref())?;
-        let mut matches = Vec::new();
-        let mut stack = vec![path.clone()];
-        while let Some(p) = stack.pop() {
-            let entry = match self.get_entry(&p).await? {
-                Some(e) => e,
-                None => return Err(FsError::NotFound(p)),
-            };
-            if entry.is_dir {
-                if recursive {
-                    for child in self.children(&p).await? {
-                        stack.push(child.path);
-                    }
-                }
-            } else if let Some(content) = entry.text()? {
-                for (idx, line) in content.lines().enumerate() {
-                    if pattern.is_match(line) {
-                        matches.push(GrepMatch {
-                            path: entry.path.clone(),
-                            line_number: idx + 1,
-                            line: line.to_string(),
-                        });
-                    }
+        if path == "/" {
+            return Ok(Metadata::root());
+        }
+        let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
+        self.ensure_dir(&parent).await?;
+
+        let (path, existing) = self.follow(path).await?;
+        match existing {
+            Some(entry) if entry.is_dir => Err(FsError::NotAFile(path)),
+            Some(entry) => {
+                immutable::ensure_mutable(&entry.path, entry.immutable)?;
+                self.set_mtime(&path, now_millis()).await?;
+                self.symlink_metadata(&path)
+                    .await?
+                    .ok_or(FsError::NotFound(path))
+            }
+            None => {
+                // A dangling symlink leads to a target whose directory is not checked yet.
+                let target_parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
+                if target_parent != parent {
+                    self.ensure_dir(&target_parent).await?;
                 }
+                self.create_file(&path, &target_parent, Some(String::new()), None)
+                    .await
             }
         }
-        Ok(matches)
     }
 
-    pub async fn glob(&self, pattern: impl AsRef<str>) -> Result<Vec<String>> {
-        let pattern = pattern.as_ref();
-        if pattern.is_empty() {
-            return Err(FsError::InvalidPath);
+    /// Set a file or directory's `updated_at` to `millis` since the Unix epoch, so imports can
+    /// keep original timestamps. Symlinks are followed and hard links share the new time.
+    pub async fn set_mtime(&self, path: impl AsRef<str>, millis: i64) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        self.ensure_writable(&path)?;
+        let (path, entry) = self.follow(path).await?;
+        if entry.is_none() {
+            return Err(FsError::NotFound(path));
         }
 
-        let normalized = normalize_path(pattern)?;
-        let trimmed = normalized.trim_start_matches('/');
-        if trimmed.is_empty() {
-            return Err(FsError::InvalidPath);
-        }
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db
+                .query(format!(
+                    "UPDATE {} SET updated_at = $updated_at",
+                    inode::content_record("$path")
+                ))
+                .bind(("table", self.table.clone()))
+                .bind(("path", path.clone()))
+                .bind(("updated_at", millis))
+        })
+        .await?;
+        self.forget(&[&path]);
+        Ok(())
+    }
 
-        let mut builder = GlobSetBuilder::new();
-        let trimmed_glob = GlobBuilder::new(trimmed)
-            .literal_separator(true)
-            .build()
-            .map_err(|_| FsError::InvalidPath)?;
-        builder.add(trimmed_glob);
+    /// Create or overwrite a text file, returning the metadata of what was written.
+    pub async fn write_file(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+    ) -> Result<Metadata> {
+        self.write_file_compressed(path, content, self.compression)
+            .await
+    }
 
-        if trimmed != normalized {
-            let absolute_glob = GlobBuilder::new(&normalized)
-                .literal_separator(true)
-                .build()
-                .map_err(|_| FsError::InvalidPath)?;
-            builder.add(absolute_glob);
+    /// `write_file`, storing the content compressed with `codec` whatever the filesystem's
+    /// default. Reads decompress transparently; a later plain write uses the default again.
+    pub async fn write_file_compressed(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+        codec: Compression,
+    ) -> Result<Metadata> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::NotAFile(path));
         }
+        let content = self.newline.apply_owned(content.into());
 
-        let matcher = builder.build().map_err(|_| FsError::InvalidPath)?;
-
-        let mut res = self
-            .db
-            .query(format!(
-                "SELECT path, name, parent, is_dir, content, updated_at FROM {}",
-                self.table
-            ))
-            .await?;
-        let mut entries: Vec<Entry> = res.take(0)?;
-
-        entries.retain(|entry| {
-            let path = entry.path.as_str();
-            let trimmed_path = path.trim_start_matches('/');
-            matcher.is_match(path) || matcher.is_match(trimmed_path)
-        });
+        match self.upsert_file(&path, &content, codec).await {
+            // Symlinks are rare; resolve the chain, then write its target the same way.
+            Err(FsError::NotASymlink(_)) => {
+                let (path, _) = self.follow(path).await?;
+                self.upsert_file(&path, &content, codec).await
+            }
+            result => result,
+        }
+    }
 
-        entries.sort_by(|a, b| {
-            let a_time = a.updated_at.unwrap_or(0);
-            let b_time = b.updated_at.unwrap_or(0);
-            b_time.cmp(&a_time).then_with(|| a.path.cmp(&b.path))
-        });
+    /// Create or overwrite the text file at `path` in a single query: the parent, type,
+    /// immutable, and permission checks run server-side alongside the `UPSERT`. A symlink at
+    /// `path` is not followed but reported as `NotASymlink`, for the caller to resolve.
+    async fn upsert_file(&self, path: &str, content: &str, codec: Compression) -> Result<Metadata> {
+        self.ensure_writable(path)?;
+        let parent = parent_path(path).ok_or(FsError::InvalidPath)?;
+        let now = now_millis();
+        let entry = self.new_file_entry(path, &parent, Some(content.to_string()), None, now);
+        let tags = symbols::of_entry(&entry);
+        let (content_text, content_bytes, compression) = compress::pack(codec, entry.content, None);
+
+        let sql = format!(
+            "BEGIN TRANSACTION;\
+             IF $parent != '/' {{\
+                 LET $dir = (SELECT VALUE is_dir FROM type::thing($table, $parent))[0];\
+                 IF $dir = NONE {{ THROW 'surrealfs:not_found' }};\
+                 IF $dir != true {{ THROW 'surrealfs:not_a_directory' }};\
+             }};\
+             LET $existing = (SELECT is_dir, symlink, immutable, mode, inode FROM type::thing($table, $path))[0];\
+             IF $existing.symlink != NONE {{ THROW 'surrealfs:symlink' }};\
+             IF $existing.is_dir = true {{ THROW 'surrealfs:not_a_file' }};\
+             IF $existing.immutable = true {{ THROW 'surrealfs:immutable' }};\
+             IF $existing != NONE AND math::floor(($existing.mode ?? {file_mode}) / 128) % 2 = 0 {{ THROW 'surrealfs:permission' }};\
+             IF type::is::record($existing.inode) {{\
+                 UPDATE $existing.inode SET content = $content, content_bytes = $content_bytes, compression = $compression, content_type = $content_type, encoding = NONE, checksum = $checksum, symbols = $symbols, size = $size, version = (version ?? 1) + 1, updated_at = $now;\
+             }} ELSE {{\
+                 UPSERT type::thing($table, $path) SET path = $path, name = $name, parent = $parent, is_dir = false, content = $content, content_bytes = $content_bytes, compression = $compression, content_type = $content_type, encoding = NONE, checksum = $checksum, symbols = $symbols, size = $size, mode = IF $existing = NONE THEN $mode ELSE mode END, owner = IF $existing = NONE THEN $owner ELSE owner END, group = IF $existing = NONE THEN $group ELSE group END, hidden = IF $existing = NONE THEN $hidden ELSE hidden END, version = IF $existing = NONE THEN 1 ELSE (version ?? 1) + 1 END, created_at = IF $existing = NONE THEN $now ELSE created_at END, updated_at = $now;\
+             }};\
+             RETURN (SELECT {fields} FROM type::thing($table, $path))[0];\
+             COMMIT TRANSACTION;",
+            file_mode = DEFAULT_FILE_MODE,
+            fields = metadata::metadata_fields(),
+        );
+        let mut res = {
+            let _permit = self.permit().await;
+            self.run(|| {
+                self.db
+                    .query(&sql)
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", entry.path.clone()))
+                    .bind(("name", entry.name.clone()))
+                    .bind(("parent", parent.clone()))
+                    .bind(("size", content.len()))
+                    .bind(("content_type", entry.content_type.clone()))
+                    .bind(("checksum", entry.checksum.clone()))
+                    .bind(("symbols", tags.clone()))
+                    .bind(("mode", entry.mode))
+                    .bind(("owner", entry.owner.clone()))
+                    .bind(("group", entry.group.clone()))
+                    .bind(("hidden", entry.hidden))
+                    .bind(("content", content_text.clone()))
+                    .bind(("content_bytes", content_bytes.clone()))
+                    .bind(("compression", compression))
+                    .bind(("now", now))
+            })
+            .await?
+        };
 
-        Ok(entries.into_iter().map(|e| e.path).collect())
+        let errors = res.take_errors();
+        if !errors.is_empty() {
+            let thrown = errors.values().map(|e| e.to_string()).find_map(|msg| {
+                let (_, code) = msg.split_once("surrealfs:")?;
+                Some(
+                    code.split(|c: char| !c.is_ascii_alphabetic() && c != '_')
+                        .next()?
+                        .to_string(),
+                )
+            });
+            let path = path.to_string();
+            return Err(match thrown.as_deref() {
+                Some("not_found") => FsError::NotFound(parent),
+                Some("not_a_directory") => FsError::NotADirectory(parent),
+                Some("symlink") => FsError::NotASymlink(path),
+                Some("not_a_file") => FsError::NotAFile(path),
+                Some("immutable") => FsError::Immutable(path),
+                Some("permission") => FsError::PermissionDenied(path),
+                _ => errors
+                    .into_values()
+                    .next()
+                    .map(FsError::from)
+                    .unwrap_or(FsError::NotFound(path)),
+            });
+        }
+        let meta: Option<Metadata> = res.take(res.num_statements() - 1)?;
+        let meta = meta.ok_or_else(|| FsError::NotFound(path.to_string()))?;
+        self.forget(&[path]);
+        self.touch_ancestors(&[path], now).await?;
+        Ok(meta)
     }
 
-    pub async fn touch(&self, path: impl AsRef<str>) -> Result<()> {
+    /// Like `write_file`, but only if the file is still at `expected_version`, as seen in its
+    /// metadata or returned by the last write; otherwise fails with `Conflict` and writes
+    /// nothing. An `expected_version` of 0 means the file must not exist yet.
+    pub async fn write_file_if(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+        expected_version: u64,
+    ) -> Result<Metadata> {
         let path = normalize_path(path.as_ref())?;
         if path == "/" {
-            return Ok(());
+            return Err(FsError::NotAFile(path));
         }
         let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
         self.ensure_dir(&parent).await?;
 
-        match self.get_entry(&path).await? {
+        let (path, existing) = self.follow(path).await?;
+        match existing {
             Some(entry) if entry.is_dir => Err(FsError::NotAFile(path)),
-            Some(entry) => {
-                self.persist_entry(&entry).await?;
-                Ok(())
-            }
-            None => {
-                self.create_file(&path, &parent, Some(String::new()), None)
-                    .await?;
-                Ok(())
+            Some(entry) if entry.version != expected_version => Err(FsError::Conflict(path)),
+            Some(mut entry) => {
+                immutable::ensure_mutable(&entry.path, entry.immutable)?;
+                mode::ensure_content_writable(&entry)?;
+                entry.content = Some(self.newline.apply_owned(content.into()));
+                entry.content_bytes = None;
+                self.persist_entry_if(&entry, Some(expected_version)).await
             }
+            None if expected_version != 0 => Err(FsError::Conflict(path)),
+            None => match self.write_file_new(&path, content).await {
+                Err(FsError::AlreadyExists(path)) => Err(FsError::Conflict(path)),
+                result => {
+                    result?;
+                    self.symlink_metadata(&path)
+                        .await?
+                        .ok_or(FsError::NotFound(path))
+                }
+            },
         }
     }
 
-    pub async fn write_file(
+    /// Write `content` to a hidden temp entry next to `path`, then rename it over `path` in one
+    /// transaction, so readers see either the old file or the new one and never a partial
+    /// write. The replaced file's mode, owner, group, xattrs, hidden flag, expiry, and creation
+    /// time carry over, but
+    /// like a rename on disk it leaves any hard links holding the old content.
+    pub async fn write_file_atomic(
         &self,
         path: impl AsRef<str>,
         content: impl Into<String>,
-    ) -> Result<()> {
+    ) -> Result<Metadata> {
         let path = normalize_path(path.as_ref())?;
         if path == "/" {
             return Err(FsError::NotAFile(path));
         }
+        self.ensure_writable(&path)?;
+        let (path, existing) = self.follow(path).await?;
         let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
         self.ensure_dir(&parent).await?;
+        if let Some(entry) = &existing {
+            if entry.is_dir {
+                return Err(FsError::NotAFile(path));
+            }
+            immutable::ensure_mutable(&entry.path, entry.immutable)?;
+            mode::ensure_content_writable(entry)?;
+        }
+
+        let temp = join_path(
+            &parent,
+            &format!(".{}.tmp-{}", leaf_name(&path), now_millis()),
+        );
+        let content = self.newline.apply_owned(content.into());
+        self.create_file(&temp, &parent, Some(content), None)
+            .await?;
+        if let Some(entry) = existing {
+            let _permit = self.permit().await;
+            self.run(|| {
+                self.db
+                    .query(
+                        "UPDATE type::thing($table, $temp) SET mode = $mode, owner = $owner, group = $group, xattrs = $xattrs, hidden = $hidden, expires_at = $expires_at, created_at = $created_at ?? created_at, version = $version + 1",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("temp", temp.clone()))
+                    .bind(("mode", entry.mode))
+                    .bind(("owner", entry.owner.clone()))
+                    .bind(("group", entry.group.clone()))
+                    .bind(("xattrs", entry.xattrs.clone()))
+                    .bind(("hidden", entry.hidden))
+                    .bind(("expires_at", entry.expires_at))
+                    .bind(("created_at", entry.created_at))
+                    .bind(("version", entry.version))
+            })
+            .await?;
+        }
 
-        let content = content.into();
+        if let Err(err) = self.mv(&temp, &path, true).await {
+            self.delete_paths(vec![temp]).await?;
+            return Err(err);
+        }
+        self.symlink_metadata(&path)
+            .await?
+            .ok_or(FsError::NotFound(path))
+    }
 
-        if let Some(mut entry) = self.get_entry(&path).await? {
-            if entry.is_dir {
+    /// Create a file only if nothing exists at `path`, failing with `AlreadyExists` otherwise.
+    /// The record id is derived from the path and the `CREATE` itself refuses an existing
+    /// record, so of several concurrent callers exactly one wins, which makes lock files safe.
+    /// Any other database error is returned as it is.
+    pub async fn write_file_new(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+    ) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::AlreadyExists(path));
+        }
+        self.ensure_writable(&path)?;
+        let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
+        self.ensure_dir(&parent).await?;
+        let content = self.newline.apply_owned(content.into());
+        let updated_at = now_millis();
+        let size = content.len();
+        let content_type = mime::detect(&path, content.as_bytes());
+        let checksum = checksum::sha256_hex(content.as_bytes());
+        let tags = symbols::extract(content_type, &content);
+        let (content, content_bytes, compression) =
+            compress::pack(self.compression, Some(content), None);
+
+        let mut res = {
+            let _permit = self.permit().await;
+            self.run_once(|| {
+                self.db
+                    .query(
+                        "CREATE type::thing($table, $path) SET path = $path, name = $name, parent = $parent, is_dir = false, content = $content, content_bytes = $content_bytes, compression = $compression, content_type = $content_type, checksum = $checksum, symbols = $symbols, mode = $mode, owner = $owner, group = $group, hidden = $hidden, size = $size, version = 1, created_at = $updated_at, updated_at = $updated_at",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("name", leaf_name(&path)))
+                    .bind(("parent", parent.clone()))
+                    .bind(("size", size))
+                    .bind(("content_type", content_type))
+                    .bind(("mode", self.defaults.file_mode()))
+                    .bind(("owner", self.defaults.owner.clone()))
+                    .bind(("group", self.defaults.group.clone()))
+                    .bind(("hidden", self.defaults.hidden))
+                    .bind(("checksum", checksum.clone()))
+                    .bind(("symbols", tags.clone()))
+                    .bind(("content", content.clone()))
+                    .bind(("content_bytes", content_bytes.clone()))
+                    .bind(("compression", compression))
+                    .bind(("updated_at", updated_at))
+                    .bind(("path", path.clone()))
+            })
+            .await?
+        };
+        self.check_statements(&mut res, &path)?;
+        self.forget(&[&path]);
+        self.touch_ancestors(&[&path], updated_at).await
+    }
+
+    /// Create or overwrite many text files in a single transaction: either every file is
+    /// written or none is. Missing parent directories are created along the way, and when a
+    /// path appears more than once the last content wins. A path another writer creates after
+    /// it was looked up fails the batch with `AlreadyExists`.
+    pub async fn write_files<I, P, C>(&self, files: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (P, C)>,
+        P: AsRef<str>,
+        C: Into<String>,
+    {
+        let mut batch: BTreeMap<String, String> = BTreeMap::new();
+        for (path, content) in files {
+            let path = normalize_path(path.as_ref())?;
+            if path == "/" {
                 return Err(FsError::NotAFile(path));
             }
-            entry.content = Some(content.clone());
-            entry.content_bytes = None;
-            self.persist_entry(&entry).await?;
-        } else {
-            self.create_file(&path, &parent, Some(content), None)
+            self.ensure_writable(&path)?;
+            batch.insert(path, self.newline.apply_owned(content.into()));
+        }
+
+        let mut dirs: BTreeMap<String, BatchDir> = BTreeMap::new();
+        let mut updates = Vec::new();
+        let mut creates = Vec::new();
+        for (path, content) in batch {
+            let mut ancestor = parent_path(&path);
+            while let Some(dir) = ancestor.filter(|d| d != "/" && !dirs.contains_key(d)) {
+                match self.symlink_metadata(&dir).await? {
+                    Some(entry) if entry.is_dir => break,
+                    Some(_) => return Err(FsError::NotADirectory(dir)),
+                    None => {
+                        ancestor = parent_path(&dir);
+                        dirs.insert(
+                            dir.clone(),
+                            BatchDir {
+                                name: leaf_name(&dir),
+                                parent: ancestor.clone().unwrap_or_else(|| "/".into()),
+                                path: dir,
+                            },
+                        );
+                    }
+                }
+            }
+
+            let (path, existing) = self.follow(path).await?;
+            match existing {
+                Some(entry) if entry.is_dir => return Err(FsError::NotAFile(path)),
+                Some(entry) => {
+                    immutable::ensure_mutable(&entry.path, entry.immutable)?;
+                    mode::ensure_content_writable(&entry)?;
+                    updates.push(BatchFile::new(
+                        path,
+                        entry.name,
+                        entry.parent.unwrap_or_default(),
+                        content,
+                        self.compression,
+                    ));
+                }
+                None => {
+                    let name = leaf_name(&path);
+                    let parent = parent_path(&path).unwrap_or_else(|| "/".into());
+                    creates.push(BatchFile::new(
+                        path,
+                        name,
+                        parent,
+                        content,
+                        self.compression,
+                    ));
+                }
+            }
+        }
+
+        if let Some(file) = creates.iter().find(|f| dirs.contains_key(&f.path)) {
+            return Err(FsError::NotADirectory(file.path.clone()));
+        }
+
+        let sql = format!(
+            "BEGIN TRANSACTION;\
+             FOR $dir IN $dirs {{ CREATE type::thing($table, $dir.path) SET path = $dir.path, name = $dir.name, parent = $dir.parent, is_dir = true, content = NONE, content_bytes = NONE, mode = $dir_mode, owner = $owner, group = $group, hidden = $hidden, size = 0, created_at = $updated_at, updated_at = $updated_at; }};\
+             FOR $file IN $updates {{ UPDATE {stored} SET content = $file.content, content_bytes = $file.content_bytes, compression = $file.compression, content_type = $file.content_type, encoding = NONE, checksum = $file.checksum, symbols = $file.symbols, size = $file.size, version = (version ?? 1) + 1, updated_at = $updated_at; }};\
+             FOR $file IN $creates {{ CREATE type::thing($table, $file.path) SET path = $file.path, name = $file.name, parent = $file.parent, is_dir = false, content = $file.content, content_bytes = $file.content_bytes, compression = $file.compression, content_type = $file.content_type, checksum = $file.checksum, symbols = $file.symbols, mode = $file_mode, owner = $owner, group = $group, hidden = $hidden, size = $file.size, version = 1, created_at = $updated_at, updated_at = $updated_at; }};\
+             COMMIT TRANSACTION;",
+            stored = inode::content_record("$file.path"),
+        );
+
+        let written: Vec<String> = updates
+            .iter()
+            .chain(creates.iter())
+            .map(|f| f.path.clone())
+            .collect();
+        let dirs: Vec<BatchDir> = dirs.into_values().collect();
+        let updated_at = now_millis();
+        let failed = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run_once(|| {
+                    self.db
+                        .query(&sql)
+                        .bind(("table", self.table.clone()))
+                        .bind(("dirs", dirs.clone()))
+                        .bind(("updates", updates.clone()))
+                        .bind(("creates", creates.clone()))
+                        .bind(("dir_mode", self.defaults.dir_mode()))
+                        .bind(("file_mode", self.defaults.file_mode()))
+                        .bind(("owner", self.defaults.owner.clone()))
+                        .bind(("group", self.defaults.group.clone()))
+                        .bind(("hidden", self.defaults.hidden))
+                        .bind(("updated_at", updated_at))
+                })
                 .await?;
+            failed_statement(&mut res)
+        };
+        let written: Vec<&str> = written.iter().map(String::as_str).collect();
+        if let Some(err) = failed {
+            // The transaction rolled back, so nothing was written. The tree changed since the
+            // batch was planned: drop what was cached about it, and name a path that was
+            // created in the meantime.
+            let created: Vec<&str> = dirs
+                .iter()
+                .map(|dir| dir.path.as_str())
+                .chain(creates.iter().map(|file| file.path.as_str()))
+                .collect();
+            self.forget(&created);
+            self.forget(&written);
+            if record_exists(&err) {
+                for path in created {
+                    if self.symlink_metadata(path).await?.is_some() {
+                        return Err(FsError::AlreadyExists(path.to_string()));
+                    }
+                }
+            }
+            return Err(err.into());
         }
-        Ok(())
+        self.forget(&written);
+        self.touch_ancestors(&written, updated_at).await
     }
 
-    pub async fn write_bytes(&self, path: impl AsRef<str>, data: impl Into<Vec<u8>>) -> Result<()> {
+    pub async fn write_bytes(
+        &self,
+        path: impl AsRef<str>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<Metadata> {
         let path = normalize_path(path.as_ref())?;
         self.write_bytes_internal(&path, data.into(), true).await
     }
@@ -339,7 +1292,7 @@ where
         path: &str,
         mut data: Vec<u8>,
         optimize_images: bool,
-    ) -> Result<()> {
+    ) -> Result<Metadata> {
         if path == "/" {
             return Err(FsError::NotAFile(path.to_string()));
         }
@@ -349,65 +1302,84 @@ where
         if optimize_images {
             data = optimize_image_bytes(path, data);
         }
+        data = self.newline.apply_to_bytes(mime::detect(path, &data), data);
 
-        if let Some(mut entry) = self.get_entry(path).await? {
+        let (path, existing) = self.follow(path.to_string()).await?;
+        if let Some(mut entry) = existing {
             if entry.is_dir {
-                return Err(FsError::NotAFile(path.to_string()));
+                return Err(FsError::NotAFile(path));
             }
+            immutable::ensure_mutable(&entry.path, entry.immutable)?;
+            mode::ensure_content_writable(&entry)?;
             entry.content = None;
             entry.content_bytes = Some(ByteBuf::from(data));
-            self.persist_entry(&entry).await?;
+            self.persist_entry(&entry).await
         } else {
-            self.create_file(path, &parent, None, Some(ByteBuf::from(data)))
-                .await?;
+            self.create_file(&path, &parent, None, Some(ByteBuf::from(data)))
+                .await
         }
-        Ok(())
     }
 
+    /// Replace `old` with `new` and return the diff. With `preview`, the diff is computed but
+    /// nothing is written.
     pub async fn edit(
         &self,
         path: impl AsRef<str>,
         old: impl AsRef<str>,
         new: impl AsRef<str>,
         replace_all: bool,
+        preview: bool,
     ) -> Result<String> {
         let path = normalize_path(path.as_ref())?;
         let old_str = old.as_ref();
         let new_str = new.as_ref();
 
         let current = self.cat(&path).await?;
-
-        let (updated, changed) = if old_str.is_empty() {
-            let changed = current != new_str;
-            (new_str.to_string(), changed)
-        } else if replace_all {
-            let replaced = current.replace(old_str, new_str);
-            let changed = replaced != current;
-            (replaced, changed)
-        } else if let Some(idx) = current.find(old_str) {
-            let mut result =
-                String::with_capacity(current.len() + new_str.len().saturating_sub(old_str.len()));
-            result.push_str(&current[..idx]);
-            result.push_str(new_str);
-            result.push_str(&current[idx + old_str.len()..]);
-            (result, true)
-        } else {
-            (current.clone(), false)
+        let updated = match apply_edit(&current, old_str, new_str, replace_all) {
+            Some(updated) if updated != current => updated,
+            _ => return Ok(String::new()),
         };
 
-        if !changed {
+        if !preview {
+            self.write_file(&path, updated.clone()).await?;
+        }
+        Ok(render_diff(&current, &updated))
+    }
+
+    /// Apply several replacements to one file in order, each seeing the result of the one
+    /// before, then write once and return the combined diff. If any edit fails to match,
+    /// nothing is written and `NoMatch` is returned. With `preview`, nothing is written either way.
+    pub async fn edit_many(
+        &self,
+        path: impl AsRef<str>,
+        ops: &[EditOp],
+        preview: bool,
+    ) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        let current = self.cat(&path).await?;
+
+        let mut updated = current.clone();
+        for (idx, op) in ops.iter().enumerate() {
+            updated = apply_edit(&updated, &op.old, &op.new, op.replace_all)
+                .ok_or_else(|| FsError::NoMatch(format!("{} (edit {})", path, idx + 1)))?;
+        }
+        if updated == current {
             return Ok(String::new());
         }
 
-        self.write_file(&path, updated.clone()).await?;
+        if !preview {
+            self.write_file(&path, updated.clone()).await?;
+        }
         Ok(render_diff(&current, &updated))
     }
 
-    pub async fn mkdir(&self, path: impl AsRef<str>, parents: bool) -> Result<()> {
+    /// Create a directory, returning its metadata. With `parents`, missing ancestors are
+    /// created too and an existing directory at `path` is returned as-is.
+    pub async fn mkdir(&self, path: impl AsRef<str>, parents: bool) -> Result<Metadata> {
         let path = normalize_path(path.as_ref())?;
         if path == "/" {
             return if parents {
-                Ok(())
+                Ok(Metadata::root())
             } else {
                 Err(FsError::AlreadyExists(path))
             };
@@ -415,6 +1387,7 @@ where
 
         if parents {
             let mut current = String::from("/");
+            let mut meta = Metadata::root();
             for segment in path.trim_start_matches('/').split('/') {
                 if segment.is_empty() {
                     continue;
@@ -424,37 +1397,41 @@ where
                 }
                 current.push_str(segment);
 
-                match self.get_entry(&current).await? {
-                    Some(entry) => {
-                        if !entry.is_dir {
+                meta = match self.symlink_metadata(&current).await? {
+                    Some(existing) => {
+                        if !existing.is_dir {
                             return Err(FsError::NotADirectory(current));
                         }
+                        existing
                     }
                     None => {
                         let parent = parent_path(&current).unwrap_or("/".to_string());
-                        self.create_dir(&current, &parent).await?;
+                        self.create_dir(&current, &parent).await?
                     }
-                }
+                };
             }
-            return Ok(());
+            return Ok(meta);
         }
 
         let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
         self.ensure_dir(&parent).await?;
 
-        match self.get_entry(&path).await? {
+        match self.symlink_metadata(&path).await? {
             Some(entry) if entry.is_dir => Err(FsError::AlreadyExists(path)),
             Some(_) => Err(FsError::AlreadyExists(path)),
-            None => {
-                self.create_dir(&path, &parent).await?;
-                Ok(())
-            }
+            None => self.create_dir(&path, &parent).await,
         }
     }
 
-    /// Copy a file from `src` to `dest`, overwriting the destination file if it exists.
-    /// Destination parent must already exist and be a directory.
-    pub async fn cp(&self, src: impl AsRef<str>, dest: impl AsRef<str>) -> Result<()> {
+    /// Copy a file from `src` to `dest`, returning the destination's metadata. An existing
+    /// destination file is overwritten unless `opts.no_clobber` is set, in which case the
+    /// copy only ever creates `dest`, and `opts.preserve` keeps the source's attributes. Destination parent must already exist and be a directory.
+    pub async fn cp(
+        &self,
+        src: impl AsRef<str>,
+        dest: impl AsRef<str>,
+        opts: &CopyOptions,
+    ) -> Result<Metadata> {
         let src = normalize_path(src.as_ref())?;
         let dest = normalize_path(dest.as_ref())?;
         let entry = self.require_file(&src).await?;
@@ -464,23 +1441,533 @@ where
         }
         let parent = parent_path(&dest).ok_or(FsError::InvalidPath)?;
         self.ensure_dir(&parent).await?;
+        self.copy_with_options(entry, &dest, opts).await
+    }
+
+    /// Copy each file in `srcs` into the existing directory `dest_dir`, keeping leaf names,
+    /// like `cp a b c dir/`. Every source, and with `opts.no_clobber` every destination, is
+    /// checked before anything is written.
+    pub async fn cp_into<I, S>(
+        &self,
+        srcs: I,
+        dest_dir: impl AsRef<str>,
+        opts: &CopyOptions,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let dest_dir = normalize_path(dest_dir.as_ref())?;
+        self.ensure_dir(&dest_dir).await?;
+
+        let mut entries = Vec::new();
+        for src in srcs {
+            entries.push(self.require_file(src.as_ref()).await?);
+        }
+        if entries.is_empty() {
+            return Err(FsError::InvalidPath);
+        }
+        if opts.no_clobber {
+            for entry in &entries {
+                let dest = join_path(&dest_dir, &entry.name);
+                if self.symlink_metadata(&dest).await?.is_some() {
+                    return Err(FsError::AlreadyExists(dest));
+                }
+            }
+        }
+
+        futures::stream::iter(entries)
+            .map(|entry| {
+                let dest = join_path(&dest_dir, &entry.name);
+                async move { self.copy_with_options(entry, &dest, opts).await }
+            })
+            .buffer_unordered(self.parallelism)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(())
+    }
+
+    /// Create a hard link: `dest` becomes a second path for the file at `src`. The content is
+    /// stored once, on an inode both paths point at, so writes through either path are seen
+    /// by both; it is removed with the last link.
+    pub async fn link(&self, src: impl AsRef<str>, dest: impl AsRef<str>) -> Result<()> {
+        let src = normalize_path(src.as_ref())?;
+        let dest = normalize_path(dest.as_ref())?;
+        self.ensure_writable(&src)?;
+        self.ensure_writable(&dest)?;
+        let entry = self.require_file(&src).await?;
+
+        if dest == "/" {
+            return Err(FsError::AlreadyExists(dest));
+        }
+        let parent = parent_path(&dest).ok_or(FsError::InvalidPath)?;
+        self.ensure_dir(&parent).await?;
+        if self.symlink_metadata(&dest).await?.is_some() {
+            return Err(FsError::AlreadyExists(dest));
+        }
+
+        // The first link moves the file's content from its own record onto a new inode.
+        {
+            let _permit = self.permit().await;
+            let mut res = self.run_once(|| {
+                self.db
+                    .query(
+                        "BEGIN TRANSACTION;\
+                         LET $file = (SELECT * FROM type::thing($table, $src))[0];\
+                         LET $inode = IF type::is::record($file.inode) {\
+                             $file.inode\
+                         } ELSE {\
+                             (CREATE type::thing($inodes, rand::guid()) SET content = $file.content, content_bytes = $file.content_bytes, compression = $file.compression, content_type = $file.content_type, encoding = $file.encoding, checksum = $file.checksum, symbols = $file.symbols, size = $file.size, version = $file.version ?? 1, updated_at = $file.updated_at, links = 1 RETURN VALUE id)[0]\
+                         };\
+                         UPDATE type::thing($table, $src) SET inode = $inode, content = NONE, content_bytes = NONE, compression = NONE, content_type = NONE, encoding = NONE, checksum = NONE, symbols = NONE, size = NONE, version = NONE, updated_at = NONE;\
+                         UPDATE $inode SET links += 1;\
+                         CREATE type::thing($table, $dest) SET path = $dest, name = $name, parent = $parent, is_dir = false, mode = $file.mode, owner = $file.owner, group = $file.group, xattrs = $file.xattrs, immutable = $file.immutable, created_at = $file.created_at, inode = $inode;\
+                         COMMIT TRANSACTION;",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("inodes", self.inode_table()))
+                    .bind(("src", entry.path.clone()))
+                    .bind(("dest", dest.clone()))
+                    .bind(("name", leaf_name(&dest)))
+                    .bind(("parent", parent.clone()))
+            })
+            .await?;
+            self.check_statements(&mut res, &dest)?;
+        }
+        self.forget(&[&src, &dest]);
+        self.touch_ancestors(&[&dest], now_millis()).await
+    }
+
+    /// Every path that shares the file at `path`, including `path` itself, sorted.
+    pub async fn links(&self, path: impl AsRef<str>) -> Result<Vec<String>> {
+        let entry = self.require_file(path.as_ref()).await?;
+        let Some(inode) = entry.inode else {
+            return Ok(vec![entry.path]);
+        };
+
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT VALUE path FROM {} WHERE inode = $inode ORDER BY path",
+                        self.table
+                    ))
+                    .bind(("inode", self.inode_record(&Some(inode.clone()))))
+            })
+            .await?;
+        let paths: Vec<String> = res.take(0)?;
+        Ok(paths)
+    }
+
+    /// Copy the file or directory tree at `src` into `other`, which may sit on a different
+    /// connection, namespace, or table, at the exact path `dest`. Existing destination files
+    /// are overwritten and existing directories are merged into; symlinks are recreated as
+    /// links rather than followed.
+    pub async fn cp_to<Other>(
+        &self,
+        src: impl AsRef<str>,
+        other: &SurrealFs<Other>,
+        dest: impl AsRef<str>,
+    ) -> Result<Metadata>
+    where
+        Other: surrealdb::Connection,
+    {
+        let src = normalize_path(src.as_ref())?;
+        let dest = normalize_path(dest.as_ref())?;
+        let (src, entry) = if src == "/" {
+            (src, None)
+        } else {
+            let (src, entry) = self.follow(src).await?;
+            let entry = entry.ok_or_else(|| FsError::NotFound(src.clone()))?;
+            (src, Some(entry))
+        };
+
+        if let Some(entry) = entry.as_ref().filter(|e| !e.is_dir) {
+            if dest == "/" {
+                return Err(FsError::NotAFile(dest));
+            }
+            let parent = parent_path(&dest).ok_or(FsError::InvalidPath)?;
+            other.ensure_dir(&parent).await?;
+            return other.copy_entry(entry.clone(), &dest, false).await;
+        }
+
+        let root = other.ensure_tree_dir(&dest).await?;
+        let prefix = if src == "/" {
+            src.clone()
+        } else {
+            format!("{}/", src)
+        };
+        // Directories are created in path order, parents first; the files and links that
+        // fill them are then copied `parallelism` at a time.
+        let mut leaves = Vec::new();
+        for entry in self.descendants(&src, false, Route::Primary).await? {
+            let target = join_path(&dest, &entry.path[prefix.len()..]);
+            if entry.is_dir && entry.symlink.is_none() {
+                other.ensure_tree_dir(&target).await?;
+            } else {
+                leaves.push((entry, target));
+            }
+        }
+        futures::stream::iter(leaves)
+            .map(|(entry, target)| async move {
+                match &entry.symlink {
+                    Some(link) => match other.symlink(link, &target).await {
+                        Err(FsError::AlreadyExists(_)) => Ok(()),
+                        result => result,
+                    },
+                    None => other.copy_entry(entry, &target, false).await.map(|_| ()),
+                }
+            })
+            .buffer_unordered(self.parallelism)
+            .try_collect::<Vec<()>>()
+            .await?;
+        Ok(root)
+    }
+
+    /// Make sure a directory exists at `path` (its parent must already), creating it if needed.
+    async fn ensure_tree_dir(&self, path: &str) -> Result<Metadata> {
+        match self.symlink_metadata(path).await? {
+            Some(meta) if meta.is_dir => Ok(meta),
+            Some(_) => Err(FsError::NotADirectory(path.to_string())),
+            None => {
+                let parent = parent_path(path).ok_or(FsError::InvalidPath)?;
+                self.ensure_dir(&parent).await?;
+                self.create_dir(path, &parent).await
+            }
+        }
+    }
+
+    async fn copy_with_options(
+        &self,
+        entry: Entry,
+        dest: &str,
+        opts: &CopyOptions,
+    ) -> Result<Metadata> {
+        if !opts.preserve {
+            return self.copy_entry(entry, dest, opts.no_clobber).await;
+        }
+        let (mode, owner, group, xattrs, updated_at) = (
+            entry.mode,
+            entry.owner.clone(),
+            entry.group.clone(),
+            entry.xattrs.clone(),
+            entry.updated_at,
+        );
+        let copied = self.copy_entry(entry, dest, opts.no_clobber).await?;
+        {
+            let _permit = self.permit().await;
+            self.run(|| {
+                self.db
+                    .query(
+                        "UPDATE type::thing($table, $path) SET mode = $mode, owner = $owner, group = $group, xattrs = $xattrs, updated_at = $updated_at",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("mode", mode))
+                    .bind(("owner", owner.clone()))
+                    .bind(("group", group.clone()))
+                    .bind(("xattrs", xattrs.clone()))
+                    .bind(("updated_at", updated_at))
+                    .bind(("path", copied.path.clone()))
+            })
+            .await?;
+        }
+        self.forget(&[&copied.path]);
+        self.symlink_metadata(&copied.path)
+            .await?
+            .ok_or(FsError::NotFound(copied.path))
+    }
 
+    /// Write `entry`'s content to `dest`. With `no_clobber` the copy is a `CREATE` on the
+    /// destination's record id, so a file that appears there after the caller checked is
+    /// reported as `AlreadyExists` instead of being overwritten.
+    async fn copy_entry(&self, entry: Entry, dest: &str, no_clobber: bool) -> Result<Metadata> {
+        if no_clobber {
+            let parent = parent_path(dest).ok_or(FsError::InvalidPath)?;
+            let (content, content_bytes) = match entry.content_bytes {
+                Some(bytes) => {
+                    let data = bytes.into_vec();
+                    let data = self.newline.apply_to_bytes(mime::detect(dest, &data), data);
+                    (None, Some(ByteBuf::from(data)))
+                }
+                None => {
+                    let text = entry.content.unwrap_or_default();
+                    (Some(self.newline.apply_owned(text)), None)
+                }
+            };
+            return self
+                .create_file(dest, &parent, content, content_bytes)
+                .await;
+        }
         if let Some(bytes) = entry.content_bytes {
-            self.write_bytes_internal(&dest, bytes.into_vec(), false)
+            self.write_bytes_internal(dest, bytes.into_vec(), false)
                 .await
         } else {
-            self.write_file(&dest, entry.content.unwrap_or_default())
+            self.write_file(dest, entry.content.unwrap_or_default())
                 .await
         }
     }
 
+    /// Remove a file. Directories are rejected with `NotAFile`; a symlink is removed itself,
+    /// never its target. In trash mode the file is moved to the trash instead.
+    pub async fn rm(&self, path: impl AsRef<str>) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        self.ensure_writable(&path)?;
+        match self.symlink_metadata(&path).await? {
+            Some(entry) if entry.is_dir => Err(FsError::NotAFile(path)),
+            Some(entry) => {
+                immutable::ensure_mutable(&entry.path, entry.immutable)?;
+                if self.trashes(&entry.path) {
+                    return self.move_to_trash(&entry.path).await;
+                }
+                self.delete_paths(vec![entry.path.clone()]).await?;
+                self.touch_ancestors(&[&entry.path], now_millis()).await
+            }
+            None => Err(FsError::NotFound(path)),
+        }
+    }
+
+    /// Remove a directory. Non-empty directories fail with `DirectoryNotEmpty` unless
+    /// `recursive` is set, in which case every descendant is removed as well. In trash mode
+    /// the directory and its subtree are moved to the trash instead.
+    pub async fn rmdir(&self, path: impl AsRef<str>, recursive: bool) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        self.ensure_writable(&path)?;
+        match self.symlink_metadata(&path).await? {
+            Some(entry) if entry.is_dir => immutable::ensure_mutable(&path, entry.immutable)?,
+            Some(_) => return Err(FsError::NotADirectory(path)),
+            None => return Err(FsError::NotFound(path)),
+        }
+
+        if !recursive && !self.children(&path).await?.is_empty() {
+            return Err(FsError::DirectoryNotEmpty(path));
+        }
+        if recursive {
+            self.ensure_subtree_mutable(&path).await?;
+        }
+        if self.trashes(&path) {
+            return self.move_to_trash(&path).await;
+        }
+        if !recursive {
+            self.delete_paths(vec![path.clone()]).await?;
+        } else {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run_once(|| {
+                    self.db
+                        .query(format!(
+                            "BEGIN TRANSACTION;\
+                             LET $removed = (DELETE {} WHERE path = $path OR string::starts_with(path, $prefix) RETURN VALUE $before.inode);\
+                             {}\
+                             COMMIT TRANSACTION;",
+                            self.table,
+                            inode::release("$removed")
+                        ))
+                        .bind(("prefix", format!("{}/", path)))
+                        .bind(("path", path.clone()))
+                })
+                .await?;
+            self.check_statements(&mut res, &path)?;
+        }
+        self.forget(&[&path]);
+        self.touch_ancestors(&[&path], now_millis()).await
+    }
+
+    /// Rename `src` to the exact path `dest`. Directories carry their whole subtree along.
+    /// An existing destination file is replaced only when `overwrite` is set; existing
+    /// destination directories are never replaced.
+    pub async fn mv(
+        &self,
+        src: impl AsRef<str>,
+        dest: impl AsRef<str>,
+        overwrite: bool,
+    ) -> Result<()> {
+        let src = normalize_path(src.as_ref())?;
+        let dest = normalize_path(dest.as_ref())?;
+        if src == "/" || dest == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        self.ensure_writable(&src)?;
+        self.ensure_writable(&dest)?;
+        if src == dest {
+            return Ok(());
+        }
+
+        let entry = self
+            .symlink_metadata(&src)
+            .await?
+            .ok_or_else(|| FsError::NotFound(src.clone()))?;
+        if entry.is_dir && dest.starts_with(&format!("{}/", src)) {
+            return Err(FsError::InvalidPath);
+        }
+        immutable::ensure_mutable(&src, entry.immutable)?;
+        if entry.is_dir {
+            self.ensure_subtree_mutable(&src).await?;
+        }
+
+        let parent = parent_path(&dest).ok_or(FsError::InvalidPath)?;
+        self.ensure_dir(&parent).await?;
+
+        let replace_dest = match self.symlink_metadata(&dest).await? {
+            Some(existing) if existing.is_dir || entry.is_dir || !overwrite => {
+                return Err(FsError::AlreadyExists(dest));
+            }
+            Some(existing) => {
+                immutable::ensure_mutable(&dest, existing.immutable)?;
+                true
+            }
+            None => false,
+        };
+
+        // Record ids follow paths, so moved records are recreated under their new ids. The
+        // whole subtree is captured before anything is deleted: scans that follow a DELETE
+        // inside the same transaction can miss rows. A replaced file is overwritten in place
+        // rather than deleted, so to `enable_history` it is an update like any other write,
+        // and its version moves past the one it replaced.
+        let scope = if entry.is_dir {
+            "path = $src OR string::starts_with(path, $prefix)"
+        } else {
+            "path = $src"
+        };
+        let sql = format!(
+            "BEGIN TRANSACTION;\
+             LET $ids = (SELECT VALUE id FROM {table} WHERE {scope});\
+             LET $moved = (SELECT * OMIT id FROM {table} WHERE {scope});\
+             DELETE $ids;\
+             FOR $entry IN $moved {{\
+                 LET $path = string::concat($dest, string::slice($entry.path, $len));\
+                 LET $replaced = (SELECT {version} AS version, inode FROM type::thing($table, $path))[0];\
+                 {write} type::thing($table, $path) CONTENT $entry;\
+                 UPDATE type::thing($table, $path) SET path = $path, name = IF $entry.path = $src THEN $name ELSE name END, parent = IF $entry.path = $src THEN $dest_parent ELSE string::concat($dest, string::slice(parent, $len)) END;\
+                 UPDATE {stored} SET version = IF $replaced = NONE THEN version ELSE math::max([$replaced.version + 1, version ?? 1]) END;\
+                 {release}\
+             }};\
+             COMMIT TRANSACTION;",
+            table = self.table,
+            write = if replace_dest { "UPSERT" } else { "CREATE" },
+            version = inode::VERSION_EXPR,
+            stored = inode::content_record("$path"),
+            release = inode::release("[$replaced.inode]"),
+        );
+
+        {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run_once(|| {
+                    self.db
+                        .query(&sql)
+                        .bind(("table", self.table.clone()))
+                        .bind(("src", src.clone()))
+                        .bind(("dest", dest.clone()))
+                        .bind(("name", leaf_name(&dest)))
+                        .bind(("dest_parent", parent.clone()))
+                        .bind(("len", src.chars().count()))
+                        .bind(("prefix", format!("{}/", src)))
+                })
+                .await?;
+            self.check_statements(&mut res, &dest)?;
+        }
+        self.forget(&[&src, &dest]);
+        self.touch_ancestors(&[&src, &dest], now_millis()).await
+    }
+
+    /// Atomically swap two paths, like `renameat2(RENAME_EXCHANGE)`: afterwards `a` holds what
+    /// was at `b` and vice versa. Directories carry their subtrees; both paths must exist and
+    /// neither may contain the other.
+    pub async fn exchange(&self, a: impl AsRef<str>, b: impl AsRef<str>) -> Result<()> {
+        let a = normalize_path(a.as_ref())?;
+        let b = normalize_path(b.as_ref())?;
+        if a == "/" || b == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        self.ensure_writable(&a)?;
+        self.ensure_writable(&b)?;
+        if a == b {
+            return Ok(());
+        }
+        if a.starts_with(&format!("{}/", b)) || b.starts_with(&format!("{}/", a)) {
+            return Err(FsError::InvalidPath);
+        }
+        for path in [&a, &b] {
+            let entry = self
+                .symlink_metadata(path)
+                .await?
+                .ok_or_else(|| FsError::NotFound(path.clone()))?;
+            immutable::ensure_mutable(path, entry.immutable)?;
+            if entry.is_dir {
+                self.ensure_subtree_mutable(path).await?;
+            }
+        }
+
+        // Both subtrees are captured before either moves and recreated under the ids of their
+        // new paths, as in `mv`. Paths held on both sides are overwritten in place, as `mv`
+        // replaces a file, taking a version past the one they replace; only the rest are
+        // deleted. Name and parent are assigned before path because SET sees earlier
+        // assignments.
+        let sql = format!(
+            "BEGIN TRANSACTION;\
+             LET $a_ids = (SELECT id, path FROM {table} WHERE path = $a OR string::starts_with(path, $a_prefix));\
+             LET $b_ids = (SELECT id, path FROM {table} WHERE path = $b OR string::starts_with(path, $b_prefix));\
+             LET $a_entries = (SELECT * OMIT id FROM {table} WHERE path = $a OR string::starts_with(path, $a_prefix));\
+             LET $b_entries = (SELECT * OMIT id FROM {table} WHERE path = $b OR string::starts_with(path, $b_prefix));\
+             LET $kept = array::concat(\
+                 (SELECT VALUE string::concat($b, string::slice(path, $a_len)) FROM $a_entries),\
+                 (SELECT VALUE string::concat($a, string::slice(path, $b_len)) FROM $b_entries)\
+             );\
+             DELETE array::concat($a_ids, $b_ids)[WHERE path NOT IN $kept].id;\
+             FOR $entry IN $a_entries {{\
+                 LET $path = string::concat($b, string::slice($entry.path, $a_len));\
+                 LET $replaced = (SELECT VALUE {version} FROM type::thing($table, $path))[0];\
+                 UPSERT type::thing($table, $path) CONTENT $entry;\
+                 UPDATE type::thing($table, $path) SET name = IF path = $a THEN $b_name ELSE name END, parent = IF path = $a THEN $b_parent ELSE string::concat($b, string::slice(parent, $a_len)) END, path = $path;\
+                 UPDATE {stored} SET version = IF $replaced = NONE THEN version ELSE math::max([$replaced + 1, version ?? 1]) END;\
+             }};\
+             FOR $entry IN $b_entries {{\
+                 LET $path = string::concat($a, string::slice($entry.path, $b_len));\
+                 LET $replaced = (SELECT VALUE {version} FROM type::thing($table, $path))[0];\
+                 UPSERT type::thing($table, $path) CONTENT $entry;\
+                 UPDATE type::thing($table, $path) SET name = IF path = $b THEN $a_name ELSE name END, parent = IF path = $b THEN $a_parent ELSE string::concat($a, string::slice(parent, $b_len)) END, path = $path;\
+                 UPDATE {stored} SET version = IF $replaced = NONE THEN version ELSE math::max([$replaced + 1, version ?? 1]) END;\
+             }};\
+             COMMIT TRANSACTION;",
+            table = self.table,
+            version = inode::VERSION_EXPR,
+            stored = inode::content_record("$path"),
+        );
+
+        {
+            let _permit = self.permit().await;
+            self.run_once(|| {
+                self.db
+                    .query(&sql)
+                    .bind(("table", self.table.clone()))
+                    .bind(("a_prefix", format!("{}/", a)))
+                    .bind(("b_prefix", format!("{}/", b)))
+                    .bind(("a_len", a.chars().count()))
+                    .bind(("b_len", b.chars().count()))
+                    .bind(("a_name", leaf_name(&a)))
+                    .bind(("b_name", leaf_name(&b)))
+                    .bind(("a_parent", parent_path(&a)))
+                    .bind(("b_parent", parent_path(&b)))
+                    .bind(("a", a.clone()))
+                    .bind(("b", b.clone()))
+            })
+            .await?;
+        }
+        self.forget(&[&a, &b]);
+        self.touch_ancestors(&[&a, &b], now_millis()).await
+    }
+
     /// Change directory: resolve `target` relative to `current`, ensure it exists and is a directory.
-    /// Returns the normalized new path.
+    /// Returns the normalized new path, with a trailing symlink replaced by its real path.
     pub async fn cd(&self, current: &str, target: &str) -> Result<String> {
         let resolved = resolve_relative(current, target)?;
-        match self.get_entry(&resolved).await? {
-            Some(e) if e.is_dir => Ok(resolved),
-            Some(_) => Err(FsError::NotADirectory(resolved)),
+        match self.metadata(&resolved).await? {
+            Some(meta) if meta.is_dir => Ok(meta.path),
+            Some(meta) => Err(FsError::NotADirectory(meta.path)),
             None => Err(FsError::NotFound(resolved)),
         }
     }
@@ -490,9 +1977,48 @@ where
         normalize_path(current)
     }
 
+    /// Wait for a query slot when a concurrency limit is configured.
+    async fn permit(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.limiter {
+            Some(limiter) => limiter.acquire().await.ok(),
+            None => None,
+        }
+    }
+
+    /// Fail with `ReadOnly` when `path` cannot be changed through this handle: the handle
+    /// came from `read_only`, or the path is under the synthetic `/proc`.
+    pub(crate) fn ensure_writable(&self, path: &str) -> Result<()> {
+        if self.read_only || procfs::is_proc_path(path) {
+            return Err(FsError::ReadOnly(path.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Stamp `accessed_at` on a file that was just read, when access tracking is enabled.
+    async fn note_access(&self, entry: &Entry) -> Result<()> {
+        if !self.track_access || procfs::is_proc_path(&entry.path) {
+            return Ok(());
+        }
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db
+                .query(format!(
+                    "UPDATE {} SET accessed_at = $now WHERE path = $path OR (inode != NONE AND inode = $inode)",
+                    self.table
+                ))
+                .bind(("path", entry.path.clone()))
+                .bind(("inode", self.inode_record(&entry.inode)))
+                .bind(("now", now_millis()))
+        })
+        .await?;
+        self.forget(&[&entry.path]);
+        Ok(())
+    }
+
     async fn require_file(&self, path: &str) -> Result<Entry> {
         let path = normalize_path(path)?;
-        match self.get_entry(&path).await? {
+        let (path, entry) = self.follow(path).await?;
+        match entry {
             Some(entry) if entry.is_dir => Err(FsError::NotAFile(path)),
             Some(entry) => Ok(entry),
             None => Err(FsError::NotFound(path)),
@@ -503,7 +2029,7 @@ where
         if path == "/" {
             return Ok(());
         }
-        match self.get_entry(path).await? {
+        match self.symlink_metadata(path).await? {
             Some(entry) if entry.is_dir => Ok(()),
             Some(_) => Err(FsError::NotADirectory(path.to_string())),
             None => Err(FsError::NotFound(path.to_string())),
@@ -511,48 +2037,288 @@ where
     }
 
     async fn children(&self, path: &str) -> Result<Vec<Entry>> {
+        if procfs::is_proc_path(path) {
+            return Ok(self.proc_children(path));
+        }
+        self.flush_pending().await?;
+        if let Some(children) = self.cache.as_ref().and_then(|c| c.listing(path)) {
+            return Ok(children);
+        }
         let parent = path.to_string();
+        let _permit = self.permit().await;
         let mut res = self
-            .db
-            .query(format!(
-                "SELECT path, name, parent, is_dir, content, content_bytes, updated_at FROM {} WHERE parent = $parent ORDER BY name",
-                self.table
-            ))
-            .bind(("parent", parent))
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT {} FROM {} ORDER BY name",
+                        ENTRY_FIELDS,
+                        self.children_source()
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("parent", parent.clone()))
+            })
             .await?;
 
-        let entries: Vec<Entry> = res.take(0)?;
+        let mut entries: Vec<Entry> = res.take(0)?;
+        entries.iter_mut().try_for_each(compress::unpack)?;
+        if let Some(cache) = &self.cache {
+            cache.store_listing(path, &entries);
+        }
         Ok(entries)
     }
 
     async fn get_entry(&self, path: &str) -> Result<Option<Entry>> {
+        if procfs::is_proc_path(path) {
+            return self.proc_entry(path).await;
+        }
+        self.flush_pending().await?;
+        if let Some(entry) = self.cache.as_ref().and_then(|c| c.entry(path)) {
+            return Ok(entry);
+        }
         let path_owned = path.to_string();
+        let _permit = self.permit().await;
         let mut res = self
-            .db
-            .query(format!(
-                "SELECT path, name, parent, is_dir, content, content_bytes, updated_at FROM {} WHERE path = $path LIMIT 1",
-                self.table
-            ))
-            .bind(("path", path_owned))
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT {} FROM type::thing($table, $path)",
+                        ENTRY_FIELDS
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", path_owned.clone()))
+            })
             .await?;
-        let entry: Option<Entry> = res.take(0)?;
+        let mut entry: Option<Entry> = res.take(0)?;
+        entry.iter_mut().try_for_each(compress::unpack)?;
+        if let Some(cache) = &self.cache {
+            cache.store_entry(path, &entry);
+        }
         Ok(entry)
     }
 
-    async fn create_dir(&self, path: &str, parent: &str) -> Result<()> {
+    async fn all_entries(&self) -> Result<Vec<Entry>> {
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!("SELECT {} FROM {}", ENTRY_FIELDS, self.table))
+            })
+            .await?;
+        let mut entries: Vec<Entry> = res.take(0)?;
+        entries.iter_mut().try_for_each(compress::unpack)?;
+        Ok(entries)
+    }
+
+    /// Every entry strictly beneath `path`, ordered so parents come before their children.
+    /// With `lazy`, file content is left in the database until `Entry::load_content`.
+    async fn descendants(&self, path: &str, lazy: bool, route: Route) -> Result<Vec<Entry>> {
+        let prefix = if path == "/" {
+            path.to_string()
+        } else {
+            format!("{}/", path)
+        };
+        let fields = if lazy {
+            lazy::lazy_entry_fields()
+        } else {
+            ENTRY_FIELDS.to_string()
+        };
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(route)
+                    .query(format!(
+                        "SELECT {} FROM {} ORDER BY path",
+                        fields,
+                        self.descendants_source()
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", path.to_string()))
+                    .bind(("prefix", prefix.clone()))
+            })
+            .await?;
+        if lazy {
+            return Ok(self.lazy_entries(res.take(0)?));
+        }
+        let mut entries: Vec<Entry> = res.take(0)?;
+        entries.iter_mut().try_for_each(compress::unpack)?;
+        Ok(entries)
+    }
+
+    /// Everything beneath the directory `path` in depth-first order, siblings by name, each
+    /// directory before its contents, fetched with one prefix query. Entries not reachable
+    /// from `path` through stored directories, such as orphans, are left out. `lazy` and
+    /// `route` are as for `descendants`.
+    pub(crate) async fn subtree(&self, path: &str, lazy: bool, route: Route) -> Result<Vec<Entry>> {
+        if procfs::is_proc_path(path) {
+            let mut entries = Vec::new();
+            let mut stack: Vec<Entry> = self.proc_children(path).into_iter().rev().collect();
+            while let Some(entry) = stack.pop() {
+                if entry.is_dir {
+                    stack.extend(self.proc_children(&entry.path).into_iter().rev());
+                }
+                entries.push(entry);
+            }
+            return Ok(entries);
+        }
+
+        let mut entries = self.descendants(path, lazy, route).await?;
+        entries.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+        let mut dirs = HashSet::from([path.to_string()]);
+        entries.retain(|entry| {
+            let reachable = entry.parent.as_ref().is_some_and(|p| dirs.contains(p));
+            if reachable && entry.is_dir && entry.symlink.is_none() {
+                dirs.insert(entry.path.clone());
+            }
+            reachable
+        });
+        Ok(entries)
+    }
+
+    /// Bump `updated_at` on every stored directory above `paths` when the filesystem was built
+    /// with `propagate_updated_at`. The root is implicit and carries no timestamp.
+    async fn touch_ancestors(&self, paths: &[&str], updated_at: i64) -> Result<()> {
+        if !self.propagate_updated_at {
+            return Ok(());
+        }
+        let mut ancestors: Vec<String> = Vec::new();
+        for path in paths {
+            let mut current = parent_path(path);
+            while let Some(dir) = current.filter(|d| d != "/" && !ancestors.contains(d)) {
+                current = parent_path(&dir);
+                ancestors.push(dir);
+            }
+        }
+        if ancestors.is_empty() {
+            return Ok(());
+        }
+
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db
+                .query(
+                    "FOR $path IN $paths { UPDATE type::thing($table, $path) SET updated_at = $updated_at WHERE is_dir = true; }",
+                )
+                .bind(("table", self.table.clone()))
+                .bind(("paths", ancestors.clone()))
+                .bind(("updated_at", updated_at))
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Fail with the error of the statement in `res` that failed, as `failed_statement` finds
+    /// it. A `CREATE` on a record that already exists, as when
+    /// another handle wrote `path` first, is reported as `AlreadyExists(path)`, and the cached
+    /// view of `path` that let it through is dropped.
+    fn check_statements(&self, res: &mut surrealdb::Response, path: &str) -> Result<()> {
+        let Some(err) = failed_statement(res) else {
+            return Ok(());
+        };
+        if record_exists(&err) {
+            self.forget(&[path]);
+            return Err(FsError::AlreadyExists(path.to_string()));
+        }
+        Err(err.into())
+    }
+
+    /// Remove the entries at `paths`, releasing the inodes of hard-linked files.
+    async fn delete_paths(&self, paths: Vec<String>) -> Result<()> {
+        let _permit = self.permit().await;
+        let mut res = self
+            .run_once(|| {
+                self.db
+                    .query(format!(
+                        "BEGIN TRANSACTION;\
+                         FOR $path IN $paths {{\
+                             LET $removed = (DELETE type::thing($table, $path) RETURN VALUE $before.inode);\
+                             {}\
+                         }};\
+                         COMMIT TRANSACTION;",
+                        inode::release("$removed")
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("paths", paths.clone()))
+            })
+            .await?;
+        self.check_statements(&mut res, paths.first().map_or("/", String::as_str))?;
+        self.forget(&paths.iter().map(String::as_str).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    async fn create_dir(&self, path: &str, parent: &str) -> Result<Metadata> {
+        self.ensure_writable(path)?;
         let path_owned = path.to_string();
         let parent_owned = parent.to_string();
-        self.db
-            .query(format!(
-                "CREATE {} SET path = $path, name = $name, parent = $parent, is_dir = true, content = NONE, content_bytes = NONE, updated_at = $updated_at",
-                self.table
-            ))
-            .bind(("path", path_owned))
-            .bind(("name", leaf_name(path)))
-            .bind(("parent", parent_owned))
-            .bind(("updated_at", now_millis()))
+        let updated_at = now_millis();
+        let meta: Option<Metadata> = {
+            let _permit = self.permit().await;
+            let mut res = self.run_once(|| {
+                self.db
+                    .query(format!(
+                        "CREATE type::thing($table, $path) SET path = $path, name = $name, parent = $parent, is_dir = true, content = NONE, content_bytes = NONE, mode = $mode, owner = $owner, group = $group, hidden = $hidden, size = 0, created_at = $updated_at, updated_at = $updated_at RETURN {}",
+                        metadata::metadata_fields()
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", path_owned.clone()))
+                    .bind(("name", leaf_name(path)))
+                    .bind(("parent", parent_owned.clone()))
+                    .bind(("mode", self.defaults.dir_mode()))
+                    .bind(("owner", self.defaults.owner.clone()))
+                    .bind(("group", self.defaults.group.clone()))
+                    .bind(("hidden", self.defaults.hidden))
+                    .bind(("updated_at", updated_at))
+            })
             .await?;
-        Ok(())
+            self.check_statements(&mut res, path)?;
+            res.take(0)?
+        };
+        self.forget(&[path]);
+        self.touch_ancestors(&[path], updated_at).await?;
+        meta.ok_or_else(|| FsError::NotFound(path.to_string()))
+    }
+
+    /// A file entry as it would be created at `path` now, with the builder's defaults and the
+    /// content type, encoding and checksum filled in.
+    fn new_file_entry(
+        &self,
+        path: &str,
+        parent: &str,
+        content: Option<String>,
+        content_bytes: Option<ByteBuf>,
+        now: i64,
+    ) -> Entry {
+        let mut entry = Entry {
+            path: path.to_string(),
+            name: leaf_name(path),
+            parent: Some(parent.to_string()),
+            is_dir: false,
+            content,
+            content_bytes,
+            content_type: None,
+            encoding: None,
+            compression: None,
+            checksum: None,
+            mode: Some(self.defaults.file_mode()),
+            owner: self.defaults.owner.clone(),
+            group: self.defaults.group.clone(),
+            xattrs: BTreeMap::new(),
+            immutable: false,
+            hidden: self.defaults.hidden,
+            created_at: Some(now),
+            updated_at: Some(now),
+            accessed_at: None,
+            expires_at: None,
+            version: 1,
+            inode: None,
+            symlink: None,
+            content_handle: ContentHandle::default(),
+        };
+        entry.content_type = mime::of_entry(&entry);
+        entry.encoding = encoding::of_entry(&entry);
+        entry.checksum = checksum::of_entry(&entry);
+        entry
     }
 
     async fn create_file(
@@ -561,59 +2327,254 @@ where
         parent: &str,
         content: Option<String>,
         content_bytes: Option<ByteBuf>,
-    ) -> Result<()> {
-        let path_owned = path.to_string();
-        let parent_owned = parent.to_string();
-        self.db
-            .query(format!(
-                "CREATE {} SET path = $path, name = $name, parent = $parent, is_dir = false, content = $content, content_bytes = $content_bytes, updated_at = $updated_at",
-                self.table
-            ))
-            .bind(("path", path_owned))
-            .bind(("name", leaf_name(path)))
-            .bind(("parent", parent_owned))
-            .bind(("content", content))
-            .bind(("content_bytes", content_bytes))
-            .bind(("updated_at", now_millis()))
+    ) -> Result<Metadata> {
+        self.ensure_writable(path)?;
+        let now = now_millis();
+        let entry = self.new_file_entry(path, parent, content, content_bytes, now);
+        let size = entry.size();
+        let tags = symbols::of_entry(&entry);
+        let (content, content_bytes, compression) =
+            compress::pack(self.compression, entry.content, entry.content_bytes);
+        let meta: Option<Metadata> = {
+            let _permit = self.permit().await;
+            let mut res = self.run_once(|| {
+                self.db
+                    .query(format!(
+                        "CREATE type::thing($table, $path) SET path = $path, name = $name, parent = $parent, is_dir = false, content = $content, content_bytes = $content_bytes, compression = $compression, content_type = $content_type, encoding = $encoding, checksum = $checksum, symbols = $symbols, mode = $mode, owner = $owner, group = $group, hidden = $hidden, size = $size, version = 1, created_at = $updated_at, updated_at = $updated_at RETURN {}",
+                        metadata::metadata_fields()
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("size", size))
+                    .bind(("content_type", entry.content_type.clone()))
+                    .bind(("encoding", entry.encoding.clone()))
+                    .bind(("checksum", entry.checksum.clone()))
+                    .bind(("symbols", tags.clone()))
+                    .bind(("mode", entry.mode))
+                    .bind(("owner", entry.owner.clone()))
+                    .bind(("group", entry.group.clone()))
+                    .bind(("hidden", entry.hidden))
+                    .bind(("path", entry.path.clone()))
+                    .bind(("name", entry.name.clone()))
+                    .bind(("parent", entry.parent.clone()))
+                    .bind(("content", content.clone()))
+                    .bind(("content_bytes", content_bytes.clone()))
+                    .bind(("compression", compression))
+                    .bind(("updated_at", entry.updated_at))
+            })
             .await?;
-        Ok(())
+            self.check_statements(&mut res, path)?;
+            res.take(0)?
+        };
+        self.forget(&[path]);
+        self.touch_ancestors(&[path], now).await?;
+        meta.ok_or_else(|| FsError::NotFound(path.to_string()))
     }
 
-    async fn persist_entry(&self, entry: &Entry) -> Result<()> {
+    async fn persist_entry(&self, entry: &Entry) -> Result<Metadata> {
+        self.persist_entry_if(entry, None).await
+    }
+
+    /// `persist_entry`, refusing with `Conflict` when `expected_version` is given and the
+    /// stored version no longer matches it by the time the update runs.
+    async fn persist_entry_if(
+        &self,
+        entry: &Entry,
+        expected_version: Option<u64>,
+    ) -> Result<Metadata> {
+        self.ensure_writable(&entry.path)?;
         let path_owned = entry.path.clone();
         let name_owned = entry.name.clone();
         let parent_owned = entry.parent.clone();
-        self.db
-            .query(format!(
-                "UPDATE {} SET content = $content, content_bytes = $content_bytes, name = $name, parent = $parent, is_dir = $is_dir, updated_at = $updated_at WHERE path = $path",
-                self.table
-            ))
-            .bind(("path", path_owned))
-            .bind(("name", name_owned))
-            .bind(("parent", parent_owned))
-            .bind(("is_dir", entry.is_dir))
-            .bind(("content", entry.content.clone()))
-            .bind(("content_bytes", entry.content_bytes.clone()))
-            .bind(("updated_at", now_millis()))
-            .await?;
-        Ok(())
+        let updated_at = now_millis();
+        let content_type = mime::of_entry(entry);
+        let encoding = encoding::of_entry(entry);
+        let checksum = checksum::of_entry(entry);
+        let tags = symbols::of_entry(entry);
+        // A hard-linked file's content, version, and mtime live on its inode, shared by all of
+        // its paths.
+        let mut sql = format!(
+            "BEGIN TRANSACTION;LET $stored = {};",
+            inode::content_record("$path")
+        );
+        if expected_version.is_some() {
+            sql.push_str(
+                "IF (SELECT VALUE version ?? 1 FROM $stored)[0] != $expected { THROW 'surrealfs:conflict' };",
+            );
+        }
+        sql.push_str(
+            "UPDATE type::thing($table, $path) SET name = $name, parent = $parent, is_dir = $is_dir;\
+             UPDATE $stored SET content = $content, content_bytes = $content_bytes, compression = $compression, content_type = $content_type, encoding = $encoding, checksum = $checksum, symbols = $symbols, size = $size, version = (version ?? 1) + 1, updated_at = $updated_at;",
+        );
+        sql.push_str(&format!(
+            "RETURN (SELECT {} FROM type::thing($table, $path))[0];COMMIT TRANSACTION;",
+            metadata::metadata_fields()
+        ));
+        let (content, content_bytes, compression) = compress::pack(
+            self.compression,
+            entry.content.clone(),
+            entry.content_bytes.clone(),
+        );
+        let query = || {
+            self.db
+                .query(&sql)
+                .bind(("table", self.table.clone()))
+                .bind(("expected", expected_version))
+                .bind(("path", path_owned.clone()))
+                .bind(("name", name_owned.clone()))
+                .bind(("parent", parent_owned.clone()))
+                .bind(("is_dir", entry.is_dir))
+                .bind(("content", content.clone()))
+                .bind(("content_bytes", content_bytes.clone()))
+                .bind(("compression", compression))
+                .bind(("size", entry.size()))
+                .bind(("content_type", content_type.clone()))
+                .bind(("encoding", encoding.clone()))
+                .bind(("checksum", checksum.clone()))
+                .bind(("symbols", tags.clone()))
+                .bind(("updated_at", updated_at))
+        };
+        let mut res = {
+            let _permit = self.permit().await;
+            // A retried version check would fail against the version its first attempt set.
+            if expected_version.is_some() {
+                self.run_once(query).await?
+            } else {
+                self.run(query).await?
+            }
+        };
+        match self.check_statements(&mut res, &entry.path) {
+            Err(FsError::Surreal(err)) if err.to_string().contains("surrealfs:conflict") => {
+                return Err(FsError::Conflict(entry.path.clone()));
+            }
+            checked => checked?,
+        }
+        let meta: Option<Metadata> = res.take(res.num_statements() - 1)?;
+        let meta = meta.ok_or_else(|| FsError::NotFound(entry.path.clone()))?;
+        self.forget(&[&entry.path]);
+        self.touch_ancestors(&[&entry.path], updated_at).await?;
+        Ok(meta)
     }
 }
 
-fn now_millis() -> i64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as i64
+/// A directory created as part of `write_files`.
+#[derive(Clone, Serialize)]
+struct BatchDir {
+    path: String,
+    name: String,
+    parent: String,
+}
+
+/// A file created or overwritten as part of `write_files`.
+#[derive(Clone, Serialize)]
+struct BatchFile {
+    path: String,
+    name: String,
+    parent: String,
+    content: Option<String>,
+    content_bytes: Option<ByteBuf>,
+    compression: Option<Compressed>,
+    content_type: &'static str,
+    checksum: String,
+    symbols: Option<Vec<symbols::Tag>>,
+    size: usize,
+}
+
+impl BatchFile {
+    fn new(
+        path: String,
+        name: String,
+        parent: String,
+        content: String,
+        codec: Compression,
+    ) -> Self {
+        let content_type = mime::detect(&path, content.as_bytes());
+        let checksum = checksum::sha256_hex(content.as_bytes());
+        let symbols = symbols::extract(content_type, &content);
+        let size = content.len();
+        let (content, content_bytes, compression) = compress::pack(codec, Some(content), None);
+        BatchFile {
+            path,
+            name,
+            parent,
+            content,
+            content_bytes,
+            compression,
+            content_type,
+            checksum,
+            symbols,
+            size,
+        }
+    }
+}
+
+/// The error of the statement in `res` that failed, rather than those a failed transaction
+/// skipped after it.
+fn failed_statement(res: &mut surrealdb::Response) -> Option<surrealdb::Error> {
+    let mut errors: Vec<(usize, surrealdb::Error)> = res.take_errors().into_iter().collect();
+    errors.sort_by_key(|(idx, _)| *idx);
+    let failed = errors.iter().position(|(_, err)| !not_executed(err));
+    match failed {
+        Some(idx) => Some(errors.swap_remove(idx).1),
+        None => errors.into_iter().next().map(|(_, err)| err),
+    }
+}
+
+/// Whether `err` marks a statement skipped because an earlier one failed its transaction.
+fn not_executed(err: &surrealdb::Error) -> bool {
+    match err {
+        surrealdb::Error::Db(err) => matches!(err, Db::QueryNotExecuted),
+        surrealdb::Error::Api(Api::Query(msg)) => {
+            msg.starts_with("The query was not executed due to a failed transaction")
+        }
+        surrealdb::Error::Api(_) => false,
+    }
+}
+
+/// Whether `err` is a `CREATE` refused because its record already exists, as reported by
+/// an embedded engine or over the wire.
+fn record_exists(err: &surrealdb::Error) -> bool {
+    match err {
+        surrealdb::Error::Db(err) => matches!(err, Db::RecordExists { .. }),
+        surrealdb::Error::Api(Api::Query(msg)) => {
+            msg.starts_with("Database record") && msg.ends_with("already exists")
+        }
+        surrealdb::Error::Api(_) => false,
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// Replace `old` with `new` in `current`: the first occurrence, every occurrence with
+/// `replace_all`, or the whole content when `old` is empty. `None` when `old` does not occur.
+fn apply_edit(current: &str, old: &str, new: &str, replace_all: bool) -> Option<String> {
+    if old.is_empty() {
+        return Some(new.to_string());
+    }
+    if !current.contains(old) {
+        return None;
+    }
+    if replace_all {
+        return Some(current.replace(old, new));
+    }
+    Some(current.replacen(old, new, 1))
 }
 
 fn render_diff(old: &str, new: &str) -> String {
+    render_labeled_diff(old, new, "original", "updated")
+}
+
+fn render_labeled_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
     if old == new {
         return String::new();
     }
 
     let diff = TextDiff::from_lines(old, new);
-    let mut out = String::from("--- original\n+++ updated\n");
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
 
     for change in diff.iter_all_changes() {
         let sign = match change.tag() {
@@ -705,6 +2666,14 @@ fn parent_path(path: &str) -> Option<String> {
     Some(parent.replace("//", "/"))
 }
 
+fn join_path(dir: &str, name: &str) -> String {
+    if dir == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
 fn normalize_path(input: &str) -> Result<String> {
     if input.is_empty() {
         return Err(FsError::InvalidPath);
@@ -746,9 +2715,11 @@ fn resolve_relative(base: &str, target: &str) -> Result<String> {
     normalize_path(&combined)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "kv-mem"))]
 mod tests {
     use super::*;
+    use futures::TryStreamExt;
+    use regex::Regex;
     use std::time::Duration;
     use surrealdb::engine::local::{Db, Mem};
     use tokio::time::sleep;
@@ -763,297 +2734,4486 @@ mod tests {
         0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
     ];
 
-    async fn setup_fs() -> Result<SurrealFs<Db>> {
-        let db = Surreal::new::<Mem>(()).await?;
-        db.use_ns("test").use_db("test").await?;
-        Ok(SurrealFs::new(db))
-    }
+    async fn setup_fs() -> Result<SurrealFs<Db>> {
+        let db = Surreal::new::<Mem>(()).await?;
+        db.use_ns("test").use_db("test").await?;
+        Ok(SurrealFs::new(db))
+    }
+
+    #[tokio::test]
+    async fn touch_and_cat() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/dir", true).await.unwrap();
+        fs.touch("/dir/file.txt").await.unwrap();
+        fs.write_file("/dir/file.txt", "hello\nworld")
+            .await
+            .unwrap();
+        let content = fs.cat("/dir/file.txt").await.unwrap();
+        assert_eq!(content, "hello\nworld");
+    }
+
+    #[tokio::test]
+    async fn tail_and_nl() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/logs", true).await.unwrap();
+        fs.write_file("/logs/app.log", "a\nb\nc\nd").await.unwrap();
+        let tail = fs.tail("/logs/app.log", 2).await.unwrap();
+        assert_eq!(tail, vec!["c", "d"]);
+        let numbered = fs.nl("/logs/app.log", 1).await.unwrap();
+        assert_eq!(numbered[0].number, 1);
+        assert_eq!(numbered[3].line, "d");
+    }
+
+    #[tokio::test]
+    async fn wc_counts_lines_words_bytes() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/poem.txt", "one two\nthree  four five\nsix")
+            .await
+            .unwrap();
+        let counts = fs.wc("/poem.txt").await.unwrap();
+        assert_eq!(
+            counts,
+            WordCount {
+                lines: 2,
+                words: 6,
+                bytes: 28,
+            }
+        );
+
+        fs.touch("/empty").await.unwrap();
+        assert_eq!(fs.wc("/empty").await.unwrap(), WordCount::default());
+    }
+
+    #[tokio::test]
+    async fn read_with_offset_and_limit() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/logs", true).await.unwrap();
+        fs.write_file("/logs/app.log", "l1\nl2\nl3\nl4\nl5")
+            .await
+            .unwrap();
+
+        let middle = fs.read("/logs/app.log", 1, 3).await.unwrap();
+        assert_eq!(middle, vec!["l2", "l3", "l4"]);
+
+        let tail = fs.read("/logs/app.log", 4, 10).await.unwrap();
+        assert_eq!(tail, vec!["l5"]);
+
+        let empty = fs.read("/logs/app.log", 10, 2).await.unwrap();
+        assert!(empty.is_empty());
+
+        let none = fs.read("/logs/app.log", 0, 0).await.unwrap();
+        assert!(none.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ls_and_grep_recursive() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/code/src", true).await.unwrap();
+        fs.write_file("/code/src/main.rs", "fn main() { println!(\"hi\"); }\n")
+            .await
+            .unwrap();
+        fs.write_file("/code/readme.md", "hi there\n")
+            .await
+            .unwrap();
+        let entries = fs.ls("/code").await.unwrap();
+        let names: Vec<String> = entries.into_iter().map(|e| e.name().to_string()).collect();
+        assert!(names.contains(&"src".to_string()));
+        assert!(names.contains(&"readme.md".to_string()));
+
+        let regex = Regex::new("hi").unwrap();
+        let matches = fs.grep(&regex, "/code", true).await.unwrap();
+        assert_eq!(matches.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn mkdir_nested_with_parents() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/a/b/c", true).await.unwrap();
+        let entries = fs.ls("/a/b").await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].is_dir());
+    }
+
+    #[tokio::test]
+    async fn mkdir_without_parents_needs_parent() {
+        let fs = setup_fs().await.unwrap();
+        let err = fs.mkdir("/missing/child", false).await.unwrap_err();
+        matches!(err, FsError::NotFound(_));
+    }
+
+    #[tokio::test]
+    async fn ls_root_lists_children() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/docs", true).await.unwrap();
+        fs.write_file("/readme.md", "hello").await.unwrap();
+
+        let entries = fs.ls("/").await.unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name()).collect();
+        assert!(names.contains(&"docs"));
+        assert!(names.contains(&"readme.md"));
+
+        let dir = entries.iter().find(|e| e.name() == "docs").unwrap();
+        assert!(dir.is_dir());
+    }
+
+    #[tokio::test]
+    async fn mkdir_without_parents_fails_when_exists() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/data", true).await.unwrap();
+        let err = fs.mkdir("/data", false).await.unwrap_err();
+        matches!(err, FsError::AlreadyExists(_));
+    }
+
+    #[tokio::test]
+    async fn cp_file() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/docs", true).await.unwrap();
+        fs.write_file("/docs/src.txt", "copy me").await.unwrap();
+        fs.mkdir("/docs/copies", true).await.unwrap();
+        fs.cp(
+            "/docs/src.txt",
+            "/docs/copies/dest.txt",
+            &CopyOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let content = fs.cat("/docs/copies/dest.txt").await.unwrap();
+        assert_eq!(content, "copy me");
+    }
+
+    #[tokio::test]
+    async fn cp_into_copies_multiple_sources() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/src/nested", true).await.unwrap();
+        fs.mkdir("/dest", true).await.unwrap();
+        fs.write_file("/src/a.txt", "a").await.unwrap();
+        fs.write_file("/src/nested/b.txt", "b").await.unwrap();
+
+        fs.cp_into(
+            ["/src/a.txt", "/src/nested/b.txt"],
+            "/dest",
+            &CopyOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs.cat("/dest/a.txt").await.unwrap(), "a");
+        assert_eq!(fs.cat("/dest/b.txt").await.unwrap(), "b");
+
+        let err = fs
+            .cp_into(
+                ["/src/a.txt", "/src/missing.txt"],
+                "/dest/other",
+                &CopyOptions::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+
+        fs.mkdir("/fresh", true).await.unwrap();
+        let err = fs
+            .cp_into(
+                ["/src/a.txt", "/src/missing.txt"],
+                "/fresh",
+                &CopyOptions::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+        assert!(fs.ls("/fresh").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn hard_links_share_content_until_last_removed() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/a", true).await.unwrap();
+        fs.write_file("/a/one.txt", "v1").await.unwrap();
+
+        fs.link("/a/one.txt", "/two.txt").await.unwrap();
+        assert_eq!(fs.cat("/two.txt").await.unwrap(), "v1");
+        assert_eq!(
+            fs.links("/two.txt").await.unwrap(),
+            vec!["/a/one.txt".to_string(), "/two.txt".to_string()]
+        );
+
+        fs.write_file("/two.txt", "v2").await.unwrap();
+        assert_eq!(fs.cat("/a/one.txt").await.unwrap(), "v2");
+
+        // The content is stored once, on an inode counting both paths.
+        async fn inodes(fs: &SurrealFs<Db>) -> Vec<(i64, String)> {
+            let mut res = fs
+                .db
+                .query("SELECT VALUE [links, content] FROM fs_entry_inode")
+                .await
+                .unwrap();
+            res.take(0).unwrap()
+        }
+        assert_eq!(inodes(&fs).await, [(2, "v2".to_string())]);
+        let mut res = fs
+            .db
+            .query("SELECT VALUE content FROM fs_entry WHERE inode != NONE")
+            .await
+            .unwrap();
+        let stored: Vec<Option<String>> = res.take(0).unwrap();
+        assert_eq!(stored, [None, None]);
+
+        // Both paths share one version too.
+        let version = fs.metadata("/a/one.txt").await.unwrap().unwrap().version;
+        assert_eq!(
+            fs.metadata("/two.txt").await.unwrap().unwrap().version,
+            version
+        );
+        fs.write_file_if("/a/one.txt", "v2!", version)
+            .await
+            .unwrap();
+        let err = fs
+            .write_file_if("/two.txt", "v2?", version)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::Conflict(_)), "{err:?}");
+        fs.write_file("/two.txt", "v2").await.unwrap();
+
+        let err = fs.link("/a/one.txt", "/two.txt").await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(_)));
+        let err = fs.link("/a", "/dir-link").await.unwrap_err();
+        assert!(matches!(err, FsError::NotAFile(_)));
+
+        fs.rm("/a/one.txt").await.unwrap();
+        assert_eq!(fs.cat("/two.txt").await.unwrap(), "v2");
+        assert_eq!(
+            fs.links("/two.txt").await.unwrap(),
+            vec!["/two.txt".to_string()]
+        );
+        assert_eq!(inodes(&fs).await, [(1, "v2".to_string())]);
+
+        fs.cp("/two.txt", "/copy.txt", &CopyOptions::default())
+            .await
+            .unwrap();
+        fs.write_file("/copy.txt", "v3").await.unwrap();
+        assert_eq!(fs.cat("/two.txt").await.unwrap(), "v2");
+
+        // The inode goes with the last link, however that is removed.
+        fs.rm("/two.txt").await.unwrap();
+        assert!(inodes(&fs).await.is_empty());
+        fs.link("/copy.txt", "/d/x.txt").await.unwrap_err();
+        fs.mkdir("/d", false).await.unwrap();
+        fs.link("/copy.txt", "/d/x.txt").await.unwrap();
+        fs.link("/copy.txt", "/d/y.txt").await.unwrap();
+        assert_eq!(inodes(&fs).await, [(3, "v3".to_string())]);
+        fs.write_file("/other.txt", "v4").await.unwrap();
+        fs.mv("/other.txt", "/d/y.txt", true).await.unwrap();
+        assert_eq!(inodes(&fs).await, [(2, "v3".to_string())]);
+        fs.rm_glob("/copy.txt", false).await.unwrap();
+        fs.rmdir("/d", true).await.unwrap();
+        assert!(inodes(&fs).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn symlinks_resolve_through_readlink_and_realpath() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/data/v2", true).await.unwrap();
+        fs.write_file("/data/v2/config.toml", "x = 1")
+            .await
+            .unwrap();
+
+        fs.symlink("v2", "/data/current").await.unwrap();
+        fs.symlink("/data/current/config.toml", "/config")
+            .await
+            .unwrap();
+        assert_eq!(fs.readlink("/data/current").await.unwrap(), "v2");
+        assert_eq!(
+            fs.realpath("/data/./current/../current/config.toml")
+                .await
+                .unwrap(),
+            "/data/v2/config.toml"
+        );
+        assert_eq!(
+            fs.realpath("/config").await.unwrap(),
+            "/data/v2/config.toml"
+        );
+        assert_eq!(fs.cat("/config").await.unwrap(), "x = 1");
+        assert_eq!(fs.cd("/", "data/current").await.unwrap(), "/data/v2");
+
+        fs.write_file("/config", "x = 2").await.unwrap();
+        assert_eq!(fs.cat("/data/v2/config.toml").await.unwrap(), "x = 2");
+
+        let err = fs.readlink("/data/v2").await.unwrap_err();
+        assert!(matches!(err, FsError::NotASymlink(_)));
+
+        fs.symlink("/loop-b", "/loop-a").await.unwrap();
+        fs.symlink("/loop-a", "/loop-b").await.unwrap();
+        let err = fs.realpath("/loop-a").await.unwrap_err();
+        assert!(matches!(err, FsError::SymlinkLoop(_)));
+
+        fs.rm("/config").await.unwrap();
+        assert_eq!(fs.cat("/data/v2/config.toml").await.unwrap(), "x = 2");
+        let err = fs.realpath("/config").await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn set_mtime_preserves_imported_timestamps() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/import", true).await.unwrap();
+        fs.write_file("/import/old.txt", "old").await.unwrap();
+        fs.link("/import/old.txt", "/import/alias.txt")
+            .await
+            .unwrap();
+
+        fs.set_mtime("/import/old.txt", 1_000).await.unwrap();
+        fs.set_mtime("/import", 2_000).await.unwrap();
+
+        let entries = fs.ls("/import").await.unwrap();
+        assert!(entries.iter().all(|e| e.attrs().updated_at == Some(1_000)));
+        let root = fs.ls("/").await.unwrap();
+        assert_eq!(root[0].attrs().updated_at, Some(2_000));
+
+        let err = fs.set_mtime("/missing", 1).await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn cp_no_clobber_keeps_existing_destination() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/a.txt", "new").await.unwrap();
+        fs.write_file("/b.txt", "old").await.unwrap();
+        fs.mkdir("/dir", false).await.unwrap();
+        fs.write_file("/dir/b.txt", "kept").await.unwrap();
+        let no_clobber = CopyOptions {
+            no_clobber: true,
+            ..Default::default()
+        };
+
+        let err = fs.cp("/a.txt", "/b.txt", &no_clobber).await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(_)));
+        assert_eq!(fs.cat("/b.txt").await.unwrap(), "old");
+
+        fs.cp("/a.txt", "/c.txt", &no_clobber).await.unwrap();
+        assert_eq!(fs.cat("/c.txt").await.unwrap(), "new");
+
+        let err = fs
+            .cp_into(["/a.txt", "/b.txt"], "/dir", &no_clobber)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(_)));
+        assert!(fs.cat("/dir/a.txt").await.is_err());
+
+        fs.cp("/a.txt", "/b.txt", &CopyOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(fs.cat("/b.txt").await.unwrap(), "new");
+
+        // A destination created after this handle last looked is still not replaced.
+        let stale = SurrealFs::builder(fs.db.clone())
+            .cache(100, Duration::from_secs(3600))
+            .build();
+        assert!(!stale.exists("/d.txt").await.unwrap());
+        fs.write_file("/d.txt", "theirs").await.unwrap();
+        let err = stale.cp("/a.txt", "/d.txt", &no_clobber).await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(path) if path == "/d.txt"));
+        assert_eq!(fs.cat("/d.txt").await.unwrap(), "theirs");
+    }
+
+    #[tokio::test]
+    async fn cp_preserve_keeps_source_attributes() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/a.txt", "data").await.unwrap();
+        fs.chmod("/a.txt", 0o600).await.unwrap();
+        fs.chown("/a.txt", "alice", Some("staff")).await.unwrap();
+        fs.setxattr("/a.txt", "user.tag", "backup").await.unwrap();
+        fs.set_mtime("/a.txt", 1_000).await.unwrap();
+        let preserve = CopyOptions {
+            preserve: true,
+            ..Default::default()
+        };
+
+        let meta = fs.cp("/a.txt", "/b.txt", &preserve).await.unwrap();
+        assert_eq!(meta.mode, Some(0o600));
+        assert_eq!(meta.owner.as_deref(), Some("alice"));
+        assert_eq!(meta.group.as_deref(), Some("staff"));
+        assert_eq!(meta.updated_at, Some(1_000));
+        assert_eq!(
+            fs.getxattr("/b.txt", "user.tag").await.unwrap(),
+            Some("backup".to_string())
+        );
+
+        let plain = fs
+            .cp("/a.txt", "/c.txt", &CopyOptions::default())
+            .await
+            .unwrap();
+        assert_ne!(plain.updated_at, Some(1_000));
+        assert_eq!(plain.owner, None);
+        assert_eq!(fs.listxattr("/c.txt").await.unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn exchange_swaps_files_and_subtrees() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/releases/new/assets", true).await.unwrap();
+        fs.write_file("/releases/new/assets/app.js", "v2")
+            .await
+            .unwrap();
+        fs.write_file("/current", "v1").await.unwrap();
+
+        fs.exchange("/current", "/releases/new").await.unwrap();
+        assert_eq!(fs.cat("/releases/new").await.unwrap(), "v1");
+        assert_eq!(fs.cat("/current/assets/app.js").await.unwrap(), "v2");
+        let listed = fs.ls("/current/assets").await.unwrap();
+        assert_eq!(listed[0].parent(), Some("/current/assets"));
+        let listed = fs.ls("/releases").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name(), "new");
+
+        let err = fs
+            .exchange("/current", "/current/assets")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::InvalidPath));
+        let err = fs.exchange("/current", "/missing").await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn write_files_is_all_or_nothing() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/README.md", "old").await.unwrap();
+
+        fs.write_files([
+            ("/README.md", "# app"),
+            ("/src/main.rs", "fn main() {}"),
+            ("/src/bin/tool.rs", "fn main() {}"),
+        ])
+        .await
+        .unwrap();
+        assert_eq!(fs.cat("/README.md").await.unwrap(), "# app");
+        assert_eq!(fs.cat("/src/bin/tool.rs").await.unwrap(), "fn main() {}");
+        let names: Vec<String> = fs
+            .ls("/src")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["bin", "main.rs"]);
+
+        let err = fs
+            .write_files([("/new.txt", "x"), ("/README.md/nested.txt", "y")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::NotADirectory(_)));
+        let err = fs
+            .write_files([("/new.txt", "x"), ("/new.txt/inner.txt", "y")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::NotADirectory(_)));
+        assert!(matches!(
+            fs.cat("/new.txt").await.unwrap_err(),
+            FsError::NotFound(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn write_files_reports_a_transaction_the_database_rejects() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let stale = SurrealFs::builder(db.clone())
+            .cache(100, Duration::from_secs(3600))
+            .build();
+        let other = SurrealFs::new(db);
+        assert!(!stale.exists("/f").await.unwrap());
+        other.write_file("/f", "from other").await.unwrap();
+
+        let err = stale
+            .write_files([("/f", "new"), ("/g", "g")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(path) if path == "/f"));
+        assert_eq!(other.cat("/f").await.unwrap(), "from other");
+        assert!(!other.exists("/g").await.unwrap());
+
+        // With the stale view dropped, the same batch overwrites `/f`.
+        stale
+            .write_files([("/f", "new"), ("/g", "g")])
+            .await
+            .unwrap();
+        assert_eq!(other.cat("/f").await.unwrap(), "new");
+        assert_eq!(other.cat("/g").await.unwrap(), "g");
+    }
+
+    #[tokio::test]
+    async fn rm_glob_deletes_matching_files() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/logs/old.log", true).await.unwrap();
+        fs.write_file("/logs/a.log", "a").await.unwrap();
+        fs.write_file("/logs/b.log", "b").await.unwrap();
+        fs.write_file("/logs/keep.txt", "k").await.unwrap();
+
+        let preview = fs.rm_glob("/logs/*.log", true).await.unwrap();
+        assert_eq!(preview, vec!["/logs/a.log", "/logs/b.log"]);
+        assert_eq!(fs.ls("/logs").await.unwrap().len(), 4);
+
+        let removed = fs.rm_glob("/logs/*.log", false).await.unwrap();
+        assert_eq!(removed, preview);
+        let names: Vec<String> = fs
+            .ls("/logs")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["keep.txt", "old.log"]);
+
+        fs.write_file("/logs/c.txt", "c").await.unwrap();
+        fs.set_immutable("/logs/keep.txt", true).await.unwrap();
+        assert!(matches!(
+            fs.rm_glob("/logs/*.txt", false).await,
+            Err(FsError::Immutable(path)) if path == "/logs/keep.txt"
+        ));
+        assert!(fs.exists("/logs/c.txt").await.unwrap());
+        assert!(matches!(
+            fs.rm_glob("/logs/[ab", false).await,
+            Err(FsError::InvalidPattern(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn write_file_new_refuses_existing_paths() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/existing.txt", "x").await.unwrap();
+
+        fs.write_file_new("/app.lock", "pid 1").await.unwrap();
+        let err = fs.write_file_new("/app.lock", "pid 2").await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(_)));
+        assert_eq!(fs.cat("/app.lock").await.unwrap(), "pid 1");
+
+        let err = fs.write_file_new("/existing.txt", "y").await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(_)));
+
+        fs.rm("/app.lock").await.unwrap();
+        fs.write_file_new("/app.lock", "pid 3").await.unwrap();
+        assert_eq!(fs.cat("/app.lock").await.unwrap(), "pid 3");
+
+        let (a, b, c) = tokio::join!(
+            fs.write_file_new("/race.lock", "a"),
+            fs.write_file_new("/race.lock", "b"),
+            fs.write_file_new("/race.lock", "c"),
+        );
+        assert_eq!([a, b, c].iter().filter(|r| r.is_ok()).count(), 1);
+
+        // Other failures are not mistaken for an existing file.
+        fs.db
+            .query("DEFINE FIELD size ON fs_entry ASSERT $value < 100")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let err = fs
+            .write_file_new("/big.lock", "x".repeat(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::Surreal(_)), "{err:?}");
+        assert!(!fs.exists("/big.lock").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn read_lines_streams_in_chunks() {
+        let fs = setup_fs().await.unwrap();
+        let expected: Vec<String> = (0..20_000).map(|i| format!("línea {}", i)).collect();
+        fs.write_file("/big.log", format!("{}\r\ntail", expected.join("\n")))
+            .await
+            .unwrap();
+
+        let lines: Vec<String> = fs.read_lines("/big.log").try_collect().await.unwrap();
+        assert_eq!(lines.len(), expected.len() + 1);
+        assert_eq!(lines[..expected.len()], expected[..]);
+        assert_eq!(lines.last().unwrap(), "tail");
+
+        fs.write_bytes("/bin.txt", b"a\nb\n".to_vec())
+            .await
+            .unwrap();
+        let lines: Vec<String> = fs.read_lines("/bin.txt").try_collect().await.unwrap();
+        assert_eq!(lines, vec!["a", "b"]);
+
+        let err = fs
+            .read_lines("/missing")
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn metadata_probes_without_content() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/docs", false).await.unwrap();
+        fs.write_file("/docs/a.txt", "hello").await.unwrap();
+        fs.symlink("a.txt", "/docs/link").await.unwrap();
+        fs.symlink("missing", "/docs/dangling").await.unwrap();
+
+        assert!(fs.exists("/").await.unwrap());
+        assert!(fs.exists("/docs/a.txt").await.unwrap());
+        assert!(fs.exists("/docs/link").await.unwrap());
+        assert!(!fs.exists("/docs/dangling").await.unwrap());
+        assert!(!fs.exists("/nope").await.unwrap());
+
+        let meta = fs.metadata("/docs/link").await.unwrap().unwrap();
+        assert_eq!(meta.path, "/docs/a.txt");
+        assert_eq!(meta.size, 5);
+        assert!(!meta.is_dir);
+
+        let link = fs.symlink_metadata("/docs/link").await.unwrap().unwrap();
+        assert!(link.is_symlink());
+        assert_eq!(link.size, 0);
+
+        let dir = fs.metadata("/docs").await.unwrap().unwrap();
+        assert!(dir.is_dir);
+        assert_eq!(dir.parent.as_deref(), Some("/"));
+    }
+
+    #[tokio::test]
+    async fn mutators_return_resulting_metadata() {
+        let fs = setup_fs().await.unwrap();
+
+        let dir = fs.mkdir("/a/./b", true).await.unwrap();
+        assert_eq!(dir.path, "/a/b");
+        assert!(dir.is_dir);
+        assert_eq!(fs.mkdir("/a", true).await.unwrap().path, "/a");
+
+        let file = fs.write_file("/a/b/../note.txt", "hello").await.unwrap();
+        assert_eq!(file.path, "/a/note.txt");
+        assert_eq!(file.parent.as_deref(), Some("/a"));
+        assert_eq!(file.size, 5);
+        assert!(file.updated_at.is_some());
+
+        let touched = fs.touch("/a/empty").await.unwrap();
+        assert_eq!(touched.size, 0);
+        let touched = fs.touch("/a/note.txt").await.unwrap();
+        assert_eq!(touched.size, 5);
+        assert!(touched.updated_at >= file.updated_at);
+
+        let copied = fs
+            .cp("/a/note.txt", "/a/b/copy.txt", &CopyOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(copied.path, "/a/b/copy.txt");
+        assert_eq!(copied.size, 5);
+        assert_eq!(
+            fs.metadata("/a/b/copy.txt")
+                .await
+                .unwrap()
+                .unwrap()
+                .updated_at,
+            copied.updated_at
+        );
+
+        // What comes back is what the database stored, not what the call asked for.
+        fs.db
+            .query("DEFINE FIELD owner ON fs_entry VALUE 'db-owner'")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let stored = [
+            fs.mkdir("/c", false).await.unwrap(),
+            fs.touch("/c/new").await.unwrap(),
+            fs.touch("/a/note.txt").await.unwrap(),
+            fs.cp("/a/note.txt", "/c/copy.txt", &CopyOptions::default())
+                .await
+                .unwrap(),
+        ];
+        for meta in stored {
+            assert_eq!(meta.owner.as_deref(), Some("db-owner"), "{}", meta.path);
+            assert_eq!(Some(&meta), fs.metadata(&meta.path).await.unwrap().as_ref());
+        }
+    }
+
+    #[tokio::test]
+    async fn parallel_copies_match_sequential_ones() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db)
+            .parallelism(8)
+            .max_concurrency(4)
+            .build();
+        for dir in ["a", "b", "b/c"] {
+            fs.mkdir(format!("/src/{dir}"), true).await.unwrap();
+            for i in 0..10 {
+                fs.write_file(format!("/src/{dir}/{i}.txt"), format!("{dir} {i}"))
+                    .await
+                    .unwrap();
+            }
+        }
+        fs.symlink("a/0.txt", "/src/link").await.unwrap();
+
+        fs.cp_to("/src", &fs, "/dst").await.unwrap();
+        let copied: Vec<String> = fs
+            .find("/dst", &FindOptions::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(copied.len(), 35);
+        assert_eq!(fs.cat("/dst/b/c/7.txt").await.unwrap(), "b/c 7");
+        assert_eq!(fs.readlink("/dst/link").await.unwrap(), "a/0.txt");
+
+        let sources: Vec<String> = (0..10).map(|i| format!("/src/a/{i}.txt")).collect();
+        fs.mkdir("/flat", false).await.unwrap();
+        fs.cp_into(&sources, "/flat", &CopyOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(fs.ls("/flat").await.unwrap().len(), 10);
+        assert_eq!(fs.cat("/flat/9.txt").await.unwrap(), "a 9");
+    }
+
+    #[tokio::test]
+    async fn buffered_appends_are_written_on_flush() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let plain = SurrealFs::new(db.clone());
+        let fs = SurrealFs::builder(db)
+            .write_buffer(20, Duration::from_secs(3600))
+            .build();
+
+        plain.append_file("/log.txt", "start\n").await.unwrap();
+        plain.append_file("/log.txt", "ready\n").await.unwrap();
+        assert_eq!(plain.cat("/log.txt").await.unwrap(), "start\nready\n");
+
+        for i in 0..5 {
+            fs.append_file("/log.txt", format!("{i}\n")).await.unwrap();
+        }
+        fs.append_file("/logs/new.txt", "x").await.unwrap();
+        assert_eq!(plain.cat("/log.txt").await.unwrap(), "start\nready\n");
+        assert!(!plain.exists("/logs/new.txt").await.unwrap());
+        fs.flush().await.unwrap();
+        assert_eq!(
+            plain.cat("/log.txt").await.unwrap(),
+            "start\nready\n0\n1\n2\n3\n4\n"
+        );
+        assert_eq!(plain.cat("/logs/new.txt").await.unwrap(), "x");
+
+        // Reads through the buffered handle see queued text; a full buffer flushes itself.
+        fs.append_file("/log.txt", "5\n").await.unwrap();
+        assert!(fs.cat("/log.txt").await.unwrap().ends_with("4\n5\n"));
+        for _ in 0..20 {
+            fs.append_file("/count.txt", ".").await.unwrap();
+        }
+        assert_eq!(plain.cat("/count.txt").await.unwrap(), ".".repeat(20));
+    }
+
+    #[tokio::test]
+    async fn buffered_appends_are_written_in_the_background() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let plain = SurrealFs::new(db.clone());
+        let fs = SurrealFs::builder(db.clone())
+            .write_buffer(100, Duration::from_millis(20))
+            .build();
+
+        // A lone append goes out once the interval passes, with no further call.
+        fs.append_file("/log.txt", "one\n").await.unwrap();
+        assert!(!plain.exists("/log.txt").await.unwrap());
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(plain.cat("/log.txt").await.unwrap(), "one\n");
+
+        // What is still queued when the handle goes away is written out too.
+        let fs = SurrealFs::builder(db)
+            .write_buffer(100, Duration::from_secs(3600))
+            .build();
+        fs.append_file("/log.txt", "two\n").await.unwrap();
+        drop(fs);
+        let mut written = String::new();
+        for _ in 0..50 {
+            written = plain.cat("/log.txt").await.unwrap();
+            if written != "one\n" {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(written, "one\ntwo\n");
+    }
+
+    #[tokio::test]
+    async fn open_picks_the_engine_from_the_endpoint() {
+        let fs = SurrealFs::open("memory").await.unwrap();
+        fs.write_file("/a.txt", "a").await.unwrap();
+        assert_eq!(fs.cat("/a.txt").await.unwrap(), "a");
+
+        let other = SurrealFs::open_builder("mem://", "test", "other")
+            .await
+            .unwrap()
+            .table("files")
+            .build();
+        assert!(!other.exists("/a.txt").await.unwrap());
+        assert!(SurrealFs::open("nosuch://x").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_replica_serves_heavy_reads_only() {
+        let primary = Surreal::new::<Mem>(()).await.unwrap();
+        primary.use_ns("test").use_db("test").await.unwrap();
+        let replica = Surreal::new::<Mem>(()).await.unwrap();
+        replica.use_ns("test").use_db("test").await.unwrap();
+        // A replica that has not caught up with the last write.
+        let stale = SurrealFs::new(replica.clone());
+        stale.mkdir("/logs", false).await.unwrap();
+        stale.write_file("/logs/a.log", "old").await.unwrap();
+
+        let fs = SurrealFs::builder(primary).read_replica(replica).build();
+        fs.mkdir("/logs", false).await.unwrap();
+        fs.write_file("/logs/a.log", "new").await.unwrap();
+        fs.write_file("/logs/b.log", "new").await.unwrap();
+
+        assert_eq!(fs.glob("/logs/*.log").await.unwrap().len(), 1);
+        let found = fs.find("/logs", &FindOptions::default()).await.unwrap();
+        assert_eq!(found.len(), 2);
+        let pattern = Regex::new("old").unwrap();
+        assert_eq!(fs.grep(&pattern, "/logs", true).await.unwrap().len(), 1);
+
+        assert_eq!(fs.cat("/logs/a.log").await.unwrap(), "new");
+        assert_eq!(fs.ls("/logs").await.unwrap().len(), 2);
+        assert_eq!(fs.rm_glob("/logs/*.log", false).await.unwrap().len(), 2);
+        assert!(fs.ls("/logs").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn status_reports_session_engine_and_schema() {
+        let fs = SurrealFs::open_builder("memory", "test", "status")
+            .await
+            .unwrap()
+            .build();
+        fs.ping().await.unwrap();
+
+        let status = fs.status().await.unwrap();
+        assert_eq!(status.engine, "any");
+        assert_eq!(status.endpoint.as_deref(), Some("memory"));
+        assert_eq!(status.namespace.as_deref(), Some("test"));
+        assert_eq!(status.database.as_deref(), Some("status"));
+        assert_eq!(status.schema_version, None);
+        assert!(!status.server_version.is_empty());
+
+        fs.define_schema().await.unwrap();
+        assert_eq!(
+            fs.status().await.unwrap().schema_version,
+            Some(SCHEMA_VERSION)
+        );
+
+        let local = setup_fs().await.unwrap();
+        let status = local.status().await.unwrap();
+        assert_eq!(status.engine, "local");
+        assert_eq!(status.endpoint, None);
+    }
+
+    #[tokio::test]
+    async fn use_ns_db_switches_between_filesystems() {
+        let fs = SurrealFs::builder(Surreal::new::<Mem>(()).await.unwrap())
+            .cache(16, Duration::from_secs(60))
+            .build();
+        fs.use_ns_db("test", "one").await.unwrap();
+        fs.write_file("/a.txt", "one").await.unwrap();
+        assert_eq!(fs.cat("/a.txt").await.unwrap(), "one");
+
+        fs.use_ns_db("test", "two").await.unwrap();
+        assert!(!fs.exists("/a.txt").await.unwrap());
+        fs.write_file("/a.txt", "two").await.unwrap();
+
+        fs.use_ns_db("test", "one").await.unwrap();
+        assert_eq!(fs.cat("/a.txt").await.unwrap(), "one");
+        let status = fs.status().await.unwrap();
+        assert_eq!(status.database.as_deref(), Some("one"));
+    }
+
+    #[tokio::test]
+    async fn mounts_serve_prefixes_from_their_own_tables() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let mut mounts = Mounts::new(SurrealFs::new(db.clone()));
+        mounts
+            .mount("/tmp", SurrealFs::new(setup_fs().await.unwrap().db.clone()))
+            .unwrap();
+        mounts
+            .mount("/data", SurrealFs::with_table(db.clone(), "data_entry"))
+            .unwrap();
+        assert!(mounts.mount("/tmp", SurrealFs::new(db.clone())).is_err());
+
+        mounts.write_file("/readme.txt", "root").await.unwrap();
+        let meta = mounts.write_file("/data/a.txt", "durable").await.unwrap();
+        assert_eq!(meta.path, "/data/a.txt");
+        mounts.mkdir("/tmp/work", false).await.unwrap();
+        mounts
+            .write_file("/tmp/work/b.txt", "scratch")
+            .await
+            .unwrap();
+
+        let names = |nodes: Vec<Node>| -> Vec<String> {
+            nodes.iter().map(|n| n.path().to_string()).collect()
+        };
+        assert_eq!(
+            names(mounts.ls("/").await.unwrap()),
+            ["/data", "/proc", "/readme.txt", "/tmp"]
+        );
+        // Each mounted filesystem brings its own /proc along.
+        assert_eq!(
+            names(mounts.ls("/tmp").await.unwrap()),
+            ["/tmp/proc", "/tmp/work"]
+        );
+        assert_eq!(mounts.cat("/data/a.txt").await.unwrap(), "durable");
+        let (_, point, inner) = mounts.resolve("/data/a.txt").unwrap();
+        assert_eq!((point, inner.as_str()), ("/data", "/a.txt"));
+
+        mounts.cp("/tmp/work", "/data/work").await.unwrap();
+        assert_eq!(mounts.cat("/data/work/b.txt").await.unwrap(), "scratch");
+        mounts.mv("/data/a.txt", "/tmp/a.txt").await.unwrap();
+        assert_eq!(mounts.cat("/tmp/a.txt").await.unwrap(), "durable");
+        assert!(!mounts.exists("/data/a.txt").await.unwrap());
+        let err = mounts.mv("/data/a.txt", "/tmp/b.txt").await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(path) if path == "/data/a.txt"));
+
+        let data = mounts.unmount("/data").unwrap();
+        assert_eq!(data.cat("/work/b.txt").await.unwrap(), "scratch");
+        assert!(!mounts.exists("/data").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn overlay_copies_up_and_whites_out_lower_entries() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let base = SurrealFs::with_table(db.clone(), "base");
+        base.mkdir("/src/old", true).await.unwrap();
+        base.write_file("/src/main.rs", "fn main() {}")
+            .await
+            .unwrap();
+        base.write_file("/src/old/a.rs", "a").await.unwrap();
+        base.write_file("/README", "base").await.unwrap();
+
+        let sandbox = Overlay::new(
+            SurrealFs::with_table(db.clone(), "base"),
+            SurrealFs::with_table(db.clone(), "sandbox"),
+        );
+        let names = |nodes: Vec<Node>| -> Vec<String> {
+            nodes.iter().map(|n| n.name().to_string()).collect()
+        };
+        assert_eq!(
+            names(sandbox.ls("/").await.unwrap()),
+            ["README", "proc", "src"]
+        );
+
+        sandbox
+            .append_file(
+                "/src/main.rs",
+                "
+// edited",
+            )
+            .await
+            .unwrap();
+        sandbox
+            .write_file("/src/lib.rs", "pub fn f() {}")
+            .await
+            .unwrap();
+        sandbox.rm("/README").await.unwrap();
+        sandbox.rmdir("/src/old", true).await.unwrap();
+        sandbox.mkdir("/src/old", false).await.unwrap();
+
+        assert_eq!(
+            sandbox.cat("/src/main.rs").await.unwrap(),
+            "fn main() {}\n// edited"
+        );
+        assert_eq!(names(sandbox.ls("/").await.unwrap()), ["proc", "src"]);
+        assert_eq!(
+            names(sandbox.ls("/src").await.unwrap()),
+            ["lib.rs", "main.rs", "old"]
+        );
+        assert!(sandbox.ls("/src/old").await.unwrap().is_empty());
+        assert!(!sandbox.exists("/README").await.unwrap());
+        assert!(matches!(
+            sandbox.cat("/src/old/a.rs").await,
+            Err(FsError::NotFound(_))
+        ));
+
+        // The lower layer is untouched.
+        assert_eq!(base.cat("/src/main.rs").await.unwrap(), "fn main() {}");
+        assert_eq!(base.cat("/README").await.unwrap(), "base");
+        assert!(base.exists("/src/old/a.rs").await.unwrap());
+        sandbox.write_file("/README", "mine").await.unwrap();
+        assert_eq!(sandbox.cat("/README").await.unwrap(), "mine");
+    }
+
+    #[tokio::test]
+    async fn overlay_copies_up_symlinks_as_links() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let base = SurrealFs::with_table(db.clone(), "base");
+        base.mkdir("/conf", false).await.unwrap();
+        base.write_file("/conf/app.toml", "port = 80")
+            .await
+            .unwrap();
+        base.symlink("app.toml", "/conf/current").await.unwrap();
+
+        let upper = SurrealFs::with_table(db.clone(), "sandbox");
+        let sandbox = Overlay::new(
+            SurrealFs::with_table(db.clone(), "base"),
+            SurrealFs::with_table(db.clone(), "sandbox"),
+        );
+        sandbox.copy_up("/conf/current").await.unwrap();
+
+        assert_eq!(upper.readlink("/conf/current").await.unwrap(), "app.toml");
+        assert!(!upper.exists("/conf/app.toml").await.unwrap());
+        assert_eq!(sandbox.cat("/conf/current").await.unwrap(), "port = 80");
+
+        sandbox
+            .write_file("/conf/current", "port = 8080")
+            .await
+            .unwrap();
+        assert_eq!(upper.readlink("/conf/current").await.unwrap(), "app.toml");
+        assert_eq!(upper.cat("/conf/app.toml").await.unwrap(), "port = 8080");
+        assert_eq!(base.cat("/conf/app.toml").await.unwrap(), "port = 80");
+    }
+
+    #[tokio::test]
+    async fn cp_to_copies_between_databases() {
+        let staging = setup_fs().await.unwrap();
+        let prod_db = Surreal::new::<Mem>(()).await.unwrap();
+        prod_db.use_ns("prod").use_db("site").await.unwrap();
+        let prod = SurrealFs::with_table(prod_db, "prod_entry");
+
+        staging.mkdir("/site/assets", true).await.unwrap();
+        staging
+            .write_file("/site/index.html", "<h1>hi</h1>")
+            .await
+            .unwrap();
+        staging
+            .write_bytes("/site/assets/logo.bin", vec![1u8, 2, 3])
+            .await
+            .unwrap();
+        staging.symlink("index.html", "/site/home").await.unwrap();
+        prod.mkdir("/www/site", true).await.unwrap();
+        prod.write_file("/www/site/old.html", "old").await.unwrap();
+
+        let root = staging.cp_to("/site", &prod, "/www/site").await.unwrap();
+        assert!(root.is_dir);
+        assert_eq!(
+            prod.cat("/www/site/index.html").await.unwrap(),
+            "<h1>hi</h1>"
+        );
+        assert_eq!(
+            prod.cat_bytes("/www/site/assets/logo.bin").await.unwrap(),
+            vec![1u8, 2, 3]
+        );
+        assert_eq!(prod.readlink("/www/site/home").await.unwrap(), "index.html");
+        assert_eq!(prod.cat("/www/site/old.html").await.unwrap(), "old");
+
+        let file = staging
+            .cp_to("/site/index.html", &prod, "/index.html")
+            .await
+            .unwrap();
+        assert_eq!(file.size, 11);
+        assert!(!staging.exists("/www").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn subtree_keeps_name_order_and_skips_orphans() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/t/b", true).await.unwrap();
+        fs.write_file("/t/b.txt", "x").await.unwrap();
+        fs.write_file("/t/b/c.txt", "needle").await.unwrap();
+        fs.write_file("/t/b-2", "needle").await.unwrap();
+        fs.db
+            .query("CREATE type::thing('fs_entry', '/t/gone/x') SET path = '/t/gone/x', name = 'x', parent = '/t/gone', is_dir = false, content = 'needle'")
+            .await
+            .unwrap();
+
+        let paths: Vec<String> = fs
+            .walk("/t")
+            .map_ok(|e| e.path)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(paths, vec!["/t/b", "/t/b/c.txt", "/t/b-2", "/t/b.txt"]);
+
+        let regex = Regex::new("needle").unwrap();
+        assert_eq!(fs.grep(&regex, "/t", true).await.unwrap().len(), 2);
+        let tree = fs.tree("/t", Some(1)).await.unwrap();
+        let names: Vec<_> = tree
+            .children
+            .iter()
+            .map(|c| c.entry.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["b", "b-2", "b.txt"]);
+        assert!(tree.children[0].children.is_empty());
+        assert_eq!(
+            fs.tree("/t", None).await.unwrap().children[0]
+                .children
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn server_side_grep_matches_client_grep() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/src/b", true).await.unwrap();
+        server
+            .write_file("/src/b/x.rs", "fn main() {}\r\nlet todo = 1;\r\n")
+            .await
+            .unwrap();
+        server
+            .write_file("/src/b.rs", "// TODO later\n")
+            .await
+            .unwrap();
+        server
+            .write_file("/src/a.rs", "todo\n\ntodo again")
+            .await
+            .unwrap();
+        server.write_file("/src/empty.rs", "").await.unwrap();
+        server
+            .write_bytes("/src/latin.txt", b"caf\xe9 todo\n".to_vec())
+            .await
+            .unwrap();
+
+        for pattern in ["(?i)todo", "^todo$", "^$", "1;$"] {
+            let regex = Regex::new(pattern).unwrap();
+            let expected = client.grep(&regex, "/src", true).await.unwrap();
+            assert_eq!(server.grep(&regex, "/src", true).await.unwrap(), expected);
+            assert_eq!(server.grep(&regex, "/", true).await.unwrap(), expected);
+        }
+        let regex = Regex::new("todo").unwrap();
+        let hits = server.grep(&regex, "/src/a.rs", false).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[1].line_number, 3);
+        assert!(server.grep(&regex, "/src", false).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn grep_stream_yields_matches_as_files_are_scanned() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/t", false).await.unwrap();
+        let files: BTreeMap<String, String> = (0..300)
+            .map(|i| {
+                (
+                    format!("/t/{:03}.txt", i),
+                    format!("hit {}\nmiss\nhit\n", i),
+                )
+            })
+            .collect();
+        server.write_files(files).await.unwrap();
+
+        let opts = GrepOptions {
+            recursive: true,
+            ..GrepOptions::new("hit")
+        };
+        for fs in [&server, &client] {
+            let first: Vec<GrepMatch> = fs
+                .grep_stream("/t", &opts)
+                .take(3)
+                .try_collect()
+                .await
+                .unwrap();
+            let lines: Vec<_> = first
+                .iter()
+                .map(|m| (m.path.as_str(), m.line_number))
+                .collect();
+            assert_eq!(
+                lines,
+                [("/t/000.txt", 1), ("/t/000.txt", 3), ("/t/001.txt", 1)]
+            );
+
+            let all: Vec<GrepMatch> = fs.grep_stream("/t", &opts).try_collect().await.unwrap();
+            assert_eq!(all, fs.grep_with("/t", &opts).await.unwrap());
+            assert_eq!(all.len(), 600);
+        }
+
+        let limited = GrepOptions {
+            max_files: Some(2),
+            max_matches: Some(3),
+            ..opts
+        };
+        let found: Vec<GrepMatch> = server
+            .grep_stream("/t", &limited)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(found, server.grep_with("/t", &limited).await.unwrap());
+        assert_eq!(found.len(), 3);
+
+        let bad = GrepOptions::new("(");
+        let err = server.grep_stream("/t", &bad).try_collect::<Vec<_>>().await;
+        assert!(matches!(err, Err(FsError::InvalidPattern(_))));
+    }
+
+    #[tokio::test]
+    async fn grep_matches_carry_offsets_and_captures() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server
+            .write_file("/log.txt", "héllo key=a1 key=b2\nnothing\n")
+            .await
+            .unwrap();
+
+        let opts = GrepOptions::new(r"key=(?P<value>\w)(\d)");
+        let matches = client.grep_with("/log.txt", &opts).await.unwrap();
+        assert_eq!(server.grep_with("/log.txt", &opts).await.unwrap(), matches);
+        assert_eq!(matches.len(), 1);
+        let subs = &matches[0].submatches;
+        assert_eq!(subs.len(), 2);
+        assert_eq!((subs[0].start, subs[0].end), (7, 13));
+        assert_eq!((subs[0].char_start, subs[0].char_end), (6, 12));
+        assert_eq!(subs[0].text, "key=a1");
+        assert_eq!(&matches[0].line[subs[1].start..subs[1].end], "key=b2");
+        assert_eq!(
+            subs[1].captures,
+            BTreeMap::from([("value".to_string(), "b".to_string())])
+        );
+
+        let inverted = GrepOptions {
+            invert: true,
+            ..opts
+        };
+        let lines = server.grep_with("/log.txt", &inverted).await.unwrap();
+        assert_eq!(lines[0].line, "nothing");
+        assert!(lines[0].submatches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn grep_reports_binary_files_once_without_their_lines() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/d", false).await.unwrap();
+        server
+            .write_file("/d/a.txt", "todo one\ntodo two\n")
+            .await
+            .unwrap();
+        server.write_file("/d/b.png", "todo\ntodo\n").await.unwrap();
+        server
+            .write_bytes("/d/c.bin", b"\x89PNG\r\n\x1a\ntodo".to_vec())
+            .await
+            .unwrap();
+        server.write_bytes("/d/d.bin", vec![0, 1, 2]).await.unwrap();
+
+        let opts = GrepOptions {
+            recursive: true,
+            ..GrepOptions::new("todo")
+        };
+        let matches = client.grep_with("/d", &opts).await.unwrap();
+        assert_eq!(server.grep_with("/d", &opts).await.unwrap(), matches);
+        let found: Vec<_> = matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.line_number, m.binary))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/d/a.txt", 1, false),
+                ("/d/a.txt", 2, false),
+                ("/d/b.png", 0, true),
+                ("/d/c.bin", 0, true),
+            ]
+        );
+        assert!(matches[2].line.is_empty());
+
+        let counts = client.grep_count("/d", &opts).await.unwrap();
+        assert_eq!(server.grep_count("/d", &opts).await.unwrap(), counts);
+        assert_eq!(counts[0].count, 2);
+        assert_eq!(counts[1].count, 1);
+        assert_eq!(
+            server.grep_files("/d", &opts).await.unwrap(),
+            ["/d/a.txt", "/d/b.png", "/d/c.bin"]
+        );
+
+        let changed = server.replace_in(&opts, "done", "/d", false).await.unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(server.cat_bytes("/d/b.png").await.unwrap(), b"todo\ntodo\n");
+    }
+
+    #[tokio::test]
+    async fn grep_options_match_like_grep_flags() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/src", false).await.unwrap();
+        server
+            .write_file("/src/a.rs", "Error: a.b\nerrors: none\nok\n")
+            .await
+            .unwrap();
+
+        let lines = |opts: GrepOptions| {
+            let (server, client) = (&server, &client);
+            async move {
+                let expected = client.grep_with("/src", &opts).await.unwrap();
+                assert_eq!(server.grep_with("/src", &opts).await.unwrap(), expected);
+                expected
+                    .into_iter()
+                    .map(|m| m.line_number)
+                    .collect::<Vec<_>>()
+            }
+        };
+        let opts = |pattern: &str| GrepOptions {
+            recursive: true,
+            ..GrepOptions::new(pattern)
+        };
+        assert_eq!(lines(opts("error")).await, [2]);
+        let ignore_case = GrepOptions {
+            ignore_case: true,
+            ..opts("error")
+        };
+        assert_eq!(lines(ignore_case.clone()).await, [1, 2]);
+        assert_eq!(
+            lines(GrepOptions {
+                word: true,
+                ..ignore_case.clone()
+            })
+            .await,
+            [1]
+        );
+        assert_eq!(
+            lines(GrepOptions {
+                invert: true,
+                ..ignore_case
+            })
+            .await,
+            [3]
+        );
+        assert_eq!(lines(opts("a.b")).await, [1]);
+        assert_eq!(lines(opts("a.c")).await, Vec::<usize>::new());
+        let fixed = GrepOptions {
+            fixed_strings: true,
+            ..opts("a.b")
+        };
+        assert_eq!(lines(fixed).await, [1]);
+        let fixed = GrepOptions {
+            fixed_strings: true,
+            ..opts("a?b")
+        };
+        assert_eq!(lines(fixed).await, Vec::<usize>::new());
+
+        let err = client.grep_with("/src", &opts("(")).await.unwrap_err();
+        assert!(matches!(err, FsError::InvalidPattern(_)));
+    }
+
+    #[tokio::test]
+    async fn grep_keeps_the_flags_of_a_built_regex() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.write_file("/a.txt", "Hello\nbye\n").await.unwrap();
+
+        let pattern = regex::RegexBuilder::new("hello")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        for fs in [&client, &server] {
+            let found = fs.grep(&pattern, "/", true).await.unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].line, "Hello");
+            assert_eq!(found[0].submatches[0].text, "Hello");
+        }
+    }
+
+    #[tokio::test]
+    async fn grep_count_and_files_report_per_file_results() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/logs/old", true).await.unwrap();
+        server
+            .write_file("/logs/app.log", "ERROR one\nok\nERROR two\n")
+            .await
+            .unwrap();
+        server
+            .write_file("/logs/old/app.log", "ERROR three\n")
+            .await
+            .unwrap();
+        server.write_file("/logs/quiet.log", "ok\n").await.unwrap();
+        server
+            .write_bytes("/logs/raw.log", b"ERROR raw\n".to_vec())
+            .await
+            .unwrap();
+
+        let opts = GrepOptions {
+            recursive: true,
+            ..GrepOptions::new("ERROR")
+        };
+        for fs in [&server, &client] {
+            let counts = fs.grep_count("/logs", &opts).await.unwrap();
+            let counts: Vec<(&str, usize)> =
+                counts.iter().map(|c| (c.path.as_str(), c.count)).collect();
+            assert_eq!(
+                counts,
+                [
+                    ("/logs/app.log", 2),
+                    ("/logs/old/app.log", 1),
+                    ("/logs/raw.log", 1)
+                ]
+            );
+            assert_eq!(
+                fs.grep_files("/logs", &opts).await.unwrap(),
+                ["/logs/app.log", "/logs/old/app.log", "/logs/raw.log"]
+            );
+            assert!(
+                fs.grep_files("/logs/quiet.log", &GrepOptions::new("ERROR"))
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn grep_limits_stop_at_the_first_matches_in_walk_order() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/t/a", true).await.unwrap();
+        server.write_file("/t/a.txt", "hit\n").await.unwrap();
+        server.write_file("/t/a/b.txt", "hit\nhit\n").await.unwrap();
+        for i in 0..300 {
+            server
+                .write_file(format!("/t/f{:03}", i), "hit\n")
+                .await
+                .unwrap();
+        }
+
+        let opts = |max_matches, max_files| GrepOptions {
+            recursive: true,
+            max_matches,
+            max_files,
+            ..GrepOptions::new("hit")
+        };
+        for fs in [&server, &client] {
+            let first = fs.grep_with("/t", &opts(Some(1), None)).await.unwrap();
+            assert_eq!(first.len(), 1);
+            assert_eq!(first[0].path, "/t/a/b.txt");
+
+            let counts = fs.grep_count("/t", &opts(Some(3), None)).await.unwrap();
+            let counts: Vec<(&str, usize)> =
+                counts.iter().map(|c| (c.path.as_str(), c.count)).collect();
+            assert_eq!(counts, [("/t/a/b.txt", 2), ("/t/a.txt", 1)]);
+
+            let files = fs.grep_files("/t", &opts(None, Some(260))).await.unwrap();
+            assert_eq!(files.len(), 260);
+            assert_eq!(files[2], "/t/f000");
+            assert_eq!(files[259], "/t/f257");
+        }
+    }
+
+    #[tokio::test]
+    async fn multiline_grep_reports_matches_spanning_lines() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let server = SurrealFs::builder(db.clone())
+            .server_side_grep(true)
+            .build();
+        let client = SurrealFs::new(db);
+        server.mkdir("/src", false).await.unwrap();
+        server
+            .write_file(
+                "/src/lib.rs",
+                "fn short() {}\nfn long(\n    a: u8,\n) -> u8 {\n    a\n}\n",
+            )
+            .await
+            .unwrap();
+        server
+            .write_file("/src/other.rs", "fn main() {}\n")
+            .await
+            .unwrap();
+
+        let opts = GrepOptions {
+            recursive: true,
+            multiline: true,
+            ..GrepOptions::new(r"fn \w+\([^)]*\n[^)]*\)")
+        };
+        for fs in [&server, &client] {
+            let matches = fs.grep_with("/src", &opts).await.unwrap();
+            assert_eq!(matches.len(), 1);
+            assert_eq!((matches[0].line_number, matches[0].end_line), (2, 4));
+            assert_eq!(matches[0].line, "fn long(\n    a: u8,\n) -> u8 {");
+            assert_eq!(fs.grep_files("/src", &opts).await.unwrap(), ["/src/lib.rs"]);
+
+            let invert = GrepOptions {
+                invert: true,
+                ..opts.clone()
+            };
+            let lines: Vec<(String, usize)> = fs
+                .grep_with("/src", &invert)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|m| (m.path, m.line_number))
+                .collect();
+            assert_eq!(
+                lines,
+                [
+                    ("/src/lib.rs".to_string(), 1),
+                    ("/src/lib.rs".to_string(), 5),
+                    ("/src/lib.rs".to_string(), 6),
+                    ("/src/other.rs".to_string(), 1)
+                ]
+            );
+        }
+
+        let single = GrepOptions {
+            recursive: true,
+            ..GrepOptions::new("^fn")
+        };
+        let matches = client.grep_with("/src", &single).await.unwrap();
+        assert_eq!(matches.len(), 3);
+        assert!(matches.iter().all(|m| m.end_line == m.line_number));
+    }
+
+    #[tokio::test]
+    async fn replace_in_rewrites_matches_across_files() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/src/util", true).await.unwrap();
+        fs.write_file(
+            "/src/main.rs",
+            "let x = old_name(1);\nold_name(old_name(2));\n",
+        )
+        .await
+        .unwrap();
+        fs.write_file("/src/util/mod.rs", "pub fn old_name(n: u8) {}\r\n")
+            .await
+            .unwrap();
+        fs.write_file("/src/notes.txt", "nothing here\n")
+            .await
+            .unwrap();
+
+        let opts = GrepOptions {
+            recursive: true,
+            word: true,
+            ..GrepOptions::new(r"old_(\w+)")
+        };
+        let preview = fs.replace_in(&opts, "new_$1", "/src", true).await.unwrap();
+        let summary: Vec<(&str, usize)> = preview
+            .iter()
+            .map(|f| (f.path.as_str(), f.replacements))
+            .collect();
+        assert_eq!(summary, [("/src/main.rs", 3), ("/src/util/mod.rs", 1)]);
+        assert!(preview[0].diff.contains("+new_name(new_name(2));\n"));
+        assert!(fs.cat("/src/main.rs").await.unwrap().contains("old_name"));
+
+        let applied = fs.replace_in(&opts, "new_$1", "/src", false).await.unwrap();
+        assert_eq!(applied, preview);
+        assert_eq!(
+            fs.cat("/src/main.rs").await.unwrap(),
+            "let x = new_name(1);\nnew_name(new_name(2));\n"
+        );
+        assert_eq!(
+            fs.cat("/src/util/mod.rs").await.unwrap(),
+            "pub fn new_name(n: u8) {}\r\n"
+        );
+
+        let literal = GrepOptions {
+            fixed_strings: true,
+            ..GrepOptions::new("(1)")
+        };
+        fs.replace_in(&literal, "($1)", "/src/main.rs", false)
+            .await
+            .unwrap();
+        assert!(
+            fs.cat("/src/main.rs")
+                .await
+                .unwrap()
+                .starts_with("let x = new_name($1);")
+        );
+
+        let err = fs
+            .read_only()
+            .replace_in(&opts, "x", "/src", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::ReadOnly(_)));
+    }
+
+    #[tokio::test]
+    async fn search_ranks_files_and_highlights_snippets() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/docs", false).await.unwrap();
+        fs.write_file(
+            "/docs/foxes.md",
+            "# Notes\nThe quick brown fox jumps over the lazy dog.\nFoxes like dogs.\n",
+        )
+        .await
+        .unwrap();
+        fs.write_file("/docs/dog.md", "A dog, and one fox.\n")
+            .await
+            .unwrap();
+        fs.write_file("/docs/cats.md", "Cats only.\n")
+            .await
+            .unwrap();
+        fs.write_file("/elsewhere.md", "fox and dog\n")
+            .await
+            .unwrap();
+
+        let hits = fs.search("foxes dog", "/docs").await.unwrap();
+        let mut paths: Vec<&str> = hits.iter().map(|h| h.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["/docs/dog.md", "/docs/foxes.md"]);
+        assert!(hits[0].score >= hits[1].score);
+
+        let foxes = hits.iter().find(|h| h.path == "/docs/foxes.md").unwrap();
+        assert_eq!(
+            foxes.highlighted("[", "]"),
+            "The quick brown [fox] jumps over the lazy [dog]."
+        );
+        assert!(fs.search("giraffe", "/").await.unwrap().is_empty());
+        assert_eq!(fs.search("fox", "/").await.unwrap().len(), 3);
+
+        fs.write_file("/docs/cats.md", "Cats chase a fox.\n")
+            .await
+            .unwrap();
+        let hits = fs.search("fox", "/docs/cats.md").await.unwrap();
+        assert_eq!(hits[0].snippet, "Cats chase a fox.");
+        assert_eq!(hits[0].highlights, [(13, 16)]);
+
+        // Hard-linked content is found under each of its paths in scope.
+        fs.link("/docs/cats.md", "/cats.md").await.unwrap();
+        let hits = fs.search("chase", "/").await.unwrap();
+        let paths: Vec<&str> = hits.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, ["/cats.md", "/docs/cats.md"]);
+        assert_eq!(hits[1].highlights, [(5, 10)]);
+        let hits = fs.search("chase", "/docs").await.unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn compressed_content_reads_back_transparently() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db.clone())
+            .compression(Compression::Gzip)
+            .build();
+        let log = "GET /index.html 200\n".repeat(500);
+        fs.write_file("/access.log", log.clone()).await.unwrap();
+        let data: Vec<u8> = (0..4096u32).map(|i| (i % 7) as u8).collect();
+        fs.write_bytes("/blob.bin", data.clone()).await.unwrap();
+        fs.write_file("/tiny.txt", "hi").await.unwrap();
+
+        let mut res = db
+            .query("SELECT path, content, compression, bytes::len(content_bytes ?? <bytes>'') AS stored FROM fs_entry ORDER BY path")
+            .await
+            .unwrap();
+        let rows: Vec<serde_json::Value> = res.take(0).unwrap();
+        assert_eq!(rows[0]["compression"]["codec"], "gzip");
+        assert!(rows[0]["content"].is_null());
+        assert!(rows[0]["stored"].as_u64().unwrap() < 200);
+        assert_eq!(rows[1]["compression"]["text"], false);
+        assert!(rows[2]["compression"].is_null());
+
+        assert_eq!(fs.cat("/access.log").await.unwrap(), log);
+        assert_eq!(fs.cat_bytes("/blob.bin").await.unwrap(), data);
+        assert_eq!(fs.ls("/access.log").await.unwrap()[0].size(), log.len());
+        let regex = Regex::new("index").unwrap();
+        assert_eq!(fs.grep(&regex, "/", true).await.unwrap().len(), 500);
+        fs.edit("/access.log", "200", "404", false, false)
+            .await
+            .unwrap();
+        assert!(
+            fs.cat("/access.log")
+                .await
+                .unwrap()
+                .starts_with("GET /index.html 404\n")
+        );
+
+        let plain = SurrealFs::new(db);
+        plain
+            .write_file_compressed("/notes.md", "# notes\n".repeat(100), Compression::Gzip)
+            .await
+            .unwrap();
+        assert_eq!(
+            plain.cat("/notes.md").await.unwrap(),
+            "# notes\n".repeat(100)
+        );
+        plain.write_file("/access.log", "plain").await.unwrap();
+        assert_eq!(fs.cat("/access.log").await.unwrap(), "plain");
+    }
+
+    #[tokio::test]
+    async fn creates_through_a_stale_cache_report_existing_records() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let stale = SurrealFs::builder(db.clone())
+            .cache(100, Duration::from_secs(3600))
+            .build();
+        let other = SurrealFs::new(db);
+        stale.write_file("/src.txt", "src").await.unwrap();
+        for path in ["/d", "/s", "/l", "/n"] {
+            assert!(!stale.exists(path).await.unwrap());
+            other.write_file(path, "from other").await.unwrap();
+        }
+
+        let err = stale.mkdir("/d", false).await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(path) if path == "/d"));
+        assert!(!other.stat("/d").await.unwrap().is_dir());
+        let err = stale.symlink("/src.txt", "/s").await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(path) if path == "/s"));
+        let err = stale.link("/src.txt", "/l").await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(path) if path == "/l"));
+        let err = stale.mv("/src.txt", "/n", false).await.unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(path) if path == "/n"));
+
+        assert_eq!(stale.cat("/src.txt").await.unwrap(), "src");
+        for path in ["/d", "/s", "/l", "/n"] {
+            assert_eq!(stale.cat(path).await.unwrap(), "from other");
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_serves_repeated_lookups_and_invalidates_on_write() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db.clone())
+            .cache(100, Duration::from_millis(300))
+            .build();
+        let other = SurrealFs::new(db);
+        fs.mkdir("/docs", false).await.unwrap();
+        fs.write_file("/docs/a.txt", "one").await.unwrap();
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "one");
+        assert_eq!(fs.ls("/docs").await.unwrap().len(), 1);
+
+        // Changes from another client stay hidden until the TTL runs out.
+        other.write_file("/docs/a.txt", "two").await.unwrap();
+        other.write_file("/docs/b.txt", "b").await.unwrap();
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "one");
+        assert_eq!(fs.ls("/docs").await.unwrap().len(), 1);
+        sleep(Duration::from_millis(350)).await;
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "two");
+        assert_eq!(fs.ls("/docs").await.unwrap().len(), 2);
+
+        // Writes through the cached handle are seen at once.
+        fs.write_file("/docs/a.txt", "three").await.unwrap();
+        fs.write_file("/docs/c.txt", "c").await.unwrap();
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "three");
+        assert_eq!(fs.ls("/docs").await.unwrap().len(), 3);
+        fs.mv("/docs", "/papers", false).await.unwrap();
+        assert!(!fs.exists("/docs/a.txt").await.unwrap());
+        assert_eq!(fs.cat("/papers/a.txt").await.unwrap(), "three");
+        fs.rm("/papers/b.txt").await.unwrap();
+        assert_eq!(fs.ls("/papers").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn subscribed_cache_sees_changes_from_other_clients() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db.clone())
+            .cache(100, Duration::from_secs(3600))
+            .build();
+        fs.subscribe_cache().await.unwrap();
+        let other = SurrealFs::new(db);
+        fs.mkdir("/docs", false).await.unwrap();
+        fs.write_file("/docs/a.txt", "one").await.unwrap();
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "one");
+        assert!(!fs.exists("/docs/gone.txt").await.unwrap());
+        other.write_file("/docs/gone.txt", "x").await.unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(fs.ls("/docs").await.unwrap().len(), 2);
+
+        other.write_file("/docs/a.txt", "two").await.unwrap();
+        other.rm("/docs/gone.txt").await.unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "two");
+        assert!(!fs.exists("/docs/gone.txt").await.unwrap());
+        assert_eq!(fs.ls("/docs").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retry_policy_retries_only_transient_errors() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use surrealdb::error::Api;
+
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(3),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(1));
+        assert_eq!(policy.backoff(2), Duration::from_millis(2));
+        assert_eq!(policy.backoff(5), Duration::from_millis(3));
+
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db).retry(policy).build();
+        let attempts = AtomicU32::new(0);
+        let fail_with = |err: fn() -> Api, failures: u32| {
+            let n = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if n <= failures {
+                    Err(surrealdb::Error::Api(err()))
+                } else {
+                    Ok(n)
+                }
+            }
+        };
+
+        let ok = fs.run(|| fail_with(|| Api::Ws("connection reset".into()), 2));
+        assert_eq!(ok.await.unwrap(), 3);
+
+        attempts.store(0, Ordering::SeqCst);
+        let exhausted = fs.run(|| fail_with(|| Api::Ws("connection reset".into()), 5));
+        assert!(matches!(exhausted.await, Err(FsError::Surreal(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        attempts.store(0, Ordering::SeqCst);
+        let parse = fs.run(|| fail_with(|| Api::ParseError("bad query".into()), 5));
+        assert!(parse.await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        attempts.store(0, Ordering::SeqCst);
+        let once = fs.run_once(|| fail_with(|| Api::Ws("connection reset".into()), 2));
+        assert!(matches!(once.await, Err(FsError::Surreal(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        fs.write_file("/ok.txt", "fine").await.unwrap();
+        assert_eq!(fs.cat("/ok.txt").await.unwrap(), "fine");
+    }
+
+    #[tokio::test]
+    async fn glob_stream_yields_every_match_across_batches() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_files((0..300).map(|i| (format!("/logs/{i:03}.log"), "x")))
+            .await
+            .unwrap();
+        fs.write_file("/logs/readme.md", "skip").await.unwrap();
+
+        let mut stream = std::pin::pin!(fs.glob_stream("/logs/*.log"));
+        assert!(stream.try_next().await.unwrap().is_some());
+        let streamed: Vec<String> = fs.glob_stream("/logs/*.log").try_collect().await.unwrap();
+        assert_eq!(streamed.len(), 300);
+        assert_eq!(streamed, fs.glob("/logs/*.log").await.unwrap());
+
+        let none: Vec<String> = fs.glob_stream("/logs/*.txt").try_collect().await.unwrap();
+        assert!(none.is_empty());
+        let invalid: Result<Vec<String>> = fs.glob_stream("").try_collect().await;
+        assert!(invalid.is_err());
+    }
+
+    #[tokio::test]
+    async fn walked_and_found_entries_load_content_on_demand() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db)
+            .compression(Compression::Gzip)
+            .build();
+        let body = "lazy line\n".repeat(100);
+        fs.mkdir("/l", false).await.unwrap();
+        fs.write_file("/l/big.txt", body.clone()).await.unwrap();
+        fs.write_bytes("/l/raw.bin", vec![0, 1, 2]).await.unwrap();
+
+        let mut walked: Vec<Entry> = fs.walk("/l").try_collect().await.unwrap();
+        let big = &mut walked[0];
+        assert!(big.content_handle.is_pending());
+        assert_eq!(big.content, None);
+        assert_eq!(big.size(), body.len());
+        assert!(matches!(
+            Node::from(big.clone()),
+            Node::File(FileEntry { content: None, .. })
+        ));
+        big.load_content().await.unwrap();
+        assert!(!big.content_handle.is_pending());
+        assert_eq!(big.text().unwrap().unwrap(), body);
+
+        let mut found = fs.find("/l", &FindOptions::default()).await.unwrap();
+        let raw = found.iter_mut().find(|e| e.name == "raw.bin").unwrap();
+        assert_eq!(raw.size(), 3);
+        fs.write_bytes("/l/raw.bin", vec![9]).await.unwrap();
+        raw.load_content().await.unwrap();
+        assert_eq!(raw.bytes().unwrap(), vec![9]);
+
+        let tree = fs.tree("/l", None).await.unwrap();
+        assert!(tree.children[0].entry.content_handle.is_pending());
+        let regex = Regex::new("lazy").unwrap();
+        assert_eq!(fs.grep(&regex, "/l", true).await.unwrap().len(), 100);
+    }
+
+    #[tokio::test]
+    async fn walk_streams_descendants_depth_first() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/w/b/inner", true).await.unwrap();
+        fs.mkdir("/w/a", true).await.unwrap();
+        fs.write_file("/w/a/1.txt", "1").await.unwrap();
+        fs.write_file("/w/b/inner/2.txt", "2").await.unwrap();
+        fs.write_file("/w/c.txt", "c").await.unwrap();
+
+        let paths: Vec<String> = fs
+            .walk("/w")
+            .map_ok(|e| e.path)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                "/w/a",
+                "/w/a/1.txt",
+                "/w/b",
+                "/w/b/inner",
+                "/w/b/inner/2.txt",
+                "/w/c.txt"
+            ]
+        );
+
+        let empty: Vec<Entry> = fs.walk("/w/c.txt").try_collect().await.unwrap();
+        assert!(empty.is_empty());
+        let err = fs.walk("/nope").try_collect::<Vec<_>>().await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn diff_compares_two_files() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/a.txt", "one\ntwo\n").await.unwrap();
+        fs.write_file("/b.txt", "one\nthree\n").await.unwrap();
+
+        let diff = fs.diff("/a.txt", "/b.txt").await.unwrap();
+        assert_eq!(diff, "--- /a.txt\n+++ /b.txt\n one\n-two\n+three\n");
+        assert_eq!(fs.diff("/a.txt", "/a.txt").await.unwrap(), "");
+
+        let err = fs.diff("/a.txt", "/missing").await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn sort_and_uniq_process_lines() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/n.txt", "10 ten\n9 nine\nb\n-1 neg\n10 ten\n")
+            .await
+            .unwrap();
+
+        let sorted = fs.sort("/n.txt", &SortOptions::default()).await.unwrap();
+        assert_eq!(sorted, vec!["-1 neg", "10 ten", "10 ten", "9 nine", "b"]);
+
+        let numeric = SortOptions {
+            numeric: true,
+            ..SortOptions::default()
+        };
+        let sorted = fs.sort("/n.txt", &numeric).await.unwrap();
+        assert_eq!(sorted, vec!["-1 neg", "b", "9 nine", "10 ten", "10 ten"]);
+
+        let reversed = SortOptions {
+            numeric: true,
+            reverse: true,
+        };
+        let sorted = fs.sort("/n.txt", &reversed).await.unwrap();
+        assert_eq!(sorted[0], "10 ten");
+        assert_eq!(sorted[4], "-1 neg");
+
+        fs.write_file("/u.txt", "a\na\nb\na\n").await.unwrap();
+        let runs = fs.uniq("/u.txt").await.unwrap();
+        let summary: Vec<(usize, &str)> = runs.iter().map(|r| (r.count, r.line.as_str())).collect();
+        assert_eq!(summary, vec![(2, "a"), (1, "b"), (1, "a")]);
+    }
+
+    #[tokio::test]
+    async fn edit_many_applies_all_or_nothing() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/main.rs", "fn a() {}\nfn b() {}\nlet x = 1;\nlet x = 1;\n")
+            .await
+            .unwrap();
+
+        let ops = vec![
+            EditOp {
+                old: "fn a()".into(),
+                new: "fn alpha()".into(),
+                replace_all: false,
+            },
+            EditOp {
+                old: "x = 1".into(),
+                new: "x = 2".into(),
+                replace_all: true,
+            },
+            EditOp {
+                old: "fn alpha() {}".into(),
+                new: "fn alpha() { b() }".into(),
+                replace_all: false,
+            },
+        ];
+        let diff = fs.edit_many("/main.rs", &ops, false).await.unwrap();
+        assert!(diff.contains("-fn a() {}\n+fn alpha() { b() }\n"));
+        assert_eq!(
+            fs.cat("/main.rs").await.unwrap(),
+            "fn alpha() { b() }\nfn b() {}\nlet x = 2;\nlet x = 2;\n"
+        );
+
+        let stale = vec![
+            EditOp {
+                old: "fn b()".into(),
+                new: "fn beta()".into(),
+                replace_all: false,
+            },
+            EditOp {
+                old: "fn missing()".into(),
+                new: "".into(),
+                replace_all: false,
+            },
+        ];
+        let err = fs.edit_many("/main.rs", &stale, false).await.unwrap_err();
+        assert!(matches!(err, FsError::NoMatch(_)));
+        assert!(fs.cat("/main.rs").await.unwrap().contains("fn b()"));
+    }
+
+    #[tokio::test]
+    async fn line_range_edits() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/list.txt", "one\ntwo\nthree\nfour\n")
+            .await
+            .unwrap();
+
+        let diff = fs
+            .insert_lines("/list.txt", 2, ["one and a half"])
+            .await
+            .unwrap();
+        assert!(diff.contains("+one and a half\n"));
+        fs.insert_lines("/list.txt", 6, ["five"]).await.unwrap();
+        assert_eq!(
+            fs.cat("/list.txt").await.unwrap(),
+            "one\none and a half\ntwo\nthree\nfour\nfive\n"
+        );
+
+        fs.delete_lines("/list.txt", 2..=3).await.unwrap();
+        fs.replace_lines("/list.txt", 2..4, ["3", "4", "4.5"])
+            .await
+            .unwrap();
+        assert_eq!(fs.cat("/list.txt").await.unwrap(), "one\n3\n4\n4.5\nfive\n");
+
+        let err = fs.delete_lines("/list.txt", 5..=6).await.unwrap_err();
+        assert!(matches!(err, FsError::LineOutOfRange(_)));
+        let err = fs.insert_lines("/list.txt", 0, ["zero"]).await.unwrap_err();
+        assert!(matches!(err, FsError::LineOutOfRange(_)));
+    }
+
+    #[tokio::test]
+    async fn write_and_cat_bytes() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/bin", true).await.unwrap();
+        let data = vec![0u8, 159, 255];
+        fs.write_bytes("/bin/blob", data.clone()).await.unwrap();
+
+        let raw = fs.cat_bytes("/bin/blob").await.unwrap();
+        assert_eq!(raw, data);
+
+        let err = fs.cat("/bin/blob").await.unwrap_err();
+        assert!(matches!(err, FsError::Binary(_)));
+        assert!(fs.is_binary("/bin/blob").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn bytes_with_nul_are_binary_even_when_valid_utf8() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_bytes("/data.dat", b"ab\0cd\nab".to_vec())
+            .await
+            .unwrap();
+        fs.write_bytes("/note", b"ab\ncd\n".to_vec()).await.unwrap();
+
+        let stored: Vec<u8> = fs.cat_bytes("/data.dat").await.unwrap();
+        assert_eq!(stored, b"ab\0cd\nab");
+        assert!(fs.is_binary("/data.dat").await.unwrap());
+        assert!(!fs.is_binary("/note").await.unwrap());
+        assert!(matches!(fs.cat("/data.dat").await, Err(FsError::Binary(_))));
+        assert!(matches!(fs.is_binary("/").await, Err(FsError::NotAFile(_))));
+
+        let regex = Regex::new("ab").unwrap();
+        let matches = fs.grep(&regex, "/", true).await.unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].path, "/data.dat");
+        assert!(matches[0].binary);
+        assert_eq!(matches[1].path, "/note");
+        assert!(!matches[1].binary);
+    }
+
+    #[tokio::test]
+    async fn write_bytes_utf8_reads_as_text() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/notes", true).await.unwrap();
+        fs.write_bytes("/notes/msg", b"hello".to_vec())
+            .await
+            .unwrap();
+
+        let text = fs.cat("/notes/msg").await.unwrap();
+        assert_eq!(text, "hello");
+    }
+
+    #[tokio::test]
+    async fn cp_preserves_binary() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/bin", true).await.unwrap();
+        fs.mkdir("/copy", true).await.unwrap();
+        let data = vec![1u8, 2, 3, 4];
+        fs.write_bytes("/bin/src.bin", data.clone()).await.unwrap();
+
+        fs.cp("/bin/src.bin", "/copy/dest.bin", &CopyOptions::default())
+            .await
+            .unwrap();
+
+        let copied = fs.cat_bytes("/copy/dest.bin").await.unwrap();
+        assert_eq!(copied, data);
+
+        let entries = fs.ls("/copy").await.unwrap();
+        let dest = entries.iter().find(|e| e.name() == "dest.bin").unwrap();
+        assert_eq!(dest.size(), data.len());
+    }
+
+    #[tokio::test]
+    async fn write_bytes_leaves_non_images_untouched() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/bin", true).await.unwrap();
+        let payload = vec![7u8, 8, 9];
+        fs.write_bytes("/bin/raw", payload.clone()).await.unwrap();
+
+        let stored = fs.cat_bytes("/bin/raw").await.unwrap();
+        assert_eq!(stored, payload);
+    }
+
+    #[tokio::test]
+    async fn write_bytes_optimizes_png() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/img", true).await.unwrap();
+
+        fs.write_bytes("/img/pixel.png", ONE_BY_ONE_PNG.to_vec())
+            .await
+            .unwrap();
+
+        let stored = fs.cat_bytes("/img/pixel.png").await.unwrap();
+        let image = Image::read(ZCursor::new(&stored), DecoderOptions::default()).unwrap();
+        assert_eq!(image.dimensions(), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn cp_does_not_recompress_virtual_files() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/data", true).await.unwrap();
+        let data = vec![5u8, 4, 3, 2, 1];
+        fs.write_bytes("/data/src.bin", data.clone()).await.unwrap();
+
+        fs.mkdir("/data/copies", true).await.unwrap();
+        fs.cp(
+            "/data/src.bin",
+            "/data/copies/dst.bin",
+            &CopyOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let copied = fs.cat_bytes("/data/copies/dst.bin").await.unwrap();
+        assert_eq!(copied, data);
+    }
+
+    #[tokio::test]
+    async fn glob_matches_newest_first() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/proj/src", true).await.unwrap();
+        fs.mkdir("/proj/tests", true).await.unwrap();
+
+        fs.write_file("/proj/src/main.rs", "main").await.unwrap();
+        sleep(Duration::from_millis(5)).await;
+        fs.write_file("/proj/src/lib.rs", "lib").await.unwrap();
+        sleep(Duration::from_millis(5)).await;
+        fs.write_file("/proj/tests/main.rs", "test").await.unwrap();
+
+        let matches = fs.glob("/proj/**/*.rs").await.unwrap();
+        assert_eq!(
+            matches,
+            vec![
+                "/proj/tests/main.rs",
+                "/proj/src/lib.rs",
+                "/proj/src/main.rs",
+            ]
+        );
+
+        let root_matches = fs.glob("**/*.rs").await.unwrap();
+        assert_eq!(root_matches, matches);
+    }
+
+    #[tokio::test]
+    async fn glob_runs_classes_and_alternation_server_side() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/logs/2024", true).await.unwrap();
+        for path in [
+            "/logs/a1.log",
+            "/logs/b2.log",
+            "/logs/c.txt",
+            "/logs/2024/d3.log",
+        ] {
+            fs.write_file(path, "x").await.unwrap();
+        }
+
+        let mut matches = fs.glob("/logs/[ab]?.log").await.unwrap();
+        matches.sort();
+        assert_eq!(matches, vec!["/logs/a1.log", "/logs/b2.log"]);
+        assert_eq!(fs.glob("/logs/[!ab]*").await.unwrap().len(), 2);
+        let mut matches = fs.glob("/logs/**/*.{txt,log}").await.unwrap();
+        matches.sort();
+        assert_eq!(
+            matches,
+            vec![
+                "/logs/2024/d3.log",
+                "/logs/a1.log",
+                "/logs/b2.log",
+                "/logs/c.txt"
+            ]
+        );
+        assert!(
+            fs.glob("/logs/*.log")
+                .await
+                .unwrap()
+                .iter()
+                .all(|p| !p.contains("2024"))
+        );
+        assert!(matches!(
+            fs.glob("/logs/[ab").await,
+            Err(FsError::InvalidPath)
+        ));
+    }
+
+    #[tokio::test]
+    async fn glob_braces_nest_and_span_segments() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/proj/src/bin", true).await.unwrap();
+        fs.mkdir("/proj/docs", true).await.unwrap();
+        for path in [
+            "/proj/src/lib.rs",
+            "/proj/src/bin/cli.rs",
+            "/proj/Cargo.toml",
+            "/proj/README.md",
+            "/proj/docs/guide.md",
+            "/proj/docs/notes.txt",
+        ] {
+            fs.write_file(path, "x").await.unwrap();
+        }
+
+        let sorted = |mut paths: Vec<String>| {
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            sorted(fs.glob("/proj/**/*.{rs,toml}").await.unwrap()),
+            [
+                "/proj/Cargo.toml",
+                "/proj/src/bin/cli.rs",
+                "/proj/src/lib.rs"
+            ]
+        );
+        assert_eq!(
+            sorted(fs.glob("/proj/{src/**/*.rs,*.toml}").await.unwrap()),
+            [
+                "/proj/Cargo.toml",
+                "/proj/src/bin/cli.rs",
+                "/proj/src/lib.rs"
+            ]
+        );
+        assert_eq!(
+            sorted(fs.glob("/proj/{docs/*.{md,txt},README.md}").await.unwrap()),
+            [
+                "/proj/README.md",
+                "/proj/docs/guide.md",
+                "/proj/docs/notes.txt"
+            ]
+        );
+        assert_eq!(
+            sorted(fs.glob("/proj/{**}/cli.rs").await.unwrap()),
+            ["/proj/src/bin/cli.rs"]
+        );
+        assert!(matches!(
+            fs.glob("/proj/{src,docs").await,
+            Err(FsError::InvalidPath)
+        ));
+    }
+
+    #[tokio::test]
+    async fn glob_many_combines_patterns_and_exclusions() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/proj/src", true).await.unwrap();
+        fs.mkdir("/proj/target/debug", true).await.unwrap();
+        fs.mkdir("/docs", true).await.unwrap();
+        for path in [
+            "/proj/src/lib.rs",
+            "/proj/src/main.rs",
+            "/proj/target/debug/build.rs",
+            "/proj/Cargo.toml",
+            "/docs/guide.md",
+        ] {
+            fs.write_file(path, "x").await.unwrap();
+        }
+
+        let mut matches = fs
+            .glob_many(&["**/*.rs", "!**/target/**", "/docs/*.md", "!**/main.rs"])
+            .await
+            .unwrap();
+        matches.sort();
+        assert_eq!(matches, ["/docs/guide.md", "/proj/src/lib.rs"]);
+
+        let mut matches = fs
+            .glob_many(&["/proj/**", "!/proj/target", "!/proj/target/**"])
+            .await
+            .unwrap();
+        matches.sort();
+        assert_eq!(
+            matches,
+            [
+                "/proj/Cargo.toml",
+                "/proj/src",
+                "/proj/src/lib.rs",
+                "/proj/src/main.rs"
+            ]
+        );
+        assert!(matches!(
+            fs.glob_many(&["!**/target/**"]).await,
+            Err(FsError::InvalidPath)
+        ));
+        assert!(matches!(
+            fs.glob("!/proj/*").await,
+            Err(FsError::InvalidPath)
+        ));
+    }
+
+    #[tokio::test]
+    async fn glob_with_filters_by_kind_size_and_mtime() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/data/logs.d", true).await.unwrap();
+        fs.write_file("/data/small.log", "x").await.unwrap();
+        fs.write_file("/data/big.log", "x".repeat(100))
+            .await
+            .unwrap();
+        fs.write_file("/data/old.log", "x".repeat(50))
+            .await
+            .unwrap();
+        fs.set_mtime("/data/old.log", 1_000).await.unwrap();
+
+        let files = GlobOptions {
+            kind: Some(EntryKind::File),
+            ..GlobOptions::default()
+        };
+        let mut paths = fs.glob_with(&["/data/*log*"], &files).await.unwrap();
+        paths.sort();
+        assert_eq!(paths, ["/data/big.log", "/data/old.log", "/data/small.log"]);
+        let dirs = GlobOptions {
+            kind: Some(EntryKind::Dir),
+            ..GlobOptions::default()
+        };
+        assert_eq!(
+            fs.glob_with(&["/data/*log*"], &dirs).await.unwrap(),
+            ["/data/logs.d"]
+        );
+
+        let sized = GlobOptions {
+            min_size: Some(10),
+            max_size: Some(99),
+            ..files.clone()
+        };
+        assert_eq!(
+            fs.glob_with(&["/data/*"], &sized).await.unwrap(),
+            ["/data/old.log"]
+        );
+        let recent = GlobOptions {
+            modified_since: Some(2_000),
+            ..files
+        };
+        let entries = fs.glob_entries(&["/data/*"], &recent).await.unwrap();
+        let mut found: Vec<(&str, usize)> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.size()))
+            .collect();
+        found.sort();
+        assert_eq!(found, [("/data/big.log", 100), ("/data/small.log", 1)]);
+        let mut big = entries
+            .into_iter()
+            .find(|e| e.path == "/data/big.log")
+            .unwrap();
+        big.load_content().await.unwrap();
+        assert_eq!(big.text().unwrap().unwrap().len(), 100);
+    }
+
+    #[tokio::test]
+    async fn stats_groups_files_by_extension() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/proj/src", true).await.unwrap();
+        fs.write_file("/proj/src/lib.rs", "x".repeat(300))
+            .await
+            .unwrap();
+        fs.write_file("/proj/src/main.RS", "x".repeat(100))
+            .await
+            .unwrap();
+        fs.write_file("/proj/Cargo.toml", "x".repeat(50))
+            .await
+            .unwrap();
+        fs.write_file("/proj/.gitignore", "target\n").await.unwrap();
+        fs.write_bytes("/proj/logo.png", vec![0u8; 1000])
+            .await
+            .unwrap();
+        fs.symlink("/proj/src/lib.rs", "/proj/link.rs")
+            .await
+            .unwrap();
+        fs.write_file("/elsewhere.rs", "x").await.unwrap();
+
+        let stats = fs.stats("/proj").await.unwrap();
+        assert_eq!((stats.files, stats.bytes), (5, 1457));
+        let summary: Vec<(Option<&str>, usize, usize)> = stats
+            .types
+            .iter()
+            .map(|t| (t.extension.as_deref(), t.files, t.bytes))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("png"), 1, 1000),
+                (Some("rs"), 2, 400),
+                (Some("toml"), 1, 50),
+                (None, 1, 7)
+            ]
+        );
+        let rust = &stats.types[1];
+        let largest: Vec<(&str, usize)> = rust
+            .largest
+            .iter()
+            .map(|f| (f.path.as_str(), f.bytes))
+            .collect();
+        assert_eq!(
+            largest,
+            [("/proj/src/lib.rs", 300), ("/proj/src/main.RS", 100)]
+        );
+
+        assert_eq!(fs.stats("/").await.unwrap().files, 6);
+        assert!(matches!(
+            fs.stats("/missing").await,
+            Err(FsError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn locate_finds_names_containing_a_fragment() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/app/conf", true).await.unwrap();
+        fs.write_file("/app/conf/config.toml", "").await.unwrap();
+        fs.write_file("/app/Config.TOML.bak", "").await.unwrap();
+        fs.write_file("/app/gifnoc", "").await.unwrap();
+        fs.write_file("/app/my notes.txt", "").await.unwrap();
+
+        assert_eq!(
+            fs.locate("config.toml").await.unwrap(),
+            ["/app/Config.TOML.bak", "/app/conf/config.toml"]
+        );
+        assert_eq!(fs.locate("CONF").await.unwrap().len(), 3);
+        assert_eq!(fs.locate("y no").await.unwrap(), ["/app/my notes.txt"]);
+        assert!(fs.locate("cofig").await.unwrap().is_empty());
+
+        fs.mv("/app/gifnoc", "/app/renamed.toml", false)
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.locate(".toml").await.unwrap(),
+            [
+                "/app/Config.TOML.bak",
+                "/app/conf/config.toml",
+                "/app/renamed.toml"
+            ]
+        );
+        assert!(matches!(fs.locate("a/b").await, Err(FsError::InvalidPath)));
+    }
+
+    #[tokio::test]
+    async fn symbols_index_definitions_on_write() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/src", false).await.unwrap();
+        fs.write_file(
+            "/src/lib.rs",
+            "pub struct Parser {}\n\nimpl Parser {\n    pub async fn parse_all(&self) {}\n}\n\nenum Token {}\n",
+        )
+        .await
+        .unwrap();
+        fs.write_file(
+            "/src/tool.py",
+            "class Parser:\n    def parse(self):\n        pass\n",
+        )
+        .await
+        .unwrap();
+        fs.write_file("/src/notes.md", "fn parse() {}\n")
+            .await
+            .unwrap();
+
+        let found: Vec<_> = fs
+            .symbols("parse")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.path, s.line, s.kind, s.name))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    "/src/tool.py".into(),
+                    2,
+                    SymbolKind::Function,
+                    "parse".into()
+                ),
+                ("/src/lib.rs".into(), 1, SymbolKind::Struct, "Parser".into()),
+                (
+                    "/src/lib.rs".into(),
+                    4,
+                    SymbolKind::Function,
+                    "parse_all".into()
+                ),
+                ("/src/tool.py".into(), 1, SymbolKind::Class, "Parser".into()),
+            ]
+        );
+
+        // Rewrites replace a file's definitions, and they follow it when it moves.
+        fs.write_file("/src/lib.rs", "trait Token {}\n")
+            .await
+            .unwrap();
+        fs.mv("/src/lib.rs", "/src/main.rs", false).await.unwrap();
+        let tokens = fs.symbols("TOKEN").await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].path, "/src/main.rs");
+        assert_eq!(tokens[0].kind, SymbolKind::Trait);
+        assert!(fs.symbols("parse_all").await.unwrap().is_empty());
+        assert_eq!(fs.symbols("").await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn modified_since_and_ls_by_mtime_order_by_updated_at() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/w/sub", true).await.unwrap();
+        for (path, mtime) in [
+            ("/w/a.txt", 3_000),
+            ("/w/b.txt", 1_000),
+            ("/w/c.txt", 3_000),
+            ("/w/sub/d.txt", 5_000),
+        ] {
+            fs.write_file(path, "x").await.unwrap();
+            fs.set_mtime(path, mtime).await.unwrap();
+        }
+        fs.set_mtime("/w/sub", 2_000).await.unwrap();
+        fs.set_mtime("/w", 500).await.unwrap();
+
+        let changed: Vec<_> = fs
+            .modified_since("/w", 2_000)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.path)
+            .collect();
+        assert_eq!(changed, ["/w/sub/d.txt", "/w/a.txt", "/w/c.txt", "/w/sub"]);
+        assert_eq!(fs.modified_since("/w/sub", 0).await.unwrap().len(), 2);
+        assert!(matches!(
+            fs.modified_since("/nope", 0).await,
+            Err(FsError::NotFound(_))
+        ));
+
+        let newest: Vec<_> = fs
+            .ls_by_mtime("/w", 3)
+            .await
+            .unwrap()
+            .iter()
+            .map(|n| n.path().to_string())
+            .collect();
+        assert_eq!(newest, ["/w/a.txt", "/w/c.txt", "/w/sub"]);
+        assert_eq!(fs.ls_by_mtime("/w/b.txt", 3).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn history_keeps_previous_revisions() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/before.txt", "untracked").await.unwrap();
+        fs.enable_history(Some(2)).await.unwrap();
+        fs.write_file("/before.txt", "tracked").await.unwrap();
+        assert_eq!(
+            fs.cat_revision("/before.txt", 1).await.unwrap(),
+            "untracked"
+        );
+
+        fs.write_file("/a.txt", "one").await.unwrap();
+        fs.write_file("/a.txt", "two").await.unwrap();
+        fs.edit("/a.txt", "two", "three", false, false)
+            .await
+            .unwrap();
+        fs.set_mtime("/a.txt", 1_000).await.unwrap();
+        fs.transaction(|tx| async move {
+            tx.write_file("/a.txt", "four")?;
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+        let history = fs.history("/a.txt").await.unwrap();
+        let kept: Vec<_> = history.iter().map(|r| (r.version, r.size)).collect();
+        assert_eq!(kept, [(3, 5), (2, 3)]);
+        assert_eq!(history[0].updated_at, Some(1_000));
+        assert_eq!(fs.cat_revision("/a.txt", 3).await.unwrap(), "three");
+        assert_eq!(fs.cat_revision("/a.txt", 2).await.unwrap(), "two");
+        assert!(matches!(
+            fs.cat_revision("/a.txt", 1).await,
+            Err(FsError::NotFound(_))
+        ));
+
+        fs.rm("/a.txt").await.unwrap();
+        assert_eq!(fs.history("/a.txt").await.unwrap().len(), 2);
+
+        fs.disable_history().await.unwrap();
+        fs.write_file("/before.txt", "untracked again")
+            .await
+            .unwrap();
+        assert_eq!(fs.history("/before.txt").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn history_keeps_files_replaced_by_rename() {
+        let fs = setup_fs().await.unwrap();
+        fs.enable_history(None).await.unwrap();
+        fs.write_file("/cfg.txt", "one").await.unwrap();
+        let meta = fs.write_file_atomic("/cfg.txt", "two").await.unwrap();
+        assert_eq!(meta.version, 2);
+        fs.write_file("/new.txt", "three").await.unwrap();
+        fs.mv("/new.txt", "/cfg.txt", true).await.unwrap();
+        fs.write_file("/other.txt", "four").await.unwrap();
+        fs.exchange("/cfg.txt", "/other.txt").await.unwrap();
+
+        let versions =
+            |history: Vec<Revision>| history.into_iter().map(|r| r.version).collect::<Vec<_>>();
+        assert_eq!(versions(fs.history("/cfg.txt").await.unwrap()), [3, 2, 1]);
+        assert_eq!(fs.cat_revision("/cfg.txt", 1).await.unwrap(), "one");
+        assert_eq!(fs.cat_revision("/cfg.txt", 2).await.unwrap(), "two");
+        assert_eq!(fs.cat_revision("/cfg.txt", 3).await.unwrap(), "three");
+        assert_eq!(fs.cat("/cfg.txt").await.unwrap(), "four");
+        assert_eq!(versions(fs.history("/other.txt").await.unwrap()), [1]);
+        assert_eq!(fs.cat_revision("/other.txt", 1).await.unwrap(), "four");
+    }
+
+    #[tokio::test]
+    async fn history_follows_writes_through_hard_links() {
+        let fs = setup_fs().await.unwrap();
+        fs.enable_history(None).await.unwrap();
+        fs.write_file("/a.txt", "one").await.unwrap();
+        fs.link("/a.txt", "/b.txt").await.unwrap();
+        assert!(fs.history("/a.txt").await.unwrap().is_empty());
+
+        fs.write_file("/b.txt", "two").await.unwrap();
+        fs.append_file("/a.txt", "!").await.unwrap();
+        for path in ["/a.txt", "/b.txt"] {
+            let versions: Vec<u64> = fs
+                .history(path)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.version)
+                .collect();
+            assert_eq!(versions, [2, 1]);
+            assert_eq!(fs.cat_revision(path, 1).await.unwrap(), "one");
+            assert_eq!(fs.cat_revision(path, 2).await.unwrap(), "two");
+        }
+
+        fs.disable_history().await.unwrap();
+        fs.write_file("/a.txt", "three").await.unwrap();
+        assert_eq!(fs.history("/b.txt").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn edit_replaces_first() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/notes", true).await.unwrap();
+        fs.write_file("/notes/todo.txt", "alpha beta alpha")
+            .await
+            .unwrap();
+
+        let diff = fs
+            .edit("/notes/todo.txt", "alpha", "ALPHA", false, false)
+            .await
+            .unwrap();
+
+        let content = fs.cat("/notes/todo.txt").await.unwrap();
+        assert_eq!(content, "ALPHA beta alpha");
+        assert!(diff.contains("-alpha beta alpha"));
+        assert!(diff.contains("+ALPHA beta alpha"));
+    }
+
+    #[tokio::test]
+    async fn edit_replaces_all() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/notes", true).await.unwrap();
+        fs.write_file("/notes/all.txt", "foo bar foo")
+            .await
+            .unwrap();
+
+        let diff = fs
+            .edit("/notes/all.txt", "foo", "FOO", true, false)
+            .await
+            .unwrap();
+
+        let content = fs.cat("/notes/all.txt").await.unwrap();
+        assert_eq!(content, "FOO bar FOO");
+        assert!(diff.contains("-foo bar foo"));
+        assert!(diff.contains("+FOO bar FOO"));
+    }
+
+    #[tokio::test]
+    async fn created_at_survives_writes() {
+        let fs = setup_fs().await.unwrap();
+        let created = fs.write_file("/log.txt", "one").await.unwrap();
+        assert!(created.created_at.is_some());
+        assert_eq!(created.created_at, created.updated_at);
+
+        sleep(Duration::from_millis(5)).await;
+        let rewritten = fs.write_file("/log.txt", "two").await.unwrap();
+        assert_eq!(rewritten.created_at, created.created_at);
+        assert!(rewritten.updated_at > created.updated_at);
+
+        let entry = fs.ls("/log.txt").await.unwrap().remove(0);
+        assert_eq!(entry.attrs().created_at, created.created_at);
+        let dir = fs.mkdir("/logs", false).await.unwrap();
+        assert!(dir.created_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn size_is_stored_on_write() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/a.txt", "héllo").await.unwrap();
+        fs.write_bytes("/b.bin", vec![0, 159, 146, 150])
+            .await
+            .unwrap();
+        fs.write_files([("/batch/c.txt", "abc")]).await.unwrap();
+
+        #[derive(Deserialize)]
+        struct SizeRow {
+            path: String,
+            size: usize,
+        }
+        let mut res = fs
+            .db
+            .query("SELECT path, size FROM fs_entry WHERE is_dir = false ORDER BY path")
+            .await
+            .unwrap();
+        let rows: Vec<SizeRow> = res.take(0).unwrap();
+        let rows: Vec<(&str, usize)> = rows.iter().map(|r| (r.path.as_str(), r.size)).collect();
+        assert_eq!(
+            rows,
+            vec![("/a.txt", 6), ("/b.bin", 4), ("/batch/c.txt", 3)]
+        );
+
+        fs.edit("/a.txt", "héllo", "hi", false, false)
+            .await
+            .unwrap();
+        let meta = fs.metadata("/a.txt").await.unwrap().unwrap();
+        assert_eq!(meta.size, 2);
+    }
+
+    #[tokio::test]
+    async fn content_type_is_detected_and_steers_grep() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/site", false).await.unwrap();
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&[0xff, 0xfe, b'l', b'o', b'g', b'o']);
+        let meta = fs.write_bytes("/site/logo.dat", png).await.unwrap();
+        assert_eq!(meta.content_type.as_deref(), Some("image/png"));
+
+        fs.write_file("/site/index.html", "<img src=logo>")
+            .await
+            .unwrap();
+        fs.write_file("/site/README", "the logo").await.unwrap();
+        fs.write_bytes("/site/latin1.txt", vec![b'l', b'o', b'g', b'o', 0xe9])
+            .await
+            .unwrap();
+        fs.write_bytes("/site/blob.bin", vec![b'l', b'o', b'g', b'o', 0x00, 0xe9])
+            .await
+            .unwrap();
+
+        let types: Vec<_> = fs
+            .ls("/site")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| match e {
+                Node::File(f) => (f.name, f.content_type.unwrap()),
+                other => panic!("expected a file, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("README".to_string(), "text/plain".to_string()),
+                (
+                    "blob.bin".to_string(),
+                    "application/octet-stream".to_string()
+                ),
+                ("index.html".to_string(), "text/html".to_string()),
+                // Latin-1 is detected and typed by what it decodes to.
+                ("latin1.txt".to_string(), "text/plain".to_string()),
+                ("logo.dat".to_string(), "image/png".to_string()),
+            ]
+        );
+
+        let re = Regex::new("logo").unwrap();
+        let matches = fs.grep(&re, "/site", true).await.unwrap();
+        let paths: Vec<_> = matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.binary))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("/site/README", false),
+                ("/site/blob.bin", true),
+                ("/site/index.html", false),
+                ("/site/latin1.txt", false),
+                ("/site/logo.dat", true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn newline_policy_normalizes_writes_and_exports() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db).newline(NewlinePolicy::Lf).build();
+        fs.mkdir("/w", false).await.unwrap();
+        fs.write_file("/w/a.txt", "one\r\ntwo\r\n").await.unwrap();
+        fs.write_bytes("/w/b.txt", b"x\r\ny".to_vec())
+            .await
+            .unwrap();
+        fs.write_bytes("/w/c.bin", vec![0, b'\r', b'\n'])
+            .await
+            .unwrap();
+        assert_eq!(fs.cat("/w/a.txt").await.unwrap(), "one\ntwo\n");
+        assert_eq!(fs.cat("/w/b.txt").await.unwrap(), "x\ny");
+        assert_eq!(
+            fs.cat_bytes("/w/c.bin").await.unwrap(),
+            vec![0, b'\r', b'\n']
+        );
+        fs.edit("/w/a.txt", "one\ntwo", "1\n2", false, false)
+            .await
+            .unwrap();
+
+        assert_eq!(NewlinePolicy::CrLf.apply("a\nb\r\nc"), "a\r\nb\r\nc");
+        assert_eq!(NewlinePolicy::Preserve.apply("a\r\nb\n"), "a\r\nb\n");
+        assert_eq!(fs.export_bytes("/w/a.txt").await.unwrap(), b"1\n2\n");
+    }
+
+    #[tokio::test]
+    async fn legacy_encodings_are_recorded_and_transcoded() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/dl", false).await.unwrap();
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("héllo\nwörld\n".encode_utf16().flat_map(u16::to_le_bytes));
+        fs.write_bytes("/dl/page.txt", utf16).await.unwrap();
+        fs.write_bytes("/dl/latin1.txt", b"caf\xe9\n".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.file_type("/dl/latin1.txt").await.unwrap(),
+            "ISO-8859 text"
+        );
+
+        let meta = fs.symlink_metadata("/dl/page.txt").await.unwrap().unwrap();
+        assert_eq!(meta.encoding.as_deref(), Some("utf-16le"));
+        assert_eq!(fs.cat("/dl/page.txt").await.unwrap(), "héllo\nwörld\n");
+        assert_eq!(fs.cat("/dl/latin1.txt").await.unwrap(), "café\n");
+
+        let re = Regex::new("wörld|café").unwrap();
+        let lines: Vec<_> = fs
+            .grep(&re, "/dl", true)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.line)
+            .collect();
+        assert_eq!(lines, vec!["café", "wörld"]);
+
+        // Rewriting as UTF-8 text clears the recorded encoding.
+        fs.write_file("/dl/page.txt", "plain").await.unwrap();
+        let meta = fs.symlink_metadata("/dl/page.txt").await.unwrap().unwrap();
+        assert_eq!(meta.encoding, None);
+    }
+
+    #[tokio::test]
+    async fn chmod_blocks_writes_to_read_only_files() {
+        let fs = setup_fs().await.unwrap();
+        let meta = fs.write_file("/conf.toml", "a = 1").await.unwrap();
+        assert_eq!(meta.permissions(), 0o644);
+        assert_eq!(fs.mkdir("/etc", false).await.unwrap().permissions(), 0o755);
+
+        fs.chmod("/conf.toml", 0o444).await.unwrap();
+        let entry = fs.ls("/conf.toml").await.unwrap().remove(0);
+        assert_eq!(entry.mode_string(), "-r--r--r--");
+
+        let err = fs.write_file("/conf.toml", "a = 2").await.unwrap_err();
+        assert!(matches!(err, FsError::PermissionDenied(_)));
+        let err = fs
+            .edit("/conf.toml", "1", "2", false, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::PermissionDenied(_)));
+        assert_eq!(fs.cat("/conf.toml").await.unwrap(), "a = 1");
+
+        fs.chmod("/conf.toml", 0o600).await.unwrap();
+        fs.write_file("/conf.toml", "a = 2").await.unwrap();
+        assert_eq!(format_mode(true, false, 0o750), "drwxr-x---");
+    }
+
+    #[tokio::test]
+    async fn chown_and_chgrp_attribute_entries() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/shared", false).await.unwrap();
+        fs.write_file("/shared/report.md", "q3").await.unwrap();
+        fs.link("/shared/report.md", "/shared/latest.md")
+            .await
+            .unwrap();
+        assert_eq!(
+            fs.metadata("/shared/report.md")
+                .await
+                .unwrap()
+                .unwrap()
+                .owner,
+            None
+        );
+
+        fs.chown("/shared/report.md", "alice", Some("finance"))
+            .await
+            .unwrap();
+        fs.chgrp("/shared", "finance").await.unwrap();
+
+        let report = fs.metadata("/shared/latest.md").await.unwrap().unwrap();
+        assert_eq!(report.owner.as_deref(), Some("alice"));
+        assert_eq!(report.group.as_deref(), Some("finance"));
+        let dir = fs
+            .ls("/")
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|node| node.name() == "shared")
+            .unwrap();
+        assert_eq!(
+            (dir.attrs().owner.as_deref(), dir.attrs().group.as_deref()),
+            (None, Some("finance"))
+        );
+
+        fs.chown("/shared/report.md", "bob", None).await.unwrap();
+        let report = fs.metadata("/shared/report.md").await.unwrap().unwrap();
+        assert_eq!(report.owner.as_deref(), Some("bob"));
+        assert_eq!(report.group.as_deref(), Some("finance"));
+    }
+
+    #[tokio::test]
+    async fn xattrs_round_trip() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/page.html", "<html>").await.unwrap();
+        assert!(fs.listxattr("/page.html").await.unwrap().is_empty());
+
+        fs.setxattr("/page.html", "source", "https://example.com")
+            .await
+            .unwrap();
+        fs.setxattr("/page.html", "label", "draft").await.unwrap();
+        fs.setxattr("/page.html", "label", "final").await.unwrap();
+        assert_eq!(
+            fs.listxattr("/page.html").await.unwrap(),
+            vec!["label", "source"]
+        );
+        assert_eq!(
+            fs.getxattr("/page.html", "label").await.unwrap().as_deref(),
+            Some("final")
+        );
+
+        fs.write_file("/page.html", "<html></html>").await.unwrap();
+        assert!(fs.removexattr("/page.html", "label").await.unwrap());
+        assert!(!fs.removexattr("/page.html", "label").await.unwrap());
+        let meta = fs.metadata("/page.html").await.unwrap().unwrap();
+        assert_eq!(meta.xattrs.len(), 1);
+        assert_eq!(fs.getxattr("/page.html", "label").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn verify_detects_content_changed_behind_our_back() {
+        let fs = setup_fs().await.unwrap();
+        let meta = fs.write_file("/hello.txt", "hello").await.unwrap();
+        assert_eq!(
+            meta.checksum.as_deref(),
+            Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+        assert!(!fs.verify("/hello.txt").await.unwrap().is_corrupt());
+
+        fs.db
+            .query("UPDATE fs_entry SET content = 'jello' WHERE path = '/hello.txt'")
+            .await
+            .unwrap();
+        let report = fs.verify("/hello.txt").await.unwrap();
+        assert!(report.is_corrupt());
+        assert_eq!(report.expected, meta.checksum);
+
+        fs.write_file("/hello.txt", "jello").await.unwrap();
+        assert!(!fs.verify("/hello.txt").await.unwrap().is_corrupt());
+    }
+
+    #[tokio::test]
+    async fn ls_page_and_glob_page_resume_from_cursor() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/big", false).await.unwrap();
+        for (i, name) in ["e", "a", "d", "b", "c"].iter().enumerate() {
+            let path = format!("/big/{name}.log");
+            fs.write_file(&path, name.to_string()).await.unwrap();
+            fs.set_mtime(&path, 1_000 + (i as i64 % 2)).await.unwrap();
+        }
+
+        let first = fs.ls_page("/big", None, 2).await.unwrap();
+        let names: Vec<&str> = first.items.iter().map(|n| n.name()).collect();
+        assert_eq!(names, ["a.log", "b.log"]);
+        fs.write_file("/big/a0.log", "late").await.unwrap();
+        let second = fs.ls_page("/big", first.next.as_deref(), 2).await.unwrap();
+        let names: Vec<&str> = second.items.iter().map(|n| n.name()).collect();
+        assert_eq!(names, ["c.log", "d.log"]);
+        let last = fs.ls_page("/big", second.next.as_deref(), 2).await.unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert_eq!(last.next, None);
+        assert_eq!(
+            fs.ls_page("/big/e.log", None, 2).await.unwrap().items.len(),
+            1
+        );
+
+        fs.rm("/big/a0.log").await.unwrap();
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = fs
+                .glob_page("/big/*.log", cursor.as_deref(), 2)
+                .await
+                .unwrap();
+            assert!(page.items.len() <= 2);
+            paged.extend(page.items);
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(paged, fs.glob("/big/*.log").await.unwrap());
+        assert_eq!(paged[..2], ["/big/a.log", "/big/b.log"]);
+        assert!(fs.glob_page("/big/*", Some("bogus"), 2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn ls_and_cd_skip_file_content() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/media", false).await.unwrap();
+        fs.write_bytes("/media/big.bin", vec![7u8; 4096])
+            .await
+            .unwrap();
+        fs.write_file("/media/note.txt", "héllo").await.unwrap();
+        fs.symlink("/media", "/m").await.unwrap();
+
+        let listed = fs.ls("/m").await.unwrap();
+        let sizes: Vec<_> = listed
+            .iter()
+            .map(|n| match n {
+                Node::File(f) => (f.name.as_str(), f.size, f.content.is_none()),
+                other => panic!("expected files, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(sizes, vec![("big.bin", 4096, true), ("note.txt", 6, true)]);
+        assert_eq!(fs.ls("/media/note.txt").await.unwrap()[0].size(), 6);
+        assert_eq!(fs.cd("/", "m").await.unwrap(), "/media");
+        assert!(matches!(
+            fs.cd("/", "media/note.txt").await,
+            Err(FsError::NotADirectory(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn stat_returns_typed_nodes() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/bin", false).await.unwrap();
+        fs.write_bytes("/bin/tool", vec![0x7f, b'E', b'L', b'F'])
+            .await
+            .unwrap();
+        fs.write_file("/notes.txt", "hi").await.unwrap();
+        fs.symlink("/notes.txt", "/latest").await.unwrap();
+
+        match fs.stat("/bin/tool").await.unwrap() {
+            Node::File(file) => {
+                assert_eq!(
+                    file.content,
+                    Some(FileContent::Binary(vec![0x7f, b'E', b'L', b'F']))
+                );
+                assert_eq!(file.content_type.as_deref(), Some("application/x-elf"));
+            }
+            other => panic!("expected a file, got {:?}", other),
+        }
+        match fs.stat("/latest").await.unwrap() {
+            Node::File(file) => {
+                assert_eq!(file.content.as_ref().and_then(|c| c.as_text()), Some("hi"))
+            }
+            other => panic!("expected the link target, got {:?}", other),
+        }
+        let root = fs.stat("/").await.unwrap();
+        assert!(root.is_dir());
+        assert_eq!(root.mode_string(), "drwxr-xr-x");
+
+        let listed = fs.ls("/").await.unwrap();
+        let kinds: Vec<_> = listed
+            .iter()
+            .map(|n| (n.name(), n.is_dir(), n.is_symlink(), n.size()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("bin", true, false, 0),
+                ("latest", false, true, 0),
+                ("notes.txt", false, false, 2),
+                ("proc", true, false, 0),
+            ]
+        );
+        assert!(matches!(
+            fs.stat("/missing").await.unwrap_err(),
+            FsError::NotFound(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn access_tracking_is_opt_in() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/cold.txt", "zzz").await.unwrap();
+        fs.cat("/cold.txt").await.unwrap();
+        let meta = fs.metadata("/cold.txt").await.unwrap().unwrap();
+        assert_eq!(meta.accessed_at, None);
+
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db).track_access(true).build();
+        let written = fs.write_file("/hot.txt", "!!!").await.unwrap();
+        assert_eq!(written.accessed_at, None);
+
+        sleep(Duration::from_millis(5)).await;
+        fs.read("/hot.txt", 0, 1).await.unwrap();
+        let meta = fs.metadata("/hot.txt").await.unwrap().unwrap();
+        assert!(meta.accessed_at > meta.updated_at);
+        assert_eq!(meta.updated_at, written.updated_at);
+    }
+
+    #[tokio::test]
+    async fn file_type_describes_content_like_file_command() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/f", false).await.unwrap();
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        fs.write_bytes("/f/pic", png).await.unwrap();
+        fs.write_file("/f/data", "{\"a\": [1, 2]}").await.unwrap();
+        fs.write_file("/f/run", "#!/usr/bin/env python3\nprint(1)\n")
+            .await
+            .unwrap();
+        fs.write_file("/f/dos.txt", "a\r\nb\r\n").await.unwrap();
+        fs.write_file("/f/empty", "").await.unwrap();
+        fs.symlink("/f/data", "/f/link").await.unwrap();
+
+        let described = |path: &'static str| {
+            let fs = &fs;
+            async move { fs.file_type(path).await.unwrap() }
+        };
+        assert_eq!(described("/f").await, "directory");
+        assert_eq!(described("/f/pic").await, "PNG image data, 640 x 480");
+        assert_eq!(described("/f/data").await, "JSON text data");
+        assert_eq!(
+            described("/f/run").await,
+            "python3 script, ASCII text executable"
+        );
+        assert_eq!(
+            described("/f/dos.txt").await,
+            "ASCII text, with CRLF line terminators"
+        );
+        assert_eq!(described("/f/empty").await, "empty");
+        assert_eq!(described("/f/link").await, "symbolic link to /f/data");
+    }
+
+    #[tokio::test]
+    async fn hidden_flag_marks_nodes_hidden_without_renaming() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/work", false).await.unwrap();
+        fs.write_file("/work/notes.md", "n").await.unwrap();
+        fs.write_file("/work/.env", "k=v").await.unwrap();
+        fs.write_file("/work/scratch.txt", "s").await.unwrap();
+        fs.set_hidden("/work/scratch.txt", true).await.unwrap();
+
+        let visible: Vec<String> = fs
+            .ls("/work")
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|n| !n.is_hidden())
+            .map(|n| n.name().to_string())
+            .collect();
+        assert_eq!(visible, vec!["notes.md"]);
+        assert_eq!(fs.ls("/work").await.unwrap().len(), 3);
+
+        fs.set_hidden("/work/scratch.txt", false).await.unwrap();
+        assert!(!fs.stat("/work/scratch.txt").await.unwrap().is_hidden());
+    }
+
+    #[tokio::test]
+    async fn immutable_entries_reject_writes_and_removal() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/etc", false).await.unwrap();
+        fs.write_file("/etc/app.toml", "port = 80").await.unwrap();
+        fs.set_immutable("/etc/app.toml", true).await.unwrap();
+        assert!(fs.stat("/etc/app.toml").await.unwrap().attrs().immutable);
+        let locked = fs.metadata("/etc/app.toml").await.unwrap().unwrap();
+
+        for err in [
+            fs.write_file("/etc/app.toml", "port = 81")
+                .await
+                .unwrap_err(),
+            fs.touch("/etc/app.toml").await.unwrap_err(),
+            fs.edit("/etc/app.toml", "80", "81", false, false)
+                .await
+                .unwrap_err(),
+            fs.rm("/etc/app.toml").await.unwrap_err(),
+            fs.mv("/etc/app.toml", "/etc/old.toml", false)
+                .await
+                .unwrap_err(),
+            fs.rmdir("/etc", true).await.unwrap_err(),
+        ] {
+            assert!(matches!(err, FsError::Immutable(ref p) if p == "/etc/app.toml"));
+        }
+        assert_eq!(fs.cat("/etc/app.toml").await.unwrap(), "port = 80");
+        assert_eq!(fs.metadata("/etc/app.toml").await.unwrap().unwrap(), locked);
+
+        fs.set_immutable("/etc/app.toml", false).await.unwrap();
+        sleep(Duration::from_millis(5)).await;
+        let touched = fs.touch("/etc/app.toml").await.unwrap();
+        assert_eq!(touched.version, locked.version);
+        assert!(touched.updated_at > locked.updated_at);
+        assert_eq!(fs.cat("/etc/app.toml").await.unwrap(), "port = 80");
+        fs.write_file("/etc/app.toml", "port = 81").await.unwrap();
+        fs.rm("/etc/app.toml").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_file_atomic_replaces_content_and_keeps_attributes() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/cfg", false).await.unwrap();
+        fs.write_file("/cfg/app.toml", "port = 80").await.unwrap();
+        fs.chmod("/cfg/app.toml", 0o600).await.unwrap();
+        fs.setxattr("/cfg/app.toml", "user.owner", "ops")
+            .await
+            .unwrap();
+        fs.set_hidden("/cfg/app.toml", true).await.unwrap();
+        let ttl = fs
+            .write_file_with_ttl("/cfg/app.toml", "port = 80", Duration::from_secs(3600))
+            .await
+            .unwrap()
+            .expires_at;
+        let created = fs
+            .symlink_metadata("/cfg/app.toml")
+            .await
+            .unwrap()
+            .unwrap()
+            .created_at;
+
+        let meta = fs
+            .write_file_atomic("/cfg/app.toml", "port = 81")
+            .await
+            .unwrap();
+        assert_eq!(meta.path, "/cfg/app.toml");
+        assert_eq!(meta.mode, Some(0o600));
+        assert_eq!(meta.created_at, created);
+        assert!(meta.hidden);
+        assert_eq!(meta.expires_at, ttl);
+        assert_eq!(fs.cat("/cfg/app.toml").await.unwrap(), "port = 81");
+        assert_eq!(
+            fs.getxattr("/cfg/app.toml", "user.owner").await.unwrap(),
+            Some("ops".to_string())
+        );
+        // The temp entry is gone, leaving only the target.
+        assert_eq!(fs.ls("/cfg").await.unwrap().len(), 1);
+
+        fs.write_file_atomic("/cfg/new.toml", "x").await.unwrap();
+        assert_eq!(fs.cat("/cfg/new.toml").await.unwrap(), "x");
+        fs.set_immutable("/cfg/new.toml", true).await.unwrap();
+        assert!(matches!(
+            fs.write_file_atomic("/cfg/new.toml", "y").await,
+            Err(FsError::Immutable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn split_and_join_round_trip() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/log.txt", "a\nb\nc\nd\ne\n").await.unwrap();
+
+        let parts = fs.split("/log.txt", SplitBy::Lines(2)).await.unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                "/log.txt.part0001",
+                "/log.txt.part0002",
+                "/log.txt.part0003"
+            ]
+        );
+        assert_eq!(fs.cat("/log.txt.part0002").await.unwrap(), "c\nd\n");
+        assert_eq!(fs.cat("/log.txt.part0003").await.unwrap(), "e\n");
+        assert!(matches!(
+            fs.split("/log.txt", SplitBy::Lines(2)).await,
+            Err(FsError::AlreadyExists(_))
+        ));
+
+        fs.rm("/log.txt").await.unwrap();
+        fs.join("/log.txt").await.unwrap();
+        assert_eq!(fs.cat("/log.txt").await.unwrap(), "a\nb\nc\nd\ne\n");
+
+        let blob: Vec<u8> = (0..=255u8).collect();
+        fs.write_bytes("/blob.bin", blob.clone()).await.unwrap();
+        let parts = fs.split("/blob.bin", SplitBy::Bytes(100)).await.unwrap();
+        assert_eq!(parts.len(), 3);
+        fs.rm("/blob.bin").await.unwrap();
+        fs.join("/blob.bin").await.unwrap();
+        assert_eq!(fs.cat_bytes("/blob.bin").await.unwrap(), blob);
+
+        assert!(matches!(fs.join("/none").await, Err(FsError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn builder_defaults_apply_to_new_entries() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db)
+            .umask(0o077)
+            .default_owner("svc", Some("apps"))
+            .default_hidden(true)
+            .build();
+        fs.mkdir("/data/cache", true).await.unwrap();
+        fs.write_file("/data/a.txt", "a").await.unwrap();
+        fs.write_files([("/data/cache/b.txt", "b")]).await.unwrap();
+
+        let dir = fs.symlink_metadata("/data").await.unwrap().unwrap();
+        assert_eq!(dir.mode, Some(0o700));
+        assert_eq!(dir.owner.as_deref(), Some("svc"));
+        assert!(dir.hidden);
+        for path in ["/data/a.txt", "/data/cache/b.txt"] {
+            let file = fs.symlink_metadata(path).await.unwrap().unwrap();
+            assert_eq!(file.mode, Some(0o600));
+            assert_eq!(file.group.as_deref(), Some("apps"));
+            assert!(file.hidden);
+        }
+
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db).default_readonly(true).build();
+        let meta = fs.write_file("/ro.txt", "fixed").await.unwrap();
+        assert_eq!(meta.mode, Some(0o444));
+        assert!(matches!(
+            fs.write_file("/ro.txt", "changed").await,
+            Err(FsError::PermissionDenied(_))
+        ));
+        fs.mkdir("/dir", false).await.unwrap();
+        assert_eq!(
+            fs.symlink_metadata("/dir").await.unwrap().unwrap().mode,
+            Some(DEFAULT_DIR_MODE)
+        );
+    }
+
+    #[tokio::test]
+    async fn write_file_if_detects_concurrent_changes() {
+        let fs = setup_fs().await.unwrap();
+        let created = fs.write_file_if("/doc.md", "v1", 0).await.unwrap();
+        assert_eq!(created.version, 1);
+        assert!(matches!(
+            fs.write_file_if("/doc.md", "again", 0).await,
+            Err(FsError::Conflict(_))
+        ));
+
+        let second = fs.write_file_if("/doc.md", "v2", 1).await.unwrap();
+        assert_eq!(second.version, 2);
+        // Another writer gets in first; the stale version is refused.
+        fs.write_file("/doc.md", "theirs").await.unwrap();
+        assert!(matches!(
+            fs.write_file_if("/doc.md", "mine", 2).await,
+            Err(FsError::Conflict(_))
+        ));
+        assert_eq!(fs.cat("/doc.md").await.unwrap(), "theirs");
+
+        let meta = fs.metadata("/doc.md").await.unwrap().unwrap();
+        assert_eq!(meta.version, 3);
+        fs.write_file_atomic("/doc.md", "atomic").await.unwrap();
+        fs.transaction(|tx| async move { tx.write_file("/doc.md", "tx") })
+            .await
+            .unwrap();
+        let meta = fs.metadata("/doc.md").await.unwrap().unwrap();
+        assert_eq!(meta.version, 5);
+        fs.write_file_if("/doc.md", "mine", 5).await.unwrap();
+        assert!(matches!(
+            fs.write_file_if("/missing.md", "x", 1).await,
+            Err(FsError::Conflict(_))
+        ));
+
+        // A write the database refuses is reported as such, with or without a version.
+        fs.db
+            .query("DEFINE FIELD size ON fs_entry ASSERT $value < 100")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let big = "x".repeat(100);
+        let err = fs
+            .write_file_if("/doc.md", big.clone(), 6)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::Surreal(_)), "{err:?}");
+        let err = fs
+            .write_bytes("/doc.md", big.into_bytes())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::Surreal(_)), "{err:?}");
+        assert_eq!(fs.cat("/doc.md").await.unwrap(), "mine");
+    }
+
+    #[tokio::test]
+    async fn write_file_upserts_and_checks_in_one_query() {
+        let fs = setup_fs().await.unwrap();
+        let created = fs.write_file("/notes.txt", "one").await.unwrap();
+        assert_eq!((created.size, created.version), (3, 1));
+        let updated = fs.write_file("/notes.txt", "three").await.unwrap();
+        assert_eq!((updated.size, updated.version), (5, 2));
+        assert_eq!(updated.created_at, created.created_at);
+
+        assert!(matches!(
+            fs.write_file("/missing/a.txt", "x").await,
+            Err(FsError::NotFound(p)) if p == "/missing"
+        ));
+        assert!(matches!(
+            fs.write_file("/notes.txt/a.txt", "x").await,
+            Err(FsError::NotADirectory(_))
+        ));
+        fs.mkdir("/dir", false).await.unwrap();
+        assert!(matches!(
+            fs.write_file("/dir", "x").await,
+            Err(FsError::NotAFile(_))
+        ));
+        fs.chmod("/notes.txt", 0o444).await.unwrap();
+        assert!(matches!(
+            fs.write_file("/notes.txt", "x").await,
+            Err(FsError::PermissionDenied(_))
+        ));
+        fs.chmod("/notes.txt", 0o644).await.unwrap();
+        fs.set_immutable("/notes.txt", true).await.unwrap();
+        assert!(matches!(
+            fs.write_file("/notes.txt", "x").await,
+            Err(FsError::Immutable(_))
+        ));
+        fs.set_immutable("/notes.txt", false).await.unwrap();
+
+        fs.symlink("/notes.txt", "/dir/link").await.unwrap();
+        fs.write_file("/dir/link", "via link").await.unwrap();
+        assert_eq!(fs.cat("/notes.txt").await.unwrap(), "via link");
+    }
+
+    #[tokio::test]
+    async fn propagate_updated_at_bumps_ancestor_directories() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db.clone())
+            .propagate_updated_at(true)
+            .build();
+        fs.mkdir("/a/b/c", true).await.unwrap();
+        fs.mkdir("/other", false).await.unwrap();
+        let before = |path: &'static str| {
+            let fs = &fs;
+            async move { fs.stat(path).await.unwrap().attrs().updated_at.unwrap() }
+        };
+        let (a, c, other) = (
+            before("/a").await,
+            before("/a/b/c").await,
+            before("/other").await,
+        );
+
+        sleep(Duration::from_millis(5)).await;
+        fs.write_file("/a/b/c/f.txt", "x").await.unwrap();
+        assert!(before("/a").await > a);
+        assert!(before("/a/b/c").await > c);
+        assert_eq!(before("/other").await, other);
+
+        let b = before("/a/b").await;
+        sleep(Duration::from_millis(5)).await;
+        fs.mv("/a/b/c/f.txt", "/other/f.txt", false).await.unwrap();
+        assert!(before("/a/b").await > b);
+        assert!(before("/other").await > other);
+
+        fs.write_file_with_ttl("/other/tmp.txt", "x", Duration::ZERO)
+            .await
+            .unwrap();
+        let trash = SurrealFs::builder(db)
+            .propagate_updated_at(true)
+            .trash(true)
+            .build();
+        for step in [
+            "symlink", "link", "exchange", "rm_glob", "expire", "trash", "restore",
+        ] {
+            let last = before("/other").await;
+            sleep(Duration::from_millis(5)).await;
+            match step {
+                "symlink" => fs.symlink("f.txt", "/other/f.lnk").await.unwrap(),
+                "link" => fs.link("/other/f.txt", "/other/g.txt").await.unwrap(),
+                "exchange" => fs.exchange("/other/g.txt", "/a/b/c").await.unwrap(),
+                "rm_glob" => drop(fs.rm_glob("/other/*.lnk", false).await.unwrap()),
+                "expire" => drop(fs.expire().await.unwrap()),
+                "trash" => trash.rm("/other/f.txt").await.unwrap(),
+                _ => drop(trash.restore("/other/f.txt").await.unwrap()),
+            }
+            assert!(before("/other").await > last, "{step}");
+        }
+    }
+
+    #[tokio::test]
+    async fn propagate_updated_at_works_with_a_single_permit() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db)
+            .max_concurrency(1)
+            .propagate_updated_at(true)
+            .build();
+        fs.mkdir("/src", false).await.unwrap();
+        fs.mkdir("/dest", false).await.unwrap();
+        fs.write_file("/src/a.txt", "a").await.unwrap();
+        fs.write_file("/dest/b.txt", "b").await.unwrap();
+
+        let within = Duration::from_secs(3);
+        tokio::time::timeout(within, fs.link("/src/a.txt", "/dest/a.txt"))
+            .await
+            .expect("link waited on its own permit")
+            .unwrap();
+        tokio::time::timeout(within, fs.exchange("/src/a.txt", "/dest/b.txt"))
+            .await
+            .expect("exchange waited on its own permit")
+            .unwrap();
+        tokio::time::timeout(within, fs.mv("/src/a.txt", "/dest/c.txt", false))
+            .await
+            .expect("mv waited on its own permit")
+            .unwrap();
+        assert_eq!(fs.cat("/dest/c.txt").await.unwrap(), "b");
+        assert_eq!(fs.limiter.as_ref().unwrap().available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn trash_mode_moves_removed_entries_and_restores_them() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db).trash(true).build();
+        fs.mkdir("/docs/old", true).await.unwrap();
+        fs.write_file("/docs/a.txt", "first").await.unwrap();
+        fs.write_file("/docs/old/b.txt", "nested").await.unwrap();
+
+        fs.rm("/docs/a.txt").await.unwrap();
+        fs.rmdir("/docs/old", true).await.unwrap();
+        assert!(!fs.exists("/docs/a.txt").await.unwrap());
+        let trashed = fs.list_trash().await.unwrap();
+        let mut originals: Vec<&str> = trashed.iter().map(|t| t.original.as_str()).collect();
+        originals.sort();
+        assert_eq!(originals, vec!["/docs/a.txt", "/docs/old"]);
+        assert!(trashed.iter().all(|t| t.path.starts_with("/.trash/")));
+
+        // A file recreated at the original path blocks the restore.
+        fs.write_file("/docs/a.txt", "second").await.unwrap();
+        assert!(matches!(
+            fs.restore("/docs/a.txt").await,
+            Err(FsError::AlreadyExists(_))
+        ));
+        fs.rm("/docs/a.txt").await.unwrap();
+        assert_eq!(fs.restore("/docs/a.txt").await.unwrap(), "/docs/a.txt");
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "second");
+
+        let old = fs
+            .list_trash()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|t| t.original == "/docs/old")
+            .unwrap();
+        fs.restore(&old.path).await.unwrap();
+        assert_eq!(fs.cat("/docs/old/b.txt").await.unwrap(), "nested");
+
+        assert_eq!(fs.empty_trash().await.unwrap(), 1);
+        assert!(fs.list_trash().await.unwrap().is_empty());
+        assert!(!fs.exists(TRASH_DIR).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn expire_removes_files_past_their_ttl() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/scratch", false).await.unwrap();
+        let short = fs
+            .write_file_with_ttl("/scratch/a.txt", "a", Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert!(short.expires_at.is_some());
+        fs.write_file_with_ttl("/scratch/b.txt", "b", Duration::from_secs(3600))
+            .await
+            .unwrap();
+        fs.write_file("/scratch/keep.txt", "c").await.unwrap();
+
+        sleep(Duration::from_millis(5)).await;
+        assert_eq!(fs.expire().await.unwrap(), vec!["/scratch/a.txt"]);
+        assert!(fs.expire().await.unwrap().is_empty());
+        let names: Vec<String> = fs
+            .ls("/scratch")
+            .await
+            .unwrap()
+            .iter()
+            .map(|n| n.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["b.txt", "keep.txt"]);
+    }
+
+    #[tokio::test]
+    async fn edit_preview_does_not_write() {
+        let fs = setup_fs().await.unwrap();
+        fs.write_file("/draft.txt", "old line\n").await.unwrap();
+
+        let diff = fs
+            .edit("/draft.txt", "old", "new", false, true)
+            .await
+            .unwrap();
+        assert!(diff.contains("-old line\n+new line\n"));
+
+        let ops = [EditOp {
+            old: "line".into(),
+            new: "row".into(),
+            replace_all: false,
+        }];
+        let diff = fs.edit_many("/draft.txt", &ops, true).await.unwrap();
+        assert!(diff.contains("+old row\n"));
+        assert_eq!(fs.cat("/draft.txt").await.unwrap(), "old line\n");
+    }
+
+    #[tokio::test]
+    async fn edit_with_empty_old_overwrites_file() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/notes", true).await.unwrap();
+        fs.write_file("/notes/full.txt", "original").await.unwrap();
+
+        let diff = fs
+            .edit("/notes/full.txt", "", "hello martin!", false, false)
+            .await
+            .unwrap();
+
+        let content = fs.cat("/notes/full.txt").await.unwrap();
+        assert_eq!(content, "hello martin!");
+        assert!(diff.contains("-original"));
+        assert!(diff.contains("+hello martin!"));
+
+        let no_diff = fs
+            .edit("/notes/full.txt", "", "hello martin!", false, false)
+            .await
+            .unwrap();
+        assert!(no_diff.is_empty());
+    }
+
+    #[tokio::test]
+    async fn proc_files_report_stats() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/docs", true).await.unwrap();
+        sleep(Duration::from_millis(5)).await;
+        fs.write_file("/docs/a.txt", "hello").await.unwrap();
+
+        let stats = fs.cat("/proc/stats").await.unwrap();
+        assert!(stats.contains("files: 1"));
+        assert!(stats.contains("directories: 1"));
+        assert!(stats.contains("bytes: 5"));
+
+        let conn = fs.cat("/proc/connections").await.unwrap();
+        assert!(conn.contains("namespace: test"));
+        assert!(conn.contains("table: fs_entry"));
+
+        let changes = fs.cat("/proc/changes/tail").await.unwrap();
+        assert!(changes.lines().next().unwrap().ends_with("/docs/a.txt"));
+
+        let names: Vec<String> = fs
+            .ls("/proc")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["changes", "connections", "quota", "stats"]);
+    }
+
+    #[tokio::test]
+    async fn proc_files_are_read_only() {
+        let fs = setup_fs().await.unwrap();
+        let err = fs.write_file("/proc/stats", "nope").await.unwrap_err();
+        assert!(matches!(err, FsError::ReadOnly(_)));
+        let err = fs.mkdir("/proc/new", false).await.unwrap_err();
+        assert!(matches!(err, FsError::ReadOnly(_)));
+
+        // Nothing reaches the table under /proc by another route either.
+        fs.write_file("/a.txt", "a").await.unwrap();
+        fs.mkdir("/d", false).await.unwrap();
+        fs.symlink("/proc/stats", "/to-proc").await.unwrap();
+        for err in [
+            fs.write_file("/to-proc", "x")
+                .await
+                .map(|_| ())
+                .unwrap_err(),
+            fs.append_file("/to-proc", "x").await.unwrap_err(),
+            fs.write_files([("/proc/batch.txt", "b")])
+                .await
+                .unwrap_err(),
+            fs.touch("/proc/t.txt").await.map(|_| ()).unwrap_err(),
+            fs.cp("/a.txt", "/proc/a.txt", &CopyOptions::default())
+                .await
+                .map(|_| ())
+                .unwrap_err(),
+            fs.mv("/a.txt", "/proc/a.txt", true)
+                .await
+                .map(|_| ())
+                .unwrap_err(),
+            fs.link("/a.txt", "/proc/a.txt")
+                .await
+                .map(|_| ())
+                .unwrap_err(),
+            fs.symlink("/a.txt", "/proc/a.txt").await.unwrap_err(),
+            fs.mv("/d", "/proc", false).await.map(|_| ()).unwrap_err(),
+        ] {
+            assert!(matches!(err, FsError::ReadOnly(_)), "{err:?}");
+        }
+        let names: Vec<String> = fs
+            .ls("/")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|node| node.name().to_string())
+            .collect();
+        assert!(names.contains(&"proc".to_string()), "{names:?}");
+        assert_eq!(fs.cat("/a.txt").await.unwrap(), "a");
+    }
+
+    #[tokio::test]
+    async fn read_only_handle_rejects_every_write() {
+        let fs = setup_fs().await.unwrap();
+        fs.mkdir("/docs", false).await.unwrap();
+        fs.write_file("/docs/a.txt", "hello").await.unwrap();
+        let viewer = fs.read_only();
+        assert!(viewer.is_read_only() && !fs.is_read_only());
+
+        assert_eq!(viewer.cat("/docs/a.txt").await.unwrap(), "hello");
+        assert_eq!(viewer.ls("/docs").await.unwrap().len(), 1);
+        for err in [
+            viewer.write_file("/docs/a.txt", "bye").await.unwrap_err(),
+            viewer.append_file("/docs/a.txt", "!").await.unwrap_err(),
+            viewer.touch("/docs/b.txt").await.map(|_| ()).unwrap_err(),
+            viewer.mkdir("/other", true).await.map(|_| ()).unwrap_err(),
+            viewer.rm("/docs/a.txt").await.unwrap_err(),
+            viewer.rmdir("/docs", true).await.unwrap_err(),
+            viewer.mv("/docs", "/moved", false).await.unwrap_err(),
+            viewer.chmod("/docs/a.txt", 0o600).await.unwrap_err(),
+            viewer
+                .setxattr("/docs/a.txt", "user.k", "v")
+                .await
+                .unwrap_err(),
+            viewer
+                .rm_glob("/docs/*", false)
+                .await
+                .map(|_| ())
+                .unwrap_err(),
+            viewer.vacuum().await.map(|_| ()).unwrap_err(),
+            viewer
+                .transaction(|tx| async move { tx.write_file("/t.txt", "x") })
+                .await
+                .unwrap_err(),
+        ] {
+            assert!(matches!(err, FsError::ReadOnly(_)), "{err:?}");
+        }
+        fs.cp_to("/docs", &viewer, "/copy").await.unwrap_err();
+
+        assert_eq!(fs.cat("/docs/a.txt").await.unwrap(), "hello");
+        assert_eq!(
+            viewer.rm_glob("/docs/*", true).await.unwrap(),
+            ["/docs/a.txt"]
+        );
+        assert!(!fs.exists("/moved").await.unwrap());
+        assert!(!fs.exists("/copy").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn export_and_import_round_trip_a_tree() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::new(db.clone());
+        fs.mkdir("/proj/assets", true).await.unwrap();
+        fs.write_file("/proj/main.rs", "fn main() {}\n")
+            .await
+            .unwrap();
+        fs.write_bytes("/proj/assets/logo.bin", vec![0u8, 159, 146, 150, 255])
+            .await
+            .unwrap();
+        fs.symlink("main.rs", "/proj/entry").await.unwrap();
+        fs.link("/proj/main.rs", "/proj/alias.rs").await.unwrap();
+        fs.chmod("/proj/main.rs", 0o600).await.unwrap();
+        fs.setxattr("/proj/assets", "user.tag", "media")
+            .await
+            .unwrap();
+        fs.set_mtime("/proj/assets/logo.bin", 1_000).await.unwrap();
+
+        let dump = fs.export("/proj").await.unwrap();
+        assert!(dump.starts_with(r#"{"format":"surrealfs-dump","version":1,"root":"/proj"}"#));
+        assert_eq!(dump.lines().count(), 7);
 
-    #[tokio::test]
-    async fn touch_and_cat() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/dir", true).await.unwrap();
-        fs.touch("/dir/file.txt").await.unwrap();
-        fs.write_file("/dir/file.txt", "hello\nworld")
+        let copy = SurrealFs::with_table(db.clone(), "copy");
+        assert_eq!(copy.import(&dump, None).await.unwrap(), 6);
+        assert_eq!(copy.cat("/proj/entry").await.unwrap(), "fn main() {}\n");
+        assert_eq!(
+            copy.cat_bytes("/proj/assets/logo.bin").await.unwrap(),
+            [0u8, 159, 146, 150, 255]
+        );
+        assert_eq!(copy.readlink("/proj/entry").await.unwrap(), "main.rs");
+        assert_eq!(copy.links("/proj/alias.rs").await.unwrap().len(), 2);
+        let meta = copy.metadata("/proj/main.rs").await.unwrap().unwrap();
+        assert_eq!(meta.mode, Some(0o600));
+        let logo = copy
+            .metadata("/proj/assets/logo.bin")
             .await
+            .unwrap()
             .unwrap();
-        let content = fs.cat("/dir/file.txt").await.unwrap();
-        assert_eq!(content, "hello\nworld");
-    }
+        assert_eq!(logo.updated_at, Some(1_000));
+        assert_eq!(
+            copy.getxattr("/proj/assets", "user.tag").await.unwrap(),
+            Some("media".to_string())
+        );
 
-    #[tokio::test]
-    async fn tail_and_nl() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/logs", true).await.unwrap();
-        fs.write_file("/logs/app.log", "a\nb\nc\nd").await.unwrap();
-        let tail = fs.tail("/logs/app.log", 2).await.unwrap();
-        assert_eq!(tail, vec!["c", "d"]);
-        let numbered = fs.nl("/logs/app.log", 1).await.unwrap();
-        assert_eq!(numbered[0].number, 1);
-        assert_eq!(numbered[3].line, "d");
+        // Importing elsewhere rebases every path and replaces what is in the way.
+        copy.write_file("/restored", "in the way").await.unwrap();
+        copy.import(&dump, Some("/restored")).await.unwrap();
+        assert_eq!(
+            copy.cat("/restored/main.rs").await.unwrap(),
+            "fn main() {}\n"
+        );
+        assert_eq!(copy.export("/restored").await.unwrap().lines().count(), 7);
+
+        let err = copy.import("{\"format\":\"tar\"}", None).await.unwrap_err();
+        assert!(matches!(err, FsError::InvalidDump(_)));
     }
 
     #[tokio::test]
-    async fn read_with_offset_and_limit() {
+    async fn vacuum_removes_unreachable_entries() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/logs", true).await.unwrap();
-        fs.write_file("/logs/app.log", "l1\nl2\nl3\nl4\nl5")
+        fs.mkdir("/keep", true).await.unwrap();
+        fs.write_file("/keep/a.txt", "kept").await.unwrap();
+        fs.create_dir("/gone/sub", "/gone").await.unwrap();
+        fs.create_file("/gone/sub/b.txt", "/gone/sub", Some("lost".into()), None)
             .await
             .unwrap();
 
-        let middle = fs.read("/logs/app.log", 1, 3).await.unwrap();
-        assert_eq!(middle, vec!["l2", "l3", "l4"]);
+        let report = fs.vacuum().await.unwrap();
+        assert_eq!(report.orphaned_entries, 2);
+        assert_eq!(report.reclaimed_bytes, 4);
+        assert!(fs.get_entry("/gone/sub/b.txt").await.unwrap().is_none());
+        assert_eq!(fs.cat("/keep/a.txt").await.unwrap(), "kept");
 
-        let tail = fs.read("/logs/app.log", 4, 10).await.unwrap();
-        assert_eq!(tail, vec!["l5"]);
+        let again = fs.vacuum().await.unwrap();
+        assert_eq!(again, VacuumReport::default());
+    }
 
-        let empty = fs.read("/logs/app.log", 10, 2).await.unwrap();
-        assert!(empty.is_empty());
+    #[tokio::test]
+    async fn vacuum_purges_expired_files_and_old_trash() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db.clone())
+            .trash(true)
+            .trash_retention(Duration::from_secs(3600))
+            .build();
+        fs.write_file_with_ttl("/tmp.txt", "scratch", Duration::ZERO)
+            .await
+            .unwrap();
+        fs.mkdir("/old", false).await.unwrap();
+        fs.write_file("/old/a.txt", "aaaa").await.unwrap();
+        fs.write_file("/recent.txt", "rr").await.unwrap();
+        fs.rmdir("/old", true).await.unwrap();
+        fs.rm("/recent.txt").await.unwrap();
+        // Backdate the directory's removal past the retention.
+        db.query("UPDATE fs_entry SET trashed_at = 0 WHERE trashed_from = '/old'")
+            .await
+            .unwrap();
 
-        let none = fs.read("/logs/app.log", 0, 0).await.unwrap();
-        assert!(none.is_empty());
+        let report = fs.vacuum().await.unwrap();
+        assert_eq!(report.expired_files, 1);
+        assert_eq!(report.trash_entries, 2);
+        assert_eq!(report.reclaimed_bytes, 11);
+        assert!(!fs.exists("/tmp.txt").await.unwrap());
+        let left: Vec<String> = fs
+            .list_trash()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|i| i.original)
+            .collect();
+        assert_eq!(left, ["/recent.txt"]);
+
+        // Without a retention the whole trash goes.
+        let purge_all = SurrealFs::new(db).vacuum().await.unwrap();
+        assert_eq!(purge_all.trash_entries, 1);
+        assert!(!fs.exists(TRASH_DIR).await.unwrap());
     }
 
     #[tokio::test]
-    async fn ls_and_grep_recursive() {
+    async fn fsck_finds_and_repairs_inconsistencies() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/code/src", true).await.unwrap();
-        fs.write_file("/code/src/main.rs", "fn main() { println!(\"hi\"); }\n")
+        fs.write_file("/ok.txt", "fine").await.unwrap();
+        fs.write_file("/notes", "was a file").await.unwrap();
+        fs.symlink("/ok.txt", "/good").await.unwrap();
+        fs.symlink("/missing.txt", "/bad").await.unwrap();
+        fs.create_file("/notes/a.txt", "/notes", Some("a".into()), None)
             .await
             .unwrap();
-        fs.write_file("/code/readme.md", "hi there\n")
+        fs.create_file("/lost/deep/b.txt", "/lost/deep", Some("b".into()), None)
+            .await
+            .unwrap();
+        fs.db
+            .query("CREATE fs_entry SET path = '/ok.txt', name = 'ok.txt', parent = '/', is_dir = false, content = 'stale', updated_at = 0")
             .await
             .unwrap();
-        let entries = fs.ls("/code").await.unwrap();
-        let names: Vec<String> = entries.into_iter().map(|e| e.name).collect();
-        assert!(names.contains(&"src".to_string()));
-        assert!(names.contains(&"readme.md".to_string()));
 
-        let regex = Regex::new("hi").unwrap();
-        let matches = fs.grep(&regex, "/code", true).await.unwrap();
-        assert_eq!(matches.len(), 2);
+        let report = fs.fsck(false).await.unwrap();
+        assert_eq!(report.duplicate_paths, ["/ok.txt"]);
+        assert_eq!(report.missing_parents, ["/lost", "/lost/deep"]);
+        assert_eq!(report.files_with_children, ["/notes"]);
+        assert_eq!(report.dangling_symlinks, ["/bad"]);
+        assert!(!report.repaired);
+
+        let repaired = fs.fsck(true).await.unwrap();
+        assert!(repaired.repaired);
+        assert_eq!(repaired.duplicate_paths, report.duplicate_paths);
+        assert_eq!(fs.cat("/ok.txt").await.unwrap(), "fine");
+        assert_eq!(fs.cat("/notes.fsck").await.unwrap(), "was a file");
+        assert_eq!(fs.cat("/notes/a.txt").await.unwrap(), "a");
+        assert_eq!(fs.cat("/lost/deep/b.txt").await.unwrap(), "b");
+        assert!(!fs.exists("/bad").await.unwrap());
+        assert!(fs.exists("/good").await.unwrap());
+        assert!(fs.fsck(false).await.unwrap().is_clean());
     }
 
     #[tokio::test]
-    async fn mkdir_nested_with_parents() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/a/b/c", true).await.unwrap();
-        let entries = fs.ls("/a/b").await.unwrap();
-        assert_eq!(entries.len(), 1);
-        assert!(entries[0].is_dir);
+    async fn record_ids_follow_paths() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::new(db.clone());
+        fs.mkdir("/src/lib", true).await.unwrap();
+        fs.write_file("/src/lib/a.rs", "a").await.unwrap();
+        fs.mv("/src", "/dst", false).await.unwrap();
+
+        let mut res = db
+            .query("SELECT VALUE path FROM fs_entry WHERE id = type::thing('fs_entry', path)")
+            .await
+            .unwrap();
+        let mut keyed: Vec<String> = res.take(0).unwrap();
+        keyed.sort();
+        assert_eq!(keyed, vec!["/dst", "/dst/lib", "/dst/lib/a.rs"]);
+        assert_eq!(fs.cat("/dst/lib/a.rs").await.unwrap(), "a");
+
+        // Rows from before path-derived ids are invisible to lookups until migrated.
+        db.query("CREATE fs_entry SET path = '/old.txt', name = 'old.txt', parent = '/', is_dir = false, content = 'legacy'")
+            .await
+            .unwrap();
+        assert!(fs.cat("/old.txt").await.is_err());
+        assert_eq!(fs.migrate_record_ids().await.unwrap(), 1);
+        assert_eq!(fs.cat("/old.txt").await.unwrap(), "legacy");
+        assert_eq!(fs.migrate_record_ids().await.unwrap(), 0);
     }
 
     #[tokio::test]
-    async fn mkdir_without_parents_needs_parent() {
-        let fs = setup_fs().await.unwrap();
-        let err = fs.mkdir("/missing/child", false).await.unwrap_err();
-        matches!(err, FsError::NotFound(_));
+    async fn graph_edges_follow_writes_renames_and_removals() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let plain = SurrealFs::new(db.clone());
+        plain.mkdir("/a/b", true).await.unwrap();
+        plain.write_file("/a/b/x.txt", "x").await.unwrap();
+        assert_eq!(plain.define_graph_edges().await.unwrap(), 3);
+
+        let fs = SurrealFs::builder(db).graph_edges(true).build();
+        fs.write_file("/a/y.txt", "y").await.unwrap();
+        fs.mkdir("/c", false).await.unwrap();
+        fs.mv("/a/b", "/c/b", false).await.unwrap();
+        fs.write_file("/c/b/z.txt", "z").await.unwrap();
+        fs.rm("/a/y.txt").await.unwrap();
+
+        for dir in ["/", "/a", "/c", "/c/b"] {
+            assert_eq!(fs.ls(dir).await.unwrap(), plain.ls(dir).await.unwrap());
+        }
+        let paths =
+            |entries: Vec<Entry>| -> Vec<String> { entries.into_iter().map(|e| e.path).collect() };
+        assert_eq!(
+            paths(fs.find("/", &FindOptions::default()).await.unwrap()),
+            ["/a", "/c", "/c/b", "/c/b/x.txt", "/c/b/z.txt"]
+        );
+        assert_eq!(
+            paths(fs.find("/c", &FindOptions::default()).await.unwrap()),
+            paths(plain.find("/c", &FindOptions::default()).await.unwrap())
+        );
+        assert_eq!(
+            fs.tree("/", None).await.unwrap(),
+            plain.tree("/", None).await.unwrap()
+        );
     }
 
     #[tokio::test]
-    async fn ls_root_lists_children() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/docs", true).await.unwrap();
-        fs.write_file("/readme.md", "hello").await.unwrap();
+    async fn ensure_indexes_creates_and_repairs_required_indexes() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::new(db.clone());
+        fs.write_file("/a.txt", "a").await.unwrap();
+
+        let report = fs.check_indexes().await.unwrap();
+        assert!(!report.is_healthy());
+        assert!(
+            report
+                .indexes
+                .iter()
+                .all(|index| index.status == IndexStatus::Missing)
+        );
+        assert!(
+            report
+                .render()
+                .contains("fs_entry_parent on fs_entry(parent): missing")
+        );
 
-        let entries = fs.ls("/").await.unwrap();
-        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
-        assert!(names.contains(&"docs"));
-        assert!(names.contains(&"readme.md"));
+        assert!(
+            report
+                .render()
+                .contains("fs_entry_content on fs_entry(content, search fs_entry_search): missing")
+        );
 
-        let dir = entries.iter().find(|e| e.name == "docs").unwrap();
-        assert!(dir.is_dir);
+        db.query(
+            "DEFINE INDEX fs_entry_path ON fs_entry FIELDS name;\
+             DEFINE INDEX fs_entry_name ON fs_entry FIELDS name;",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+        let report = fs.check_indexes().await.unwrap();
+        let status = |name: &str| {
+            report
+                .indexes
+                .iter()
+                .find(|index| index.name == name)
+                .map(|index| index.status.clone())
+        };
+        assert!(matches!(
+            status("fs_entry_path"),
+            Some(IndexStatus::Mismatched(_))
+        ));
+        assert!(matches!(
+            status("fs_entry_name"),
+            Some(IndexStatus::Mismatched(_))
+        ));
+
+        let report = fs.ensure_indexes().await.unwrap();
+        assert!(report.is_healthy(), "{}", report.render());
+        assert_eq!(report.indexes.len(), 4);
+        assert_eq!(fs.check_indexes().await.unwrap(), report);
+        assert_eq!(fs.locate("a.t").await.unwrap(), ["/a.txt"]);
     }
 
     #[tokio::test]
-    async fn mkdir_without_parents_fails_when_exists() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/data", true).await.unwrap();
-        let err = fs.mkdir("/data", false).await.unwrap_err();
-        matches!(err, FsError::AlreadyExists(_));
+    async fn define_schema_indexes_paths() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::new(db.clone());
+        fs.define_schema().await.unwrap();
+        fs.define_schema().await.unwrap();
+
+        fs.mkdir("/a", false).await.unwrap();
+        fs.write_file("/a/x.txt", "x").await.unwrap();
+        fs.write_file("/y.txt", "y").await.unwrap();
+        fs.mv("/a/x.txt", "/y.txt", true).await.unwrap();
+        fs.mkdir("/b", false).await.unwrap();
+        fs.exchange("/a", "/b").await.unwrap();
+        assert_eq!(fs.cat("/y.txt").await.unwrap(), "x");
+
+        let res = db
+            .query("CREATE fs_entry SET path = '/y.txt', name = 'y.txt', parent = '/'")
+            .await
+            .unwrap();
+        assert!(res.check().is_err());
+        let mut res = db.query("INFO FOR TABLE fs_entry").await.unwrap();
+        let info: Option<serde_json::Value> = res.take(0).unwrap();
+        let indexes = info.unwrap()["indexes"].clone();
+        assert!(indexes.get("fs_entry_path").is_some());
+        assert!(indexes.get("fs_entry_parent").is_some());
     }
 
     #[tokio::test]
-    async fn cp_file() {
+    async fn transaction_applies_all_or_nothing() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/docs", true).await.unwrap();
-        fs.write_file("/docs/src.txt", "copy me").await.unwrap();
-        fs.mkdir("/docs/copies", true).await.unwrap();
-        fs.cp("/docs/src.txt", "/docs/copies/dest.txt")
+        fs.mkdir("/old/sub", true).await.unwrap();
+        fs.write_file("/old/sub/a.txt", "a").await.unwrap();
+        fs.write_file("/keep.txt", "v1").await.unwrap();
+
+        let count = fs
+            .transaction(|tx| async move {
+                tx.mkdir("/new/dir")?;
+                tx.write_file("/new/dir/b.txt", "b")?;
+                tx.write_bytes("/new/blob.bin", vec![0u8, 1, 2])?;
+                tx.write_file("/keep.txt", "v2")?;
+                tx.rm("/old")?;
+                Ok(3)
+            })
             .await
             .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(fs.cat("/new/dir/b.txt").await.unwrap(), "b");
+        assert_eq!(fs.cat_bytes("/new/blob.bin").await.unwrap(), vec![0, 1, 2]);
+        assert_eq!(fs.cat("/keep.txt").await.unwrap(), "v2");
+        assert!(
+            fs.symlink_metadata("/old/sub/a.txt")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let dir = fs.symlink_metadata("/new/dir").await.unwrap().unwrap();
+        assert!(dir.is_dir);
+        assert_eq!(dir.mode, Some(DEFAULT_DIR_MODE));
+        assert_eq!(dir.inode, None);
+
+        // A failing closure and an invalid staged change both leave everything untouched.
+        let err = fs
+            .transaction(|tx| async move {
+                tx.write_file("/keep.txt", "v3")?;
+                Err::<(), _>(FsError::InvalidPath)
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::InvalidPath));
+        let err = fs
+            .transaction(|tx| async move {
+                tx.write_file("/keep.txt", "v3")?;
+                tx.write_file("/missing/c.txt", "c")
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+        assert_eq!(fs.cat("/keep.txt").await.unwrap(), "v2");
+    }
 
-        let content = fs.cat("/docs/copies/dest.txt").await.unwrap();
-        assert_eq!(content, "copy me");
+    #[tokio::test]
+    async fn builder_limits_concurrent_queries() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db)
+            .table("limited_entry")
+            .max_concurrency(1)
+            .build();
+
+        fs.mkdir("/jobs", true).await.unwrap();
+        let (a, b, c) = tokio::join!(
+            fs.write_file("/jobs/a.txt", "a"),
+            fs.write_file("/jobs/b.txt", "b"),
+            fs.write_file("/jobs/c.txt", "c"),
+        );
+        a.unwrap();
+        b.unwrap();
+        c.unwrap();
+
+        assert_eq!(fs.ls("/jobs").await.unwrap().len(), 3);
+        assert_eq!(fs.limiter.as_ref().unwrap().available_permits(), 1);
     }
 
     #[tokio::test]
-    async fn write_and_cat_bytes() {
+    async fn rm_removes_files_only() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/bin", true).await.unwrap();
-        let data = vec![0u8, 159, 255];
-        fs.write_bytes("/bin/blob", data.clone()).await.unwrap();
+        fs.mkdir("/docs", true).await.unwrap();
+        fs.write_file("/docs/a.txt", "bye").await.unwrap();
 
-        let raw = fs.cat_bytes("/bin/blob").await.unwrap();
-        assert_eq!(raw, data);
+        fs.rm("/docs/a.txt").await.unwrap();
+        assert!(fs.ls("/docs").await.unwrap().is_empty());
 
-        let err = fs.cat("/bin/blob").await.unwrap_err();
-        matches!(err, FsError::InvalidUtf8(_));
+        let err = fs.rm("/docs/a.txt").await.unwrap_err();
+        assert!(matches!(err, FsError::NotFound(_)));
+        let err = fs.rm("/docs").await.unwrap_err();
+        assert!(matches!(err, FsError::NotAFile(_)));
     }
 
     #[tokio::test]
-    async fn write_bytes_utf8_reads_as_text() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/notes", true).await.unwrap();
-        fs.write_bytes("/notes/msg", b"hello".to_vec())
-            .await
-            .unwrap();
+    async fn usage_aggregates_by_top_level() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::builder(db).quota_bytes(100).build();
+        fs.mkdir("/docs/deep", true).await.unwrap();
+        fs.write_file("/docs/deep/a.txt", "héllo").await.unwrap();
+        fs.write_bytes("/blob.bin", vec![1u8, 2, 3]).await.unwrap();
+
+        let usage = fs.usage().await.unwrap();
+        assert_eq!(usage.table, "fs_entry");
+        assert_eq!(usage.entries, 4);
+        assert_eq!(usage.files, 2);
+        assert_eq!(usage.directories, 2);
+        assert_eq!(usage.bytes, 9);
+        assert_eq!(usage.remaining_bytes, Some(91));
+        assert_eq!(
+            usage.top_level,
+            vec![
+                DirUsage {
+                    path: "/blob.bin".into(),
+                    entries: 1,
+                    bytes: 3,
+                },
+                DirUsage {
+                    path: "/docs".into(),
+                    entries: 3,
+                    bytes: 6,
+                },
+            ]
+        );
+    }
 
-        let text = fs.cat("/notes/msg").await.unwrap();
-        assert_eq!(text, "hello");
+    #[tokio::test]
+    async fn df_reports_totals_and_the_backing_table() {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let fs = SurrealFs::with_table(db, "ops_entry");
+        fs.mkdir("/logs", false).await.unwrap();
+        fs.write_file("/logs/app.log", "started").await.unwrap();
+        fs.write_file("/motd", "hi").await.unwrap();
+
+        let df = fs.df().await.unwrap();
+        assert_eq!(df, fs.usage().await.unwrap());
+        assert_eq!(df.table, "ops_entry");
+        assert_eq!((df.entries, df.files, df.directories), (3, 2, 1));
+        assert_eq!(df.bytes, 9);
+        let top: Vec<(&str, usize)> = df
+            .top_level
+            .iter()
+            .map(|dir| (dir.path.as_str(), dir.bytes))
+            .collect();
+        assert_eq!(top, [("/logs", 7), ("/motd", 2)]);
     }
 
     #[tokio::test]
-    async fn cp_preserves_binary() {
+    async fn mv_renames_file_with_explicit_overwrite() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/bin", true).await.unwrap();
-        fs.mkdir("/copy", true).await.unwrap();
-        let data = vec![1u8, 2, 3, 4];
-        fs.write_bytes("/bin/src.bin", data.clone()).await.unwrap();
+        fs.mkdir("/notes", true).await.unwrap();
+        fs.write_file("/notes/a.txt", "alpha").await.unwrap();
+        fs.write_file("/notes/b.txt", "beta").await.unwrap();
 
-        fs.cp("/bin/src.bin", "/copy/dest.bin").await.unwrap();
+        let err = fs
+            .mv("/notes/a.txt", "/notes/b.txt", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::AlreadyExists(_)));
 
-        let copied = fs.cat_bytes("/copy/dest.bin").await.unwrap();
-        assert_eq!(copied, data);
+        fs.mv("/notes/a.txt", "/notes/b.txt", true).await.unwrap();
+        assert_eq!(fs.cat("/notes/b.txt").await.unwrap(), "alpha");
+        assert_eq!(fs.ls("/notes").await.unwrap().len(), 1);
 
-        let entries = fs.ls("/copy").await.unwrap();
-        let dest = entries.iter().find(|e| e.name == "dest.bin").unwrap();
-        assert_eq!(dest.size(), data.len());
+        fs.mv("/notes/b.txt", "/c.txt", false).await.unwrap();
+        let moved = fs.get_entry("/c.txt").await.unwrap().unwrap();
+        assert_eq!(moved.name, "c.txt");
+        assert_eq!(moved.parent.as_deref(), Some("/"));
     }
 
     #[tokio::test]
-    async fn write_bytes_leaves_non_images_untouched() {
+    async fn mv_directory_rewrites_descendants() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/bin", true).await.unwrap();
-        let payload = vec![7u8, 8, 9];
-        fs.write_bytes("/bin/raw", payload.clone()).await.unwrap();
-
-        let stored = fs.cat_bytes("/bin/raw").await.unwrap();
-        assert_eq!(stored, payload);
-    }
+        fs.mkdir("/proj/src/bin", true).await.unwrap();
+        fs.write_file("/proj/src/lib.rs", "lib").await.unwrap();
+        fs.write_file("/proj/src/bin/main.rs", "main")
+            .await
+            .unwrap();
+        fs.mkdir("/projects", true).await.unwrap();
 
-    #[tokio::test]
-    async fn write_bytes_optimizes_png() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/img", true).await.unwrap();
+        fs.mv("/proj", "/projects/app", false).await.unwrap();
 
-        fs.write_bytes("/img/pixel.png", ONE_BY_ONE_PNG.to_vec())
+        assert!(matches!(
+            fs.ls("/proj").await.unwrap_err(),
+            FsError::NotFound(_)
+        ));
+        assert_eq!(fs.cat("/projects/app/src/lib.rs").await.unwrap(), "lib");
+        let main = fs
+            .get_entry("/projects/app/src/bin/main.rs")
             .await
+            .unwrap()
             .unwrap();
-
-        let stored = fs.cat_bytes("/img/pixel.png").await.unwrap();
-        let image = Image::read(ZCursor::new(&stored), DecoderOptions::default()).unwrap();
-        assert_eq!(image.dimensions(), (1, 1));
+        assert_eq!(main.parent.as_deref(), Some("/projects/app/src/bin"));
+        let names: Vec<String> = fs
+            .ls("/projects/app/src")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["bin", "lib.rs"]);
+
+        let err = fs
+            .mv("/projects", "/projects/app/inner", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FsError::InvalidPath));
     }
 
     #[tokio::test]
-    async fn cp_does_not_recompress_virtual_files() {
+    async fn rmdir_requires_recursive_for_non_empty() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/data", true).await.unwrap();
-        let data = vec![5u8, 4, 3, 2, 1];
-        fs.write_bytes("/data/src.bin", data.clone()).await.unwrap();
+        fs.mkdir("/tmp/cache/deep", true).await.unwrap();
+        fs.write_file("/tmp/cache/deep/blob", "x").await.unwrap();
+        fs.mkdir("/tmp-other", true).await.unwrap();
 
-        fs.mkdir("/data/copies", true).await.unwrap();
-        fs.cp("/data/src.bin", "/data/copies/dst.bin")
-            .await
-            .unwrap();
+        let err = fs.rmdir("/tmp", false).await.unwrap_err();
+        assert!(matches!(err, FsError::DirectoryNotEmpty(_)));
 
-        let copied = fs.cat_bytes("/data/copies/dst.bin").await.unwrap();
-        assert_eq!(copied, data);
+        fs.rmdir("/tmp", true).await.unwrap();
+        let names: Vec<String> = fs
+            .ls("/")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name().to_string())
+            .collect();
+        assert_eq!(names, vec!["proc", "tmp-other"]);
+
+        fs.rmdir("/tmp-other", false).await.unwrap();
+        assert_eq!(fs.ls("/").await.unwrap().len(), 1);
+
+        fs.write_file("/file.txt", "x").await.unwrap();
+        let err = fs.rmdir("/file.txt", true).await.unwrap_err();
+        assert!(matches!(err, FsError::NotADirectory(_)));
     }
 
     #[tokio::test]
-    async fn glob_matches_newest_first() {
+    async fn du_sums_children_recursively() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/proj/src", true).await.unwrap();
-        fs.mkdir("/proj/tests", true).await.unwrap();
-
-        fs.write_file("/proj/src/main.rs", "main").await.unwrap();
-        sleep(Duration::from_millis(5)).await;
-        fs.write_file("/proj/src/lib.rs", "lib").await.unwrap();
-        sleep(Duration::from_millis(5)).await;
-        fs.write_file("/proj/tests/main.rs", "test").await.unwrap();
-
-        let matches = fs.glob("/proj/**/*.rs").await.unwrap();
+        fs.mkdir("/proj/src/bin", true).await.unwrap();
+        fs.write_file("/proj/src/lib.rs", "12345").await.unwrap();
+        fs.write_file("/proj/src/bin/main.rs", "123").await.unwrap();
+        fs.write_file("/proj/README", "12").await.unwrap();
+        fs.write_file("/projects.txt", "ignored").await.unwrap();
+
+        let du = fs.du("/proj").await.unwrap();
+        assert_eq!(du.bytes, 10);
         assert_eq!(
-            matches,
+            du.children,
             vec![
-                "/proj/tests/main.rs",
-                "/proj/src/lib.rs",
-                "/proj/src/main.rs",
+                DirUsage {
+                    path: "/proj/README".into(),
+                    entries: 1,
+                    bytes: 2,
+                },
+                DirUsage {
+                    path: "/proj/src".into(),
+                    entries: 4,
+                    bytes: 8,
+                },
             ]
         );
 
-        let root_matches = fs.glob("**/*.rs").await.unwrap();
-        assert_eq!(root_matches, matches);
+        let file = fs.du("/proj/README").await.unwrap();
+        assert_eq!(file.bytes, 2);
+        assert!(file.children.is_empty());
+
+        assert_eq!(fs.du("/").await.unwrap().bytes, 17);
     }
 
     #[tokio::test]
-    async fn edit_replaces_first() {
+    async fn tree_renders_with_depth_limit() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/notes", true).await.unwrap();
-        fs.write_file("/notes/todo.txt", "alpha beta alpha")
-            .await
-            .unwrap();
+        fs.mkdir("/proj/src/bin", true).await.unwrap();
+        fs.write_file("/proj/src/bin/main.rs", "").await.unwrap();
+        fs.write_file("/proj/src/lib.rs", "").await.unwrap();
+        fs.write_file("/proj/README", "").await.unwrap();
 
-        let diff = fs
-            .edit("/notes/todo.txt", "alpha", "ALPHA", false)
-            .await
-            .unwrap();
+        let tree = fs.tree("/proj", None).await.unwrap();
+        assert_eq!(
+            tree.render(),
+            "/proj\n|-- README\n`-- src\n    |-- bin\n    |   `-- main.rs\n    `-- lib.rs\n"
+        );
 
-        let content = fs.cat("/notes/todo.txt").await.unwrap();
-        assert_eq!(content, "ALPHA beta alpha");
-        assert!(diff.contains("-alpha beta alpha"));
-        assert!(diff.contains("+ALPHA beta alpha"));
+        let shallow = fs.tree("/proj", Some(1)).await.unwrap();
+        assert_eq!(shallow.children.len(), 2);
+        assert!(shallow.children.iter().all(|c| c.children.is_empty()));
+
+        let root = fs.tree("/", Some(1)).await.unwrap();
+        assert_eq!(root.render(), "/\n`-- proj\n");
     }
 
     #[tokio::test]
-    async fn edit_replaces_all() {
+    async fn find_filters_by_name_type_size_and_time() {
         let fs = setup_fs().await.unwrap();
-        fs.mkdir("/notes", true).await.unwrap();
-        fs.write_file("/notes/all.txt", "foo bar foo")
+        fs.mkdir("/proj/src", true).await.unwrap();
+        fs.write_file("/proj/src/lib.rs", "0123456789")
             .await
             .unwrap();
+        fs.write_file("/proj/src/small.rs", "01").await.unwrap();
+        fs.write_file("/proj/notes.md", "0123456789").await.unwrap();
+        fs.write_file("/other.rs", "x").await.unwrap();
 
-        let diff = fs.edit("/notes/all.txt", "foo", "FOO", true).await.unwrap();
+        let paths =
+            |entries: Vec<Entry>| -> Vec<String> { entries.into_iter().map(|e| e.path).collect() };
 
-        let content = fs.cat("/notes/all.txt").await.unwrap();
-        assert_eq!(content, "FOO bar FOO");
-        assert!(diff.contains("-foo bar foo"));
-        assert!(diff.contains("+FOO bar FOO"));
-    }
+        let rs = FindOptions {
+            name: Some("*.rs".into()),
+            ..FindOptions::default()
+        };
+        assert_eq!(
+            paths(fs.find("/proj", &rs).await.unwrap()),
+            vec!["/proj/src/lib.rs", "/proj/src/small.rs"]
+        );
 
-    #[tokio::test]
-    async fn edit_with_empty_old_overwrites_file() {
-        let fs = setup_fs().await.unwrap();
-        fs.mkdir("/notes", true).await.unwrap();
-        fs.write_file("/notes/full.txt", "original").await.unwrap();
+        let dirs = FindOptions {
+            kind: Some(EntryKind::Dir),
+            ..FindOptions::default()
+        };
+        assert_eq!(
+            paths(fs.find("/proj", &dirs).await.unwrap()),
+            vec!["/proj", "/proj/src"]
+        );
 
-        let diff = fs
-            .edit("/notes/full.txt", "", "hello martin!", false)
-            .await
-            .unwrap();
+        let big_files = FindOptions {
+            kind: Some(EntryKind::File),
+            min_size: Some(5),
+            ..FindOptions::default()
+        };
+        assert_eq!(
+            paths(fs.find("/", &big_files).await.unwrap()),
+            vec!["/proj/notes.md", "/proj/src/lib.rs"]
+        );
 
-        let content = fs.cat("/notes/full.txt").await.unwrap();
-        assert_eq!(content, "hello martin!");
-        assert!(diff.contains("-original"));
-        assert!(diff.contains("+hello martin!"));
+        sleep(Duration::from_millis(5)).await;
+        let since = now_millis();
+        fs.write_file("/proj/src/small.rs", "012").await.unwrap();
+        let recent = FindOptions {
+            modified_since: Some(since),
+            max_size: Some(3),
+            ..FindOptions::default()
+        };
+        assert_eq!(
+            paths(fs.find("/", &recent).await.unwrap()),
+            vec!["/proj/src/small.rs"]
+        );
 
-        let no_diff = fs
-            .edit("/notes/full.txt", "", "hello martin!", false)
-            .await
-            .unwrap();
-        assert!(no_diff.is_empty());
+        let bad = FindOptions {
+            name: Some("[ab".into()),
+            ..FindOptions::default()
+        };
+        let err = fs.find("/", &bad).await.unwrap_err();
+        assert!(matches!(err, FsError::InvalidPattern(pattern) if pattern == "[ab"));
     }
 
     #[tokio::test]
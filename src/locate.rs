@@ -0,0 +1,67 @@
+use serde::Deserialize;
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{FsError, Result, SurrealFs};
+
+/// Longest name fragment the `name` index holds as one term; longer fragments are found
+/// through their pieces and confirmed against the name.
+const NGRAM_MAX: usize = 32;
+
+#[derive(Debug, Deserialize)]
+struct Located {
+    path: String,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Define the index `locate` runs on: every lowercased substring of each entry name, up
+    /// to `NGRAM_MAX` characters, so a fragment is looked up rather than matched against
+    /// every row. Indexing an existing table happens once, here, and every write after
+    /// keeps it current. Safe to run repeatedly; existing definitions are left alone.
+    pub async fn define_name_index(&self) -> Result<()> {
+        self.ensure_writable("/")?;
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db.query(format!(
+                "DEFINE ANALYZER IF NOT EXISTS {table}_names TOKENIZERS blank FILTERS lowercase, ngram(1, {max});\
+                 DEFINE INDEX IF NOT EXISTS {table}_name ON {table} FIELDS name SEARCH ANALYZER {table}_names BM25;",
+                table = self.table,
+                max = NGRAM_MAX,
+            ))
+        })
+        .await?
+        .check()?;
+        Ok(())
+    }
+
+    /// Paths of entries whose name contains `name_fragment`, ignoring case, like
+    /// `locate -i -b`, sorted by path. Candidates come from the `name` index and are then
+    /// checked against the whole fragment. The index is defined on first use unless the
+    /// handle is read-only, when `define_name_index` must have run.
+    pub async fn locate(&self, name_fragment: &str) -> Result<Vec<String>> {
+        let fragment = name_fragment.to_lowercase();
+        if fragment.trim().is_empty() || fragment.contains('/') {
+            return Err(FsError::InvalidPath);
+        }
+        if !self.is_read_only() {
+            self.define_name_index().await?;
+        }
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(Route::Replica)
+                    .query(format!(
+                        "SELECT path FROM {} WHERE name @@ $fragment AND string::contains(string::lowercase(name), $fragment) ORDER BY path",
+                        self.table
+                    ))
+                    .bind(("fragment", fragment.clone()))
+            })
+            .await?;
+        let located: Vec<Located> = res.take(0)?;
+        Ok(located.into_iter().map(|l| l.path).collect())
+    }
+}
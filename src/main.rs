@@ -1,12 +1,9 @@
 use std::env;
-use std::path::PathBuf;
 
-use surrealdb::Surreal;
 use surrealdb::engine::any::connect;
-use surrealdb::engine::local::RocksDb;
 use surrealdb::opt::auth::Root;
 
-use surrealfs::SurrealFs;
+use surrealfs::{NewlinePolicy, SurrealFs};
 
 mod commands;
 mod repl;
@@ -16,6 +13,14 @@ async fn main() -> surrealfs::Result<()> {
     // Demo using either a file-backed engine (default) or a remote SurrealDB.
     // Set env SURREALFS_REMOTE=1 to use remote at ws://127.0.0.1:8000 with root/root.
     let use_remote = env::var("SURREALFS_REMOTE").is_ok();
+    // Set env SURREALFS_TRASH=1 to make rm/rmdir move entries to /.trash instead.
+    let use_trash = env::var("SURREALFS_TRASH").is_ok();
+    // Set env SURREALFS_NEWLINE=lf or crlf to normalize line endings on write and export.
+    let newline = match env::var("SURREALFS_NEWLINE").as_deref() {
+        Ok("lf") => NewlinePolicy::Lf,
+        Ok("crlf") => NewlinePolicy::CrLf,
+        _ => NewlinePolicy::Preserve,
+    };
 
     if use_remote {
         println!("Using remote SurrealDB at ws://127.0.0.1:8000 (ns=surrealfs, db=demo)");
@@ -26,14 +31,22 @@ async fn main() -> surrealfs::Result<()> {
         })
         .await?;
         db.use_ns("surrealfs").use_db("demo").await?;
-        let fs = SurrealFs::new(db);
+        let fs = SurrealFs::builder(db)
+            .trash(use_trash)
+            .newline(newline)
+            .build();
+        fs.migrate_record_ids().await?;
+        fs.ensure_indexes().await?;
         repl::run(fs).await
     } else {
         println!("Using RocksDB-backed SurrealDB at ./demo-db (ns=surrealfs, db=demo)");
-        let db_path = PathBuf::from("./demo-db");
-        let db = Surreal::new::<RocksDb>(db_path.as_path()).await?;
-        db.use_ns("surrealfs").use_db("demo").await?;
-        let fs = SurrealFs::new(db);
+        let fs = SurrealFs::open_builder("rocksdb:./demo-db", "surrealfs", "demo")
+            .await?
+            .trash(use_trash)
+            .newline(newline)
+            .build();
+        fs.migrate_record_ids().await?;
+        fs.ensure_indexes().await?;
         repl::run(fs).await
     }
 }
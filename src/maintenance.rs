@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::usage::SIZE_EXPR;
+use crate::{Result, SurrealFs, TRASH_DIR, now_millis};
+
+/// Counts of what a `vacuum` pass removed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VacuumReport {
+    pub orphaned_entries: usize,
+    /// Files removed because their TTL ran out, as `expire` would.
+    #[serde(default)]
+    pub expired_files: usize,
+    /// Entries purged from the trash, counting everything inside trashed directories.
+    #[serde(default)]
+    pub trash_entries: usize,
+    /// Bytes of file content freed across all of the above.
+    pub reclaimed_bytes: usize,
+}
+
+/// The part of an entry `vacuum` needs: where it hangs and how much it stores, with the
+/// size computed server-side so content is never fetched.
+#[derive(Debug, Deserialize)]
+struct Node {
+    path: String,
+    parent: Option<String>,
+    is_dir: bool,
+    size: usize,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Permanently remove what is no longer wanted and report how much was reclaimed:
+    /// entries that can no longer be reached from `/` (their parent chain is missing or
+    /// broken), files whose TTL has run out, and trashed items older than the builder's
+    /// `trash_retention` (all of them without one). Like `expire`, nothing runs in the
+    /// background; call it periodically, e.g. from a `tokio::time::interval` loop.
+    pub async fn vacuum(&self) -> Result<VacuumReport> {
+        self.ensure_writable("/")?;
+        let entries = self.nodes().await?;
+        let orphans = unreachable_entries(&entries);
+        let mut report = VacuumReport {
+            orphaned_entries: orphans.len(),
+            reclaimed_bytes: orphans.iter().map(|e| e.size).sum(),
+            ..VacuumReport::default()
+        };
+        let orphan_paths: HashSet<&str> = orphans.iter().map(|e| e.path.as_str()).collect();
+        let sizes: HashMap<&str, usize> = entries
+            .iter()
+            .filter(|e| !orphan_paths.contains(e.path.as_str()))
+            .map(|e| (e.path.as_str(), e.size))
+            .collect();
+        if !orphans.is_empty() {
+            let paths: Vec<String> = orphans.into_iter().map(|e| e.path.clone()).collect();
+            self.delete_paths(paths).await?;
+        }
+
+        let expired = self.expire().await?;
+        report.expired_files = expired.len();
+        report.reclaimed_bytes += expired
+            .iter()
+            .filter_map(|path| sizes.get(path.as_str()))
+            .sum::<usize>();
+
+        // Without a retention the whole trash goes, `TRASH_DIR` included, as `empty_trash`.
+        let roots: Vec<String> = match self.trash_retention {
+            None => vec![TRASH_DIR.to_string()],
+            Some(retention) => {
+                let cutoff = now_millis().saturating_sub(retention.as_millis() as i64);
+                self.list_trash()
+                    .await?
+                    .into_iter()
+                    .filter(|item| item.trashed_at <= cutoff)
+                    .map(|item| item.path)
+                    .collect()
+            }
+        };
+        let purged: Vec<(&str, usize)> = sizes
+            .iter()
+            .filter(|(path, _)| {
+                roots.iter().any(|root| {
+                    *path == root
+                        || path
+                            .strip_prefix(root.as_str())
+                            .is_some_and(|r| r.starts_with('/'))
+                })
+            })
+            .map(|(path, size)| (*path, *size))
+            .collect();
+        if !purged.is_empty() {
+            report.trash_entries = purged.iter().filter(|(path, _)| *path != TRASH_DIR).count();
+            report.reclaimed_bytes += purged.iter().map(|(_, size)| size).sum::<usize>();
+            self.delete_paths(purged.iter().map(|(path, _)| path.to_string()).collect())
+                .await?;
+        }
+
+        Ok(report)
+    }
+
+    async fn nodes(&self) -> Result<Vec<Node>> {
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db.query(format!(
+                    "SELECT path, parent, is_dir, {} AS size FROM {}",
+                    SIZE_EXPR, self.table
+                ))
+            })
+            .await?;
+        let nodes: Vec<Node> = res.take(0)?;
+        Ok(nodes)
+    }
+
+    /// Move entries stored under generated record ids, as written before ids were derived
+    /// from paths, to the id of their path so direct lookups find them. Returns how many
+    /// were moved; running it again is a no-op.
+    pub async fn migrate_record_ids(&self) -> Result<usize> {
+        self.ensure_writable("/")?;
+        let _permit = self.permit().await;
+        self.forget_all();
+        let mut res = self
+            .run_once(|| {
+                self.db
+                    .query(format!(
+                        "BEGIN TRANSACTION;\
+                         LET $ids = (SELECT VALUE id FROM {table} WHERE id != type::thing($table, path));\
+                         LET $stale = (SELECT * OMIT id FROM {table} WHERE id != type::thing($table, path));\
+                         DELETE $ids;\
+                         FOR $entry IN $stale {{ UPSERT type::thing($table, $entry.path) CONTENT $entry; }};\
+                         RETURN array::len($ids);\
+                         COMMIT TRANSACTION;",
+                        table = self.table
+                    ))
+                    .bind(("table", self.table.clone()))
+            })
+            .await?;
+        let moved: Option<usize> = res.take(res.num_statements() - 1)?;
+        Ok(moved.unwrap_or(0))
+    }
+}
+
+fn unreachable_entries(entries: &[Node]) -> Vec<&Node> {
+    let mut by_parent: HashMap<&str, Vec<&Node>> = HashMap::new();
+    for entry in entries {
+        if let Some(parent) = entry.parent.as_deref() {
+            by_parent.entry(parent).or_default().push(entry);
+        }
+    }
+
+    let mut reachable: HashSet<&str> = HashSet::new();
+    let mut stack = vec!["/"];
+    while let Some(dir) = stack.pop() {
+        for child in by_parent.get(dir).into_iter().flatten() {
+            if reachable.insert(child.path.as_str()) && child.is_dir {
+                stack.push(child.path.as_str());
+            }
+        }
+    }
+
+    entries
+        .iter()
+        .filter(|e| !reachable.contains(e.path.as_str()))
+        .collect()
+}
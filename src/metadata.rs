@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::usage::SIZE_EXPR;
+use crate::{Entry, FsError, Result, SurrealFs, mode, normalize_path, procfs};
+
+/// Columns selected whenever metadata is loaded; the size is computed server-side.
+pub(crate) fn metadata_fields() -> String {
+    format!(
+        "path, name, parent, is_dir, {} AS size, inode.content_type ?? content_type AS content_type, inode.encoding ?? encoding AS encoding, inode.checksum ?? checksum AS checksum, mode, owner, group, xattrs ?? {{}} AS xattrs, immutable ?? false AS immutable, hidden ?? false AS hidden, created_at, inode.updated_at ?? updated_at AS updated_at, accessed_at, expires_at, inode.version ?? version ?? 1 AS version, IF type::is::record(inode) THEN record::id(inode) END AS inode, symlink",
+        SIZE_EXPR
+    )
+}
+
+/// Everything about an entry except its content, as returned by `SurrealFs::metadata`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Metadata {
+    pub path: String,
+    pub name: String,
+    pub parent: Option<String>,
+    pub is_dir: bool,
+    /// Content length in bytes; 0 for directories and symlinks.
+    pub size: usize,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub checksum: Option<String>,
+    #[serde(default)]
+    pub mode: Option<u32>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub xattrs: BTreeMap<String, String>,
+    #[serde(default)]
+    pub immutable: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub created_at: Option<i64>,
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+    #[serde(default)]
+    pub accessed_at: Option<i64>,
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default)]
+    pub inode: Option<String>,
+    #[serde(default)]
+    pub symlink: Option<String>,
+}
+
+impl Metadata {
+    pub fn is_symlink(&self) -> bool {
+        self.symlink.is_some()
+    }
+
+    /// The permission bits that apply, with defaults for entries stored without a mode.
+    pub fn permissions(&self) -> u32 {
+        mode::effective(self.is_dir, self.is_symlink(), self.mode)
+    }
+
+    /// The root directory, which is implicit and never stored.
+    pub(crate) fn root() -> Self {
+        Metadata {
+            path: "/".into(),
+            name: "/".into(),
+            parent: None,
+            is_dir: true,
+            size: 0,
+            content_type: None,
+            encoding: None,
+            checksum: None,
+            mode: None,
+            owner: None,
+            group: None,
+            xattrs: BTreeMap::new(),
+            immutable: false,
+            hidden: false,
+            created_at: None,
+            updated_at: None,
+            accessed_at: None,
+            expires_at: None,
+            version: 0,
+            inode: None,
+            symlink: None,
+        }
+    }
+}
+
+impl From<&Entry> for Metadata {
+    fn from(entry: &Entry) -> Self {
+        Metadata {
+            path: entry.path.clone(),
+            name: entry.name.clone(),
+            parent: entry.parent.clone(),
+            is_dir: entry.is_dir,
+            size: entry.size(),
+            content_type: entry.content_type.clone(),
+            encoding: entry.encoding.clone(),
+            checksum: entry.checksum.clone(),
+            mode: entry.mode,
+            owner: entry.owner.clone(),
+            group: entry.group.clone(),
+            xattrs: entry.xattrs.clone(),
+            immutable: entry.immutable,
+            hidden: entry.hidden,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            accessed_at: entry.accessed_at,
+            expires_at: entry.expires_at,
+            version: entry.version,
+            inode: entry.inode.clone(),
+            symlink: entry.symlink.clone(),
+        }
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Whether anything exists at `path`, following a trailing symlink. Content is never
+    /// fetched.
+    pub async fn exists(&self, path: impl AsRef<str>) -> Result<bool> {
+        Ok(self.metadata(path).await?.is_some())
+    }
+
+    /// Metadata for `path`, following a trailing symlink; `None` when nothing is there or the
+    /// link dangles. The size is computed server-side, so content is never fetched.
+    pub async fn metadata(&self, path: impl AsRef<str>) -> Result<Option<Metadata>> {
+        match self.symlink_metadata(path).await? {
+            Some(meta) if meta.is_symlink() => match self.realpath(&meta.path).await {
+                Ok(real) => self.symlink_metadata(real).await,
+                Err(FsError::NotFound(_)) => Ok(None),
+                Err(err) => Err(err),
+            },
+            other => Ok(other),
+        }
+    }
+
+    /// Like `metadata`, but describes a symlink itself rather than its target.
+    pub async fn symlink_metadata(&self, path: impl AsRef<str>) -> Result<Option<Metadata>> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Ok(Some(Metadata::root()));
+        }
+        if procfs::is_proc_path(&path) {
+            return Ok(self.proc_entry(&path).await?.as_ref().map(Metadata::from));
+        }
+        self.flush_pending().await?;
+        // With a cache, loading the whole entry once beats a metadata query per lookup.
+        if self.cache.is_some() {
+            return Ok(self.get_entry(&path).await?.as_ref().map(Metadata::from));
+        }
+
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT {} FROM type::thing($table, $path)",
+                        metadata_fields()
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", path.clone()))
+            })
+            .await?;
+        let meta: Option<Metadata> = res.take(0)?;
+        Ok(meta)
+    }
+
+    /// Metadata for the children of the directory `path` in name order, without their content.
+    pub(crate) async fn child_metadata(&self, path: &str) -> Result<Vec<Metadata>> {
+        if procfs::is_proc_path(path) {
+            return Ok(self
+                .proc_children(path)
+                .iter()
+                .map(Metadata::from)
+                .collect());
+        }
+        self.flush_pending().await?;
+        if self.cache.is_some() {
+            return Ok(self
+                .children(path)
+                .await?
+                .iter()
+                .map(Metadata::from)
+                .collect());
+        }
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT {} FROM {} ORDER BY name",
+                        metadata_fields(),
+                        self.children_source()
+                    ))
+                    .bind(("table", self.table.clone()))
+                    .bind(("parent", path.to_string()))
+            })
+            .await?;
+        let children: Vec<Metadata> = res.take(0)?;
+        Ok(children)
+    }
+}
@@ -0,0 +1,276 @@
+use surrealdb::Connection;
+
+use crate::{Entry, FsError, Result, SurrealFs, encoding, normalize_path};
+
+/// How much of the content is searched for NUL bytes when telling text from binary.
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Signatures checked against the start of the content, most specific first.
+const MAGIC: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\0asm", "application/wasm"),
+    (b"\x7fELF", "application/x-elf"),
+];
+
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("ts", "text/x-typescript"),
+    ("rs", "text/x-rust"),
+    ("py", "text/x-python"),
+    ("go", "text/x-go"),
+    ("c", "text/x-c"),
+    ("h", "text/x-c"),
+    ("sh", "text/x-shellscript"),
+    ("surql", "text/x-surrealql"),
+    ("json", "application/json"),
+    ("toml", "application/toml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("ico", "image/x-icon"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("wasm", "application/wasm"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+];
+
+/// How `file_type` describes content of a given MIME type, in the words of file(1).
+const DESCRIPTIONS: &[(&str, &str)] = &[
+    ("image/png", "PNG image data"),
+    ("image/jpeg", "JPEG image data"),
+    ("image/gif", "GIF image data"),
+    ("image/webp", "RIFF (little-endian) data, Web/P image"),
+    ("application/pdf", "PDF document"),
+    ("application/zip", "Zip archive data"),
+    ("application/gzip", "gzip compressed data"),
+    ("application/wasm", "WebAssembly (wasm) binary module"),
+    ("application/x-elf", "ELF executable"),
+    ("application/json", "JSON text data"),
+    ("application/xml", "XML document text"),
+    ("text/html", "HTML document text"),
+    ("text/csv", "CSV text"),
+    ("image/svg+xml", "SVG Scalable Vector Graphics image"),
+];
+
+/// Types outside `text/` whose content is text all the same.
+pub(crate) const TEXT_APPLICATION_TYPES: [&str; 5] = [
+    "application/json",
+    "application/toml",
+    "application/yaml",
+    "application/xml",
+    "image/svg+xml",
+];
+
+/// Whether content of this type is text that `grep` and friends can read line by line.
+pub fn is_text_type(content_type: &str) -> bool {
+    content_type.starts_with("text/") || TEXT_APPLICATION_TYPES.contains(&content_type)
+}
+
+/// Guess a MIME type from the leading bytes, then the extension, then whether the content is
+/// valid UTF-8. A text extension never wins over content that is not UTF-8.
+pub(crate) fn detect(path: &str, bytes: &[u8]) -> &'static str {
+    if let Some((_, ty)) = MAGIC.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return ty;
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    // NUL bytes do not occur in text, so like grep and git, a NUL near the start marks
+    // content as binary even when it is valid UTF-8.
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+    let utf8 = !sample.contains(&0) && std::str::from_utf8(bytes).is_ok();
+    match by_extension(path) {
+        Some(ty) if utf8 || !is_text_type(ty) => ty,
+        _ if utf8 => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The content type to store for `entry`; directories and symlinks have none.
+pub(crate) fn of_entry(entry: &Entry) -> Option<String> {
+    if entry.is_dir || entry.symlink.is_some() {
+        return None;
+    }
+    let bytes = match (&entry.content_bytes, &entry.content) {
+        (Some(bytes), _) => bytes.as_ref(),
+        (None, Some(text)) => text.as_bytes(),
+        (None, None) => &[],
+    };
+    // Legacy-encoded text is typed by what it decodes to, so it is not mistaken for binary.
+    if let Some(text) = encoding::detect(bytes).and_then(|enc| encoding::decode(bytes, enc)) {
+        return Some(detect(&entry.path, text.as_bytes()).to_string());
+    }
+    Some(detect(&entry.path, bytes).to_string())
+}
+
+/// A file(1)-style description of `entry`, e.g. `PNG image data, 640 x 480` or `ASCII text`.
+/// The stored content type is trusted when present; legacy entries without one are
+/// detected from their content.
+pub(crate) fn describe(entry: &Entry) -> String {
+    if let Some(target) = &entry.symlink {
+        return format!("symbolic link to {}", target);
+    }
+    if entry.is_dir {
+        return "directory".into();
+    }
+    let bytes = match (&entry.content_bytes, &entry.content) {
+        (Some(bytes), _) => bytes.as_ref(),
+        (None, Some(text)) => text.as_bytes(),
+        (None, None) => &[],
+    };
+    if bytes.is_empty() {
+        return "empty".into();
+    }
+    let ty = match &entry.content_type {
+        Some(ty) => ty.as_str(),
+        None => detect(&entry.path, bytes),
+    };
+
+    // Legacy entries have no recorded encoding, so detect one on the fly for binary content.
+    let legacy = match &entry.encoding {
+        Some(enc) => Some(enc.as_str()),
+        None if entry.content_bytes.is_some() => encoding::detect(bytes),
+        None => None,
+    };
+    let decoded = legacy.and_then(|enc| encoding::decode(bytes, enc));
+    let text = match (&decoded, std::str::from_utf8(bytes)) {
+        (Some(text), _) => text.as_str(),
+        (None, Ok(text)) => text,
+        (None, Err(_)) => {
+            let description = described(ty).unwrap_or("data").to_string();
+            return match png_dimensions(bytes) {
+                Some((w, h)) if ty == "image/png" => format!("{}, {} x {}", description, w, h),
+                _ => description,
+            };
+        }
+    };
+
+    let charset = match legacy.filter(|_| decoded.is_some()) {
+        Some("utf-16le") => "Unicode text, UTF-16, little-endian",
+        Some("utf-16be") => "Unicode text, UTF-16, big-endian",
+        Some(_) => "ISO-8859",
+        None if text.is_ascii() => "ASCII",
+        None => "Unicode text, UTF-8",
+    };
+    let mut description = if let Some(interpreter) = shebang(text) {
+        format!("{} script, {} text executable", interpreter, charset)
+    } else if let Some(known) = described(ty) {
+        known.to_string()
+    } else if looks_like_json(text) {
+        "JSON text data".into()
+    } else {
+        format!("{} text", charset)
+    };
+    if text.contains("\r\n") {
+        description.push_str(", with CRLF line terminators");
+    }
+    description
+}
+
+fn described(content_type: &str) -> Option<&'static str> {
+    DESCRIPTIONS
+        .iter()
+        .find(|(ty, _)| *ty == content_type)
+        .map(|(_, description)| *description)
+}
+
+/// The interpreter named on a `#!` line, looking through `env`: `#!/usr/bin/env python3` →
+/// `python3`.
+fn shebang(text: &str) -> Option<&str> {
+    let line = text.strip_prefix("#!")?.lines().next()?;
+    let mut words = line.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        words.find(|w| !w.starts_with('-'))
+    } else {
+        Some(program)
+    }
+}
+
+fn looks_like_json(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+}
+
+/// Width and height from a PNG's IHDR chunk, which always comes first.
+fn png_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.len() < 24 || &bytes[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Whether the file at `path` holds binary data rather than text, following a trailing
+    /// symlink. The stored content type decides, so content is only fetched for files
+    /// written before it was tracked.
+    pub async fn is_binary(&self, path: impl AsRef<str>) -> Result<bool> {
+        let path = normalize_path(path.as_ref())?;
+        let meta = self
+            .metadata(&path)
+            .await?
+            .ok_or_else(|| FsError::NotFound(path.clone()))?;
+        if meta.is_dir {
+            return Err(FsError::NotAFile(meta.path));
+        }
+        match meta.content_type {
+            Some(content_type) => Ok(!is_text_type(&content_type)),
+            None => Ok(self.require_file(&meta.path).await?.is_binary()),
+        }
+    }
+
+    /// Describe what `path` holds the way file(1) does: `directory`, `ASCII text`,
+    /// `JSON text data`, `PNG image data, 1 x 1`, and so on. Symlinks are described rather
+    /// than followed.
+    pub async fn file_type(&self, path: impl AsRef<str>) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Ok("directory".into());
+        }
+        let entry = self
+            .get_entry(&path)
+            .await?
+            .ok_or(FsError::NotFound(path))?;
+        Ok(describe(&entry))
+    }
+}
+
+fn by_extension(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next()?;
+    let (_, ext) = name.rsplit_once('.')?;
+    let ext = ext.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(candidate, _)| *candidate == ext)
+        .map(|(_, ty)| *ty)
+}
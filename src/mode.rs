@@ -0,0 +1,80 @@
+use surrealdb::Connection;
+
+use crate::{Entry, FsError, Result, SurrealFs, normalize_path};
+
+/// Permission bits given to new files.
+pub const DEFAULT_FILE_MODE: u32 = 0o644;
+/// Permission bits given to new directories.
+pub const DEFAULT_DIR_MODE: u32 = 0o755;
+/// Permission bits of a symlink, which are never consulted.
+pub(crate) const SYMLINK_MODE: u32 = 0o777;
+
+const OWNER_WRITE: u32 = 0o200;
+
+/// The permission bits that apply to an entry, filling in the defaults for entries written
+/// before modes were stored.
+pub(crate) fn effective(is_dir: bool, symlink: bool, mode: Option<u32>) -> u32 {
+    match mode {
+        Some(mode) => mode,
+        None if symlink => SYMLINK_MODE,
+        None if is_dir => DEFAULT_DIR_MODE,
+        None => DEFAULT_FILE_MODE,
+    }
+}
+
+/// Render a type character and permission bits the way `ls -l` does, e.g. `drwxr-xr-x`.
+pub fn format_mode(is_dir: bool, symlink: bool, mode: u32) -> String {
+    let kind = if symlink {
+        'l'
+    } else if is_dir {
+        'd'
+    } else {
+        '-'
+    };
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    out
+}
+
+/// Refuse to change the content of a file whose owner write bit is clear.
+pub(crate) fn ensure_content_writable(entry: &Entry) -> Result<()> {
+    if entry.permissions() & OWNER_WRITE == 0 {
+        return Err(FsError::PermissionDenied(entry.path.clone()));
+    }
+    Ok(())
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Set the permission bits of `path`, following a trailing symlink. Only the low twelve
+    /// bits are kept. Hard links share one mode, as they share one file.
+    pub async fn chmod(&self, path: impl AsRef<str>, mode: u32) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        self.ensure_writable(&path)?;
+        let (path, entry) = self.follow(path).await?;
+        let entry = entry.ok_or_else(|| FsError::NotFound(path.clone()))?;
+
+        let _permit = self.permit().await;
+        self.forget(&[&path]);
+        self.run(|| {
+            self.db
+                .query(format!(
+                    "UPDATE {} SET mode = $mode WHERE path = $path OR (inode != NONE AND inode = $inode)",
+                    self.table
+                ))
+                .bind(("path", path.clone()))
+                .bind(("inode", self.inode_record(&entry.inode)))
+                .bind(("mode", mode & 0o7777))
+        })
+        .await?;
+        Ok(())
+    }
+}
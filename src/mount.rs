@@ -0,0 +1,245 @@
+use surrealdb::Connection;
+use surrealdb::engine::remote::ws::Client;
+
+use crate::{FsError, Metadata, Node, Result, SurrealFs, leaf_name, normalize_path, parent_path};
+
+/// One tree stitched together from several filesystems: each mount point serves the paths
+/// beneath it from its own `SurrealFs`, over another table or another connection, and the
+/// root filesystem serves everything else. Symlinks resolve within the filesystem that
+/// holds them.
+pub struct Mounts<DB = Client>
+where
+    DB: Connection,
+{
+    root: SurrealFs<DB>,
+    /// Mount points with their filesystems, deepest first so the longest prefix wins.
+    mounts: Vec<(String, SurrealFs<DB>)>,
+}
+
+impl<DB> Mounts<DB>
+where
+    DB: Connection,
+{
+    pub fn new(root: SurrealFs<DB>) -> Self {
+        Self {
+            root,
+            mounts: Vec::new(),
+        }
+    }
+
+    /// Serve `point` and everything beneath it from `fs`, whose own root becomes `point`.
+    /// Whatever the filesystems below held at `point` is hidden while it is mounted.
+    pub fn mount(&mut self, point: impl AsRef<str>, fs: SurrealFs<DB>) -> Result<()> {
+        let point = normalize_path(point.as_ref())?;
+        if point == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        if self.mounts.iter().any(|(p, _)| *p == point) {
+            return Err(FsError::AlreadyExists(point));
+        }
+        self.mounts.push((point, fs));
+        self.mounts
+            .sort_by_key(|(p, _)| std::cmp::Reverse(p.matches('/').count()));
+        Ok(())
+    }
+
+    /// Detach the filesystem mounted at `point` and hand it back.
+    pub fn unmount(&mut self, point: impl AsRef<str>) -> Result<SurrealFs<DB>> {
+        let point = normalize_path(point.as_ref())?;
+        let at = self
+            .mounts
+            .iter()
+            .position(|(p, _)| *p == point)
+            .ok_or_else(|| FsError::NotFound(point))?;
+        Ok(self.mounts.remove(at).1)
+    }
+
+    /// Mount points in path order.
+    pub fn mount_points(&self) -> Vec<&str> {
+        let mut points: Vec<&str> = self.mounts.iter().map(|(p, _)| p.as_str()).collect();
+        points.sort();
+        points
+    }
+
+    /// The filesystem serving `path`, the mount point it sits under (`/` for the root
+    /// filesystem), and the path within that filesystem.
+    pub fn resolve(&self, path: impl AsRef<str>) -> Result<(&SurrealFs<DB>, &str, String)> {
+        let path = normalize_path(path.as_ref())?;
+        for (point, fs) in &self.mounts {
+            if path == *point {
+                return Ok((fs, point, "/".to_string()));
+            }
+            if let Some(rest) = path.strip_prefix(point.as_str())
+                && rest.starts_with('/')
+            {
+                return Ok((fs, point, rest.to_string()));
+            }
+        }
+        Ok((&self.root, "/", path))
+    }
+
+    /// `SurrealFs::ls` across mount points: mount points directly beneath a directory are
+    /// listed as directories, and a directory that exists only to hold mount points lists
+    /// just those.
+    pub async fn ls(&self, path: impl AsRef<str>) -> Result<Vec<Node>> {
+        let path = normalize_path(path.as_ref())?;
+        let (fs, point, inner) = self.resolve(&path)?;
+        let mounted = self.mounted_below(&path);
+        let mut nodes = match fs.ls(&inner).await {
+            Ok(nodes) => nodes
+                .into_iter()
+                .map(|node| rebase_node(point, node))
+                .collect(),
+            Err(FsError::NotFound(_)) if !mounted.is_empty() => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        nodes.retain(|node| !mounted.iter().any(|m| m.path() == node.path()));
+        nodes.extend(mounted);
+        nodes.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(nodes)
+    }
+
+    pub async fn metadata(&self, path: impl AsRef<str>) -> Result<Option<Metadata>> {
+        let (fs, point, inner) = self.resolve(path)?;
+        if inner == "/" && point != "/" {
+            return Ok(Some(mount_point_metadata(point)));
+        }
+        Ok(fs
+            .metadata(&inner)
+            .await?
+            .map(|meta| rebase_metadata(point, meta)))
+    }
+
+    pub async fn exists(&self, path: impl AsRef<str>) -> Result<bool> {
+        Ok(self.metadata(path).await?.is_some())
+    }
+
+    pub async fn cat(&self, path: impl AsRef<str>) -> Result<String> {
+        let (fs, _, inner) = self.resolve(path)?;
+        fs.cat(&inner).await
+    }
+
+    pub async fn cat_bytes(&self, path: impl AsRef<str>) -> Result<Vec<u8>> {
+        let (fs, _, inner) = self.resolve(path)?;
+        fs.cat_bytes(&inner).await
+    }
+
+    pub async fn write_file(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+    ) -> Result<Metadata> {
+        let (fs, point, inner) = self.resolve(path)?;
+        let meta = fs.write_file(&inner, content).await?;
+        Ok(rebase_metadata(point, meta))
+    }
+
+    pub async fn write_bytes(&self, path: impl AsRef<str>, data: Vec<u8>) -> Result<Metadata> {
+        let (fs, point, inner) = self.resolve(path)?;
+        let meta = fs.write_bytes(&inner, data).await?;
+        Ok(rebase_metadata(point, meta))
+    }
+
+    pub async fn mkdir(&self, path: impl AsRef<str>, parents: bool) -> Result<Metadata> {
+        let (fs, point, inner) = self.resolve(path)?;
+        let meta = fs.mkdir(&inner, parents).await?;
+        Ok(rebase_metadata(point, meta))
+    }
+
+    pub async fn rm(&self, path: impl AsRef<str>) -> Result<()> {
+        let (fs, _, inner) = self.resolve(path)?;
+        fs.rm(&inner).await
+    }
+
+    pub async fn rmdir(&self, path: impl AsRef<str>, recursive: bool) -> Result<()> {
+        let (fs, _, inner) = self.resolve(path)?;
+        fs.rmdir(&inner, recursive).await
+    }
+
+    /// Copy the file or directory tree at `src` to `dest`, within one filesystem or from one
+    /// mount to another.
+    pub async fn cp(&self, src: impl AsRef<str>, dest: impl AsRef<str>) -> Result<Metadata> {
+        let (src_fs, _, src_inner) = self.resolve(src)?;
+        let (dest_fs, dest_point, dest_inner) = self.resolve(dest)?;
+        let meta = src_fs.cp_to(&src_inner, dest_fs, &dest_inner).await?;
+        Ok(rebase_metadata(dest_point, meta))
+    }
+
+    /// Move `src` to `dest`. Within one filesystem this is `SurrealFs::mv`; across mounts
+    /// the tree is copied, then removed from its source.
+    pub async fn mv(&self, src: impl AsRef<str>, dest: impl AsRef<str>) -> Result<()> {
+        let (src_fs, src_point, src_inner) = self.resolve(src)?;
+        let (dest_fs, dest_point, dest_inner) = self.resolve(dest)?;
+        if std::ptr::eq(src_fs, dest_fs) {
+            return src_fs.mv(&src_inner, &dest_inner, false).await;
+        }
+        if dest_fs.symlink_metadata(&dest_inner).await?.is_some() {
+            return Err(FsError::AlreadyExists(outer_path(dest_point, &dest_inner)));
+        }
+        let is_dir = src_fs
+            .symlink_metadata(&src_inner)
+            .await?
+            .ok_or_else(|| FsError::NotFound(outer_path(src_point, &src_inner)))?
+            .is_dir;
+        src_fs.cp_to(&src_inner, dest_fs, &dest_inner).await?;
+        if is_dir {
+            src_fs.rmdir(&src_inner, true).await
+        } else {
+            src_fs.rm(&src_inner).await
+        }
+    }
+
+    /// Mount points directly beneath `dir`, as directory nodes.
+    fn mounted_below(&self, dir: &str) -> Vec<Node> {
+        self.mounts
+            .iter()
+            .filter(|(point, _)| parent_path(point).as_deref() == Some(dir))
+            .map(|(point, _)| Node::from(mount_point_metadata(point)))
+            .collect()
+    }
+}
+
+/// The directory a mount point shows up as.
+fn mount_point_metadata(point: &str) -> Metadata {
+    Metadata {
+        path: point.to_string(),
+        name: leaf_name(point),
+        parent: parent_path(point),
+        ..Metadata::root()
+    }
+}
+
+/// `inner`, a path within the filesystem mounted at `point`, as seen from the whole tree.
+fn outer_path(point: &str, inner: &str) -> String {
+    match (point, inner) {
+        ("/", _) => inner.to_string(),
+        (_, "/") => point.to_string(),
+        _ => format!("{}{}", point, inner),
+    }
+}
+
+fn rebase_metadata(point: &str, mut meta: Metadata) -> Metadata {
+    if point == "/" {
+        return meta;
+    }
+    if meta.path == "/" {
+        return mount_point_metadata(point);
+    }
+    meta.path = outer_path(point, &meta.path);
+    meta.parent = meta.parent.map(|parent| outer_path(point, &parent));
+    meta
+}
+
+fn rebase_node(point: &str, mut node: Node) -> Node {
+    if point == "/" {
+        return node;
+    }
+    let (path, parent) = match &mut node {
+        Node::File(f) => (&mut f.path, &mut f.parent),
+        Node::Dir(d) => (&mut d.path, &mut d.parent),
+        Node::Symlink(l) => (&mut l.path, &mut l.parent),
+    };
+    *path = outer_path(point, path);
+    *parent = parent.as_deref().map(|parent| outer_path(point, parent));
+    node
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{Entry, FsError, Metadata, Result, SurrealFs, format_mode, mode, normalize_path};
+
+/// A typed view of an entry as returned by `ls` and `stat`: files own content, directories
+/// and symlinks do not, so there is no `content` field to be always-`None`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Node {
+    File(FileEntry),
+    Dir(DirEntry),
+    Symlink(SymlinkEntry),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileEntry {
+    pub path: String,
+    pub name: String,
+    pub parent: Option<String>,
+    /// Content length in bytes, known even when `content` was not fetched.
+    pub size: usize,
+    /// `None` in listings from `ls`, which never fetch file bodies; `stat` loads it.
+    pub content: Option<FileContent>,
+    pub content_type: Option<String>,
+    /// Legacy text encoding of binary content, e.g. `iso-8859-1`; see `Entry::encoding`.
+    pub encoding: Option<String>,
+    pub checksum: Option<String>,
+    /// Shared by every hard link to the same file.
+    pub inode: Option<String>,
+    pub attrs: Attributes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirEntry {
+    pub path: String,
+    pub name: String,
+    pub parent: Option<String>,
+    pub attrs: Attributes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SymlinkEntry {
+    pub path: String,
+    pub name: String,
+    pub parent: Option<String>,
+    /// The stored target, which need not exist.
+    pub target: String,
+    pub attrs: Attributes,
+}
+
+/// File content as it was written: text from `write_file`, bytes from `write_bytes`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FileContent {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Fields every kind of node carries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Attributes {
+    /// Permission bits that apply, with defaults filled in for entries stored without a mode.
+    pub mode: u32,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub xattrs: BTreeMap<String, String>,
+    pub immutable: bool,
+    /// Set with `set_hidden`; see `Node::is_hidden` for the check listings use.
+    pub hidden: bool,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+    pub accessed_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
+
+impl FileContent {
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            FileContent::Text(text) => text.as_bytes(),
+            FileContent::Binary(bytes) => bytes,
+        }
+    }
+
+    /// The content as text, if it is valid UTF-8.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            FileContent::Text(text) => Some(text),
+            FileContent::Binary(bytes) => std::str::from_utf8(bytes).ok(),
+        }
+    }
+}
+
+impl Node {
+    pub fn path(&self) -> &str {
+        match self {
+            Node::File(f) => &f.path,
+            Node::Dir(d) => &d.path,
+            Node::Symlink(l) => &l.path,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Node::File(f) => &f.name,
+            Node::Dir(d) => &d.name,
+            Node::Symlink(l) => &l.name,
+        }
+    }
+
+    pub fn parent(&self) -> Option<&str> {
+        match self {
+            Node::File(f) => f.parent.as_deref(),
+            Node::Dir(d) => d.parent.as_deref(),
+            Node::Symlink(l) => l.parent.as_deref(),
+        }
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        match self {
+            Node::File(f) => &f.attrs,
+            Node::Dir(d) => &d.attrs,
+            Node::Symlink(l) => &l.attrs,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Node::Dir(_))
+    }
+
+    pub fn is_file(&self) -> bool {
+        matches!(self, Node::File(_))
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        matches!(self, Node::Symlink(_))
+    }
+
+    /// Whether listings should leave this out by default: it is a dotfile or was flagged with
+    /// `set_hidden`.
+    pub fn is_hidden(&self) -> bool {
+        self.attrs().hidden || self.name().starts_with('.')
+    }
+
+    /// Content length in bytes; 0 for directories and symlinks.
+    pub fn size(&self) -> usize {
+        match self {
+            Node::File(f) => f.size,
+            _ => 0,
+        }
+    }
+
+    /// Type character and permission bits as `ls -l` shows them, e.g. `-rw-r--r--`.
+    pub fn mode_string(&self) -> String {
+        format_mode(self.is_dir(), self.is_symlink(), self.attrs().mode)
+    }
+}
+
+impl From<Entry> for Node {
+    fn from(entry: Entry) -> Self {
+        let size = entry.size();
+        let attrs = Attributes {
+            mode: mode::effective(entry.is_dir, entry.symlink.is_some(), entry.mode),
+            owner: entry.owner,
+            group: entry.group,
+            xattrs: entry.xattrs,
+            immutable: entry.immutable,
+            hidden: entry.hidden,
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            accessed_at: entry.accessed_at,
+            expires_at: entry.expires_at,
+        };
+        if let Some(target) = entry.symlink {
+            return Node::Symlink(SymlinkEntry {
+                path: entry.path,
+                name: entry.name,
+                parent: entry.parent,
+                target,
+                attrs,
+            });
+        }
+        if entry.is_dir {
+            return Node::Dir(DirEntry {
+                path: entry.path,
+                name: entry.name,
+                parent: entry.parent,
+                attrs,
+            });
+        }
+        let content = match (entry.content, entry.content_bytes) {
+            _ if entry.content_handle.is_pending() => None,
+            (Some(text), _) => Some(FileContent::Text(text)),
+            (None, Some(bytes)) => Some(FileContent::Binary(bytes.into_vec())),
+            (None, None) => Some(FileContent::Text(String::new())),
+        };
+        Node::File(FileEntry {
+            path: entry.path,
+            name: entry.name,
+            parent: entry.parent,
+            size,
+            content,
+            content_type: entry.content_type,
+            encoding: entry.encoding,
+            checksum: entry.checksum,
+            inode: entry.inode,
+            attrs,
+        })
+    }
+}
+
+impl From<Metadata> for Node {
+    fn from(meta: Metadata) -> Self {
+        let attrs = Attributes {
+            mode: mode::effective(meta.is_dir, meta.symlink.is_some(), meta.mode),
+            owner: meta.owner,
+            group: meta.group,
+            xattrs: meta.xattrs,
+            immutable: meta.immutable,
+            hidden: meta.hidden,
+            created_at: meta.created_at,
+            updated_at: meta.updated_at,
+            accessed_at: meta.accessed_at,
+            expires_at: meta.expires_at,
+        };
+        if let Some(target) = meta.symlink {
+            return Node::Symlink(SymlinkEntry {
+                path: meta.path,
+                name: meta.name,
+                parent: meta.parent,
+                target,
+                attrs,
+            });
+        }
+        if meta.is_dir {
+            return Node::Dir(DirEntry {
+                path: meta.path,
+                name: meta.name,
+                parent: meta.parent,
+                attrs,
+            });
+        }
+        Node::File(FileEntry {
+            path: meta.path,
+            name: meta.name,
+            parent: meta.parent,
+            size: meta.size,
+            content: None,
+            content_type: meta.content_type,
+            encoding: meta.encoding,
+            checksum: meta.checksum,
+            inode: meta.inode,
+            attrs,
+        })
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// The node at `path`, following a trailing symlink. Fails with `NotFound` when nothing is
+    /// there or the link dangles.
+    pub async fn stat(&self, path: impl AsRef<str>) -> Result<Node> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Ok(Node::Dir(DirEntry {
+                path: path.clone(),
+                name: path,
+                parent: None,
+                attrs: Attributes {
+                    mode: mode::DEFAULT_DIR_MODE,
+                    ..Attributes::default()
+                },
+            }));
+        }
+        let (path, entry) = self.follow(path).await?;
+        entry.map(Node::from).ok_or(FsError::NotFound(path))
+    }
+}
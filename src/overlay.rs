@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+
+use surrealdb::Connection;
+use surrealdb::engine::remote::ws::Client;
+
+use crate::symlink::MAX_SYMLINK_HOPS;
+use crate::{
+    FsError, Metadata, Node, Result, SurrealFs, normalize_path, parent_path, resolve_relative,
+};
+
+/// Extended attribute marking an upper-layer file as a whiteout: the lower entry at its
+/// path has been removed.
+pub const WHITEOUT_XATTR: &str = "trusted.overlay.whiteout";
+/// Extended attribute marking an upper-layer directory as opaque: nothing beneath it shows
+/// through from the lower layer.
+pub const OPAQUE_XATTR: &str = "trusted.overlay.opaque";
+
+/// A writable `upper` filesystem stacked over a read-only `lower` one, as with overlayfs:
+/// reads see the upper entry where there is one and the lower one otherwise, writes land in
+/// the upper layer (files are copied up first), and removing a lower entry leaves a
+/// whiteout in the upper layer. The lower layer is never written, so one base tree can back
+/// many sandboxes, each with its own upper table.
+pub struct Overlay<DB = Client>
+where
+    DB: Connection,
+{
+    lower: SurrealFs<DB>,
+    upper: SurrealFs<DB>,
+}
+
+/// Where a path is served from.
+enum Layer {
+    Upper(Metadata),
+    Lower(Metadata),
+    Absent,
+}
+
+impl<DB> Overlay<DB>
+where
+    DB: Connection,
+{
+    pub fn new(lower: SurrealFs<DB>, upper: SurrealFs<DB>) -> Self {
+        Self { lower, upper }
+    }
+
+    pub fn lower(&self) -> &SurrealFs<DB> {
+        &self.lower
+    }
+
+    pub fn upper(&self) -> &SurrealFs<DB> {
+        &self.upper
+    }
+
+    pub async fn metadata(&self, path: impl AsRef<str>) -> Result<Option<Metadata>> {
+        match self.layer(&normalize_path(path.as_ref())?).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) => Ok(Some(meta)),
+            Layer::Absent => Ok(None),
+        }
+    }
+
+    pub async fn exists(&self, path: impl AsRef<str>) -> Result<bool> {
+        Ok(self.metadata(path).await?.is_some())
+    }
+
+    /// The merged children of a directory in name order: upper entries shadow lower ones
+    /// of the same name, and whited-out lower entries are left out.
+    pub async fn ls(&self, path: impl AsRef<str>) -> Result<Vec<Node>> {
+        let path = normalize_path(path.as_ref())?;
+        let (upper_dir, lower_visible) = match self.layer(&path).await? {
+            Layer::Upper(meta) if meta.is_dir => (
+                true,
+                lower_visible_below(&meta) && self.lower_visible(&path).await?,
+            ),
+            Layer::Lower(meta) if meta.is_dir => (false, true),
+            Layer::Upper(meta) | Layer::Lower(meta) => return Ok(vec![Node::from(meta)]),
+            Layer::Absent => return Err(FsError::NotFound(path)),
+        };
+
+        let mut merged: BTreeMap<String, Option<Node>> = BTreeMap::new();
+        if upper_dir {
+            for node in self.upper.ls(&path).await? {
+                let hidden = node.attrs().xattrs.contains_key(WHITEOUT_XATTR);
+                merged.insert(node.name().to_string(), (!hidden).then_some(node));
+            }
+        }
+        if lower_visible {
+            let lower = match self.lower.ls(&path).await {
+                Err(FsError::NotFound(_)) => Vec::new(),
+                listed => listed?,
+            };
+            for node in lower {
+                merged.entry(node.name().to_string()).or_insert(Some(node));
+            }
+        }
+        Ok(merged.into_values().flatten().collect())
+    }
+
+    pub async fn cat(&self, path: impl AsRef<str>) -> Result<String> {
+        let (path, layer) = self.follow(normalize_path(path.as_ref())?).await?;
+        match layer {
+            Layer::Upper(_) => self.upper.cat(&path).await,
+            Layer::Lower(_) => self.lower.cat(&path).await,
+            Layer::Absent => Err(FsError::NotFound(path)),
+        }
+    }
+
+    pub async fn cat_bytes(&self, path: impl AsRef<str>) -> Result<Vec<u8>> {
+        let (path, layer) = self.follow(normalize_path(path.as_ref())?).await?;
+        match layer {
+            Layer::Upper(_) => self.upper.cat_bytes(&path).await,
+            Layer::Lower(_) => self.lower.cat_bytes(&path).await,
+            Layer::Absent => Err(FsError::NotFound(path)),
+        }
+    }
+
+    pub async fn write_file(
+        &self,
+        path: impl AsRef<str>,
+        content: impl Into<String>,
+    ) -> Result<Metadata> {
+        let (path, _) = self.follow(normalize_path(path.as_ref())?).await?;
+        self.prepare_write(&path).await?;
+        self.upper.write_file(&path, content).await
+    }
+
+    pub async fn write_bytes(&self, path: impl AsRef<str>, data: Vec<u8>) -> Result<Metadata> {
+        let (path, _) = self.follow(normalize_path(path.as_ref())?).await?;
+        self.prepare_write(&path).await?;
+        self.upper.write_bytes(&path, data).await
+    }
+
+    pub async fn append_file(&self, path: impl AsRef<str>, content: impl AsRef<str>) -> Result<()> {
+        let (path, _) = self.follow(normalize_path(path.as_ref())?).await?;
+        self.prepare_write(&path).await?;
+        self.upper.append_file(&path, content).await
+    }
+
+    /// Create a directory in the upper layer. One made where a lower directory was removed
+    /// is opaque, so the removed contents stay hidden.
+    pub async fn mkdir(&self, path: impl AsRef<str>, parents: bool) -> Result<Metadata> {
+        let path = normalize_path(path.as_ref())?;
+        match self.layer(&path).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) if parents && meta.is_dir => return Ok(meta),
+            Layer::Upper(_) | Layer::Lower(_) => return Err(FsError::AlreadyExists(path)),
+            Layer::Absent => {}
+        }
+        let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
+        match self.layer(&parent).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) if meta.is_dir => {}
+            Layer::Upper(_) | Layer::Lower(_) => return Err(FsError::NotADirectory(parent)),
+            Layer::Absent if parents => {
+                Box::pin(self.mkdir(&parent, true)).await?;
+            }
+            Layer::Absent => return Err(FsError::NotFound(parent)),
+        }
+        self.copy_up(&parent).await?;
+        let whiteout = self.upper.symlink_metadata(&path).await?.is_some();
+        if whiteout {
+            self.upper.rm(&path).await?;
+        }
+        let meta = self.upper.mkdir(&path, false).await?;
+        if whiteout {
+            self.upper.setxattr(&path, OPAQUE_XATTR, "y").await?;
+        }
+        Ok(meta)
+    }
+
+    /// Remove a file: from the upper layer if it is there, and with a whiteout over the
+    /// lower layer's copy if that would otherwise show through.
+    pub async fn rm(&self, path: impl AsRef<str>) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        match self.layer(&path).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) if meta.is_dir => Err(FsError::NotAFile(path)),
+            Layer::Upper(_) => {
+                self.upper.rm(&path).await?;
+                self.whiteout_lower(&path).await
+            }
+            Layer::Lower(_) => self.whiteout_lower(&path).await,
+            Layer::Absent => Err(FsError::NotFound(path)),
+        }
+    }
+
+    /// Remove a directory, merged contents included when `recursive` is set.
+    pub async fn rmdir(&self, path: impl AsRef<str>, recursive: bool) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Err(FsError::InvalidPath);
+        }
+        match self.layer(&path).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) if meta.is_dir => {}
+            Layer::Upper(_) | Layer::Lower(_) => return Err(FsError::NotADirectory(path)),
+            Layer::Absent => return Err(FsError::NotFound(path)),
+        }
+        if !recursive && !self.ls(&path).await?.is_empty() {
+            return Err(FsError::DirectoryNotEmpty(path));
+        }
+        if self.upper.symlink_metadata(&path).await?.is_some() {
+            self.upper.rmdir(&path, true).await?;
+        }
+        self.whiteout_lower(&path).await
+    }
+
+    /// Give `path` a copy in the upper layer, along with its parent directories, so it can
+    /// be changed there. A symlink is copied as a link to the same target. A no-op for
+    /// paths already in the upper layer.
+    pub async fn copy_up(&self, path: impl AsRef<str>) -> Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        if path == "/" {
+            return Ok(());
+        }
+        let meta = match self.layer(&path).await? {
+            Layer::Upper(_) => return Ok(()),
+            Layer::Lower(meta) => meta,
+            Layer::Absent => return Err(FsError::NotFound(path)),
+        };
+        if let Some(parent) = parent_path(&path) {
+            Box::pin(self.copy_up(&parent)).await?;
+        }
+        if meta.is_dir {
+            self.upper.mkdir(&path, false).await?;
+        } else if let Some(target) = &meta.symlink {
+            self.upper.symlink(target, &path).await?;
+        } else {
+            self.lower.cp_to(&path, &self.upper, &path).await?;
+        }
+        Ok(())
+    }
+
+    /// Make way for a write at `path`: copy up an existing file and the parent chain, and
+    /// clear a whiteout.
+    async fn prepare_write(&self, path: &str) -> Result<()> {
+        match self.layer(path).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) if meta.is_dir => {
+                return Err(FsError::NotAFile(path.to_string()));
+            }
+            Layer::Upper(_) => return Ok(()),
+            Layer::Lower(_) => return self.copy_up(path).await,
+            Layer::Absent => {}
+        }
+        let parent = parent_path(path).ok_or(FsError::InvalidPath)?;
+        match self.layer(&parent).await? {
+            Layer::Upper(meta) | Layer::Lower(meta) if meta.is_dir => {}
+            Layer::Upper(_) | Layer::Lower(_) => return Err(FsError::NotADirectory(parent)),
+            Layer::Absent => return Err(FsError::NotFound(parent)),
+        }
+        self.copy_up(&parent).await?;
+        if self.upper.symlink_metadata(path).await?.is_some() {
+            self.upper.rm(path).await?;
+        }
+        Ok(())
+    }
+
+    /// Hide the lower layer's entry at `path`, when it would show through.
+    async fn whiteout_lower(&self, path: &str) -> Result<()> {
+        if !self.lower_visible(path).await? || self.lower.symlink_metadata(path).await?.is_none() {
+            return Ok(());
+        }
+        if let Some(parent) = parent_path(path) {
+            self.copy_up(&parent).await?;
+        }
+        self.upper.write_file(path, "").await?;
+        self.upper.setxattr(path, WHITEOUT_XATTR, "y").await
+    }
+
+    /// `path` with a final symlink followed through the merged view, so a link copied up
+    /// from the lower layer still reaches a target that only exists there, along with the
+    /// layer serving the path it leads to.
+    async fn follow(&self, mut path: String) -> Result<(String, Layer)> {
+        for _ in 0..=MAX_SYMLINK_HOPS {
+            let layer = self.layer(&path).await?;
+            let target = match &layer {
+                Layer::Upper(meta) | Layer::Lower(meta) => meta.symlink.clone(),
+                Layer::Absent => None,
+            };
+            let Some(target) = target else {
+                return Ok((path, layer));
+            };
+            let dir = parent_path(&path).unwrap_or_else(|| "/".to_string());
+            path = resolve_relative(&dir, &target)?;
+        }
+        Err(FsError::SymlinkLoop(path))
+    }
+
+    /// Which layer serves `path`.
+    async fn layer(&self, path: &str) -> Result<Layer> {
+        if path == "/" {
+            return Ok(Layer::Upper(Metadata::root()));
+        }
+        if let Some(meta) = self.upper.symlink_metadata(path).await? {
+            if meta.xattrs.contains_key(WHITEOUT_XATTR) {
+                return Ok(Layer::Absent);
+            }
+            return Ok(Layer::Upper(meta));
+        }
+        if !self.lower_visible(path).await? {
+            return Ok(Layer::Absent);
+        }
+        Ok(match self.lower.symlink_metadata(path).await? {
+            Some(meta) => Layer::Lower(meta),
+            None => Layer::Absent,
+        })
+    }
+
+    /// Whether the lower layer shows through at `path`: no ancestor is whited out, opaque,
+    /// or replaced by a non-directory in the upper layer.
+    async fn lower_visible(&self, path: &str) -> Result<bool> {
+        let mut ancestor = parent_path(path);
+        while let Some(dir) = ancestor.filter(|d| d != "/") {
+            if let Some(meta) = self.upper.symlink_metadata(&dir).await?
+                && !lower_visible_below(&meta)
+            {
+                return Ok(false);
+            }
+            ancestor = parent_path(&dir);
+        }
+        Ok(true)
+    }
+}
+
+/// Whether lower entries beneath the upper entry `meta` show through.
+fn lower_visible_below(meta: &Metadata) -> bool {
+    meta.is_dir
+        && !meta.xattrs.contains_key(WHITEOUT_XATTR)
+        && !meta.xattrs.contains_key(OPAQUE_XATTR)
+}
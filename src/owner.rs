@@ -0,0 +1,68 @@
+use surrealdb::Connection;
+
+use crate::{FsError, Result, SurrealFs, normalize_path};
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Attribute `path` to `owner`, and to `group` as well when given, following a trailing
+    /// symlink. Principals are free-form names; hard links share ownership.
+    pub async fn chown(
+        &self,
+        path: impl AsRef<str>,
+        owner: impl Into<String>,
+        group: Option<&str>,
+    ) -> Result<()> {
+        let owner = owner.into();
+        if owner.is_empty() {
+            return Err(FsError::InvalidPath);
+        }
+        let mut assignments = vec![("owner", owner)];
+        if let Some(group) = group {
+            assignments.push(("group", group.to_string()));
+        }
+        self.set_principals(path.as_ref(), assignments).await
+    }
+
+    /// Change only the group of `path`, following a trailing symlink.
+    pub async fn chgrp(&self, path: impl AsRef<str>, group: impl Into<String>) -> Result<()> {
+        let group = group.into();
+        if group.is_empty() {
+            return Err(FsError::InvalidPath);
+        }
+        self.set_principals(path.as_ref(), vec![("group", group)])
+            .await
+    }
+
+    async fn set_principals(&self, path: &str, fields: Vec<(&str, String)>) -> Result<()> {
+        let path = normalize_path(path)?;
+        self.ensure_writable(&path)?;
+        let (path, entry) = self.follow(path).await?;
+        let entry = entry.ok_or_else(|| FsError::NotFound(path.clone()))?;
+
+        let sets: Vec<String> = fields
+            .iter()
+            .map(|(field, _)| format!("{field} = ${field}"))
+            .collect();
+        let _permit = self.permit().await;
+        self.forget(&[&path]);
+        self.run(|| {
+            let mut query = self
+                .db
+                .query(format!(
+                    "UPDATE {} SET {} WHERE path = $path OR (inode != NONE AND inode = $inode)",
+                    self.table,
+                    sets.join(", ")
+                ))
+                .bind(("path", path.clone()))
+                .bind(("inode", self.inode_record(&entry.inode)));
+            for (field, value) in &fields {
+                query = query.bind((field.to_string(), value.clone()));
+            }
+            query
+        })
+        .await?;
+        Ok(())
+    }
+}
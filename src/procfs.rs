@@ -0,0 +1,194 @@
+use std::fmt::Write as FmtWrite;
+
+use surrealdb::Connection;
+
+use crate::inode::UPDATED_AT_EXPR;
+use crate::{Entry, Metadata, Result, SurrealFs, leaf_name, now_millis, parent_path};
+
+pub(crate) const PROC_ROOT: &str = "/proc";
+
+const CHANGES_DIR: &str = "/proc/changes";
+const CHANGES_TAIL_LIMIT: usize = 20;
+
+const PROC_FILES: &[&str] = &[
+    "/proc/stats",
+    "/proc/connections",
+    "/proc/quota",
+    "/proc/changes/tail",
+];
+
+const PROC_DIRS: &[&str] = &[PROC_ROOT, CHANGES_DIR];
+
+pub(crate) fn is_proc_path(path: &str) -> bool {
+    path == PROC_ROOT || path.starts_with("/proc/")
+}
+
+/// Put `/proc` into a listing of `/` in name order, in place of anything stored there, when
+/// it falls after `cursor`.
+pub(crate) fn list_in_root(listed: &mut Vec<Metadata>, cursor: Option<&str>) {
+    listed.retain(|meta| meta.path != PROC_ROOT);
+    let name = leaf_name(PROC_ROOT);
+    if cursor.is_some_and(|after| name.as_str() <= after) {
+        return;
+    }
+    let at = listed.partition_point(|meta| meta.name < name);
+    listed.insert(at, Metadata::from(&proc_node(PROC_ROOT, None)));
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Build the synthetic entry for a `/proc` path, rendering file contents on demand.
+    pub(crate) async fn proc_entry(&self, path: &str) -> Result<Option<Entry>> {
+        if PROC_DIRS.contains(&path) {
+            return Ok(Some(proc_node(path, None)));
+        }
+
+        let content = match path {
+            "/proc/stats" => self.proc_stats().await?,
+            "/proc/connections" => self.proc_connections().await?,
+            "/proc/quota" => self.proc_quota().await?,
+            "/proc/changes/tail" => self.proc_changes_tail().await?,
+            _ => return Ok(None),
+        };
+        Ok(Some(proc_node(path, Some(content))))
+    }
+
+    pub(crate) fn proc_children(&self, path: &str) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = PROC_DIRS
+            .iter()
+            .chain(PROC_FILES.iter())
+            .filter(|p| parent_path(p).as_deref() == Some(path))
+            .map(|p| {
+                if PROC_DIRS.contains(p) {
+                    proc_node(p, None)
+                } else {
+                    proc_node(p, Some(String::new()))
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    async fn proc_stats(&self) -> Result<String> {
+        let usage = self.usage().await?;
+
+        let mut out = String::new();
+        let _ = writeln!(&mut out, "entries: {}", usage.entries);
+        let _ = writeln!(&mut out, "files: {}", usage.files);
+        let _ = writeln!(&mut out, "directories: {}", usage.directories);
+        let _ = writeln!(&mut out, "bytes: {}", usage.bytes);
+        Ok(out)
+    }
+
+    async fn proc_connections(&self) -> Result<String> {
+        let status = self.status().await?;
+
+        let mut out = String::new();
+        let _ = writeln!(
+            &mut out,
+            "namespace: {}",
+            status.namespace.unwrap_or_default()
+        );
+        let _ = writeln!(
+            &mut out,
+            "database: {}",
+            status.database.unwrap_or_default()
+        );
+        let _ = writeln!(&mut out, "table: {}", status.table);
+        let _ = writeln!(&mut out, "engine: {}", status.engine);
+        if let Some(endpoint) = &status.endpoint {
+            let _ = writeln!(&mut out, "endpoint: {}", endpoint);
+        }
+        let _ = writeln!(&mut out, "server: {}", status.server_version);
+        let _ = writeln!(&mut out, "latency_us: {}", status.latency.as_micros());
+        match status.schema_version {
+            Some(version) => {
+                let _ = writeln!(&mut out, "schema: {}", version);
+            }
+            None => {
+                let _ = writeln!(&mut out, "schema: undefined");
+            }
+        }
+        Ok(out)
+    }
+
+    async fn proc_quota(&self) -> Result<String> {
+        let usage = self.usage().await?;
+
+        let mut out = String::new();
+        match (usage.quota_bytes, usage.remaining_bytes) {
+            (Some(limit), Some(remaining)) => {
+                let _ = writeln!(&mut out, "limit: {}", limit);
+                let _ = writeln!(&mut out, "used: {}", usage.bytes);
+                let _ = writeln!(&mut out, "remaining: {}", remaining);
+            }
+            _ => {
+                let _ = writeln!(&mut out, "limit: none");
+                let _ = writeln!(&mut out, "used: {}", usage.bytes);
+            }
+        }
+        Ok(out)
+    }
+
+    async fn proc_changes_tail(&self) -> Result<String> {
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT path, name, parent, is_dir, {} AS updated_at FROM {} ORDER BY updated_at DESC LIMIT $limit",
+                        UPDATED_AT_EXPR, self.table
+                    ))
+                    .bind(("limit", CHANGES_TAIL_LIMIT))
+            })
+            .await?;
+        let entries: Vec<Entry> = res.take(0)?;
+
+        let mut out = String::new();
+        for e in entries {
+            let suffix = if e.is_dir { "/" } else { "" };
+            let _ = writeln!(
+                &mut out,
+                "{} {}{}",
+                e.updated_at.unwrap_or(0),
+                e.path,
+                suffix
+            );
+        }
+        Ok(out)
+    }
+}
+
+/// Files are rendered as they are read, so they are stamped now; directories, like `/`,
+/// carry no timestamps.
+fn proc_node(path: &str, content: Option<String>) -> Entry {
+    Entry {
+        path: path.to_string(),
+        name: leaf_name(path),
+        parent: parent_path(path),
+        is_dir: content.is_none(),
+        updated_at: content.as_ref().map(|_| now_millis()),
+        content,
+        content_bytes: None,
+        content_type: None,
+        encoding: None,
+        compression: None,
+        checksum: None,
+        mode: None,
+        owner: None,
+        group: None,
+        xattrs: Default::default(),
+        immutable: false,
+        hidden: false,
+        created_at: None,
+        accessed_at: None,
+        expires_at: None,
+        version: 0,
+        inode: None,
+        symlink: None,
+        content_handle: Default::default(),
+    }
+}
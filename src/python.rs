@@ -2,20 +2,22 @@
 
 use std::fmt::Write as FmtWrite;
 use std::sync::Mutex;
+use std::time::Duration;
 
+use futures::TryStreamExt;
 use pyo3::create_exception;
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 use pyo3::types::{PyBytes, PyType};
-use regex::Regex;
-use surrealdb::Surreal;
-use surrealdb::engine::any::connect;
-use surrealdb::engine::local::{Db, Mem};
+use surrealdb::engine::any::{Any, connect};
 use surrealdb::opt::auth::Root;
 use tokio::runtime::Runtime;
 
 use crate::curl::{self, CurlOutput, CurlRequest};
-use crate::{Entry, FsError, SurrealFs};
+use crate::{
+    CopyOptions, EditOp, Entry, EntryKind, FindOptions, FsError, GlobOptions, GrepOptions, Node,
+    SortOptions, SurrealFs,
+};
 
 create_exception!(surrealfs_py, SurrealFsError, pyo3::exceptions::PyException);
 
@@ -28,148 +30,11 @@ struct LsOptions {
     human: bool,
 }
 
-enum FsInner {
-    Remote(SurrealFs<surrealdb::engine::any::Any>),
-    Local(SurrealFs<Db>),
-}
-
-impl FsInner {
-    async fn ls(&self, path: &str) -> crate::Result<Vec<Entry>> {
-        match self {
-            FsInner::Remote(fs) => fs.ls(path).await,
-            FsInner::Local(fs) => fs.ls(path).await,
-        }
-    }
-
-    async fn cat(&self, path: &str) -> crate::Result<String> {
-        match self {
-            FsInner::Remote(fs) => fs.cat(path).await,
-            FsInner::Local(fs) => fs.cat(path).await,
-        }
-    }
-
-    async fn tail(&self, path: &str, n: usize) -> crate::Result<Vec<String>> {
-        match self {
-            FsInner::Remote(fs) => fs.tail(path, n).await,
-            FsInner::Local(fs) => fs.tail(path, n).await,
-        }
-    }
-
-    async fn read(&self, path: &str, offset: usize, limit: usize) -> crate::Result<Vec<String>> {
-        match self {
-            FsInner::Remote(fs) => fs.read(path, offset, limit).await,
-            FsInner::Local(fs) => fs.read(path, offset, limit).await,
-        }
-    }
-
-    async fn nl(&self, path: &str, start_at: usize) -> crate::Result<Vec<crate::NumberedLine>> {
-        match self {
-            FsInner::Remote(fs) => fs.nl(path, start_at).await,
-            FsInner::Local(fs) => fs.nl(path, start_at).await,
-        }
-    }
-
-    async fn grep(
-        &self,
-        pattern: &Regex,
-        path: &str,
-        recursive: bool,
-    ) -> crate::Result<Vec<crate::GrepMatch>> {
-        match self {
-            FsInner::Remote(fs) => fs.grep(pattern, path, recursive).await,
-            FsInner::Local(fs) => fs.grep(pattern, path, recursive).await,
-        }
-    }
-
-    async fn touch(&self, path: &str) -> crate::Result<()> {
-        match self {
-            FsInner::Remote(fs) => fs.touch(path).await,
-            FsInner::Local(fs) => fs.touch(path).await,
-        }
-    }
-
-    async fn write_file(&self, path: &str, content: String) -> crate::Result<()> {
-        match self {
-            FsInner::Remote(fs) => fs.write_file(path, content).await,
-            FsInner::Local(fs) => fs.write_file(path, content).await,
-        }
-    }
-
-    async fn write_bytes(&self, path: &str, data: Vec<u8>) -> crate::Result<()> {
-        match self {
-            FsInner::Remote(fs) => fs.write_bytes(path, data).await,
-            FsInner::Local(fs) => fs.write_bytes(path, data).await,
-        }
-    }
-
-    async fn edit(
-        &self,
-        path: &str,
-        old: &str,
-        new: &str,
-        replace_all: bool,
-    ) -> crate::Result<String> {
-        match self {
-            FsInner::Remote(fs) => fs.edit(path, old, new, replace_all).await,
-            FsInner::Local(fs) => fs.edit(path, old, new, replace_all).await,
-        }
-    }
-
-    async fn mkdir(&self, path: &str, parents: bool) -> crate::Result<()> {
-        match self {
-            FsInner::Remote(fs) => fs.mkdir(path, parents).await,
-            FsInner::Local(fs) => fs.mkdir(path, parents).await,
-        }
-    }
-
-    async fn cp(&self, src: &str, dest: &str) -> crate::Result<()> {
-        match self {
-            FsInner::Remote(fs) => fs.cp(src, dest).await,
-            FsInner::Local(fs) => fs.cp(src, dest).await,
-        }
-    }
-
-    async fn glob(&self, pattern: &str) -> crate::Result<Vec<String>> {
-        match self {
-            FsInner::Remote(fs) => fs.glob(pattern).await,
-            FsInner::Local(fs) => fs.glob(pattern).await,
-        }
-    }
-
-    async fn cd(&self, current: &str, target: &str) -> crate::Result<String> {
-        match self {
-            FsInner::Remote(fs) => fs.cd(current, target).await,
-            FsInner::Local(fs) => fs.cd(current, target).await,
-        }
-    }
-
-    fn pwd(&self, current: &str) -> crate::Result<String> {
-        match self {
-            FsInner::Remote(fs) => fs.pwd(current),
-            FsInner::Local(fs) => fs.pwd(current),
-        }
-    }
-
-    async fn curl(&self, request: CurlRequest) -> crate::Result<curl::CurlResult> {
-        match self {
-            FsInner::Remote(fs) => curl::curl(fs, request.clone()).await,
-            FsInner::Local(fs) => curl::curl(fs, request).await,
-        }
-    }
-
-    async fn cat_bytes(&self, path: &str) -> crate::Result<Vec<u8>> {
-        match self {
-            FsInner::Remote(fs) => fs.cat_bytes(path).await,
-            FsInner::Local(fs) => fs.cat_bytes(path).await,
-        }
-    }
-}
-
 #[pyclass(module = "surrealfs_py")]
 pub struct PySurrealFs {
     rt: Runtime,
     cwd: Mutex<String>,
-    fs: FsInner,
+    fs: SurrealFs<Any>,
 }
 
 #[pymethods]
@@ -180,6 +45,7 @@ impl PySurrealFs {
         url: &str,
         namespace: Option<&str>,
         database: Option<&str>,
+        trash: Option<bool>,
     ) -> PyResult<Self> {
         let ns = namespace.unwrap_or("surrealfs");
         let db_name = database.unwrap_or("demo");
@@ -194,35 +60,64 @@ impl PySurrealFs {
                 })
                 .await?;
                 db.use_ns(ns).use_db(db_name).await?;
-                Ok::<_, FsError>(SurrealFs::new(db))
+                Ok::<_, FsError>(SurrealFs::builder(db).trash(trash.unwrap_or(false)).build())
             })
             .map_err(to_py_err)?;
 
         Ok(Self {
             rt,
             cwd: Mutex::new("/".to_string()),
-            fs: FsInner::Remote(fs),
+            fs,
         })
     }
 
     #[classmethod]
-    pub fn mem(_cls: &PyType, namespace: Option<&str>, database: Option<&str>) -> PyResult<Self> {
+    pub fn mem(
+        _cls: &PyType,
+        namespace: Option<&str>,
+        database: Option<&str>,
+        trash: Option<bool>,
+    ) -> PyResult<Self> {
         let ns = namespace.unwrap_or("surrealfs");
         let db_name = database.unwrap_or("demo");
 
         let rt = Runtime::new().map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         let fs = rt
-            .block_on(async move {
-                let db = Surreal::new::<Mem>(()).await?;
-                db.use_ns(ns).use_db(db_name).await?;
-                Ok::<_, FsError>(SurrealFs::new(db))
-            })
-            .map_err(to_py_err)?;
+            .block_on(SurrealFs::open_builder("memory", ns, db_name))
+            .map_err(to_py_err)?
+            .trash(trash.unwrap_or(false))
+            .build();
+
+        Ok(Self {
+            rt,
+            cwd: Mutex::new("/".to_string()),
+            fs,
+        })
+    }
+
+    /// Open a filesystem on any engine by endpoint, e.g. `memory` or `rocksdb:./data`.
+    #[classmethod]
+    pub fn open(
+        _cls: &PyType,
+        endpoint: &str,
+        namespace: Option<&str>,
+        database: Option<&str>,
+        trash: Option<bool>,
+    ) -> PyResult<Self> {
+        let ns = namespace.unwrap_or(crate::DEFAULT_NAMESPACE);
+        let db_name = database.unwrap_or(crate::DEFAULT_DATABASE);
+
+        let rt = Runtime::new().map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let fs = rt
+            .block_on(SurrealFs::open_builder(endpoint, ns, db_name))
+            .map_err(to_py_err)?
+            .trash(trash.unwrap_or(false))
+            .build();
 
         Ok(Self {
             rt,
             cwd: Mutex::new("/".to_string()),
-            fs: FsInner::Local(fs),
+            fs,
         })
     }
 
@@ -296,21 +191,261 @@ impl PySurrealFs {
         Ok(out)
     }
 
-    pub fn grep(&self, pattern: &str, path: &str, recursive: Option<bool>) -> PyResult<String> {
+    pub fn wc(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let counts = self.rt.block_on(self.fs.wc(&resolved)).map_err(to_py_err)?;
+        Ok(format!(
+            "{} {} {} {}\n",
+            counts.lines, counts.words, counts.bytes, resolved
+        ))
+    }
+
+    /// Matching lines as `path:line: text`; with `count`, `path:n` per file that matched,
+    /// and with `files_only` just the paths, like `grep -c` and `grep -l`. `max_matches` and
+    /// `max_files` stop the search early, and `multiline` lets the pattern span lines,
+    /// reported as `path:first-last: text`. A binary file that matches is reported as
+    /// `grep: path: binary file matches` rather than by line.
+    #[allow(clippy::too_many_arguments)]
+    pub fn grep(
+        &self,
+        pattern: &str,
+        path: &str,
+        recursive: Option<bool>,
+        ignore_case: Option<bool>,
+        word: Option<bool>,
+        invert: Option<bool>,
+        fixed_strings: Option<bool>,
+        count: Option<bool>,
+        files_only: Option<bool>,
+        max_matches: Option<usize>,
+        max_files: Option<usize>,
+        multiline: Option<bool>,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let opts = GrepOptions {
+            pattern: pattern.to_string(),
+            recursive: recursive.unwrap_or(false),
+            ignore_case: ignore_case.unwrap_or(false),
+            word: word.unwrap_or(false),
+            invert: invert.unwrap_or(false),
+            fixed_strings: fixed_strings.unwrap_or(false),
+            max_matches,
+            max_files,
+            multiline: multiline.unwrap_or(false),
+        };
+        let mut out = String::new();
+        if files_only.unwrap_or(false) {
+            let paths = self
+                .rt
+                .block_on(self.fs.grep_files(&resolved, &opts))
+                .map_err(to_py_err)?;
+            for p in paths {
+                let _ = writeln!(&mut out, "{}", p);
+            }
+        } else if count.unwrap_or(false) {
+            let counts = self
+                .rt
+                .block_on(self.fs.grep_count(&resolved, &opts))
+                .map_err(to_py_err)?;
+            for c in counts {
+                let _ = writeln!(&mut out, "{}:{}", c.path, c.count);
+            }
+        } else {
+            let matches = self
+                .rt
+                .block_on(self.fs.grep_with(&resolved, &opts))
+                .map_err(to_py_err)?;
+            for m in matches {
+                if m.binary {
+                    let _ = writeln!(&mut out, "grep: {}: binary file matches", m.path);
+                } else if m.end_line == m.line_number {
+                    let _ = writeln!(&mut out, "{}:{}: {}", m.path, m.line_number, m.line);
+                } else {
+                    let _ = writeln!(
+                        &mut out,
+                        "{}:{}-{}: {}",
+                        m.path, m.line_number, m.end_line, m.line
+                    );
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Ranked full-text search, one `path: snippet` line per file with matched terms in
+    /// brackets.
+    pub fn search(&self, query: &str, path: &str) -> PyResult<String> {
         let resolved = self.resolve_path(path)?;
-        let recursive = recursive.unwrap_or(false);
-        let re = Regex::new(pattern).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-        let matches = self
+        let hits = self
             .rt
-            .block_on(self.fs.grep(&re, &resolved, recursive))
+            .block_on(self.fs.search(query, &resolved))
             .map_err(to_py_err)?;
         let mut out = String::new();
-        for m in matches {
-            let _ = writeln!(&mut out, "{}:{}: {}", m.path, m.line_number, m.line);
+        for hit in hits {
+            let _ = writeln!(&mut out, "{}: {}", hit.path, hit.highlighted("[", "]"));
         }
         Ok(out)
     }
 
+    /// Set permission bits from an octal string such as `"644"` or `"0755"`.
+    pub fn chmod(&self, path: &str, mode: &str) -> PyResult<String> {
+        let mode = u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+            .map_err(|_| SurrealFsError::new_err(format!("invalid mode: {}", mode)))?;
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.chmod(&resolved, mode))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn set_immutable(&self, path: &str, immutable: bool) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.set_immutable(&resolved, immutable))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    /// A file(1)-style description of `path`, e.g. `"PNG image data, 640 x 480"`.
+    pub fn file_type(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.file_type(&resolved))
+            .map_err(to_py_err)
+    }
+
+    pub fn set_hidden(&self, path: &str, hidden: bool) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.set_hidden(&resolved, hidden))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    /// Set the owner and, when given, the group of `path`.
+    pub fn chown(&self, path: &str, owner: &str, group: Option<&str>) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.chown(&resolved, owner, group))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn chgrp(&self, path: &str, group: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.chgrp(&resolved, group))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn setxattr(&self, path: &str, name: &str, value: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.setxattr(&resolved, name, value))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn getxattr(&self, path: &str, name: &str) -> PyResult<Option<String>> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.getxattr(&resolved, name))
+            .map_err(to_py_err)
+    }
+
+    pub fn listxattr(&self, path: &str) -> PyResult<Vec<String>> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.listxattr(&resolved))
+            .map_err(to_py_err)
+    }
+
+    pub fn removexattr(&self, path: &str, name: &str) -> PyResult<bool> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.removexattr(&resolved, name))
+            .map_err(to_py_err)
+    }
+
+    /// Filesystem totals (entries, bytes, per top-level directory, table name) as JSON.
+    pub fn df(&self) -> PyResult<String> {
+        let usage = self.rt.block_on(self.fs.df()).map_err(to_py_err)?;
+        serde_json::to_string(&usage).map_err(|e| SurrealFsError::new_err(e.to_string()))
+    }
+
+    /// Files and bytes per extension beneath `path`, with the largest files of each, as JSON.
+    pub fn stats(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let stats = self
+            .rt
+            .block_on(self.fs.stats(&resolved))
+            .map_err(to_py_err)?;
+        serde_json::to_string(&stats).map_err(|e| SurrealFsError::new_err(e.to_string()))
+    }
+
+    /// Keep up to `keep` previous revisions of every file on each write, or all of them
+    /// when `keep` is not given.
+    pub fn enable_history(&self, keep: Option<usize>) -> PyResult<()> {
+        self.rt
+            .block_on(self.fs.enable_history(keep))
+            .map_err(to_py_err)
+    }
+
+    /// The kept revisions of `path`, newest first, as JSON.
+    pub fn history(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let revisions = self
+            .rt
+            .block_on(self.fs.history(&resolved))
+            .map_err(to_py_err)?;
+        serde_json::to_string(&revisions).map_err(|e| SurrealFsError::new_err(e.to_string()))
+    }
+
+    /// The text of the revision of `path` that was current at `version`.
+    pub fn cat_revision(&self, path: &str, version: u64) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.cat_revision(&resolved, version))
+            .map_err(to_py_err)
+    }
+
+    /// Re-hash `path` and return the verification report as JSON.
+    pub fn verify(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let report = self
+            .rt
+            .block_on(self.fs.verify(&resolved))
+            .map_err(to_py_err)?;
+        serde_json::to_string(&report).map_err(|e| SurrealFsError::new_err(e.to_string()))
+    }
+
+    /// Check the table for inconsistencies, repairing them with `repair`; one line per
+    /// problem, or `clean`.
+    pub fn fsck(&self, repair: Option<bool>) -> PyResult<String> {
+        self.rt
+            .block_on(self.fs.fsck(repair.unwrap_or(false)))
+            .map(|report| report.render())
+            .map_err(to_py_err)
+    }
+
+    /// The tree at `path` as a portable JSON Lines dump; see `SurrealFs::export`.
+    pub fn export(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.export(&resolved))
+            .map_err(to_py_err)
+    }
+
+    /// Restore a dump made by `export` under `dest`, or where it came from, returning how
+    /// many entries it held. Named so because `import` is a Python keyword.
+    pub fn import_dump(&self, dump: &str, dest: Option<&str>) -> PyResult<usize> {
+        let dest = dest.map(|d| self.resolve_path(d)).transpose()?;
+        self.rt
+            .block_on(self.fs.import(dump, dest.as_deref()))
+            .map_err(to_py_err)
+    }
+
     pub fn touch(&self, path: &str) -> PyResult<String> {
         let resolved = self.resolve_path(path)?;
         self.rt
@@ -327,6 +462,84 @@ impl PySurrealFs {
         Ok(String::new())
     }
 
+    /// Write only if the file is still at `expected_version` (0 for a new file) and return the
+    /// new version; raises on conflict.
+    pub fn write_file_if(&self, path: &str, content: &str, expected_version: u64) -> PyResult<u64> {
+        let resolved = self.resolve_path(path)?;
+        let meta = self
+            .rt
+            .block_on(
+                self.fs
+                    .write_file_if(&resolved, content.to_string(), expected_version),
+            )
+            .map_err(to_py_err)?;
+        Ok(meta.version)
+    }
+
+    /// Write through a temp entry renamed over `path`, so readers never see a partial file.
+    pub fn write_file_atomic(&self, path: &str, content: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.write_file_atomic(&resolved, content.to_string()))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    /// Cut `path` into `path.part0001`, … of at most `lines` lines or `bytes` bytes each.
+    pub fn split(
+        &self,
+        path: &str,
+        lines: Option<usize>,
+        bytes: Option<usize>,
+    ) -> PyResult<Vec<String>> {
+        let by = match (lines, bytes) {
+            (Some(n), None) => crate::SplitBy::Lines(n),
+            (None, Some(n)) => crate::SplitBy::Bytes(n),
+            _ => {
+                return Err(SurrealFsError::new_err(
+                    "split takes exactly one of lines or bytes",
+                ));
+            }
+        };
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.split(&resolved, by))
+            .map_err(to_py_err)
+    }
+
+    /// Reassemble `path` from the parts left by `split`.
+    pub fn join(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.join(&resolved))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    /// Write a file that `expire` removes once `ttl_secs` seconds have passed.
+    pub fn write_file_with_ttl(
+        &self,
+        path: &str,
+        content: &str,
+        ttl_secs: f64,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let ttl = Duration::try_from_secs_f64(ttl_secs)
+            .map_err(|e| SurrealFsError::new_err(e.to_string()))?;
+        self.rt
+            .block_on(
+                self.fs
+                    .write_file_with_ttl(&resolved, content.to_string(), ttl),
+            )
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    /// Remove files whose TTL has run out, returning their paths.
+    pub fn expire(&self) -> PyResult<Vec<String>> {
+        self.rt.block_on(self.fs.expire()).map_err(to_py_err)
+    }
+
     pub fn write_bytes(&self, path: &str, data: &[u8]) -> PyResult<String> {
         let resolved = self.resolve_path(path)?;
         self.rt
@@ -341,16 +554,110 @@ impl PySurrealFs {
         old: &str,
         new: &str,
         replace_all: Option<bool>,
+        preview: Option<bool>,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.edit(
+                &resolved,
+                old,
+                new,
+                replace_all.unwrap_or(false),
+                preview.unwrap_or(false),
+            ))
+            .map_err(to_py_err)
+    }
+
+    /// Replace matches of `pattern` under `path`, like `sed -i`, returning each changed
+    /// file's diff; nothing is written with `dry_run`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn replace_in(
+        &self,
+        pattern: &str,
+        replacement: &str,
+        path: &str,
+        recursive: Option<bool>,
+        ignore_case: Option<bool>,
+        word: Option<bool>,
+        fixed_strings: Option<bool>,
+        multiline: Option<bool>,
+        dry_run: Option<bool>,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let opts = GrepOptions {
+            pattern: pattern.to_string(),
+            recursive: recursive.unwrap_or(false),
+            ignore_case: ignore_case.unwrap_or(false),
+            word: word.unwrap_or(false),
+            fixed_strings: fixed_strings.unwrap_or(false),
+            multiline: multiline.unwrap_or(false),
+            ..GrepOptions::default()
+        };
+        let changed = self
+            .rt
+            .block_on(
+                self.fs
+                    .replace_in(&opts, replacement, &resolved, dry_run.unwrap_or(false)),
+            )
+            .map_err(to_py_err)?;
+        Ok(changed.into_iter().map(|file| file.diff).collect())
+    }
+
+    /// Apply `(old, new, replace_all)` edits in order and write once; nothing is written if
+    /// any edit fails to match or `preview` is set.
+    pub fn edit_many(
+        &self,
+        path: &str,
+        ops: Vec<(String, String, bool)>,
+        preview: Option<bool>,
     ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let ops: Vec<EditOp> = ops
+            .into_iter()
+            .map(|(old, new, replace_all)| EditOp {
+                old,
+                new,
+                replace_all,
+            })
+            .collect();
+        self.rt
+            .block_on(self.fs.edit_many(&resolved, &ops, preview.unwrap_or(false)))
+            .map_err(to_py_err)
+    }
+
+    /// Insert `lines` before 1-based line `at`; one past the last line appends.
+    pub fn insert_lines(&self, path: &str, at: usize, lines: Vec<String>) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.replace_lines(&resolved, at..at, lines))
+            .map_err(to_py_err)
+    }
+
+    /// Delete 1-based lines `start` through `end` inclusive.
+    pub fn delete_lines(&self, path: &str, start: usize, end: usize) -> PyResult<String> {
         let resolved = self.resolve_path(path)?;
         self.rt
             .block_on(
                 self.fs
-                    .edit(&resolved, old, new, replace_all.unwrap_or(false)),
+                    .replace_lines(&resolved, start..end + 1, Vec::<String>::new()),
             )
             .map_err(to_py_err)
     }
 
+    /// Replace 1-based lines `start` through `end` inclusive with `lines`.
+    pub fn replace_lines(
+        &self,
+        path: &str,
+        start: usize,
+        end: usize,
+        lines: Vec<String>,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.replace_lines(&resolved, start..end + 1, lines))
+            .map_err(to_py_err)
+    }
+
     pub fn mkdir(&self, path: &str, parents: bool) -> PyResult<String> {
         let resolved = self.resolve_path(path)?;
         self.rt
@@ -359,11 +666,253 @@ impl PySurrealFs {
         Ok(String::new())
     }
 
-    pub fn cp(&self, src: &str, dest: &str) -> PyResult<String> {
+    pub fn rm(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt.block_on(self.fs.rm(&resolved)).map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn rm_glob(&self, pattern: &str, dry_run: Option<bool>) -> PyResult<String> {
+        let resolved = self.resolve_path(pattern)?;
+        let paths = self
+            .rt
+            .block_on(self.fs.rm_glob(&resolved, dry_run.unwrap_or(false)))
+            .map_err(to_py_err)?;
+        Ok(paths.join("\n"))
+    }
+
+    /// Trashed entries, most recent first, as JSON.
+    pub fn list_trash(&self) -> PyResult<String> {
+        let items = self.rt.block_on(self.fs.list_trash()).map_err(to_py_err)?;
+        serde_json::to_string(&items).map_err(|e| SurrealFsError::new_err(e.to_string()))
+    }
+
+    pub fn restore(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.restore(&resolved))
+            .map_err(to_py_err)
+    }
+
+    pub fn empty_trash(&self) -> PyResult<usize> {
+        self.rt.block_on(self.fs.empty_trash()).map_err(to_py_err)
+    }
+
+    /// Create the path and parent indexes on the backing table; safe to call repeatedly.
+    pub fn define_schema(&self) -> PyResult<String> {
+        self.rt
+            .block_on(self.fs.define_schema())
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    /// Create or repair the indexes the filesystem relies on and return a line per index.
+    pub fn ensure_indexes(&self) -> PyResult<String> {
+        let report = self
+            .rt
+            .block_on(self.fs.ensure_indexes())
+            .map_err(to_py_err)?;
+        Ok(report.render())
+    }
+
+    pub fn rmdir(&self, path: &str, recursive: Option<bool>) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.rmdir(&resolved, recursive.unwrap_or(false)))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn mv(&self, src: &str, dest: &str, overwrite: Option<bool>) -> PyResult<String> {
+        let resolved_src = self.resolve_path(src)?;
+        let resolved_dest = self.resolve_path(dest)?;
+        self.rt
+            .block_on(
+                self.fs
+                    .mv(&resolved_src, &resolved_dest, overwrite.unwrap_or(false)),
+            )
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn exists(&self, path: &str) -> PyResult<bool> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.exists(&resolved))
+            .map_err(to_py_err)
+    }
+
+    /// Metadata as a JSON object, or an empty string when nothing exists at `path`.
+    pub fn metadata(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let meta = self
+            .rt
+            .block_on(self.fs.metadata(&resolved))
+            .map_err(to_py_err)?;
+        match meta {
+            Some(meta) => {
+                serde_json::to_string(&meta).map_err(|e| SurrealFsError::new_err(e.to_string()))
+            }
+            None => Ok(String::new()),
+        }
+    }
+
+    pub fn write_file_new(&self, path: &str, content: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.write_file_new(&resolved, content.to_string()))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn write_files(&self, files: Vec<(String, String)>) -> PyResult<String> {
+        let resolved = files
+            .into_iter()
+            .map(|(path, content)| Ok((self.resolve_path(&path)?, content)))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.rt
+            .block_on(self.fs.write_files(resolved))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn sort(
+        &self,
+        path: &str,
+        reverse: Option<bool>,
+        numeric: Option<bool>,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let opts = SortOptions {
+            reverse: reverse.unwrap_or(false),
+            numeric: numeric.unwrap_or(false),
+        };
+        let lines = self
+            .rt
+            .block_on(self.fs.sort(&resolved, &opts))
+            .map_err(to_py_err)?;
+        Ok(lines.join("\n"))
+    }
+
+    pub fn uniq(&self, path: &str, count: Option<bool>) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let runs = self
+            .rt
+            .block_on(self.fs.uniq(&resolved))
+            .map_err(to_py_err)?;
+        let mut out = String::new();
+        for run in runs {
+            if count.unwrap_or(false) {
+                let _ = writeln!(&mut out, "{:>7} {}", run.count, run.line);
+            } else {
+                let _ = writeln!(&mut out, "{}", run.line);
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn diff(&self, a: &str, b: &str) -> PyResult<String> {
+        let resolved_a = self.resolve_path(a)?;
+        let resolved_b = self.resolve_path(b)?;
+        self.rt
+            .block_on(self.fs.diff(&resolved_a, &resolved_b))
+            .map_err(to_py_err)
+    }
+
+    pub fn exchange(&self, a: &str, b: &str) -> PyResult<String> {
+        let resolved_a = self.resolve_path(a)?;
+        let resolved_b = self.resolve_path(b)?;
+        self.rt
+            .block_on(self.fs.exchange(&resolved_a, &resolved_b))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn cp(
+        &self,
+        src: &str,
+        dest: &str,
+        no_clobber: Option<bool>,
+        preserve: Option<bool>,
+    ) -> PyResult<String> {
         let resolved_src = self.resolve_path(src)?;
         let resolved_dest = self.resolve_path(dest)?;
+        let opts = CopyOptions {
+            no_clobber: no_clobber.unwrap_or(false),
+            preserve: preserve.unwrap_or(false),
+        };
+        self.rt
+            .block_on(self.fs.cp(&resolved_src, &resolved_dest, &opts))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn ln(&self, src: &str, dest: &str) -> PyResult<String> {
+        let resolved_src = self.resolve_path(src)?;
+        let resolved_dest = self.resolve_path(dest)?;
+        self.rt
+            .block_on(self.fs.link(&resolved_src, &resolved_dest))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn links(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let paths = self
+            .rt
+            .block_on(self.fs.links(&resolved))
+            .map_err(to_py_err)?;
+        Ok(paths.join("\n"))
+    }
+
+    pub fn symlink(&self, target: &str, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.symlink(target, &resolved))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn readlink(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.readlink(&resolved))
+            .map_err(to_py_err)
+    }
+
+    pub fn realpath(&self, path: &str) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.realpath(&resolved))
+            .map_err(to_py_err)
+    }
+
+    pub fn set_mtime(&self, path: &str, millis: i64) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        self.rt
+            .block_on(self.fs.set_mtime(&resolved, millis))
+            .map_err(to_py_err)?;
+        Ok(String::new())
+    }
+
+    pub fn cp_into(
+        &self,
+        srcs: Vec<String>,
+        dest_dir: &str,
+        no_clobber: Option<bool>,
+        preserve: Option<bool>,
+    ) -> PyResult<String> {
+        let resolved_srcs = srcs
+            .iter()
+            .map(|src| self.resolve_path(src))
+            .collect::<PyResult<Vec<_>>>()?;
+        let resolved_dest = self.resolve_path(dest_dir)?;
+        let opts = CopyOptions {
+            no_clobber: no_clobber.unwrap_or(false),
+            preserve: preserve.unwrap_or(false),
+        };
         self.rt
-            .block_on(self.fs.cp(&resolved_src, &resolved_dest))
+            .block_on(self.fs.cp_into(&resolved_srcs, &resolved_dest, &opts))
             .map_err(to_py_err)?;
         Ok(String::new())
     }
@@ -386,6 +935,47 @@ impl PySurrealFs {
         Ok(format!("{}\n", path))
     }
 
+    pub fn tree(&self, path: Option<&str>, max_depth: Option<usize>) -> PyResult<String> {
+        let resolved = self.resolve_path(path.unwrap_or("."))?;
+        self.rt
+            .block_on(self.fs.tree(&resolved, max_depth))
+            .map(|tree| tree.render())
+            .map_err(to_py_err)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn find(
+        &self,
+        path: Option<&str>,
+        name: Option<&str>,
+        kind: Option<&str>,
+        min_size: Option<usize>,
+        max_size: Option<usize>,
+        modified_since: Option<i64>,
+    ) -> PyResult<String> {
+        let resolved = self.resolve_path(path.unwrap_or("."))?;
+        let kind = match kind {
+            None => None,
+            Some("f") | Some("file") => Some(EntryKind::File),
+            Some("d") | Some("dir") => Some(EntryKind::Dir),
+            Some(other) => {
+                return Err(PyRuntimeError::new_err(format!("invalid kind: {}", other)));
+            }
+        };
+        let opts = FindOptions {
+            name: name.map(|n| n.to_string()),
+            kind,
+            min_size,
+            max_size,
+            modified_since,
+        };
+        let entries = self
+            .rt
+            .block_on(self.fs.find(&resolved, &opts))
+            .map_err(to_py_err)?;
+        Ok(join_lines(entries.into_iter().map(|e| e.path).collect()))
+    }
+
     pub fn glob(&self, pattern: &str) -> PyResult<String> {
         let resolved = self.resolve_path(pattern)?;
         let paths = self
@@ -395,6 +985,96 @@ impl PySurrealFs {
         Ok(join_lines(paths))
     }
 
+    /// Definitions whose name contains `query`, ignoring case, as `path:line: kind name`,
+    /// exact matches first.
+    pub fn symbols(&self, query: &str) -> PyResult<String> {
+        let symbols = self
+            .rt
+            .block_on(self.fs.symbols(query))
+            .map_err(to_py_err)?;
+        let mut out = String::new();
+        for s in symbols {
+            let _ = writeln!(
+                &mut out,
+                "{}:{}: {} {}",
+                s.path,
+                s.line,
+                s.kind.as_str(),
+                s.name
+            );
+        }
+        Ok(out)
+    }
+
+    /// Paths at or beneath `path` modified at or after `since` (epoch millis), newest first.
+    pub fn modified_since(&self, path: &str, since: i64) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let changed = self
+            .rt
+            .block_on(self.fs.modified_since(&resolved, since))
+            .map_err(to_py_err)?;
+        Ok(join_lines(changed.into_iter().map(|m| m.path).collect()))
+    }
+
+    /// Paths of the `limit` most recently modified children of `path`, newest first.
+    pub fn ls_by_mtime(&self, path: &str, limit: usize) -> PyResult<String> {
+        let resolved = self.resolve_path(path)?;
+        let nodes = self
+            .rt
+            .block_on(self.fs.ls_by_mtime(&resolved, limit))
+            .map_err(to_py_err)?;
+        Ok(join_lines(
+            nodes.iter().map(|n| n.path().to_string()).collect(),
+        ))
+    }
+
+    /// Paths of entries whose name contains `fragment`, ignoring case.
+    pub fn locate(&self, fragment: &str) -> PyResult<String> {
+        let paths = self
+            .rt
+            .block_on(self.fs.locate(fragment))
+            .map_err(to_py_err)?;
+        Ok(join_lines(paths))
+    }
+
+    /// Paths matching any of `patterns`, less those matching one written with a leading `!`,
+    /// optionally narrowed by kind (`"f"` or `"d"`), size, and modification time.
+    pub fn glob_many(
+        &self,
+        patterns: Vec<String>,
+        kind: Option<&str>,
+        min_size: Option<usize>,
+        max_size: Option<usize>,
+        modified_since: Option<i64>,
+    ) -> PyResult<String> {
+        let resolved = patterns
+            .iter()
+            .map(|pattern| match pattern.strip_prefix('!') {
+                Some(negated) => self.resolve_path(negated).map(|p| format!("!{}", p)),
+                None => self.resolve_path(pattern),
+            })
+            .collect::<PyResult<Vec<String>>>()?;
+        let kind = match kind {
+            None => None,
+            Some("f") | Some("file") => Some(EntryKind::File),
+            Some("d") | Some("dir") => Some(EntryKind::Dir),
+            Some(other) => {
+                return Err(PyRuntimeError::new_err(format!("invalid kind: {}", other)));
+            }
+        };
+        let opts = GlobOptions {
+            kind,
+            min_size,
+            max_size,
+            modified_since,
+        };
+        let paths = self
+            .rt
+            .block_on(self.fs.glob_with(&resolved, &opts))
+            .map_err(to_py_err)?;
+        Ok(join_lines(paths))
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn curl(
         &self,
@@ -429,7 +1109,7 @@ impl PySurrealFs {
         };
 
         self.rt
-            .block_on(self.fs.curl(request))
+            .block_on(curl::curl(&self.fs, request))
             .map_err(to_py_err)
             .map(|resp| {
                 if let Some(saved) = resp.saved_to {
@@ -462,19 +1142,19 @@ fn join_lines(lines: Vec<String>) -> String {
     }
 }
 
-fn format_entry(entry: &Entry, opts: LsOptions) -> String {
+fn format_entry(node: &Node, opts: LsOptions) -> String {
     if opts.long {
-        let kind = if entry.is_dir { 'd' } else { '-' };
-        let size = entry.size();
+        let mode = node.mode_string();
+        let size = node.size();
         if opts.human {
             let (val, unit) = human_size(size as f64);
-            format!("{} {:>6.1}{} {}", kind, val, unit, entry.name)
+            format!("{} {:>6.1}{} {}", mode, val, unit, node.name())
         } else {
-            format!("{} {:>8} {}", kind, size, entry.name)
+            format!("{} {:>8} {}", mode, size, node.name())
         }
     } else {
-        let suffix = if entry.is_dir { "/" } else { "" };
-        format!("{}{}", entry.name, suffix)
+        let suffix = if node.is_dir() { "/" } else { "" };
+        format!("{}{}", node.name(), suffix)
     }
 }
 
@@ -492,11 +1172,11 @@ fn human_size(bytes: f64) -> (f64, &'static str) {
     (value, UNITS[idx])
 }
 
-fn should_show(entry: &Entry, opts: LsOptions) -> bool {
-    if !opts.all && entry.name.starts_with('.') {
+fn should_show(node: &Node, opts: LsOptions) -> bool {
+    if !opts.all && node.is_hidden() {
         return false;
     }
-    if opts.dir_only && !entry.is_dir {
+    if opts.dir_only && !node.is_dir() {
         return false;
     }
     true
@@ -510,18 +1190,26 @@ fn resolve_cli_path(current: &str, input: &str) -> crate::Result<String> {
     }
 }
 
-async fn format_ls(fs: &FsInner, path: &str, opts: LsOptions) -> crate::Result<String> {
+/// Whether `node` sits inside a hidden directory already seen in a depth-first walk,
+/// recording it when it is a hidden directory itself.
+fn inside_hidden_dir(hidden_dirs: &mut Vec<String>, node: &Node) -> bool {
+    if hidden_dirs.iter().any(|d| node.path().starts_with(d)) {
+        return true;
+    }
+    if node.is_dir() && node.is_hidden() {
+        hidden_dirs.push(format!("{}/", node.path()));
+    }
+    false
+}
+
+async fn format_ls(fs: &SurrealFs<Any>, path: &str, opts: LsOptions) -> crate::Result<String> {
     if opts.recursive {
         let mut out = String::new();
-        let mut stack = vec![path.to_string()];
-        while let Some(p) = stack.pop() {
-            let entries = fs.ls(&p).await?;
-            let display_entries: Vec<_> = entries.iter().filter(|e| should_show(e, opts)).collect();
-            for e in &display_entries {
-                let _ = writeln!(&mut out, "{}", format_entry(e, opts));
-                if e.is_dir {
-                    stack.push(e.path.clone());
-                }
+        let mut hidden_dirs = Vec::new();
+        let entries: Vec<Entry> = fs.walk(path).try_collect().await?;
+        for e in entries.into_iter().map(Node::from) {
+            if (opts.all || !inside_hidden_dir(&mut hidden_dirs, &e)) && should_show(&e, opts) {
+                let _ = writeln!(&mut out, "{}", format_entry(&e, opts));
             }
         }
         Ok(out)
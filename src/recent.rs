@@ -0,0 +1,80 @@
+use surrealdb::Connection;
+
+use crate::inode::UPDATED_AT_EXPR;
+use crate::replica::Route;
+use crate::{FsError, Metadata, Node, Result, SurrealFs, metadata, normalize_path, procfs};
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// `path` and everything beneath it modified at or after `since` (epoch millis), newest
+    /// first with ties by path, for sync tools and "what changed while I was away" views.
+    /// The filtering and ordering happen in the database. A directory's time moves when
+    /// entries are added to or removed from it, which is the only trace removals leave.
+    pub async fn modified_since(&self, path: impl AsRef<str>, since: i64) -> Result<Vec<Metadata>> {
+        let path = normalize_path(path.as_ref())?;
+        if path != "/" && self.symlink_metadata(&path).await?.is_none() {
+            return Err(FsError::NotFound(path));
+        }
+        let scope = if path == "/" {
+            "true"
+        } else {
+            "(path = $path OR string::starts_with(path, $prefix))"
+        };
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.conn(Route::Replica)
+                    .query(format!(
+                        "SELECT {} FROM {} WHERE {} AND {} >= $since ORDER BY updated_at DESC, path",
+                        metadata::metadata_fields(),
+                        self.table,
+                        scope,
+                        UPDATED_AT_EXPR
+                    ))
+                    .bind(("path", path.clone()))
+                    .bind(("prefix", format!("{}/", path)))
+                    .bind(("since", since))
+            })
+            .await?;
+        let changed: Vec<Metadata> = res.take(0)?;
+        Ok(changed)
+    }
+
+    /// The `limit` most recently modified children of `path`, newest first with ties by
+    /// name, like `ls -t | head`. Only those children are fetched, so the latest entries of
+    /// a very large directory come back without listing it. A file lists as itself.
+    pub async fn ls_by_mtime(&self, path: impl AsRef<str>, limit: usize) -> Result<Vec<Node>> {
+        let path = normalize_path(path.as_ref())?;
+        let listed = match self.metadata(&path).await? {
+            Some(meta) if !meta.is_dir => vec![meta],
+            Some(meta) if procfs::is_proc_path(&meta.path) => {
+                let mut children = self.child_metadata(&meta.path).await?;
+                children.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then(a.name.cmp(&b.name)));
+                children.truncate(limit);
+                children
+            }
+            Some(meta) => {
+                let _permit = self.permit().await;
+                let mut res = self
+                    .run(|| {
+                        self.db
+                            .query(format!(
+                                "SELECT {} FROM {} WHERE parent = $parent ORDER BY updated_at DESC, name LIMIT $limit",
+                                metadata::metadata_fields(),
+                                self.table
+                            ))
+                            .bind(("parent", meta.path.clone()))
+                            .bind(("limit", limit))
+                    })
+                    .await?;
+                let listed: Vec<Metadata> = res.take(0)?;
+                listed
+            }
+            None => return Err(FsError::NotFound(path)),
+        };
+        Ok(listed.into_iter().map(Node::from).collect())
+    }
+}
@@ -42,7 +42,7 @@ where
         }
 
         if let Some((left, right)) = line.split_once('|') {
-            let mut parts = left.trim().split_whitespace();
+            let mut parts = left.split_whitespace();
             let cmd = parts.next().unwrap_or("");
             let args: Vec<&str> = parts.collect();
             let right = right.trim();
@@ -62,7 +62,7 @@ where
                         ("write_file", [path]) => {
                             let target = resolve_cli_path(&state.cwd, path);
                             match state.fs.write_file(&target, resp.body).await {
-                                Ok(()) => println!("Saved to {} (status {})", target, resp.status),
+                                Ok(_) => println!("Saved to {} (status {})", target, resp.status),
                                 Err(e) => println!("Error: {}", e),
                             }
                         }
@@ -100,7 +100,7 @@ where
 
                 match curl::run_capture(&args, &mut state).await {
                     Ok(resp) => match state.fs.write_file(&target, resp.body).await {
-                        Ok(()) => println!("Saved to {} (status {})", target, resp.status),
+                        Ok(_) => println!("Saved to {} (status {})", target, resp.status),
                         Err(e) => println!("Error: {}", e),
                     },
                     Err(e) => println!("Error: {}", e),
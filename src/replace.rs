@@ -0,0 +1,110 @@
+use regex::{NoExpand, Regex};
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{FsError, GrepOptions, Result, SurrealFs, normalize_path, render_labeled_diff};
+
+/// What `replace_in` changed, or would change on a dry run, in one file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileReplacement {
+    pub path: String,
+    /// How many matches were replaced.
+    pub replacements: usize,
+    /// The change in the format `diff` produces, labelled with the path.
+    pub diff: String,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Replace every match of `opts` with `replacement` in the file at `path`, or in every
+    /// text file beneath it with `opts.recursive`, like `sed -i 's/pattern/replacement/g'`.
+    /// Matching follows `grep_with`, so without `multiline` each line is matched on its own.
+    /// `replacement` may refer to capture groups as `$1` or `${name}` unless
+    /// `fixed_strings` is set, when it is inserted as is. Files are found with `grep_files`
+    /// and written with `write_file_if`, so one changed since it was read fails with
+    /// `Conflict` rather than losing the other write; files before it stay replaced. With
+    /// `dry_run`, nothing is written. Returns the changed files in walk order.
+    pub async fn replace_in(
+        &self,
+        opts: &GrepOptions,
+        replacement: &str,
+        path: impl AsRef<str>,
+        dry_run: bool,
+    ) -> Result<Vec<FileReplacement>> {
+        if opts.invert {
+            return Err(FsError::InvalidPattern(
+                "replace_in cannot invert a pattern".to_string(),
+            ));
+        }
+        let pattern = opts.regex()?;
+        let path = normalize_path(path.as_ref())?;
+        if !dry_run {
+            self.ensure_writable(&path)?;
+        }
+        let mut changed = Vec::new();
+        for path in self.grep_files(&path, opts).await? {
+            let entry = self.require_file(&path).await?;
+            if entry.is_binary() {
+                continue;
+            }
+            let Some(current) = entry.text()? else {
+                continue;
+            };
+            let (updated, replacements) = if opts.multiline {
+                replace_text(&pattern, replacement, opts.fixed_strings, &current)
+            } else {
+                replace_lines(&pattern, replacement, opts.fixed_strings, &current)
+            };
+            if updated == current {
+                continue;
+            }
+            if !dry_run {
+                self.write_file_if(&path, updated.clone(), entry.version)
+                    .await?;
+            }
+            changed.push(FileReplacement {
+                diff: render_labeled_diff(&current, &updated, &path, &path),
+                path,
+                replacements,
+            });
+        }
+        Ok(changed)
+    }
+}
+
+/// `replace_text` on each line of `content` by itself, line endings left as they were.
+fn replace_lines(
+    pattern: &Regex,
+    replacement: &str,
+    literal: bool,
+    content: &str,
+) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut replacements = 0;
+    for line in content.split_inclusive('\n') {
+        let body = match line.strip_suffix('\n') {
+            Some(body) => body.strip_suffix('\r').unwrap_or(body),
+            None => line,
+        };
+        let (replaced, n) = replace_text(pattern, replacement, literal, body);
+        out.push_str(&replaced);
+        out.push_str(&line[body.len()..]);
+        replacements += n;
+    }
+    (out, replacements)
+}
+
+fn replace_text(pattern: &Regex, replacement: &str, literal: bool, text: &str) -> (String, usize) {
+    let replacements = pattern.find_iter(text).count();
+    if replacements == 0 {
+        return (text.to_string(), 0);
+    }
+    let replaced = if literal {
+        pattern.replace_all(text, NoExpand(replacement))
+    } else {
+        pattern.replace_all(text, replacement)
+    };
+    (replaced.into_owned(), replacements)
+}
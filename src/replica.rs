@@ -0,0 +1,25 @@
+use surrealdb::{Connection, Surreal};
+
+use crate::SurrealFs;
+
+/// Which connection a read is sent to. Reads that feed a write stay on the primary, so
+/// they never act on a replica that has not caught up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Route {
+    Primary,
+    /// The read replica when one is configured, the primary otherwise.
+    Replica,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// The connection reads routed by `route` go to.
+    pub(crate) fn conn(&self, route: Route) -> &Surreal<DB> {
+        match (route, &self.replica) {
+            (Route::Replica, Some(replica)) => replica,
+            _ => &self.db,
+        }
+    }
+}
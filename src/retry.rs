@@ -0,0 +1,106 @@
+use std::future::IntoFuture;
+use std::time::Duration;
+
+use surrealdb::Connection;
+use surrealdb::error::{Api, Db};
+
+use crate::{Result, SurrealFs};
+
+/// How queries are retried when the connection drops or the database times out; set with
+/// `SurrealFsBuilder::retry`. Other errors, such as failed permission checks or parse
+/// errors, are returned at once, and writes that are not safe to repeat, such as
+/// `write_file_new`, `mv`, or `exchange`, are never retried. The default makes a single
+/// attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; 0 is treated as 1.
+    pub max_attempts: u32,
+    /// Wait before the second attempt; it doubles for each one after.
+    pub initial_backoff: Duration,
+    /// Upper bound on any single wait.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Up to `max_attempts` attempts with the default backoff.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// The wait after failed attempt number `attempt` (1-based).
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Whether `err` is worth another attempt: the connection failed or the query timed out.
+pub(crate) fn is_transient(err: &surrealdb::Error) -> bool {
+    match err {
+        surrealdb::Error::Api(err) => matches!(
+            err,
+            Api::Ws(_) | Api::Http(_) | Api::ConnectionUninitialised
+        ),
+        surrealdb::Error::Db(err) => matches!(err, Db::QueryTimedout),
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Send the query built by `query`, building and sending it again after transient errors
+    /// as the retry policy allows. Only for reads and for writes that come out the same when
+    /// applied twice, since a write whose reply was lost may already have landed.
+    pub(crate) async fn run<F, Q, T>(&self, query: F) -> Result<T>
+    where
+        F: Fn() -> Q,
+        Q: IntoFuture<Output = surrealdb::Result<T>>,
+    {
+        retrying(self.retry, query).await
+    }
+
+    /// `run` with a single attempt, for writes a second attempt could undo or fail on after
+    /// the first one landed: creating, moving, or swapping entries, or checking a version
+    /// first. Transient errors are returned to the caller, who can see what was applied.
+    pub(crate) async fn run_once<F, Q, T>(&self, query: F) -> Result<T>
+    where
+        F: Fn() -> Q,
+        Q: IntoFuture<Output = surrealdb::Result<T>>,
+    {
+        retrying(RetryPolicy::default(), query).await
+    }
+}
+
+/// `SurrealFs::run` for callers that hold a policy rather than the filesystem.
+pub(crate) async fn retrying<F, Q, T>(policy: RetryPolicy, query: F) -> Result<T>
+where
+    F: Fn() -> Q,
+    Q: IntoFuture<Output = surrealdb::Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match query().await {
+            Err(err) if attempt < policy.max_attempts && is_transient(&err) => {
+                tokio::time::sleep(policy.backoff(attempt)).await;
+                attempt += 1;
+            }
+            res => return Ok(res?),
+        }
+    }
+}
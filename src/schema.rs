@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{Result, SurrealFs};
+
+/// Version of the table layout `define_schema` sets up, recorded in the database so
+/// `status` can tell which layout a deployment has.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// An index the crate's queries are written against, named `{table}_{suffix}`.
+struct RequiredIndex {
+    suffix: &'static str,
+    field: &'static str,
+    unique: bool,
+    /// For a full-text index, the suffix of the analyzer it is defined with,
+    /// `{table}_{analyzer}`.
+    analyzer: Option<&'static str>,
+    /// What goes slow or wrong without it.
+    without: &'static str,
+}
+
+const REQUIRED_INDEXES: &[RequiredIndex] = &[
+    RequiredIndex {
+        suffix: "path",
+        field: "path",
+        unique: true,
+        analyzer: None,
+        without: "path lookups scan the table and racing writers can store a path twice",
+    },
+    RequiredIndex {
+        suffix: "parent",
+        field: "parent",
+        unique: false,
+        analyzer: None,
+        without: "directory listings scan the table",
+    },
+    RequiredIndex {
+        suffix: "content",
+        field: "content",
+        unique: false,
+        analyzer: Some("search"),
+        without: "the first `search` indexes every file, and read-only handles cannot search",
+    },
+    RequiredIndex {
+        suffix: "name",
+        field: "name",
+        unique: false,
+        analyzer: Some("names"),
+        without: "the first `locate` indexes every name, and read-only handles cannot locate",
+    },
+];
+
+/// Whether an index the crate relies on is defined as expected.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum IndexStatus {
+    Present,
+    Missing,
+    /// Defined under the expected name but over other fields or with other uniqueness;
+    /// holds the definition found.
+    Mismatched(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexCheck {
+    pub name: String,
+    pub field: String,
+    pub unique: bool,
+    /// The analyzer of a full-text index.
+    pub analyzer: Option<String>,
+    pub status: IndexStatus,
+    /// What goes slow or wrong while the index is not in place.
+    pub without: String,
+}
+
+/// The state of the indexes on the backing table, from `check_indexes` or `ensure_indexes`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexReport {
+    pub table: String,
+    pub indexes: Vec<IndexCheck>,
+}
+
+impl IndexReport {
+    /// Whether every required index is present as expected.
+    pub fn is_healthy(&self) -> bool {
+        self.indexes
+            .iter()
+            .all(|index| index.status == IndexStatus::Present)
+    }
+
+    /// One line per index, saying what is wrong and what it costs when it is not present.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for index in &self.indexes {
+            let kind = match &index.analyzer {
+                Some(analyzer) => format!(", search {}", analyzer),
+                None if index.unique => ", unique".to_string(),
+                None => String::new(),
+            };
+            let status = match &index.status {
+                IndexStatus::Present => "ok".to_string(),
+                IndexStatus::Missing => format!("missing: {}", index.without),
+                IndexStatus::Mismatched(found) => {
+                    format!("mismatched ({}): {}", found, index.without)
+                }
+            };
+            out.push_str(&format!(
+                "{} on {}({}{}): {}\n",
+                index.name, self.table, index.field, kind, status
+            ));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TableInfo {
+    #[serde(default)]
+    indexes: BTreeMap<String, String>,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Define the entry table with a unique index on `path` and an index on `parent`, so
+    /// directory listings stop scanning the table and racing writers cannot store the same
+    /// path twice, and record `SCHEMA_VERSION`. Safe to run on every start; existing
+    /// definitions are left alone.
+    pub async fn define_schema(&self) -> Result<()> {
+        self.ensure_writable("/")?;
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db.query(format!(
+                "DEFINE TABLE IF NOT EXISTS {table} SCHEMALESS;\
+                 DEFINE INDEX IF NOT EXISTS {table}_path ON {table} FIELDS path UNIQUE;\
+                 DEFINE INDEX IF NOT EXISTS {table}_parent ON {table} FIELDS parent;\
+                 DEFINE PARAM OVERWRITE ${param} VALUE {version};",
+                table = self.table,
+                param = self.schema_param(),
+                version = SCHEMA_VERSION,
+            ))
+        })
+        .await?
+        .check()?;
+        Ok(())
+    }
+
+    /// The database parameter holding the schema version of this table.
+    pub(crate) fn schema_param(&self) -> String {
+        format!("{}_schema_version", self.table)
+    }
+
+    /// Report which of the indexes the crate relies on are missing or defined differently,
+    /// without changing anything.
+    pub async fn check_indexes(&self) -> Result<IndexReport> {
+        let info: Option<TableInfo> = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run(|| self.db.query(format!("INFO FOR TABLE {}", self.table)))
+                .await?;
+            res.take(0)?
+        };
+        let mut defined = info.map(|info| info.indexes).unwrap_or_default();
+
+        let indexes = REQUIRED_INDEXES
+            .iter()
+            .map(|required| {
+                let name = format!("{}_{}", self.table, required.suffix);
+                let analyzer = required
+                    .analyzer
+                    .map(|suffix| format!("{}_{}", self.table, suffix));
+                let status = match defined.remove(&name) {
+                    None => IndexStatus::Missing,
+                    Some(found) if matches_definition(&found, required, analyzer.as_deref()) => {
+                        IndexStatus::Present
+                    }
+                    Some(found) => IndexStatus::Mismatched(found),
+                };
+                IndexCheck {
+                    name,
+                    field: required.field.to_string(),
+                    unique: required.unique,
+                    analyzer,
+                    status,
+                    without: required.without.to_string(),
+                }
+            })
+            .collect();
+        Ok(IndexReport {
+            table: self.table.clone(),
+            indexes,
+        })
+    }
+
+    /// `define_schema`, `define_search_index` and `define_name_index`, then redefine any
+    /// required index found with the wrong fields, uniqueness or analyzer, and report the
+    /// result. Building the full-text indexes over an existing table reads every entry once.
+    /// Fails when an index cannot be built, such as a unique `path` index over a table
+    /// already holding duplicate paths.
+    pub async fn ensure_indexes(&self) -> Result<IndexReport> {
+        self.define_schema().await?;
+        self.define_search_index().await?;
+        self.define_name_index().await?;
+        let report = self.check_indexes().await?;
+        // Full-text indexes are removed and defined again along with their analyzers.
+        let redefine: String = report
+            .indexes
+            .iter()
+            .filter(|index| matches!(index.status, IndexStatus::Mismatched(_)))
+            .map(|index| match index.analyzer {
+                Some(_) => format!("REMOVE INDEX {} ON {};", index.name, self.table),
+                None => format!(
+                    "DEFINE INDEX OVERWRITE {} ON {} FIELDS {}{};",
+                    index.name,
+                    self.table,
+                    index.field,
+                    if index.unique { " UNIQUE" } else { "" }
+                ),
+            })
+            .collect();
+        if redefine.is_empty() {
+            return Ok(report);
+        }
+        {
+            let _permit = self.permit().await;
+            self.run(|| self.db.query(&redefine)).await?.check()?;
+        }
+        self.define_search_index().await?;
+        self.define_name_index().await?;
+        self.check_indexes().await
+    }
+}
+
+/// Whether the `DEFINE INDEX` statement `found`, as `INFO FOR TABLE` returns it, indexes
+/// the required field with the required uniqueness and, for a full-text index, `analyzer`.
+fn matches_definition(found: &str, required: &RequiredIndex, analyzer: Option<&str>) -> bool {
+    let words: Vec<&str> = found.split_whitespace().collect();
+    let after = |keyword: &str| {
+        words
+            .iter()
+            .position(|word| word.eq_ignore_ascii_case(keyword))
+            .and_then(|at| words.get(at + 1).copied())
+    };
+    let fields = after("FIELDS").or_else(|| after("COLUMNS"));
+    let unique = words.iter().any(|word| word.eq_ignore_ascii_case("UNIQUE"));
+    fields == Some(required.field) && unique == required.unique && after("ANALYZER") == analyzer
+}
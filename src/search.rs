@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{Result, SurrealFs, normalize_path};
+
+/// Characters of context fetched before a file's first match to build its snippet.
+const SNIPPET_BEFORE: usize = 60;
+/// Characters fetched in all for a snippet; the snippet is the line of the first match
+/// within them.
+const SNIPPET_WINDOW: usize = 200;
+
+/// A file matching a `search` query.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHit {
+    pub path: String,
+    /// BM25 relevance; higher ranks first. Only comparable within one search.
+    pub score: f64,
+    /// The line holding the first match, or the part of it around the match when long.
+    pub snippet: String,
+    /// Byte ranges of the matched terms within `snippet`.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+impl SearchHit {
+    /// The snippet with each matched term wrapped in `open` and `close`.
+    pub fn highlighted(&self, open: &str, close: &str) -> String {
+        let mut out = String::with_capacity(self.snippet.len());
+        let mut at = 0;
+        for &(start, end) in &self.highlights {
+            out.push_str(&self.snippet[at..start]);
+            out.push_str(open);
+            out.push_str(&self.snippet[start..end]);
+            out.push_str(close);
+            at = end;
+        }
+        out.push_str(&self.snippet[at..]);
+        out
+    }
+}
+
+/// A search result as the database returns it: a window of content around the first
+/// match, and every match's character offsets in the whole content.
+#[derive(Debug, Deserialize)]
+struct SearchRow {
+    path: String,
+    score: f64,
+    offsets: Vec<Offset>,
+    from: usize,
+    window: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Offset {
+    s: usize,
+    e: usize,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Define the analyzer and full-text index `search` runs on: content is split on
+    /// whitespace, punctuation, and character class changes, lowercased, folded to ASCII,
+    /// and stemmed as English. The content of hard-linked files is indexed on their inodes.
+    /// Indexing an existing table happens once, here. Safe to run repeatedly; existing
+    /// definitions are left alone.
+    pub async fn define_search_index(&self) -> Result<()> {
+        self.ensure_writable("/")?;
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db.query(format!(
+                "DEFINE ANALYZER IF NOT EXISTS {table}_search TOKENIZERS blank, class, punct FILTERS lowercase, ascii, snowball(english);\
+                 DEFINE INDEX IF NOT EXISTS {table}_content ON {table} FIELDS content SEARCH ANALYZER {table}_search BM25 HIGHLIGHTS;\
+                 DEFINE INDEX IF NOT EXISTS {table}_inode_content ON {table}_inode FIELDS content SEARCH ANALYZER {table}_search BM25 HIGHLIGHTS;",
+                table = self.table,
+            ))
+        })
+        .await?
+        .check()?;
+        Ok(())
+    }
+
+    /// Ranked keyword search over the text content of files at or beneath `path`, done by
+    /// SurrealDB's full-text index so only the matching files' snippets leave the database.
+    /// Every term of `query` must appear, in any form the stemmer folds together ("fox"
+    /// finds "foxes"). Best matches first, ties by path. The index is defined on first use
+    /// unless the handle is read-only, when `define_search_index` must have run. Content
+    /// stored as bytes, compressed content included, is not indexed.
+    pub async fn search(&self, query: &str, path: impl AsRef<str>) -> Result<Vec<SearchHit>> {
+        let path = normalize_path(path.as_ref())?;
+        if !self.is_read_only() {
+            self.define_search_index().await?;
+        }
+        self.flush_pending().await?;
+        let scope = if path == "/" {
+            "true"
+        } else {
+            "(path = $path OR string::starts_with(path, $prefix))"
+        };
+        let rows: Vec<SearchRow> = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run(|| {
+                    self.conn(Route::Replica)
+                        .query(format!(
+                            "LET $own = (\
+                                 SELECT path, content, search::score(1) AS score, search::offsets(1)['0'] AS offsets \
+                                 FROM {table} WHERE content @1@ $query AND is_dir = false AND {scope}\
+                             );\
+                             LET $shared = (\
+                                 SELECT id, search::score(1) AS score, search::offsets(1)['0'] AS offsets \
+                                 FROM {table}_inode WHERE content @1@ $query\
+                             );\
+                             LET $linked = IF $shared = [] THEN [] ELSE (\
+                                 SELECT path, inode.content AS content, (SELECT VALUE score FROM $shared WHERE id = $parent.inode)[0] AS score, (SELECT VALUE offsets FROM $shared WHERE id = $parent.inode)[0] AS offsets \
+                                 FROM {table} WHERE inode IN $shared.id AND {scope}\
+                             ) END;\
+                             SELECT path, score, offsets, \
+                                 math::max([0, offsets[0].s - {before}]) AS from, \
+                                 string::slice(content, math::max([0, offsets[0].s - {before}]), {window}) AS window \
+                             FROM array::concat($own, $linked) ORDER BY score DESC, path;",
+                            table = self.table,
+                            before = SNIPPET_BEFORE,
+                            window = SNIPPET_WINDOW,
+                        ))
+                        .bind(("query", query.to_string()))
+                        .bind(("path", path.clone()))
+                        .bind(("prefix", format!("{}/", path)))
+                })
+                .await?;
+            res.take(3)?
+        };
+        Ok(rows.into_iter().map(snippet).collect())
+    }
+}
+
+/// Cut the line of the first match out of a row's window and place its highlights.
+fn snippet(row: SearchRow) -> SearchHit {
+    let window = &row.window;
+    // Offsets count characters from the start of the content; the window starts `from`
+    // characters in.
+    let byte_at = |offset: usize| {
+        let chars = offset.saturating_sub(row.from);
+        window
+            .char_indices()
+            .nth(chars)
+            .map_or(window.len(), |(at, _)| at)
+    };
+    let first = row.offsets.first().map_or(0, |o| byte_at(o.s));
+    let start = window[..first].rfind('\n').map_or(0, |at| at + 1);
+    let end = window[first..]
+        .find('\n')
+        .map_or(window.len(), |at| first + at);
+    let highlights = row
+        .offsets
+        .iter()
+        .filter(|o| o.s >= row.from)
+        .map(|o| (byte_at(o.s), byte_at(o.e)))
+        .filter(|&(s, e)| s >= start && e <= end && s < e)
+        .map(|(s, e)| (s - start, e - start))
+        .collect();
+    SearchHit {
+        path: row.path,
+        score: row.score,
+        snippet: window[start..end].trim_end_matches('\r').to_string(),
+        highlights,
+    }
+}
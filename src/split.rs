@@ -0,0 +1,98 @@
+use surrealdb::Connection;
+
+use crate::{Entry, FsError, Metadata, Result, SurrealFs, leaf_name, normalize_path, parent_path};
+
+/// How `SurrealFs::split` sizes each part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// At most this many lines per part, each keeping its line ending.
+    Lines(usize),
+    /// At most this many bytes per part, cut without regard to lines or characters.
+    Bytes(usize),
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Cut the file at `path` into `path.part0001`, `path.part0002`, … next to it and return
+    /// the part paths in order; the original is left alone. Fails with `AlreadyExists` when a
+    /// first part is already there, so an earlier split is never mixed in.
+    pub async fn split(&self, path: impl AsRef<str>, by: SplitBy) -> Result<Vec<String>> {
+        let path = normalize_path(path.as_ref())?;
+        let entry = self.require_file(&path).await?;
+        if matches!(by, SplitBy::Lines(0) | SplitBy::Bytes(0)) {
+            return Err(FsError::InvalidPath);
+        }
+        if self.symlink_metadata(part_path(&path, 1)).await?.is_some() {
+            return Err(FsError::AlreadyExists(part_path(&path, 1)));
+        }
+
+        match by {
+            SplitBy::Lines(per_part) => {
+                let text = entry.text()?.unwrap_or_default();
+                let lines: Vec<&str> = text.split_inclusive('\n').collect();
+                let parts: Vec<(String, String)> = lines
+                    .chunks(per_part)
+                    .enumerate()
+                    .map(|(idx, chunk)| (part_path(&path, idx + 1), chunk.concat()))
+                    .collect();
+                let paths = parts.iter().map(|(p, _)| p.clone()).collect();
+                self.write_files(parts).await?;
+                Ok(paths)
+            }
+            SplitBy::Bytes(per_part) => {
+                let bytes = entry.bytes().unwrap_or_default();
+                let mut paths = Vec::new();
+                for (idx, chunk) in bytes.chunks(per_part).enumerate() {
+                    let part = part_path(&path, idx + 1);
+                    self.write_bytes_internal(&part, chunk.to_vec(), false)
+                        .await?;
+                    paths.push(part);
+                }
+                Ok(paths)
+            }
+        }
+    }
+
+    /// Reassemble `path` from the `path.partNNNN` entries left by `split`, in part order,
+    /// replacing anything already at `path`. The parts are kept. Fails with `NotFound` when
+    /// there are none.
+    pub async fn join(&self, path: impl AsRef<str>) -> Result<Metadata> {
+        let path = normalize_path(path.as_ref())?;
+        let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
+        let prefix = format!("{}.part", leaf_name(&path));
+        let mut parts: Vec<(usize, Entry)> = self
+            .children(&parent)
+            .await?
+            .into_iter()
+            .filter(|e| !e.is_dir && e.symlink.is_none())
+            .filter_map(|e| {
+                let number = e.name.strip_prefix(&prefix)?;
+                if !number.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                Some((number.parse().ok()?, e))
+            })
+            .collect();
+        if parts.is_empty() {
+            return Err(FsError::NotFound(part_path(&path, 1)));
+        }
+        parts.sort_by_key(|(number, _)| *number);
+
+        let mut joined = Vec::new();
+        for (_, part) in &parts {
+            joined.extend(part.bytes().unwrap_or_default());
+        }
+        if parts.iter().all(|(_, part)| part.content.is_some()) {
+            let text = String::from_utf8(joined).map_err(|_| FsError::InvalidUtf8(path.clone()))?;
+            self.write_file(&path, text).await
+        } else {
+            self.write_bytes_internal(&path, joined, false).await
+        }
+    }
+}
+
+fn part_path(path: &str, number: usize) -> String {
+    format!("{}.part{:04}", path, number)
+}
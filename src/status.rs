@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use surrealdb::Connection;
+
+use crate::{Result, SurrealFs};
+
+/// What `SurrealFs::status` found out about the connection, for readiness checks.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Status {
+    /// Round trip of a trivial query.
+    pub latency: Duration,
+    /// The client engine, such as `any`, `local`, or `remote::ws`.
+    pub engine: String,
+    /// The endpoint passed to `SurrealFs::open`; unknown for connections made by hand.
+    pub endpoint: Option<String>,
+    pub server_version: String,
+    pub namespace: Option<String>,
+    pub database: Option<String>,
+    pub table: String,
+    /// The schema version `define_schema` recorded, or `None` when it never ran here.
+    pub schema_version: Option<u32>,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Time a round trip to the database, failing when it cannot be reached.
+    pub async fn ping(&self) -> Result<Duration> {
+        let _permit = self.permit().await;
+        let started = Instant::now();
+        self.run(|| self.db.query("RETURN true")).await?.check()?;
+        Ok(started.elapsed())
+    }
+
+    /// Latency, engine, session, and schema details of the connection in one call.
+    pub async fn status(&self) -> Result<Status> {
+        let latency = self.ping().await?;
+        let server_version = {
+            let _permit = self.permit().await;
+            self.run(|| self.db.version()).await?.to_string()
+        };
+        let mut res = {
+            let _permit = self.permit().await;
+            self.run(|| {
+                self.db.query(format!(
+                    "RETURN [session::ns(), session::db()]; RETURN ${};",
+                    self.schema_param()
+                ))
+            })
+            .await?
+        };
+        let session: Vec<Option<String>> = res.take(0)?;
+        let schema_version: Option<u32> = res.take(1)?;
+        Ok(Status {
+            latency,
+            engine: engine_name::<DB>(),
+            endpoint: self.endpoint.clone(),
+            server_version,
+            namespace: session.first().cloned().flatten(),
+            database: session.get(1).cloned().flatten(),
+            table: self.table.clone(),
+            schema_version,
+        })
+    }
+}
+
+/// The engine module of the connection type, e.g. `remote::ws` for
+/// `surrealdb::engine::remote::ws::Client`.
+fn engine_name<DB>() -> String {
+    let name = std::any::type_name::<DB>();
+    let module = name.rsplit_once("::").map_or(name, |(module, _)| module);
+    module
+        .split_once("engine::")
+        .map_or(module, |(_, engine)| engine)
+        .to_string()
+}
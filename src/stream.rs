@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+
+use futures::stream::{self, Stream};
+use serde::Deserialize;
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{Entry, FsError, Result, SurrealFs, normalize_path, procfs};
+
+/// Characters fetched per round trip by `read_lines`.
+const LINE_CHUNK_CHARS: usize = 64 * 1024;
+
+/// Matches fetched per round trip by `glob_stream`.
+const GLOB_BATCH: usize = 256;
+
+#[derive(Debug, Deserialize)]
+struct FileProbe {
+    is_dir: bool,
+    binary: bool,
+    #[serde(default)]
+    symlink: Option<String>,
+}
+
+struct LineReader {
+    path: String,
+    offset: usize,
+    pending: String,
+    lines: VecDeque<String>,
+    started: bool,
+    done: bool,
+}
+
+struct Walk {
+    root: Option<String>,
+    stack: Vec<Entry>,
+}
+
+struct GlobBatches {
+    pattern: String,
+    matches: VecDeque<String>,
+    cursor: Option<String>,
+    done: bool,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Stream every entry beneath `path`, depth-first with siblings in name order: each
+    /// directory is yielded before its contents. The whole subtree is fetched in one query
+    /// when the stream is first polled, without file content; see `Entry::load_content`.
+    /// Symlinks are yielded but never descended into.
+    pub fn walk(&self, path: impl AsRef<str>) -> impl Stream<Item = Result<Entry>> + '_ {
+        let walk = Walk {
+            root: Some(path.as_ref().to_string()),
+            stack: Vec::new(),
+        };
+
+        stream::try_unfold(walk, move |mut walk| async move {
+            if let Some(root) = walk.root.take() {
+                let root = normalize_path(&root)?;
+                let meta = self
+                    .symlink_metadata(&root)
+                    .await?
+                    .ok_or_else(|| FsError::NotFound(root.clone()))?;
+                if meta.is_dir {
+                    walk.stack = self.subtree(&root, true, Route::Replica).await?;
+                    walk.stack.reverse();
+                }
+            }
+
+            let Some(entry) = walk.stack.pop() else {
+                return Ok(None);
+            };
+            Ok(Some((entry, walk)))
+        })
+    }
+
+    /// Stream the paths matching `pattern` in `glob` order, newest first. Matches are fetched
+    /// in batches as the stream is polled, so callers can start on the first ones before the
+    /// rest have been found.
+    pub fn glob_stream(&self, pattern: impl AsRef<str>) -> impl Stream<Item = Result<String>> + '_ {
+        let batches = GlobBatches {
+            pattern: pattern.as_ref().to_string(),
+            matches: VecDeque::new(),
+            cursor: None,
+            done: false,
+        };
+
+        stream::try_unfold(batches, move |mut batches| async move {
+            loop {
+                if let Some(path) = batches.matches.pop_front() {
+                    return Ok(Some((path, batches)));
+                }
+                if batches.done {
+                    return Ok(None);
+                }
+                let page = self
+                    .glob_page(&batches.pattern, batches.cursor.as_deref(), GLOB_BATCH)
+                    .await?;
+                batches.matches.extend(page.items);
+                batches.done = page.next.is_none();
+                batches.cursor = page.next;
+            }
+        })
+    }
+
+    /// Stream the lines of a text file, fetching the content from SurrealDB in chunks so very
+    /// large files are never held in memory at once. Lines follow `str::lines` semantics.
+    pub fn read_lines(&self, path: impl AsRef<str>) -> impl Stream<Item = Result<String>> + '_ {
+        let reader = LineReader {
+            path: path.as_ref().to_string(),
+            offset: 0,
+            pending: String::new(),
+            lines: VecDeque::new(),
+            started: false,
+            done: false,
+        };
+
+        stream::try_unfold(reader, move |mut reader| async move {
+            loop {
+                if let Some(line) = reader.lines.pop_front() {
+                    return Ok(Some((line, reader)));
+                }
+                if reader.done {
+                    return Ok(None);
+                }
+                if !reader.started {
+                    self.start_lines(&mut reader).await?;
+                } else {
+                    self.next_line_chunk(&mut reader).await?;
+                }
+            }
+        })
+    }
+
+    /// Resolve and check the file without fetching its content. Binary-stored files cannot be
+    /// sliced as text server-side, so they are decoded in one go instead.
+    async fn start_lines(&self, reader: &mut LineReader) -> Result<()> {
+        reader.started = true;
+        let mut path = normalize_path(&reader.path)?;
+        if procfs::is_proc_path(&path) {
+            let content = self.cat(&path).await?;
+            reader.lines.extend(content.lines().map(str::to_string));
+            reader.done = true;
+            return Ok(());
+        }
+        let mut probe = self.probe_file(&path).await?;
+        if probe.as_ref().is_some_and(|p| p.symlink.is_some()) {
+            path = self.realpath(&path).await?;
+            probe = self.probe_file(&path).await?;
+        }
+
+        match probe {
+            None => Err(FsError::NotFound(path)),
+            Some(p) if p.is_dir => Err(FsError::NotAFile(path)),
+            Some(p) if p.binary => {
+                let content = self.cat(&path).await?;
+                reader.lines.extend(content.lines().map(str::to_string));
+                reader.done = true;
+                Ok(())
+            }
+            Some(_) => {
+                reader.path = path;
+                Ok(())
+            }
+        }
+    }
+
+    async fn next_line_chunk(&self, reader: &mut LineReader) -> Result<()> {
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(
+                        "SELECT VALUE string::slice(inode.content ?? content ?? '', $start, $len) FROM type::thing($table, $path)",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", reader.path.clone()))
+                    .bind(("start", reader.offset))
+                    .bind(("len", LINE_CHUNK_CHARS))
+            })
+            .await?;
+        let chunk: Option<String> = res.take(0)?;
+        let chunk = chunk.ok_or_else(|| FsError::NotFound(reader.path.clone()))?;
+
+        if chunk.is_empty() {
+            reader.done = true;
+            if !reader.pending.is_empty() {
+                reader.lines.push_back(std::mem::take(&mut reader.pending));
+            }
+            return Ok(());
+        }
+
+        reader.offset += chunk.chars().count();
+        reader.pending.push_str(&chunk);
+        while let Some(idx) = reader.pending.find('\n') {
+            let line: String = reader.pending.drain(..=idx).collect();
+            let line = line.trim_end_matches('\n');
+            reader
+                .lines
+                .push_back(line.strip_suffix('\r').unwrap_or(line).to_string());
+        }
+        Ok(())
+    }
+
+    async fn probe_file(&self, path: &str) -> Result<Option<FileProbe>> {
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(
+                        "SELECT is_dir, ((inode.content ?? content) = NONE AND (inode.content_bytes ?? content_bytes) != NONE) AS binary, symlink FROM type::thing($table, $path)",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("path", path.to_string()))
+            })
+            .await?;
+        let probe: Option<FileProbe> = res.take(0)?;
+        Ok(probe)
+    }
+}
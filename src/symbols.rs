@@ -0,0 +1,234 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{Entry, Result, SurrealFs, mime};
+
+/// What a definition found by `symbols` defines.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Enum,
+    Trait,
+    Class,
+    Interface,
+}
+
+impl SymbolKind {
+    /// The kind as ctags spells it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Class => "class",
+            SymbolKind::Interface => "interface",
+        }
+    }
+}
+
+/// A definition returned by `symbols`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Symbol {
+    pub path: String,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 1-based line the definition starts on.
+    pub line: usize,
+}
+
+/// A definition as stored in a file's `symbols` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Tag {
+    name: String,
+    kind: SymbolKind,
+    line: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaggedFile {
+    path: String,
+    symbols: Vec<Tag>,
+}
+
+/// Definitions recognized per content type: each pattern is matched against every line,
+/// its first group capturing the name.
+const RULES: &[(&str, SymbolKind, &str)] = &[
+    (
+        "text/x-rust",
+        SymbolKind::Function,
+        r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*fn\s+([A-Za-z_]\w*)"#,
+    ),
+    (
+        "text/x-rust",
+        SymbolKind::Struct,
+        r"^\s*(?:pub(?:\([^)]*\))?\s+)?struct\s+([A-Za-z_]\w*)",
+    ),
+    (
+        "text/x-rust",
+        SymbolKind::Enum,
+        r"^\s*(?:pub(?:\([^)]*\))?\s+)?enum\s+([A-Za-z_]\w*)",
+    ),
+    (
+        "text/x-rust",
+        SymbolKind::Trait,
+        r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:unsafe\s+)?trait\s+([A-Za-z_]\w*)",
+    ),
+    (
+        "text/x-python",
+        SymbolKind::Function,
+        r"^\s*(?:async\s+)?def\s+([A-Za-z_]\w*)",
+    ),
+    (
+        "text/x-python",
+        SymbolKind::Class,
+        r"^\s*class\s+([A-Za-z_]\w*)",
+    ),
+    (
+        "text/x-go",
+        SymbolKind::Function,
+        r"^func\s+(?:\([^)]*\)\s*)?([A-Za-z_]\w*)",
+    ),
+    (
+        "text/x-go",
+        SymbolKind::Struct,
+        r"^type\s+([A-Za-z_]\w*)\s+struct\b",
+    ),
+    (
+        "text/x-go",
+        SymbolKind::Interface,
+        r"^type\s+([A-Za-z_]\w*)\s+interface\b",
+    ),
+    (
+        "text/javascript",
+        SymbolKind::Function,
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)",
+    ),
+    (
+        "text/javascript",
+        SymbolKind::Class,
+        r"^\s*(?:export\s+)?(?:default\s+)?class\s+([A-Za-z_$][\w$]*)",
+    ),
+    (
+        "text/x-typescript",
+        SymbolKind::Function,
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)",
+    ),
+    (
+        "text/x-typescript",
+        SymbolKind::Class,
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)",
+    ),
+    (
+        "text/x-typescript",
+        SymbolKind::Interface,
+        r"^\s*(?:export\s+)?interface\s+([A-Za-z_$][\w$]*)",
+    ),
+    (
+        "text/x-typescript",
+        SymbolKind::Enum,
+        r"^\s*(?:export\s+)?(?:const\s+)?enum\s+([A-Za-z_$][\w$]*)",
+    ),
+];
+
+static COMPILED: LazyLock<Vec<(&str, SymbolKind, Regex)>> = LazyLock::new(|| {
+    RULES
+        .iter()
+        .map(|(content_type, kind, pattern)| {
+            (
+                *content_type,
+                *kind,
+                Regex::new(pattern).expect("valid rule"),
+            )
+        })
+        .collect()
+});
+
+/// The definitions to store for `entry`; see `extract`.
+pub(crate) fn of_entry(entry: &Entry) -> Option<Vec<Tag>> {
+    if entry.is_dir || entry.symlink.is_some() {
+        return None;
+    }
+    let content_type = mime::of_entry(entry)?;
+    extract(&content_type, &entry.text().ok()??)
+}
+
+/// The definitions in `text`, or `None` when `content_type` is not a recognized language:
+/// Rust, Python, Go, JavaScript or TypeScript.
+pub(crate) fn extract(content_type: &str, text: &str) -> Option<Vec<Tag>> {
+    let rules: Vec<_> = COMPILED
+        .iter()
+        .filter(|(ty, _, _)| *ty == content_type)
+        .collect();
+    if rules.is_empty() {
+        return None;
+    }
+    let mut tags = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        if let Some((kind, name)) = rules
+            .iter()
+            .find_map(|(_, kind, regex)| Some((*kind, regex.captures(line)?.get(1)?)))
+        {
+            tags.push(Tag {
+                name: name.as_str().to_string(),
+                kind,
+                line: idx + 1,
+            });
+        }
+    }
+    Some(tags)
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Definitions whose name contains `query`, ignoring case, like looking a tag up in a
+    /// ctags file: definitions named exactly `query` first, then by path and line. An
+    /// empty query lists every definition. Names are extracted from recognized languages
+    /// (see `SymbolKind`) when a file is written, by matching each line against simple
+    /// patterns, so definitions spread over several lines or made by macros are missed, as
+    /// are files last written before symbols were recorded.
+    pub async fn symbols(&self, query: &str) -> Result<Vec<Symbol>> {
+        self.flush_pending().await?;
+        let files: Vec<TaggedFile> = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run(|| {
+                    self.conn(Route::Replica)
+                        .query(format!(
+                            "SELECT * FROM (\
+                                 SELECT path, (inode.symbols ?? symbols)[WHERE string::contains(string::lowercase(name), $query)] AS symbols \
+                                 FROM {} WHERE (inode.symbols ?? symbols) != NONE\
+                             ) WHERE array::len(symbols) > 0",
+                            self.table
+                        ))
+                        .bind(("query", query.to_lowercase()))
+                })
+                .await?;
+            res.take(0)?
+        };
+        let mut symbols: Vec<Symbol> = files
+            .into_iter()
+            .flat_map(|file| {
+                let path = file.path;
+                file.symbols.into_iter().map(move |tag| Symbol {
+                    path: path.clone(),
+                    name: tag.name,
+                    kind: tag.kind,
+                    line: tag.line,
+                })
+            })
+            .collect();
+        symbols.sort_by(|a, b| {
+            (a.name != query, &a.path, a.line).cmp(&(b.name != query, &b.path, b.line))
+        });
+        Ok(symbols)
+    }
+}
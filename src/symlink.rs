@@ -0,0 +1,129 @@
+use surrealdb::Connection;
+
+use crate::mode::SYMLINK_MODE;
+
+use crate::{
+    Entry, FsError, Metadata, Result, SurrealFs, join_path, leaf_name, normalize_path, now_millis,
+    parent_path,
+};
+
+/// Symlinks followed while resolving one path before giving up, as on Linux.
+pub(crate) const MAX_SYMLINK_HOPS: usize = 40;
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Create a symbolic link at `path` pointing at `target`. The target is stored as given
+    /// and need not exist; relative targets resolve against the link's directory.
+    pub async fn symlink(&self, target: impl AsRef<str>, path: impl AsRef<str>) -> Result<()> {
+        let target = target.as_ref();
+        if target.is_empty() {
+            return Err(FsError::InvalidPath);
+        }
+        let path = normalize_path(path.as_ref())?;
+        self.ensure_writable(&path)?;
+        if path == "/" {
+            return Err(FsError::AlreadyExists(path));
+        }
+        let parent = parent_path(&path).ok_or(FsError::InvalidPath)?;
+        self.ensure_dir(&parent).await?;
+        if self.symlink_metadata(&path).await?.is_some() {
+            return Err(FsError::AlreadyExists(path));
+        }
+
+        let updated_at = now_millis();
+        {
+            let _permit = self.permit().await;
+            self.forget(&[&path]);
+            let mut res = self.run_once(|| {
+                self.db
+                    .query(
+                        "CREATE type::thing($table, $path) SET path = $path, name = $name, parent = $parent, is_dir = false, content = NONE, content_bytes = NONE, mode = $mode, owner = $owner, group = $group, hidden = $hidden, size = 0, created_at = $updated_at, updated_at = $updated_at, symlink = $target",
+                    )
+                    .bind(("table", self.table.clone()))
+                    .bind(("name", leaf_name(&path)))
+                    .bind(("path", path.clone()))
+                    .bind(("parent", parent.clone()))
+                    .bind(("mode", SYMLINK_MODE))
+                    .bind(("owner", self.defaults.owner.clone()))
+                    .bind(("group", self.defaults.group.clone()))
+                    .bind(("hidden", self.defaults.hidden))
+                    .bind(("updated_at", updated_at))
+                    .bind(("target", target.to_string()))
+            })
+            .await?;
+            self.check_statements(&mut res, &path)?;
+        }
+        self.touch_ancestors(&[&path], updated_at).await
+    }
+
+    /// The stored target of the symlink at `path`, without resolving it.
+    pub async fn readlink(&self, path: impl AsRef<str>) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        match self.symlink_metadata(&path).await? {
+            Some(Metadata {
+                symlink: Some(target),
+                ..
+            }) => Ok(target),
+            Some(_) => Err(FsError::NotASymlink(path)),
+            None => Err(FsError::NotFound(path)),
+        }
+    }
+
+    /// Canonical absolute path for `path`: `.`/`..` are collapsed and every symlink along the
+    /// way is followed. Every component must exist.
+    pub async fn realpath(&self, path: impl AsRef<str>) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        let mut pending: Vec<String> = path.split('/').rev().map(str::to_string).collect();
+        let mut resolved = String::from("/");
+        let mut hops = 0;
+
+        while let Some(component) = pending.pop() {
+            match component.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    resolved = parent_path(&resolved).unwrap_or_else(|| "/".into());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let candidate = join_path(&resolved, &component);
+            let entry = self
+                .symlink_metadata(&candidate)
+                .await?
+                .ok_or_else(|| FsError::NotFound(candidate.clone()))?;
+
+            if let Some(target) = entry.symlink {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    return Err(FsError::SymlinkLoop(path));
+                }
+                if target.starts_with('/') {
+                    resolved = String::from("/");
+                }
+                pending.extend(target.split('/').rev().map(str::to_string));
+            } else if !entry.is_dir && pending.iter().any(|c| !c.is_empty() && c != ".") {
+                return Err(FsError::NotADirectory(candidate));
+            } else {
+                resolved = candidate;
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Look up `path`, following a trailing symlink to the entry it points at. Returns the
+    /// path that was actually resolved alongside the entry; dangling links fail with `NotFound`.
+    pub(crate) async fn follow(&self, path: String) -> Result<(String, Option<Entry>)> {
+        match self.get_entry(&path).await? {
+            Some(entry) if entry.symlink.is_some() => {
+                let real = self.realpath(&path).await?;
+                let entry = self.get_entry(&real).await?;
+                Ok((real, entry))
+            }
+            entry => Ok((path, entry)),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{FsError, Result, SurrealFs, is_text_type, mime, normalize_path, render_diff};
+
+/// Ordering for `SurrealFs::sort`. The default is a plain ascending byte-wise sort.
+#[derive(Debug, Clone, Default)]
+pub struct SortOptions {
+    pub reverse: bool,
+    /// Compare by leading numeric value like `sort -n`; lines without one count as zero.
+    pub numeric: bool,
+}
+
+/// How line endings in text are treated on write and on export to the host, set with
+/// `SurrealFsBuilder::newline`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NewlinePolicy {
+    /// Store text exactly as given.
+    #[default]
+    Preserve,
+    /// Rewrite `\r\n` to `\n`.
+    Lf,
+    /// Rewrite every line ending to `\r\n`.
+    CrLf,
+}
+
+impl NewlinePolicy {
+    /// `text` with its line endings rewritten; borrowed when nothing had to change.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            NewlinePolicy::Preserve => Cow::Borrowed(text),
+            NewlinePolicy::Lf if text.contains("\r\n") => Cow::Owned(text.replace("\r\n", "\n")),
+            NewlinePolicy::CrLf if has_bare_lf(text) => {
+                Cow::Owned(text.replace("\r\n", "\n").replace('\n', "\r\n"))
+            }
+            _ => Cow::Borrowed(text),
+        }
+    }
+
+    /// Like `apply`, reusing `text` when nothing has to change.
+    pub(crate) fn apply_owned(&self, text: String) -> String {
+        match self.apply(&text) {
+            Cow::Owned(changed) => changed,
+            Cow::Borrowed(_) => text,
+        }
+    }
+
+    /// Like `apply` for raw bytes, touching only content that is UTF-8 text of type
+    /// `content_type`. NUL bytes mark the data as binary whatever its type says.
+    pub(crate) fn apply_to_bytes(&self, content_type: &str, data: Vec<u8>) -> Vec<u8> {
+        if *self == NewlinePolicy::Preserve || !is_text_type(content_type) || data.contains(&0) {
+            return data;
+        }
+        match std::str::from_utf8(&data) {
+            Ok(text) => match self.apply(text) {
+                Cow::Owned(text) => text.into_bytes(),
+                Cow::Borrowed(_) => data,
+            },
+            Err(_) => data,
+        }
+    }
+}
+
+fn has_bare_lf(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    text.match_indices('\n')
+        .any(|(idx, _)| idx == 0 || bytes[idx - 1] != b'\r')
+}
+
+/// One run of identical adjacent lines, as reported by `SurrealFs::uniq`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UniqLine {
+    pub count: usize,
+    pub line: String,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// The lines of a file in sorted order. Ties under numeric comparison fall back to the
+    /// byte-wise order so output is deterministic.
+    pub async fn sort(&self, path: impl AsRef<str>, opts: &SortOptions) -> Result<Vec<String>> {
+        let content = self.cat(path.as_ref()).await?;
+        let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+        lines.sort_by(|a, b| {
+            let ord = if opts.numeric {
+                compare_numeric(a, b).then_with(|| a.cmp(b))
+            } else {
+                a.cmp(b)
+            };
+            if opts.reverse { ord.reverse() } else { ord }
+        });
+        Ok(lines)
+    }
+
+    /// Collapse runs of identical adjacent lines, counting each run like `uniq -c`. As with
+    /// `uniq`, only neighbours are merged; sort first to count across the whole file.
+    pub async fn uniq(&self, path: impl AsRef<str>) -> Result<Vec<UniqLine>> {
+        let content = self.cat(path.as_ref()).await?;
+        let mut runs: Vec<UniqLine> = Vec::new();
+        for line in content.lines() {
+            match runs.last_mut() {
+                Some(run) if run.line == line => run.count += 1,
+                _ => runs.push(UniqLine {
+                    count: 1,
+                    line: line.to_string(),
+                }),
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Insert `lines` before line `at` (1-based, as printed by `nl`); `at` one past the last
+    /// line appends. Returns a diff like `edit`.
+    pub async fn insert_lines<I, S>(
+        &self,
+        path: impl AsRef<str>,
+        at: usize,
+        lines: I,
+    ) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.splice_lines(path.as_ref(), at..at, lines).await
+    }
+
+    /// Delete the 1-based line numbers in `range`, e.g. `3..=5`. Returns a diff like `edit`.
+    pub async fn delete_lines(
+        &self,
+        path: impl AsRef<str>,
+        range: impl RangeBounds<usize>,
+    ) -> Result<String> {
+        self.splice_lines(path.as_ref(), range, std::iter::empty::<&str>())
+            .await
+    }
+
+    /// Replace the 1-based line numbers in `range` with `lines`, which may be shorter or longer
+    /// than the range. Returns a diff like `edit`.
+    pub async fn replace_lines<I, S>(
+        &self,
+        path: impl AsRef<str>,
+        range: impl RangeBounds<usize>,
+        lines: I,
+    ) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.splice_lines(path.as_ref(), range, lines).await
+    }
+
+    /// The bytes of `path` as they should be written out to the host: text files get the
+    /// newline policy applied, everything else is returned untouched.
+    pub async fn export_bytes(&self, path: impl AsRef<str>) -> Result<Vec<u8>> {
+        let entry = self.require_file(path.as_ref()).await?;
+        let data = entry.bytes().unwrap_or_default();
+        match entry
+            .content_type
+            .clone()
+            .or_else(|| mime::of_entry(&entry))
+        {
+            Some(content_type) => Ok(self.newline.apply_to_bytes(&content_type, data)),
+            None => Ok(data),
+        }
+    }
+
+    async fn splice_lines<I, S>(
+        &self,
+        path: &str,
+        range: impl RangeBounds<usize>,
+        lines: I,
+    ) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let path = normalize_path(path)?;
+        let current = self.cat(&path).await?;
+        let mut existing: Vec<&str> = current.lines().collect();
+        let (start, end) = line_span(&range, existing.len())
+            .ok_or_else(|| FsError::LineOutOfRange(path.clone()))?;
+
+        let inserted: Vec<String> = lines.into_iter().map(|l| l.as_ref().to_string()).collect();
+        existing.splice(start..end, inserted.iter().map(String::as_str));
+
+        // Keep the file's line ending style and whether it ends with one.
+        let newline = if current.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut updated = existing.join(newline);
+        if !updated.is_empty() && (current.ends_with('\n') || current.is_empty()) {
+            updated.push_str(newline);
+        }
+        if updated == current {
+            return Ok(String::new());
+        }
+
+        self.write_file(&path, updated.clone()).await?;
+        Ok(render_diff(&current, &updated))
+    }
+}
+
+/// Convert a range of 1-based line numbers into 0-based `start..end` indices, or `None` when
+/// it falls outside a file of `len` lines. An empty range at `len + 1` is allowed for appends.
+fn line_span(range: &impl RangeBounds<usize>, len: usize) -> Option<(usize, usize)> {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.checked_add(1)?,
+        Bound::Unbounded => 1,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1)?,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len + 1,
+    };
+    if start == 0 || start > end || end > len + 1 {
+        return None;
+    }
+    Some((start - 1, end - 1))
+}
+
+fn compare_numeric(a: &str, b: &str) -> Ordering {
+    leading_number(a).total_cmp(&leading_number(b))
+}
+
+/// Parse the numeric prefix of a line after leading blanks, e.g. `" -12.5 apples"` → -12.5.
+fn leading_number(line: &str) -> f64 {
+    let trimmed = line.trim_start();
+    let mut end = 0;
+    let mut seen_dot = false;
+    for (idx, ch) in trimmed.char_indices() {
+        match ch {
+            '-' | '+' if idx == 0 => {}
+            '0'..='9' => {}
+            '.' if !seen_dot => seen_dot = true,
+            _ => break,
+        }
+        end = idx + ch.len_utf8();
+    }
+    trimmed[..end].parse().unwrap_or(0.0)
+}
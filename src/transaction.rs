@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde_bytes::ByteBuf;
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{
+    Entry, FsError, Result, SurrealFs, checksum, compress, encoding, immutable, inode, mime, mode,
+    normalize_path, now_millis, parent_path, procfs, symbols,
+};
+
+/// Changes staged inside `SurrealFs::transaction`. Nothing touches the database until the
+/// closure returns `Ok`; then every change is applied in one SurrealDB transaction, or none
+/// is. Reads made through the filesystem meanwhile see the state before the transaction.
+#[derive(Debug, Clone, Default)]
+pub struct Transaction {
+    ops: Arc<Mutex<Vec<Op>>>,
+}
+
+#[derive(Debug)]
+enum Op {
+    Write(String, Content),
+    Mkdir(String),
+    Remove(String),
+}
+
+#[derive(Debug)]
+enum Content {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Transaction {
+    /// Create or overwrite the text file at `path`. Its parent must exist by then. Paths are
+    /// taken literally: a symlink at `path` fails with `NotAFile` rather than being followed.
+    pub fn write_file(&self, path: impl AsRef<str>, content: impl Into<String>) -> Result<()> {
+        let path = stage_path(path.as_ref())?;
+        self.push(Op::Write(path, Content::Text(content.into())));
+        Ok(())
+    }
+
+    /// Create or overwrite the file at `path` with raw bytes. Its parent must exist by then.
+    pub fn write_bytes(&self, path: impl AsRef<str>, data: impl Into<Vec<u8>>) -> Result<()> {
+        let path = stage_path(path.as_ref())?;
+        self.push(Op::Write(path, Content::Bytes(data.into())));
+        Ok(())
+    }
+
+    /// Create the directory at `path` along with any missing parents, like `mkdir -p`.
+    pub fn mkdir(&self, path: impl AsRef<str>) -> Result<()> {
+        let path = stage_path(path.as_ref())?;
+        self.push(Op::Mkdir(path));
+        Ok(())
+    }
+
+    /// Remove the file, symlink, or directory at `path`; a directory goes with its whole
+    /// subtree. Removal inside a transaction is permanent, even in trash mode.
+    pub fn rm(&self, path: impl AsRef<str>) -> Result<()> {
+        let path = stage_path(path.as_ref())?;
+        self.push(Op::Remove(path));
+        Ok(())
+    }
+
+    fn push(&self, op: Op) {
+        self.ops.lock().unwrap_or_else(|e| e.into_inner()).push(op);
+    }
+
+    fn take(&self) -> Vec<Op> {
+        std::mem::take(&mut *self.ops.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+fn stage_path(path: &str) -> Result<String> {
+    let path = normalize_path(path)?;
+    if path == "/" {
+        return Err(FsError::InvalidPath);
+    }
+    if procfs::is_proc_path(&path) {
+        return Err(FsError::ReadOnly(path));
+    }
+    Ok(path)
+}
+
+enum Step {
+    Create(Entry),
+    Update(Entry),
+    Delete(Vec<String>),
+}
+
+/// The tree as it will look once the staged steps so far are applied: entries written or
+/// removed in the transaction, then removed directories, then the database.
+#[derive(Default)]
+struct Plan {
+    overlay: BTreeMap<String, Option<Entry>>,
+    removed_dirs: Vec<String>,
+    steps: Vec<Step>,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Run `f` with a `Transaction` to stage writes, directory creation, and removals on,
+    /// then apply them all atomically. When `f` fails, or a staged change turns out invalid
+    /// (missing parent, immutable file, and so on), nothing is written.
+    pub async fn transaction<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let tx = Transaction::default();
+        let value = f(tx.clone()).await?;
+        self.commit(tx.take()).await?;
+        Ok(value)
+    }
+
+    async fn commit(&self, ops: Vec<Op>) -> Result<()> {
+        let now = now_millis();
+        let mut plan = Plan::default();
+        let mut touched: Vec<String> = Vec::new();
+        for op in ops {
+            let (Op::Write(path, _) | Op::Mkdir(path) | Op::Remove(path)) = &op;
+            self.ensure_writable(path)?;
+            match op {
+                Op::Write(path, content) => self.plan_write(&mut plan, &path, content, now).await?,
+                Op::Mkdir(path) => self.plan_mkdir(&mut plan, &path, now).await?,
+                Op::Remove(path) => self.plan_remove(&mut plan, &path).await?,
+            }
+        }
+        if plan.steps.is_empty() {
+            return Ok(());
+        }
+
+        let mut sql = String::from("BEGIN TRANSACTION;");
+        for (idx, step) in plan.steps.iter().enumerate() {
+            let fields = format!(
+                "content = $e{idx}.content, content_bytes = $e{idx}.content_bytes, compression = $e{idx}.compression, content_type = $e{idx}.content_type, encoding = $e{idx}.encoding, checksum = $e{idx}.checksum, symbols = $symbols{idx}, size = $size{idx}, updated_at = $now"
+            );
+            match step {
+                Step::Create(entry) => {
+                    touched.push(entry.path.clone());
+                    sql.push_str(&format!(
+                        "CREATE type::thing($table, $e{idx}.path) SET path = $e{idx}.path, name = $e{idx}.name, parent = $e{idx}.parent, is_dir = $e{idx}.is_dir, {fields}, mode = $e{idx}.mode, owner = $e{idx}.owner, group = $e{idx}.group, hidden = $e{idx}.hidden, version = 1, created_at = $now;"
+                    ));
+                }
+                Step::Update(entry) => {
+                    touched.push(entry.path.clone());
+                    // Hard links share one file, written on their inode as in `persist_entry`.
+                    sql.push_str(&format!(
+                        "UPDATE {} SET {fields}, version = (version ?? 1) + 1;",
+                        inode::content_record(&format!("$e{idx}.path"))
+                    ));
+                }
+                Step::Delete(paths) => {
+                    touched.extend(paths.iter().cloned());
+                    sql.push_str(&format!(
+                        "FOR $path IN $e{idx} {{ LET $removed = (DELETE type::thing($table, $path) RETURN VALUE $before.inode); {} }};",
+                        inode::release("$removed")
+                    ));
+                }
+            }
+        }
+        sql.push_str("COMMIT TRANSACTION;");
+
+        {
+            let _permit = self.permit().await;
+            self.run_once(|| {
+                let mut query = self
+                    .db
+                    .query(&sql)
+                    .bind(("table", self.table.clone()))
+                    .bind(("now", now));
+                for (idx, step) in plan.steps.iter().enumerate() {
+                    query = match step {
+                        Step::Create(entry) | Step::Update(entry) => {
+                            let mut entry = entry.clone();
+                            let size = entry.size();
+                            let tags = symbols::of_entry(&entry);
+                            (entry.content, entry.content_bytes, entry.compression) =
+                                compress::pack(
+                                    self.compression,
+                                    entry.content,
+                                    entry.content_bytes,
+                                );
+                            query
+                                .bind((format!("size{idx}"), size))
+                                .bind((format!("symbols{idx}"), tags))
+                                .bind((format!("e{idx}"), entry))
+                        }
+                        Step::Delete(paths) => query.bind((format!("e{idx}"), paths.clone())),
+                    };
+                }
+                query
+            })
+            .await?
+            .check()?;
+        }
+        let touched: Vec<&str> = touched.iter().map(String::as_str).collect();
+        self.forget(&touched);
+        self.touch_ancestors(&touched, now).await
+    }
+
+    async fn plan_lookup(&self, plan: &mut Plan, path: &str) -> Result<Option<Entry>> {
+        if let Some(entry) = plan.overlay.get(path) {
+            return Ok(entry.clone());
+        }
+        if plan
+            .removed_dirs
+            .iter()
+            .any(|dir| path.starts_with(&format!("{}/", dir)))
+        {
+            return Ok(None);
+        }
+        let entry = self.get_entry(path).await?;
+        plan.overlay.insert(path.to_string(), entry.clone());
+        Ok(entry)
+    }
+
+    async fn plan_write(
+        &self,
+        plan: &mut Plan,
+        path: &str,
+        content: Content,
+        now: i64,
+    ) -> Result<()> {
+        let parent = parent_path(path).ok_or(FsError::InvalidPath)?;
+        if parent != "/" {
+            match self.plan_lookup(plan, &parent).await? {
+                Some(dir) if dir.is_dir => {}
+                Some(_) => return Err(FsError::NotADirectory(parent)),
+                None => return Err(FsError::NotFound(parent)),
+            }
+        }
+        let (content, content_bytes) = match content {
+            Content::Text(text) => (Some(self.newline.apply_owned(text)), None),
+            Content::Bytes(data) => {
+                let data = self.newline.apply_to_bytes(mime::detect(path, &data), data);
+                (None, Some(ByteBuf::from(data)))
+            }
+        };
+
+        let entry = match self.plan_lookup(plan, path).await? {
+            Some(existing) if existing.is_dir || existing.symlink.is_some() => {
+                return Err(FsError::NotAFile(path.to_string()));
+            }
+            Some(mut existing) => {
+                immutable::ensure_mutable(path, existing.immutable)?;
+                mode::ensure_content_writable(&existing)?;
+                existing.content = content;
+                existing.content_bytes = content_bytes;
+                existing.content_type = mime::of_entry(&existing);
+                existing.encoding = encoding::of_entry(&existing);
+                existing.checksum = checksum::of_entry(&existing);
+                existing.updated_at = Some(now);
+                existing.version += 1;
+                plan.steps.push(Step::Update(existing.clone()));
+                existing
+            }
+            None => {
+                let entry = self.new_file_entry(path, &parent, content, content_bytes, now);
+                plan.steps.push(Step::Create(entry.clone()));
+                entry
+            }
+        };
+        plan.overlay.insert(path.to_string(), Some(entry));
+        Ok(())
+    }
+
+    async fn plan_mkdir(&self, plan: &mut Plan, path: &str, now: i64) -> Result<()> {
+        let mut missing = Vec::new();
+        let mut current = Some(path.to_string());
+        while let Some(dir) = current.filter(|d| d != "/") {
+            match self.plan_lookup(plan, &dir).await? {
+                Some(entry) if entry.is_dir => break,
+                Some(_) => return Err(FsError::NotADirectory(dir)),
+                None => {
+                    current = parent_path(&dir);
+                    missing.push(dir);
+                }
+            }
+        }
+
+        for dir in missing.into_iter().rev() {
+            let parent = parent_path(&dir).ok_or(FsError::InvalidPath)?;
+            let entry = Entry {
+                is_dir: true,
+                content_type: None,
+                checksum: None,
+                mode: Some(self.defaults.dir_mode()),
+                ..self.new_file_entry(&dir, &parent, None, None, now)
+            };
+            plan.steps.push(Step::Create(entry.clone()));
+            plan.overlay.insert(dir, Some(entry));
+        }
+        Ok(())
+    }
+
+    async fn plan_remove(&self, plan: &mut Plan, path: &str) -> Result<()> {
+        let entry = self
+            .plan_lookup(plan, path)
+            .await?
+            .ok_or_else(|| FsError::NotFound(path.to_string()))?;
+        immutable::ensure_mutable(path, entry.immutable)?;
+
+        let mut paths = BTreeSet::from([path.to_string()]);
+        if entry.is_dir {
+            let prefix = format!("{}/", path);
+            self.ensure_subtree_mutable(path).await?;
+            for child in self.descendants(path, true, Route::Primary).await? {
+                paths.insert(child.path);
+            }
+            for (staged, entry) in plan.overlay.iter_mut() {
+                if staged.starts_with(&prefix) && entry.take().is_some() {
+                    paths.insert(staged.clone());
+                }
+            }
+            plan.removed_dirs.push(path.to_string());
+        }
+        plan.overlay.insert(path.to_string(), None);
+        plan.steps.push(Step::Delete(paths.into_iter().collect()));
+        Ok(())
+    }
+}
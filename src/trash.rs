@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{
+    FsError, Result, SurrealFs, inode, leaf_name, normalize_path, now_millis, parent_path,
+};
+
+/// Where `rm` and `rmdir` move entries when the filesystem was built with `trash(true)`.
+pub const TRASH_DIR: &str = "/.trash";
+
+/// Something removed while trash mode was on, as listed by `list_trash`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrashItem {
+    /// Where the entry lives now, directly under `TRASH_DIR`.
+    pub path: String,
+    /// Where it was removed from and where `restore` puts it back.
+    pub original: String,
+    pub trashed_at: i64,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Every entry in the trash, most recently removed first.
+    pub async fn list_trash(&self) -> Result<Vec<TrashItem>> {
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT path, trashed_from AS original, trashed_at FROM {} WHERE parent = $trash AND trashed_from != NONE ORDER BY trashed_at DESC, path DESC",
+                        self.table
+                    ))
+                    .bind(("trash", TRASH_DIR))
+            })
+            .await?;
+        let items: Vec<TrashItem> = res.take(0)?;
+        Ok(items)
+    }
+
+    /// Move a trashed entry back to where it was removed from and return that path. `path`
+    /// may be either the original path, picking the latest removal, or the item's path under
+    /// `TRASH_DIR`. Fails with `AlreadyExists` when something has taken its place since.
+    pub async fn restore(&self, path: impl AsRef<str>) -> Result<String> {
+        let path = normalize_path(path.as_ref())?;
+        self.ensure_writable(&path)?;
+        let item = self
+            .list_trash()
+            .await?
+            .into_iter()
+            .find(|item| item.original == path || item.path == path)
+            .ok_or_else(|| FsError::NotFound(path.clone()))?;
+
+        // The original directory may have been removed since; bring it back empty.
+        if let Some(parent) = parent_path(&item.original) {
+            self.mkdir(parent, true).await?;
+        }
+        self.mv(&item.path, &item.original, false).await?;
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db
+                .query(
+                    "UPDATE type::thing($table, $path) SET trashed_from = NONE, trashed_at = NONE",
+                )
+                .bind(("table", self.table.clone()))
+                .bind(("path", item.original.clone()))
+        })
+        .await?;
+        Ok(item.original)
+    }
+
+    /// Permanently delete everything in the trash and return how many items were removed.
+    pub async fn empty_trash(&self) -> Result<usize> {
+        self.ensure_writable(TRASH_DIR)?;
+        let count = self.list_trash().await?.len();
+        let _permit = self.permit().await;
+        self.forget(&[TRASH_DIR]);
+        self.run_once(|| {
+            self.db
+                .query(format!(
+                    "BEGIN TRANSACTION;\
+                     LET $removed = (DELETE {} WHERE path = $trash OR string::starts_with(path, $prefix) RETURN VALUE $before.inode);\
+                     {}\
+                     COMMIT TRANSACTION;",
+                    self.table,
+                    inode::release("$removed")
+                ))
+                .bind(("trash", TRASH_DIR))
+                .bind(("prefix", format!("{}/", TRASH_DIR)))
+        })
+        .await?;
+        Ok(count)
+    }
+
+    /// Whether removing `path` should go through the trash rather than delete outright.
+    /// Entries already in the trash are always deleted for good.
+    pub(crate) fn trashes(&self, path: &str) -> bool {
+        self.trash && path != TRASH_DIR && !path.starts_with(&format!("{}/", TRASH_DIR))
+    }
+
+    /// Move `path` and anything below it into the trash, recording where it came from.
+    pub(crate) async fn move_to_trash(&self, path: &str) -> Result<()> {
+        let trashed_at = now_millis();
+        let base = format!("{}/{}-{}", TRASH_DIR, trashed_at, leaf_name(path));
+        self.mkdir(TRASH_DIR, true).await?;
+        let mut dest = base.clone();
+        let mut n = 1;
+        while self.symlink_metadata(&dest).await?.is_some() {
+            dest = format!("{}-{}", base, n);
+            n += 1;
+        }
+
+        self.mv(path, &dest, false).await?;
+        let _permit = self.permit().await;
+        self.run(|| {
+            self.db
+                .query(
+                    "UPDATE type::thing($table, $dest) SET trashed_from = $original, trashed_at = $trashed_at",
+                )
+                .bind(("table", self.table.clone()))
+                .bind(("original", path.to_string()))
+                .bind(("trashed_at", trashed_at))
+                .bind(("dest", dest.clone()))
+        })
+        .await?;
+        Ok(())
+    }
+}
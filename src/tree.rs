@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::replica::Route;
+use crate::{Entry, FsError, Result, SurrealFs, normalize_path};
+
+/// One entry of a `SurrealFs::tree` listing together with its (depth-limited) children.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TreeNode {
+    pub entry: Entry,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Render the tree with ASCII connectors, one entry per line, rooted at the node's path.
+    pub fn render(&self) -> String {
+        let mut out = format!("{}\n", self.entry.path);
+        render_children(&self.children, "", &mut out);
+        out
+    }
+}
+
+fn render_children(children: &[TreeNode], prefix: &str, out: &mut String) {
+    for (idx, child) in children.iter().enumerate() {
+        let last = idx + 1 == children.len();
+        out.push_str(prefix);
+        out.push_str(if last { "`-- " } else { "|-- " });
+        out.push_str(&child.entry.name);
+        out.push('\n');
+
+        let nested = format!("{}{}", prefix, if last { "    " } else { "|   " });
+        render_children(&child.children, &nested, out);
+    }
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Hierarchical listing of `path`. `max_depth` limits how many directory levels below
+    /// `path` are expanded; `None` walks the whole subtree. Entries below the root come
+    /// without their content; see `Entry::load_content`.
+    pub async fn tree(&self, path: impl AsRef<str>, max_depth: Option<usize>) -> Result<TreeNode> {
+        let path = normalize_path(path.as_ref())?;
+        let root = if path == "/" {
+            Entry {
+                path: path.clone(),
+                name: path.clone(),
+                parent: None,
+                is_dir: true,
+                content: None,
+                content_bytes: None,
+                content_type: None,
+                encoding: None,
+                compression: None,
+                checksum: None,
+                mode: None,
+                owner: None,
+                group: None,
+                xattrs: Default::default(),
+                immutable: false,
+                hidden: false,
+                created_at: None,
+                updated_at: None,
+                accessed_at: None,
+                expires_at: None,
+                version: 0,
+                inode: None,
+                symlink: None,
+                content_handle: Default::default(),
+            }
+        } else {
+            self.get_entry(&path)
+                .await?
+                .ok_or_else(|| FsError::NotFound(path.clone()))?
+        };
+
+        let mut by_parent: HashMap<String, Vec<Entry>> = HashMap::new();
+        if root.is_dir && max_depth != Some(0) {
+            let prefix_len = if root.path == "/" {
+                1
+            } else {
+                root.path.len() + 1
+            };
+            for entry in self.subtree(&root.path, true, Route::Replica).await? {
+                let depth = entry.path[prefix_len..].matches('/').count() + 1;
+                if max_depth.is_some_and(|max| depth > max) {
+                    continue;
+                }
+                let parent = entry.parent.clone().unwrap_or_default();
+                by_parent.entry(parent).or_default().push(entry);
+            }
+        }
+
+        Ok(build_node(root, &mut by_parent))
+    }
+}
+
+fn build_node(entry: Entry, by_parent: &mut HashMap<String, Vec<Entry>>) -> TreeNode {
+    let children = by_parent
+        .remove(&entry.path)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| build_node(child, by_parent))
+        .collect();
+    TreeNode { entry, children }
+}
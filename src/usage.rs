@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::Connection;
+
+use crate::{FsError, Result, SurrealFs, normalize_path};
+
+/// Byte length of an entry: the stored `size` (its inode's for a hard-linked file), or for
+/// entries written before it was stored, computed inside SurrealQL. Either way aggregates
+/// never ship content.
+pub(crate) const SIZE_EXPR: &str = "(inode.size ?? size ?? IF is_dir THEN 0 ELSE IF content_bytes != NONE THEN bytes::len(content_bytes) ELSE bytes::len(<bytes>(content ?? '')) END)";
+
+/// Totals for the whole filesystem, as reported by `SurrealFs::usage` and `SurrealFs::df`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Usage {
+    /// The SurrealDB table backing the filesystem.
+    pub table: String,
+    pub entries: usize,
+    pub files: usize,
+    pub directories: usize,
+    pub bytes: usize,
+    pub quota_bytes: Option<usize>,
+    pub remaining_bytes: Option<usize>,
+    pub top_level: Vec<DirUsage>,
+}
+
+/// Entry and byte totals for a path and everything beneath it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirUsage {
+    pub path: String,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// Recursive size of a path, as reported by `SurrealFs::du`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub path: String,
+    pub bytes: usize,
+    pub children: Vec<DirUsage>,
+}
+
+/// How many of the largest files `SurrealFs::stats` lists per type.
+const STATS_LARGEST: usize = 5;
+
+/// What fills a tree, as reported by `SurrealFs::stats`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TreeStats {
+    pub path: String,
+    pub files: usize,
+    pub bytes: usize,
+    /// One group per extension, most bytes first.
+    pub types: Vec<TypeStats>,
+}
+
+/// Files sharing an extension.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TypeStats {
+    /// Lowercased extension, or `None` for names without one (including dotfiles such as
+    /// `.bashrc`).
+    pub extension: Option<String>,
+    /// The distinct content types stored for these files.
+    pub content_types: Vec<String>,
+    pub files: usize,
+    pub bytes: usize,
+    /// The largest of these files, biggest first.
+    pub largest: Vec<FileSize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileSize {
+    pub path: String,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct TypeRow {
+    extension: Option<String>,
+    content_types: Vec<Option<String>>,
+    files: usize,
+    bytes: usize,
+    largest: Vec<FileSize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChildRow {
+    child: String,
+    entries: usize,
+    bytes: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageRow {
+    top: Option<String>,
+    is_dir: bool,
+    entries: usize,
+    bytes: usize,
+}
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Aggregate entry counts and stored bytes server-side, broken down by top-level path.
+    pub async fn usage(&self) -> Result<Usage> {
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT top, is_dir, count() AS entries, math::sum(size) AS bytes FROM (SELECT is_dir, {} AS size, string::split(path, '/')[1] AS top FROM {}) GROUP BY top, is_dir",
+                        SIZE_EXPR, self.table
+                    ))
+            })
+            .await?;
+        let rows: Vec<UsageRow> = res.take(0)?;
+
+        let mut usage = Usage {
+            table: self.table.clone(),
+            quota_bytes: self.quota_bytes,
+            ..Usage::default()
+        };
+        for row in rows {
+            usage.entries += row.entries;
+            usage.bytes += row.bytes;
+            if row.is_dir {
+                usage.directories += row.entries;
+            } else {
+                usage.files += row.entries;
+            }
+
+            let path = format!("/{}", row.top.unwrap_or_default());
+            match usage.top_level.iter_mut().find(|d| d.path == path) {
+                Some(dir) => {
+                    dir.entries += row.entries;
+                    dir.bytes += row.bytes;
+                }
+                None => usage.top_level.push(DirUsage {
+                    path,
+                    entries: row.entries,
+                    bytes: row.bytes,
+                }),
+            }
+        }
+        usage.top_level.sort_by(|a, b| a.path.cmp(&b.path));
+        usage.remaining_bytes = self
+            .quota_bytes
+            .map(|quota| quota.saturating_sub(usage.bytes));
+
+        Ok(usage)
+    }
+
+    /// Filesystem totals for monitoring growth of the backing table, as the `df` command
+    /// prints them. The same report as `usage`.
+    pub async fn df(&self) -> Result<Usage> {
+        self.usage().await
+    }
+
+    /// Recursive byte totals for `path`, summed server-side per direct child.
+    pub async fn du(&self, path: impl AsRef<str>) -> Result<DiskUsage> {
+        let path = normalize_path(path.as_ref())?;
+        if path != "/" {
+            let entry = self
+                .symlink_metadata(&path)
+                .await?
+                .ok_or_else(|| FsError::NotFound(path.clone()))?;
+            if !entry.is_dir {
+                return Ok(DiskUsage {
+                    bytes: entry.size,
+                    path,
+                    children: Vec::new(),
+                });
+            }
+        }
+
+        let prefix = if path == "/" {
+            path.clone()
+        } else {
+            format!("{}/", path)
+        };
+        self.flush_pending().await?;
+        let _permit = self.permit().await;
+        let mut res = self
+            .run(|| {
+                self.db
+                    .query(format!(
+                        "SELECT child, count() AS entries, math::sum(size) AS bytes FROM (SELECT {} AS size, string::split(string::slice(path, $len), '/')[0] AS child FROM {} WHERE string::starts_with(path, $prefix)) GROUP BY child",
+                        SIZE_EXPR, self.table
+                    ))
+                    .bind(("len", prefix.chars().count()))
+                    .bind(("prefix", prefix.clone()))
+            })
+            .await?;
+        let rows: Vec<ChildRow> = res.take(0)?;
+
+        let mut children: Vec<DirUsage> = rows
+            .into_iter()
+            .map(|row| DirUsage {
+                path: format!("{}{}", prefix, row.child),
+                entries: row.entries,
+                bytes: row.bytes,
+            })
+            .collect();
+        children.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(DiskUsage {
+            bytes: children.iter().map(|c| c.bytes).sum(),
+            path,
+            children,
+        })
+    }
+
+    /// Files at or beneath `path` grouped by extension, with counts, bytes, the content
+    /// types seen, and the largest files of each group, all aggregated server-side so no
+    /// content is fetched. Symlinks are left out.
+    pub async fn stats(&self, path: impl AsRef<str>) -> Result<TreeStats> {
+        let path = normalize_path(path.as_ref())?;
+        if path != "/" && self.symlink_metadata(&path).await?.is_none() {
+            return Err(FsError::NotFound(path));
+        }
+        let prefix = if path == "/" {
+            path.clone()
+        } else {
+            format!("{}/", path)
+        };
+        self.flush_pending().await?;
+        let rows: Vec<TypeRow> = {
+            let _permit = self.permit().await;
+            let mut res = self
+                .run(|| {
+                    self.db
+                        .query(format!(
+                            "LET $files = (\
+                                 SELECT path, {size} AS bytes, content_type, \
+                                     IF string::contains(string::slice(name, 1), '.') THEN string::lowercase(array::last(string::split(name, '.'))) ELSE NONE END AS extension \
+                                 FROM {table} WHERE is_dir = false AND symlink = NONE AND (path = $path OR string::starts_with(path, $prefix))\
+                             );\
+                             SELECT *, (SELECT path, bytes FROM $files WHERE extension = $parent.extension ORDER BY bytes DESC, path LIMIT {largest}) AS largest FROM (\
+                                 SELECT extension, count() AS files, math::sum(bytes) AS bytes, array::group(content_type) AS content_types FROM $files GROUP BY extension\
+                             );",
+                            size = SIZE_EXPR,
+                            table = self.table,
+                            largest = STATS_LARGEST,
+                        ))
+                        .bind(("path", path.clone()))
+                        .bind(("prefix", prefix.clone()))
+                })
+                .await?;
+            res.take(1)?
+        };
+
+        let mut types: Vec<TypeStats> = rows
+            .into_iter()
+            .map(|row| {
+                let mut content_types: Vec<String> =
+                    row.content_types.into_iter().flatten().collect();
+                content_types.sort();
+                TypeStats {
+                    extension: row.extension,
+                    content_types,
+                    files: row.files,
+                    bytes: row.bytes,
+                    largest: row.largest,
+                }
+            })
+            .collect();
+        types.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        Ok(TreeStats {
+            files: types.iter().map(|t| t.files).sum(),
+            bytes: types.iter().map(|t| t.bytes).sum(),
+            path,
+            types,
+        })
+    }
+}
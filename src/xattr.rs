@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use surrealdb::Connection;
+
+use crate::{Entry, FsError, Result, SurrealFs, normalize_path};
+
+impl<DB> SurrealFs<DB>
+where
+    DB: Connection,
+{
+    /// Attach `name = value` to `path`, replacing any previous value. Symlinks are followed
+    /// and hard links share one set of attributes.
+    pub async fn setxattr(
+        &self,
+        path: impl AsRef<str>,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<()> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(FsError::InvalidPath);
+        }
+        let entry = self.xattr_target(path.as_ref(), true).await?;
+        let mut xattrs = entry.xattrs.clone();
+        xattrs.insert(name, value.into());
+        self.store_xattrs(&entry, xattrs).await
+    }
+
+    /// The value of attribute `name` on `path`, or `None` when it is not set.
+    pub async fn getxattr(
+        &self,
+        path: impl AsRef<str>,
+        name: impl AsRef<str>,
+    ) -> Result<Option<String>> {
+        let mut entry = self.xattr_target(path.as_ref(), false).await?;
+        Ok(entry.xattrs.remove(name.as_ref()))
+    }
+
+    /// Names of every attribute set on `path`, sorted.
+    pub async fn listxattr(&self, path: impl AsRef<str>) -> Result<Vec<String>> {
+        let entry = self.xattr_target(path.as_ref(), false).await?;
+        Ok(entry.xattrs.into_keys().collect())
+    }
+
+    /// Remove attribute `name` from `path`, returning whether it was set.
+    pub async fn removexattr(&self, path: impl AsRef<str>, name: impl AsRef<str>) -> Result<bool> {
+        let entry = self.xattr_target(path.as_ref(), true).await?;
+        let mut xattrs = entry.xattrs.clone();
+        if xattrs.remove(name.as_ref()).is_none() {
+            return Ok(false);
+        }
+        self.store_xattrs(&entry, xattrs).await?;
+        Ok(true)
+    }
+
+    async fn xattr_target(&self, path: &str, write: bool) -> Result<Entry> {
+        let path = normalize_path(path)?;
+        if write {
+            self.ensure_writable(&path)?;
+        }
+        let (path, entry) = self.follow(path).await?;
+        entry.ok_or(FsError::NotFound(path))
+    }
+
+    async fn store_xattrs(&self, entry: &Entry, xattrs: BTreeMap<String, String>) -> Result<()> {
+        let _permit = self.permit().await;
+        self.forget(&[&entry.path]);
+        self.run(|| {
+            self.db
+                .query(format!(
+                    "UPDATE {} SET xattrs = $xattrs WHERE path = $path OR (inode != NONE AND inode = $inode)",
+                    self.table
+                ))
+                .bind(("path", entry.path.clone()))
+                .bind(("inode", self.inode_record(&entry.inode)))
+                .bind(("xattrs", xattrs.clone()))
+        })
+        .await?;
+        Ok(())
+    }
+}